@@ -14,6 +14,6 @@ function get_party_member()
     return nil
   else
     -- random party member
-    return targets[math.random(#targets)]
+    return targets[math.cosmetic_random(#targets)]
   end
 end
\ No newline at end of file
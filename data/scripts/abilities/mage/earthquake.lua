@@ -40,7 +40,7 @@ end
 
 function create_random_anim(parent, ability, targets)
   local ids = { "01", "02", "03" }
-  local id = ids[math.random(#ids)]
+  local id = ids[math.cosmetic_random(#ids)]
   local id = "01"
 
   local position = targets:selected_point()
@@ -48,11 +48,11 @@ function create_random_anim(parent, ability, targets)
   local anim = parent:create_anim("shooting_bolt" .. id, 0.7)
   anim:set_draw_below_entities()
   anim:set_position(
-    anim:param(position.x - radius - 1.0 + (math.random() - 0.5) * 4.0),
-    anim:param(position.y - 1.0 + (math.random() - 0.5) * 4.0)
+    anim:param(position.x - radius - 1.0 + (math.cosmetic_random() - 0.5) * 4.0),
+    anim:param(position.y - 1.0 + (math.cosmetic_random() - 0.5) * 4.0)
   )
   anim:set_particle_size_dist(anim:fixed_dist(20.0), anim:fixed_dist(2.5))
-  anim:set_rotation(anim:param(math.random() * 2.0 * math.pi))
+  anim:set_rotation(anim:param(math.cosmetic_random() * 2.0 * math.pi))
   anim:set_color(anim:param(0.53), anim:param(0.46), anim:param(0.40))
   anim:activate()
 end
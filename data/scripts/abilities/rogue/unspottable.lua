@@ -8,6 +8,7 @@ function on_activate(parent, ability)
   
   local effect = parent:create_effect(ability:name(), ability:duration() + parent:ability_level(ability) - 1)
   effect:set_tag("unspottable")
+  effect:add_num_bonus("stealth", 1000)
 
   local gen = parent:create_particle_generator("wind_particle", duration)
   gen:set_moves_with_parent()
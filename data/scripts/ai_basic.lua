@@ -38,10 +38,24 @@ function ai_action(parent, params)
         return end_turn(parent)
     end
 
+    local board = game:ai_group_blackboard(parent)
+    if board ~= nil and board:is_retreating() then
+        game:log("  Retreating on the orders of the group")
+        attempt_run_away(parent, hostiles:visible():to_table())
+        return end_turn(parent)
+    end
+
     if check_swap_weapons_to_melee(parent, hostiles).done then
         return parent:state_wait(WAIT_TIME)
     end
 
+    -- all hostiles are hidden, so look for them
+    if hostiles:num_targets() == 0 and not parent:has_effect_with_tag("searching") then
+        game:log("  No hostiles found.  Searching")
+        parent:search()
+        return parent:state_wait(WAIT_TIME)
+    end
+
     local items = parent:inventory():usable_items()
 
     game:log("  Got " .. tostring(#items) .. " items")
@@ -54,6 +68,10 @@ function ai_action(parent, params)
     precompute_weights(parent, hostiles, weights)
     precompute_weights(parent, friendlies, weights)
 
+    if board ~= nil then
+        update_focus(parent, board, hostiles, weights)
+    end
+
     local failed_use_count = 0
 
     if not parent:has_flag("ai_force_attack") then
@@ -125,6 +143,7 @@ function ai_action(parent, params)
             local result = check_move_for_attack(parent, target, retry)
             if result.attack then
                 game:log("  Perform attack")
+                game:ai_trace_choose("Attack", target)
                 parent:anim_weapon_attack(target, nil, true)
                 parent:clear_flag("ai_force_attack")
 
@@ -296,11 +315,13 @@ function find_and_use_item(parent, items, hostiles, friendlies, failed_use_count
 
         if result.target then
             game:log("      Use item")
+            trace_candidate(item:name(), result.target, weights)
             parent:use_item(item)
-            local result = handle_targeter(parent, result.target, item, item:ai_data(),
+            local targeter_result = handle_targeter(parent, result.target, item, item:ai_data(),
                 hostiles, friendlies, weights)
 
-            if result.done then
+            if targeter_result.done then
+                game:ai_trace_choose(item:name(), result.target)
                 return { done=true }
             end
         end
@@ -331,13 +352,16 @@ function find_and_use_ability(parent, params, abilities, hostiles, friendlies,
             return { done=true }
         end
 
-        if result.target then
+        if result.target and not is_support_claimed(parent, ai_data, result.target) then
             game:log("      Use ability")
+            trace_candidate(ability:name(), result.target, weights)
             parent:use_ability(ability)
-            local result = handle_targeter(parent, result.target, ability, ai_data,
+            local targeter_result = handle_targeter(parent, result.target, ability, ai_data,
                 hostiles, friendlies, weights)
 
-            if result.done then
+            if targeter_result.done then
+                claim_support(parent, ai_data, result.target)
+                game:ai_trace_choose(ability:name(), result.target)
                 return { done=true }
             end
         end
@@ -346,6 +370,39 @@ function find_and_use_ability(parent, params, abilities, hostiles, friendlies,
     return { done=false, no_abilities=true }
 end
 
+-- heals and buffs that another member of the group has already given this
+-- round are not repeated
+function is_support_claimed(parent, ai_data, target)
+    if ai_data.kind ~= "Heal" and ai_data.kind ~= "Buff" then
+        return false
+    end
+
+    local board = game:ai_group_blackboard(parent)
+    if board == nil then
+        return false
+    end
+
+    return board:is_support_claimed(parent, ai_data.kind, ai_data.group, target)
+end
+
+function claim_support(parent, ai_data, target)
+    if ai_data.kind ~= "Heal" and ai_data.kind ~= "Buff" then
+        return
+    end
+
+    local board = game:ai_group_blackboard(parent)
+    if board ~= nil then
+        board:claim_support(parent, ai_data.kind, ai_data.group, target)
+    end
+end
+
+-- records an ability or item use in the AI trace.  hostiles have negative
+-- weights, so the magnitude of the weight is used as the score
+function trace_candidate(name, target, weights)
+    local weight = weights[target:id()] or 0
+    game:ai_trace_candidate(name, target, math.abs(weight))
+end
+
 -- find the best target for the given targeter
 function handle_targeter(parent, closest_target, src, ai_data, hostiles, friendlies, weights)
     if not game:has_targeter() then
@@ -651,6 +708,8 @@ function sort_attack_targets(parent, hostiles, weights)
         local target = hostiles[i]
         local score = weights[target:id()]
         game:debug("        Got score of " .. tostring(score) .. " for " .. target:id())
+        -- hostiles have negative weights, with the lowest being the best target
+        game:ai_trace_candidate("Attack", target, -score, parent:stats().attack_cost)
         ranked[score] = target
         table.insert(scores, score)
     end
@@ -674,6 +733,36 @@ function precompute_weights(parent, targets, weights)
     end
 end
 
+-- keeps the group focused on one hostile, picking the best weighted one when
+-- there is no focus or it is no longer a valid target
+function update_focus(parent, board, hostiles, weights)
+    local focus = board:focus()
+    if focus ~= nil then
+        for i = 1, #hostiles do
+            if hostiles[i]:id() == focus:id() then
+                return
+            end
+        end
+    end
+
+    -- hostiles have negative weights, with the lowest being the best target
+    local best = nil
+    for i = 1, #hostiles do
+        local target = hostiles[i]
+        if best == nil or weights[target:id()] < weights[best:id()] then
+            best = target
+        end
+    end
+
+    if best == nil then
+        return
+    end
+
+    game:log("  Setting the group focus to " .. best:id())
+    board:set_focus(best)
+    weights[best:id()] = compute_weight(parent, best)
+end
+
 function compute_weight(parent, target)
     local base = parent:get_relationship(target)
     local target_stats = target:stats()
@@ -701,6 +790,15 @@ function compute_weight(parent, target)
     -- hostiles that are difficult to damage with our regular attack are lower priority
     modifiers = modifiers + parent:get_num_flag("__hard_target_for" .. target:id())
 
+    -- the target the group is concentrating on is higher priority
+    local board = game:ai_group_blackboard(parent)
+    if board ~= nil then
+        local focus = board:focus()
+        if focus ~= nil and focus:id() == target:id() then
+            modifiers = modifiers + 0.5
+        end
+    end
+
     game:debug("        Computed weight of " .. tostring(modifiers) .. " for " .. target:id())
 
     return base * (1 + modifiers)
@@ -39,6 +39,9 @@ function add_injury(parent)
   -- don't add injuries outside of combat
   if not game:is_combat_active() then return end
 
+  -- injuries may be disabled by the difficulty
+  if not game:difficulty().party_injuries then return end
+
   game:say_line("Injured!", parent)
   
   local injuries = {
@@ -0,0 +1,23 @@
+-- Script tests for abilities used outside of combat.  Run with:
+--   cargo run --bin sulis_script_test -- --campaign campaigns/twin_expanse_act1 script_tests
+
+fixture = farmhouse_fixture {
+  actors = {
+    { name = "ally", id = "npc_cragnik", x = 12, y = 13, party = true },
+  },
+}
+
+function test_minor_heal_restores_hp(t)
+  local caster = t:entity("caster")
+  local ally = t:entity("ally")
+  caster:add_ability("minor_heal")
+
+  ally:take_damage(caster, 5, 5, "Raw")
+  t:assert_true(ally:stats().current_hp < ally:stats().max_hp, "ally is damaged")
+
+  t:assert_true(caster:use_ability(caster:get_ability("minor_heal")))
+  t:select_target(ally)
+  t:wait_for_animations()
+
+  t:assert_eq(ally:stats().current_hp, ally:stats().max_hp)
+end
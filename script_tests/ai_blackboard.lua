@@ -0,0 +1,82 @@
+-- Script tests for the blackboard shared by the members of an AI group.  Both
+-- goblins are part of the road encounter, and use the native planner.
+
+fixture = {
+  area = "naathfir_road",
+  actors = {
+    { name = "caster", id = "npc_jhilsara", x = 14, y = 36, party = true },
+    { name = "goblin1", id = "goblin", x = 21, y = 34, ai = "ai_tactical", encounter = 0 },
+    { name = "goblin2", id = "goblin", x = 21, y = 38, ai = "ai_tactical", encounter = 0 },
+  },
+}
+
+function test_shared_by_group(t)
+  local board = game:ai_group_blackboard(t:entity("goblin1"))
+  t:assert_true(board ~= nil)
+  t:assert_eq(game:ai_group_blackboard(t:entity("goblin2")):group(), board:group())
+  t:assert_true(game:ai_group_blackboard(t:entity("caster")) == nil, "party has no group")
+end
+
+function test_read_write(t)
+  local caster = t:entity("caster")
+  local goblin1 = t:entity("goblin1")
+  local goblin2 = t:entity("goblin2")
+  local board = game:ai_group_blackboard(goblin1)
+
+  t:assert_true(board:focus() == nil)
+  board:set_focus(caster)
+  t:assert_eq(board:focus():id(), caster:id())
+  board:clear_focus()
+  t:assert_true(board:focus() == nil)
+
+  board:set_role(goblin1, "Defender")
+  t:assert_eq(board:role(goblin1), "Defender")
+  t:assert_true(board:role(goblin2) == nil)
+
+  t:assert_true(board:claim_flank(goblin1, 5, 6))
+  t:assert_false(board:claim_flank(goblin2, 5, 6), "already claimed")
+  t:assert_eq(board:flank(goblin1).x, 5)
+  t:assert_eq(board:flank(goblin1).y, 6)
+  board:release_flank(goblin1)
+  t:assert_true(board:flank(goblin1) == nil)
+
+  t:assert_false(board:is_support_claimed(goblin2, "Heal", "Single", goblin1))
+  board:claim_support(goblin1, "Heal", "Single", goblin2)
+  t:assert_false(board:is_support_claimed(goblin2, "Heal", "Single", goblin1))
+  t:assert_true(board:is_support_claimed(goblin2, "Heal", "Single", goblin2), "already healed")
+  t:assert_false(board:is_support_claimed(goblin1, "Heal", "Single", goblin2), "own claim")
+
+  t:assert_false(board:is_retreating())
+  board:order_retreat()
+  t:assert_true(board:is_retreating())
+  board:cancel_retreat()
+  t:assert_false(board:is_retreating())
+end
+
+function test_focus_and_flanks(t)
+  local caster = t:entity("caster")
+  local goblin1 = t:entity("goblin1")
+  local goblin2 = t:entity("goblin2")
+  local board = game:ai_group_blackboard(goblin1)
+
+  t:run_turn(goblin1)
+  t:assert_eq(board:focus():id(), caster:id())
+  t:assert_eq(board:role(goblin1), "Striker")
+
+  t:run_turn(goblin2)
+  local flank1 = board:flank(goblin1)
+  local flank2 = board:flank(goblin2)
+  t:assert_true(flank1 ~= nil and flank2 ~= nil, "both goblins claim a flank")
+  t:assert_true(flank1.x ~= flank2.x or flank1.y ~= flank2.y, "flanks differ")
+  t:assert_true(goblin2:is_within_attack_dist(caster))
+end
+
+function test_retreat_runs_away(t)
+  local caster = t:entity("caster")
+  local goblin = t:entity("goblin1")
+  local dist = goblin:dist_to_entity(caster)
+
+  game:ai_group_blackboard(goblin):order_retreat()
+  t:run_turn(goblin)
+  t:assert_true(goblin:dist_to_entity(caster) > dist, "goblin retreats")
+end
@@ -0,0 +1,47 @@
+-- Script tests for the blackboard used by the basic AI script.  Both goblins
+-- are part of the road encounter, and each is closer to a different one of
+-- two otherwise identical party members.
+
+fixture = {
+  area = "naathfir_road",
+  actors = {
+    { name = "north", id = "npc_cragnik", x = 15, y = 33, party = true },
+    { name = "south", id = "npc_cragnik", x = 15, y = 41, party = true },
+    { name = "goblin1", id = "goblin", x = 20, y = 33, encounter = 0 },
+    { name = "goblin2", id = "goblin", x = 20, y = 41, encounter = 0 },
+  },
+}
+
+local function attack_target(trace)
+  for _, step in ipairs(trace.steps) do
+    if step.kind == "Plan" and step.chosen ~= nil then
+      local chosen = step.candidates[step.chosen]
+      if chosen.action == "Attack" then
+        return chosen.target
+      end
+    end
+  end
+  return nil
+end
+
+function test_sets_focus(t)
+  local goblin2 = t:entity("goblin2")
+  local board = game:ai_group_blackboard(goblin2)
+
+  t:run_turn(goblin2)
+  t:assert_eq(attack_target(game:ai_trace()), t:entity("south"):id())
+  t:assert_eq(board:focus():id(), t:entity("south"):id())
+end
+
+function test_shares_focus(t)
+  local north = t:entity("north")
+  local board = game:ai_group_blackboard(t:entity("goblin1"))
+
+  t:run_turn(t:entity("goblin1"))
+  t:assert_eq(attack_target(game:ai_trace()), north:id())
+  t:assert_eq(board:focus():id(), north:id())
+
+  t:run_turn(t:entity("goblin2"))
+  t:assert_eq(attack_target(game:ai_trace()), north:id(), "goblin2 follows the focus")
+  t:assert_eq(board:focus():id(), north:id())
+end
@@ -0,0 +1,57 @@
+-- Script tests for the native tactical AI planner, used by the ai_tactical
+-- template.
+
+fixture = farmhouse_fixture {
+  actors = {
+    { name = "goblin", id = "goblin", x = 16, y = 13, ai = "ai_tactical" },
+  },
+}
+
+local function find_step(trace, kind)
+  for _, step in ipairs(trace.steps) do
+    if step.kind == kind then
+      return step
+    end
+  end
+  return nil
+end
+
+function test_moves_and_attacks(t)
+  local caster = t:entity("caster")
+  local goblin = t:entity("goblin")
+  t:assert_false(goblin:is_within_attack_dist(caster))
+
+  t:run_turn(goblin)
+  t:assert_true(goblin:is_within_attack_dist(caster), "goblin closes to melee")
+  t:assert_true(goblin:stats().current_ap < goblin:stats().attack_cost, "AP is spent")
+end
+
+function test_fear_runs_away(t)
+  local caster = t:entity("caster")
+  local goblin = t:entity("goblin")
+  local dist = goblin:dist_to_entity(caster)
+
+  local fear = goblin:create_effect("Fear", 2)
+  fear:set_tag("fear")
+  fear:apply()
+
+  t:run_turn(goblin)
+  t:assert_true(goblin:dist_to_entity(caster) > dist, "goblin flees")
+end
+
+function test_uses_ability(t)
+  local caster = t:entity("caster")
+  local goblin = t:entity("goblin")
+  goblin:add_ability("rupture")
+
+  t:run_turn(goblin)
+  local plan = find_step(game:ai_trace(), "Plan")
+  t:assert_true(plan.chosen ~= nil, "an action is chosen")
+  local chosen = plan.candidates[plan.chosen]
+  t:assert_eq(chosen.action, "Rupture")
+  t:assert_eq(chosen.target, caster:id())
+
+  -- with AP to spare, rupture would still be usable if it had not been used
+  goblin:add_ap(10000)
+  t:assert_true(goblin:abilities():can_activate():is_empty(), "rupture was used")
+end
@@ -0,0 +1,87 @@
+-- Script tests for the trace of the decisions made by the AI on each turn.
+-- The first goblin uses the native planner, the second the basic AI script.
+
+fixture = farmhouse_fixture {
+  actors = {
+    { name = "goblin", id = "goblin", x = 16, y = 13, ai = "ai_tactical" },
+    { name = "scripted", id = "goblin", x = 16, y = 16 },
+  },
+}
+
+local function find_step(trace, kind)
+  for _, step in ipairs(trace.steps) do
+    if step.kind == kind then
+      return step
+    end
+  end
+  return nil
+end
+
+function test_native_turn(t)
+  local caster = t:entity("caster")
+  local goblin = t:entity("goblin")
+
+  t:run_turn(goblin)
+  local trace = game:ai_trace()
+  t:assert_eq(trace.entity, goblin:id())
+  t:assert_eq(trace.template, "ai_tactical")
+  t:assert_eq(find_step(trace, "Action").count, 1)
+  t:assert_true(find_step(trace, "Wait") ~= nil, "waits between actions")
+  t:assert_eq(find_step(trace, "State").state, "Wait")
+  t:assert_true(trace["end"] ~= nil, "turn has ended")
+
+  local plan = find_step(trace, "Plan")
+  t:assert_true(plan.chosen ~= nil, "an action is chosen")
+  local chosen = plan.candidates[plan.chosen]
+  t:assert_eq(chosen.action, "Attack")
+  t:assert_eq(chosen.target, caster:id())
+  for _, candidate in ipairs(plan.candidates) do
+    t:assert_true(candidate.score <= chosen.score, "best candidate first")
+  end
+end
+
+function test_script_turn(t)
+  local caster = t:entity("caster")
+  local goblin = t:entity("scripted")
+
+  t:run_turn(goblin)
+  local trace = game:ai_trace()
+  t:assert_eq(trace.entity, goblin:id())
+  t:assert_eq(find_step(trace, "Script").func, "ai_action")
+  t:assert_true(find_step(trace, "State") ~= nil, "state returned by the script")
+
+  -- the basic AI script reports its attack targets
+  local plan = find_step(trace, "Plan")
+  t:assert_true(plan ~= nil, "candidates reported by the script")
+  local found = false
+  for _, candidate in ipairs(plan.candidates) do
+    if candidate.action == "Attack" and candidate.target == caster:id() then
+      found = true
+    end
+  end
+  t:assert_true(found, "attack on the caster considered")
+end
+
+function test_script_candidates(t)
+  local caster = t:entity("caster")
+  local goblin = t:entity("goblin")
+
+  -- outside of an AI turn, candidates are not recorded
+  game:ai_trace_candidate("Attack", caster, 1.0)
+  t:assert_false(game:ai_trace_choose("Attack", caster))
+
+  t:run_turn(goblin)
+  t:assert_false(game:ai_trace_choose("Unknown", caster))
+end
+
+function test_fear_noted(t)
+  local goblin = t:entity("goblin")
+  local fear = goblin:create_effect("Fear", 2)
+  fear:set_tag("fear")
+  fear:apply()
+
+  t:run_turn(goblin)
+  local trace = game:ai_trace()
+  t:assert_eq(find_step(trace, "Note").text, "Running away due to fear")
+  t:assert_eq(trace["end"], "NothingToDo")
+end
@@ -0,0 +1,28 @@
+-- Script tests for abilities used in combat.  The goblin is in view of the
+-- party, so combat starts as soon as the fixture is built.
+
+fixture = farmhouse_fixture {
+  actors = {
+    { name = "ally", id = "npc_cragnik", x = 12, y = 13, party = true },
+    { name = "goblin", id = "goblin", x = 16, y = 13 },
+  },
+}
+
+function test_haste_expires(t)
+  local caster = t:entity("caster")
+  local ally = t:entity("ally")
+  -- haste is a 2nd circle spell, which needs a level 5 mage
+  caster:add_levels("mage", 1)
+  caster:add_ability("haste")
+  t:assert_true(game:is_combat_active(), "combat started")
+
+  t:assert_true(caster:use_ability(caster:get_ability("haste")))
+  t:select_target(ally)
+  t:assert_true(ally:has_effect_with_tag("haste"))
+
+  t:advance_rounds(2)
+  t:assert_true(ally:has_effect_with_tag("haste"), "haste lasts 3 rounds")
+
+  t:advance_rounds(1)
+  t:assert_false(ally:has_effect_with_tag("haste"))
+end
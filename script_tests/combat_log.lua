@@ -0,0 +1,105 @@
+-- Script tests for the combat log and its JSON export.  Each entry is a table
+-- in the exported form, with the event stored under the name of its kind.
+
+fixture = farmhouse_fixture {
+  actors = {
+    { name = "goblin", id = "goblin", x = 16, y = 13 },
+  },
+}
+
+local function deep_eq(a, b)
+  if type(a) ~= "table" or type(b) ~= "table" then
+    return a == b
+  end
+  for key, value in pairs(a) do
+    if not deep_eq(value, b[key]) then return false end
+  end
+  for key, _ in pairs(b) do
+    if a[key] == nil then return false end
+  end
+  return true
+end
+
+function test_attack_entry(t)
+  local caster = t:entity("caster")
+  local goblin = t:entity("goblin")
+  local count = #t:combat_log()
+
+  caster:weapon_attack(goblin)
+  local log = t:combat_log()
+  t:assert_true(#log > count, "the attack was logged")
+
+  -- a hit may also log the goblin's death
+  local entry = log[count + 1]
+  local time = game:current_time()
+  t:assert_eq(entry.time.day, time.day)
+  t:assert_eq(entry.time.hour, time.hour)
+  t:assert_eq(entry.time.round, time.round)
+  local attack = entry.event.Attack
+  t:assert_true(attack ~= nil, "the first new entry is the attack")
+  t:assert_eq(attack.attacker.id, caster:id())
+  t:assert_eq(attack.attacker.name, caster:name())
+  t:assert_eq(attack.target.id, goblin:id())
+  t:assert_eq(attack.accuracy_kind, "Ranged", "the caster wields a crossbow")
+  t:assert_true(attack.roll >= 1 and attack.roll <= 100, "roll is a d100")
+  t:assert_eq(attack.defense, goblin:stats().defense)
+  t:assert_eq(attack.cover, "None")
+  t:assert_true(attack.hit_kind ~= nil)
+  if attack.hit_kind == "Miss" then
+    t:assert_eq(#attack.damage, 0, "a miss deals no damage")
+  end
+end
+
+function test_export_round_trip(t)
+  local caster = t:entity("caster")
+  local goblin = t:entity("goblin")
+  caster:weapon_attack(goblin)
+  goblin:weapon_attack(caster)
+
+  local log = t:combat_log()
+  t:assert_true(#log >= 2)
+  t:assert_true(deep_eq(t:export_combat_log(), log), "export matches the log")
+end
+
+local function effects_named(log, name)
+  local count = 0
+  for _, entry in ipairs(log) do
+    if entry.event.Effect ~= nil and entry.event.Effect.name == name then
+      count = count + 1
+    end
+  end
+  return count
+end
+
+local function apply_logged(target, policy, value, duration)
+  local effect = target:create_effect("Logged Bonus", duration)
+  effect:set_tag("logged_bonus")
+  effect:set_stacking(policy, value)
+  effect:add_num_bonus("defense", 5)
+  effect:apply()
+end
+
+function test_effect_entries(t)
+  local goblin = t:entity("goblin")
+
+  apply_logged(goblin, "replace_if_stronger", 2, 2)
+  t:assert_eq(effects_named(t:combat_log(), "Logged Bonus"), 1)
+
+  apply_logged(goblin, "replace_if_stronger", 1, 2)
+  t:assert_eq(effects_named(t:combat_log(), "Logged Bonus"), 1,
+    "a discarded effect is not logged")
+
+  apply_logged(goblin, "replace_if_stronger", 3, 3)
+  t:assert_eq(effects_named(t:combat_log(), "Logged Bonus"), 2,
+    "a replacing effect is logged")
+end
+
+function test_refreshed_effect_entry(t)
+  local goblin = t:entity("goblin")
+
+  apply_logged(goblin, "refresh", nil, 2)
+  apply_logged(goblin, "refresh", nil, 3)
+  local log = t:combat_log()
+  t:assert_eq(effects_named(log, "Logged Bonus"), 2, "a refresh is logged")
+  t:assert_eq(log[#log].event.Effect.duration, 3)
+end
@@ -0,0 +1,70 @@
+-- Script tests for bonuses contingent on the state of the parent, which are
+-- re-evaluated as the game updates.
+
+fixture = farmhouse_fixture {
+  actors = {
+    { name = "goblin", id = "goblin", x = 24, y = 13 },
+    { name = "ally", id = "npc_cragnik", x = 10, y = 18, party = true },
+  },
+}
+
+local function add_defense(entity, amount, when)
+  local effect = entity:create_effect("Contingent Defense")
+  effect:add_num_bonus("defense", amount, when)
+  effect:apply()
+end
+
+function test_hp_below(t)
+  local caster = t:entity("caster")
+  local defense = caster:stats().defense
+
+  add_defense(caster, 10, "hp_below 50")
+  t:update(16)
+  t:assert_eq(caster:stats().defense, defense, "not applied at full health")
+
+  local stats = caster:stats()
+  local damage = stats.current_hp - math.floor(stats.max_hp / 4)
+  caster:take_damage(caster, damage, damage, "Raw", 100)
+  t:update(16)
+  t:assert_eq(caster:stats().defense, defense + 10)
+end
+
+function test_location_and_time(t)
+  local caster = t:entity("caster")
+  local defense = caster:stats().defense
+
+  add_defense(caster, 5, "location_kind indoors")
+  add_defense(caster, 7, "location_kind outdoors")
+  add_defense(caster, 11, "time_of_day 0 24")
+  add_defense(caster, 13, "time_of_day 0 25")
+  t:update(16)
+  t:assert_eq(caster:stats().defense, defense + 5 + 11, "invalid hours are ignored")
+end
+
+function test_adjacent_to_ally(t)
+  local caster = t:entity("caster")
+  local ally = t:entity("ally")
+  local defense = caster:stats().defense
+
+  add_defense(caster, 5, "adjacent_to_ally")
+  t:update(16)
+  t:assert_eq(caster:stats().defense, defense, "ally is not adjacent")
+
+  ally:teleport_to({ x = 12, y = 13 })
+  t:update(16)
+  t:assert_eq(caster:stats().defense, defense + 5, "updated once the ally moves")
+
+  ally:teleport_to({ x = 10, y = 18 })
+  t:update(16)
+  t:assert_eq(caster:stats().defense, defense)
+end
+
+function test_combat(t)
+  local caster = t:entity("caster")
+  local defense = caster:stats().defense
+
+  add_defense(caster, 5, "in_combat")
+  add_defense(caster, 7, "out_of_combat")
+  t:update(16)
+  t:assert_eq(caster:stats().defense, defense + 7)
+end
@@ -0,0 +1,39 @@
+-- Script tests for cover provided by walls, props, and creatures standing
+-- between an attacker and a defender.
+
+fixture = {
+  area = "farmhouse01",
+  actors = {
+    { name = "archer", id = "npc_jhilsara", x = 8, y = 13, party = true },
+    { name = "ally", id = "npc_cragnik", x = 8, y = 20, party = true },
+    { name = "goblin", id = "goblin", x = 16, y = 13 },
+  },
+  rules = { "cover" },
+}
+
+function test_clear_line_has_no_cover(t)
+  local archer = t:entity("archer")
+  local goblin = t:entity("goblin")
+  t:assert_eq(archer:cover_of(goblin), "none")
+end
+
+function test_creature_in_the_way_gives_cover(t)
+  local archer = t:entity("archer")
+  local ally = t:entity("ally")
+  local goblin = t:entity("goblin")
+
+  ally:teleport_to({ x = 12, y = 13 })
+  t:assert_eq(archer:cover_of(goblin), "heavy")
+
+  ally:teleport_to({ x = 12, y = 12 })
+  t:assert_eq(archer:cover_of(goblin), "partial")
+end
+
+function test_points_out_of_sight_range_give_no_cover(t)
+  local archer = t:entity("archer")
+  local goblin = t:entity("goblin")
+
+  -- the near half of the goblin is within the area's vision distance of 12
+  goblin:teleport_to({ x = 20, y = 13 })
+  t:assert_eq(archer:cover_of(goblin), "none")
+end
@@ -0,0 +1,32 @@
+-- Script tests for the difficulty presets defined in the rules.
+
+fixture = farmhouse_fixture {
+  actors = {
+    { name = "goblin", id = "goblin", x = 16, y = 13 },
+  },
+}
+
+function test_difficulty_scales_hostile_hp(t)
+  local caster = t:entity("caster")
+  local goblin = t:entity("goblin")
+
+  game:set_difficulty("normal")
+  local goblin_hp = goblin:stats().max_hp
+  local caster_hp = caster:stats().max_hp
+
+  game:set_difficulty("hard")
+  t:assert_eq(game:difficulty().id, "hard")
+  t:assert_eq(goblin:stats().max_hp, math.floor(goblin_hp * 1.25 + 0.5))
+  t:assert_eq(caster:stats().max_hp, caster_hp, "party is not affected")
+
+  game:set_difficulty("normal")
+  t:assert_eq(goblin:stats().max_hp, goblin_hp)
+end
+
+function test_story_disables_injuries(t)
+  game:set_difficulty("story")
+  t:assert_false(game:difficulty().party_injuries)
+
+  game:set_difficulty("normal")
+  t:assert_true(game:difficulty().party_injuries)
+end
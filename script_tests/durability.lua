@@ -0,0 +1,30 @@
+-- Script tests for item durability, breakage, and repair, which is disabled
+-- by default.
+
+fixture = farmhouse_fixture { rules = { "durability" } }
+
+function test_worn_item_tracks_durability(t)
+  local inv = t:entity("caster"):inventory()
+  local stats = inv:equipped_stats("feet")
+  t:assert_eq(stats.durability, stats.max_durability, "starts undamaged")
+
+  inv:wear_equipped("feet", 10)
+  t:assert_eq(inv:equipped_stats("feet").durability, stats.max_durability - 10)
+
+  inv:repair_all()
+  t:assert_eq(inv:equipped_stats("feet").durability, stats.max_durability)
+end
+
+function test_broken_item_grants_no_bonuses(t)
+  local caster = t:entity("caster")
+  local inv = caster:inventory()
+  local armor = caster:stats().base_armor
+  local max = inv:equipped_stats("feet").max_durability
+
+  inv:wear_equipped("feet", max)
+  t:assert_eq(inv:equipped_stats("feet").durability, 0)
+  t:assert_eq(caster:stats().base_armor, armor - 1, "broken boots give no armor")
+
+  inv:repair_all()
+  t:assert_eq(caster:stats().base_armor, armor)
+end
@@ -0,0 +1,15 @@
+-- Script tests for item durability with the durability rules left disabled,
+-- as they are by default.
+
+fixture = farmhouse_fixture()
+
+function test_wear_does_nothing(t)
+  local caster = t:entity("caster")
+  local inv = caster:inventory()
+  local stats = inv:equipped_stats("feet")
+  local armor = caster:stats().base_armor
+
+  inv:wear_equipped("feet", stats.max_durability)
+  t:assert_eq(inv:equipped_stats("feet").durability, stats.max_durability)
+  t:assert_eq(caster:stats().base_armor, armor, "boots are not broken")
+end
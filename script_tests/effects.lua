@@ -0,0 +1,93 @@
+-- Script tests for effect stacking policies.
+
+fixture = {
+  area = "farmhouse01",
+  actors = {
+    { name = "target", id = "npc_cragnik", x = 12, y = 13, party = true },
+  },
+}
+
+local function apply_bonus(target, policy, value, defense, duration)
+  local effect = target:create_effect("Test Bonus", duration)
+  effect:set_tag("test_bonus")
+  effect:set_stacking(policy, value)
+  effect:add_num_bonus("defense", defense)
+  effect:apply()
+end
+
+local function effect_count(target)
+  return #target:get_effects_with_tag("test_bonus")
+end
+
+function test_independent_effects_add_up(t)
+  local target = t:entity("target")
+  local base = target:stats().defense
+
+  apply_bonus(target, "independent", nil, 5, 2)
+  apply_bonus(target, "independent", nil, 5, 2)
+  t:assert_eq(effect_count(target), 2)
+  t:assert_eq(target:stats().defense, base + 10)
+end
+
+function test_refresh_restarts_duration(t)
+  local target = t:entity("target")
+  local base = target:stats().defense
+
+  apply_bonus(target, "refresh", nil, 5, 2)
+  t:advance_rounds(1)
+  apply_bonus(target, "refresh", nil, 5, 2)
+  t:assert_eq(effect_count(target), 1)
+  t:assert_eq(target:stats().defense, base + 5)
+
+  t:advance_rounds(1)
+  t:assert_eq(effect_count(target), 1, "refreshed effect outlasts the first")
+  t:advance_rounds(2)
+  t:assert_eq(effect_count(target), 0)
+end
+
+function test_refresh_resets_longer_duration(t)
+  local target = t:entity("target")
+
+  apply_bonus(target, "refresh", nil, 5, 4)
+  apply_bonus(target, "refresh", nil, 5, 1)
+  t:assert_eq(effect_count(target), 1)
+
+  t:advance_rounds(2)
+  t:assert_eq(effect_count(target), 0, "the shorter refresh replaces the duration")
+end
+
+function test_unknown_policy_errors(t)
+  local target = t:entity("target")
+  local effect = target:create_effect("Test Bonus", 2)
+  effect:set_tag("test_bonus")
+
+  local ok, err = pcall(function() effect:set_stacking("sometimes") end)
+  t:assert_true(not ok, "an unknown policy is an error")
+  t:assert_true(string.find(tostring(err), "sometimes", 1, true) ~= nil)
+end
+
+function test_stacks_up_to_max(t)
+  local target = t:entity("target")
+  local base = target:stats().defense
+
+  for i = 1, 4 do
+    apply_bonus(target, "stack", 3, 5, 2)
+  end
+  t:assert_eq(effect_count(target), 1)
+  t:assert_eq(target:get_effects_with_tag("test_bonus")[1]:stacks(), 3)
+  t:assert_eq(target:stats().defense, base + 15)
+end
+
+function test_replace_if_stronger(t)
+  local target = t:entity("target")
+  local base = target:stats().defense
+
+  apply_bonus(target, "replace_if_stronger", 2, 10, 2)
+  apply_bonus(target, "replace_if_stronger", 1, 5, 2)
+  t:assert_eq(target:stats().defense, base + 10, "weaker effect is discarded")
+
+  apply_bonus(target, "replace_if_stronger", 3, 20, 2)
+  t:assert_eq(target:stats().defense, base + 20)
+  t:update(100)
+  t:assert_eq(effect_count(target), 1)
+end
@@ -0,0 +1,37 @@
+-- Script tests for the accuracy and ranged reach gained by attacking from
+-- higher ground.  The plateau around 40,58 is two levels above the ground
+-- to the west.
+
+fixture = {
+  area = "vezula_hills",
+  actors = {
+    { name = "archer", id = "npc_jhilsara", x = 40, y = 58, party = true },
+    { name = "ally", id = "npc_cragnik", x = 28, y = 60, party = true },
+  },
+  rules = { "elevation" },
+}
+
+function test_elevation_in_stats(t)
+  t:assert_eq(t:entity("archer"):stats().elevation, 2)
+  t:assert_eq(t:entity("ally"):stats().elevation, 0)
+end
+
+function test_high_ground_accuracy(t)
+  local archer = t:entity("archer")
+  local ally = t:entity("ally")
+  t:assert_eq(archer:elevation_accuracy_bonus(ally), 10, "two levels above")
+  t:assert_eq(ally:elevation_accuracy_bonus(archer), -10, "two levels below")
+end
+
+function test_high_ground_ranged_distance(t)
+  local archer = t:entity("archer")
+  local ally = t:entity("ally")
+  local level_dist = archer:attack_distance_to(archer)
+  t:assert_eq(archer:attack_distance_to(ally), level_dist + 4.0, "reach from above")
+  t:assert_eq(ally:attack_distance_to(archer), ally:attack_distance_to(ally),
+    "no change from below")
+
+  ally:teleport_to({ x = 44, y = 58 })
+  t:assert_eq(archer:attack_distance_to(ally), level_dist, "same level")
+  t:assert_eq(archer:elevation_accuracy_bonus(ally), 0)
+end
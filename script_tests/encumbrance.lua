@@ -0,0 +1,40 @@
+-- Script tests for carrying capacity and encumbrance, which is disabled by
+-- default.  The caster is the only party member, so carries the whole party
+-- stash.
+
+fixture = farmhouse_fixture { rules = { "encumbrance" } }
+
+function test_stash_weight_encumbers_party(t)
+  local caster = t:entity("caster")
+  local stats = caster:stats()
+  t:assert_eq(stats.encumbrance, nil, "not encumbered to start")
+  local movement_rate = stats.movement_rate
+
+  local items = {}
+  while caster:stats().load <= caster:stats().carry_capacity do
+    table.insert(items, game:add_party_item("torso_plate"))
+    t:update(16)
+  end
+
+  stats = caster:stats()
+  t:assert_eq(stats.encumbrance, "Burdened")
+  t:assert_true(stats.movement_rate < movement_rate, "movement rate is reduced")
+
+  for _, item in ipairs(items) do
+    game:remove_party_item(item)
+  end
+  t:update(16)
+  t:assert_eq(caster:stats().encumbrance, nil)
+  t:assert_eq(caster:stats().movement_rate, movement_rate)
+end
+
+function test_carry_capacity_bonus(t)
+  local caster = t:entity("caster")
+  local capacity = caster:stats().carry_capacity
+
+  local effect = caster:create_effect("Mule")
+  effect:add_num_bonus("carry_capacity", 1000)
+  effect:apply()
+
+  t:assert_eq(caster:stats().carry_capacity, capacity + 1000)
+end
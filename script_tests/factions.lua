@@ -0,0 +1,58 @@
+-- Script tests for faction relationships and the party's reputation.  The
+-- farmer belongs to the built in Neutral faction, which has no relationship
+-- with the party other than its reputation, and is given an AI so that it
+-- fights the party once hostile.
+
+fixture = farmhouse_fixture {
+  actors = {
+    { name = "farmer", id = "farmer01", x = 14, y = 13, ai = "ai_melee" },
+  },
+}
+
+local function raises(func)
+  return not pcall(func)
+end
+
+function test_thresholds(t)
+  local caster = t:entity("caster")
+  local farmer = t:entity("farmer")
+  t:assert_eq(game:faction_relationship("Friendly", "Neutral"), 0)
+  t:assert_false(caster:is_hostile(farmer))
+  t:assert_false(caster:is_friendly(farmer))
+
+  game:set_reputation("Neutral", 49)
+  t:assert_false(caster:is_friendly(farmer), "just below the friendly threshold")
+  game:set_reputation("Neutral", 50)
+  t:assert_true(caster:is_friendly(farmer))
+  t:assert_true(farmer:is_friendly(caster), "relationships are symmetric")
+
+  game:set_reputation("Neutral", -49)
+  t:assert_false(caster:is_hostile(farmer), "just above the hostile threshold")
+  t:assert_false(game:is_combat_active())
+
+  game:add_reputation("Neutral", -1)
+  t:assert_eq(game:faction_relationship("Neutral", "Friendly"), -50)
+  t:assert_true(caster:is_hostile(farmer))
+  t:assert_true(game:is_combat_active(), "the farmer sees the party and attacks")
+end
+
+function test_persists_on_load(t)
+  local farmer_id = t:entity("farmer"):id()
+  game:set_reputation("Neutral", 60)
+
+  t:save_and_load()
+  t:assert_eq(game:reputation("Neutral"), 60)
+  t:assert_true(game:player():is_friendly(game:entity_with_id(farmer_id)))
+end
+
+function test_unknown_faction_rejected(t)
+  local farmer = t:entity("farmer")
+
+  t:assert_true(raises(function() game:reputation("no_such_faction") end))
+  t:assert_true(raises(function() game:set_reputation("no_such_faction", 10) end))
+  t:assert_true(raises(function() game:add_reputation("no_such_faction", 10) end))
+  t:assert_true(raises(function() game:faction_relationship("Neutral", "no_such_faction") end))
+
+  farmer:set_faction("no_such_faction")
+  t:assert_eq(farmer:get_faction(), "Neutral", "faction is unchanged")
+end
@@ -0,0 +1,12 @@
+-- Script tests for the test harness itself.
+
+fixture = farmhouse_fixture()
+
+function test_game_script_error_fails_test(t)
+  t:expect_failure()
+
+  -- the campaign script has no such function, so calling it is an error in
+  -- a game script rather than in this test
+  game:run_script_delayed("campaign", "no_such_function", 0.0)
+  t:update(100)
+end
@@ -0,0 +1,72 @@
+-- Script tests for party members placed under AI control, either with an AI
+-- mode of their own or with auto-combat.
+
+fixture = {
+  area = "farmhouse01",
+  actors = {
+    { name = "caster", id = "npc_jhilsara", x = 8, y = 13, party = true },
+    { name = "ally", id = "npc_vaalyun", x = 10, y = 13, party = true },
+    { name = "goblin", id = "goblin", x = 16, y = 13 },
+  },
+}
+
+function test_set_mode(t)
+  local ally = t:entity("ally")
+  t:assert_true(ally:party_ai_mode() == nil)
+
+  ally:set_party_ai_mode("Defensive")
+  t:assert_eq(ally:party_ai_mode(), "Defensive")
+
+  ally:set_party_ai_mode()
+  t:assert_true(ally:party_ai_mode() == nil)
+
+  t:entity("goblin"):set_party_ai_mode("Aggressive")
+  t:assert_true(t:entity("goblin"):party_ai_mode() == nil, "not a party member")
+end
+
+function test_aggressive_attacks(t)
+  local ally = t:entity("ally")
+  local goblin = t:entity("goblin")
+  t:assert_false(ally:is_within_attack_dist(goblin))
+
+  ally:set_party_ai_mode("Aggressive")
+  t:run_turn(ally)
+  t:assert_true(ally:is_within_attack_dist(goblin), "ally closes to melee")
+  t:assert_true(ally:stats().current_ap < ally:stats().attack_cost, "AP is spent")
+end
+
+function test_defensive_holds_position(t)
+  local ally = t:entity("ally")
+  local x, y = ally:location().x, ally:location().y
+
+  ally:set_party_ai_mode("Defensive")
+  t:run_turn(ally)
+  t:assert_eq(ally:location().x, x)
+  t:assert_eq(ally:location().y, y)
+end
+
+function test_passive_ends_turn(t)
+  local ally = t:entity("ally")
+  local goblin = t:entity("goblin")
+  local x, y = ally:location().x, ally:location().y
+  local hp = goblin:stats().current_hp
+  ally:set_party_ai_mode("Passive")
+
+  t:run_turn(ally)
+  t:assert_eq(ally:location().x, x)
+  t:assert_eq(ally:location().y, y)
+  t:assert_eq(goblin:stats().current_hp, hp)
+end
+
+function test_auto_combat(t)
+  local ally = t:entity("ally")
+  local goblin = t:entity("goblin")
+  local dist = ally:dist_to_entity(goblin)
+
+  game:set_auto_combat(true)
+  t:assert_true(game:is_auto_combat())
+  t:run_turn(ally)
+  game:set_auto_combat(false)
+
+  t:assert_true(ally:dist_to_entity(goblin) < dist, "ally uses the default mode")
+end
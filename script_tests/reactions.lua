@@ -0,0 +1,72 @@
+-- Script tests for reactions, such as attacks of opportunity.  The goblin is
+-- in view of the party, so combat starts as soon as the fixture is built.
+
+fixture = {
+  area = "farmhouse01",
+  actors = {
+    { name = "caster", id = "npc_jhilsara", x = 8, y = 13, party = true },
+    { name = "ally", id = "npc_cragnik", x = 13, y = 13, party = true },
+    { name = "goblin", id = "goblin", x = 16, y = 13 },
+  },
+  rules = { "reactions" },
+}
+
+-- moves the ally next to the goblin, so that the goblin threatens it
+local function engage(ally, goblin, t)
+  ally:teleport_to({ x = 14, y = 13 })
+  t:assert_true(ally:is_threatened_by(goblin), "goblin threatens ally")
+  t:assert_eq(goblin:reactions_remaining(), 1)
+end
+
+function test_moving_away_provokes(t)
+  local ally = t:entity("ally")
+  local goblin = t:entity("goblin")
+  engage(ally, goblin, t)
+
+  ally:add_ap(4000)
+  t:assert_true(ally:move_towards_point(10, 13))
+  t:wait_for_animations()
+  t:update(500)
+  t:wait_for_animations()
+  t:assert_eq(goblin:reactions_remaining(), 0, "goblin reacted")
+end
+
+function test_teleport_does_not_provoke(t)
+  local ally = t:entity("ally")
+  local goblin = t:entity("goblin")
+  engage(ally, goblin, t)
+
+  ally:teleport_to({ x = 10, y = 13 })
+  t:update(500)
+  t:assert_eq(goblin:reactions_remaining(), 1)
+end
+
+function test_reactions_reset_each_round(t)
+  local ally = t:entity("ally")
+  local goblin = t:entity("goblin")
+  engage(ally, goblin, t)
+
+  ally:provoke_reactions()
+  t:update(100)
+  t:wait_for_animations()
+  t:assert_eq(goblin:reactions_remaining(), 0, "goblin reacted")
+
+  t:advance_rounds(1)
+  t:assert_eq(goblin:reactions_remaining(), 1)
+end
+
+function test_no_reaction_without_melee_attack(t)
+  local ally = t:entity("ally")
+  local goblin = t:entity("goblin")
+  engage(ally, goblin, t)
+
+  local effect = goblin:create_effect("Disarmed")
+  effect:add_attack_disabled()
+  effect:apply()
+  t:assert_true(ally:is_threatened_by(goblin), "goblin still threatens ally")
+
+  ally:provoke_reactions()
+  t:update(100)
+  t:wait_for_animations()
+  t:assert_eq(goblin:reactions_remaining(), 1, "reaction was not used")
+end
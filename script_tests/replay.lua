@@ -0,0 +1,63 @@
+-- Script tests for recording a replay and playing it back.  The goblin is in
+-- view of the party, but the fixture leaves its AI inactive, so combat starts
+-- inside the recording, as replays can only start out of combat.
+
+fixture = farmhouse_fixture {
+  activate_ai = false,
+  actors = {
+    { name = "goblin", id = "goblin", x = 16, y = 13 },
+  },
+}
+
+local function snapshot(ids)
+  local result = {}
+  for _, id in ipairs(ids) do
+    local entity = game:entity_with_id(id)
+    result[id] = {
+      hp = entity:stats().current_hp,
+      x = entity:location().x,
+      y = entity:location().y,
+    }
+  end
+  return result
+end
+
+function test_round_trip(t)
+  local goblin = t:entity("goblin")
+  local ids = { t:entity("caster"):id(), goblin:id() }
+
+  t:record_replay()
+  t:activate_ai()
+  t:assert_true(game:is_combat_active())
+  t:run_turn(goblin)
+  t:run_turn(goblin)
+  local round = game:current_round()
+  local recorded = snapshot(ids)
+  t:assert_eq(recorded[goblin:id()].x, 12, "the goblin moved to attack")
+  t:assert_eq(recorded[goblin:id()].y, 14, "the goblin moved to attack")
+
+  t:play_replay()
+  t:assert_eq(game:current_round(), round)
+  local played = snapshot(ids)
+  for _, id in ipairs(ids) do
+    t:assert_eq(played[id].hp, recorded[id].hp, id .. " hp")
+    t:assert_eq(played[id].x, recorded[id].x, id .. " x")
+    t:assert_eq(played[id].y, recorded[id].y, id .. " y")
+  end
+end
+
+function test_rounds_replayed(t)
+  t:record_replay()
+  t:activate_ai()
+  t:advance_rounds(2)
+  local round = game:current_round()
+
+  t:play_replay()
+  t:assert_eq(game:current_round(), round)
+end
+
+function test_no_recording_in_combat(t)
+  t:activate_ai()
+  local ok = pcall(function() t:record_replay() end)
+  t:assert_false(ok, "recording is an error during combat")
+end
@@ -0,0 +1,52 @@
+-- Script tests for saving throws rolled against a DC.  The outcome margins
+-- come from the saving_throws section of the rules.
+
+fixture = farmhouse_fixture {
+  actors = {
+    { name = "goblin", id = "goblin", x = 16, y = 13 },
+  },
+}
+
+function test_extreme_dcs(t)
+  local goblin = t:entity("goblin")
+
+  local save = goblin:roll_save("reflex", -1000)
+  t:assert_true(save:is_crit_success())
+  t:assert_true(save:is_success(), "a critical success is also a success")
+
+  save = goblin:roll_save("will", 1000)
+  t:assert_true(save:is_fail())
+  t:assert_eq(save:result(), "fail")
+  t:assert_eq(save:kind(), "will")
+end
+
+function test_save_uses_defense(t)
+  local goblin = t:entity("goblin")
+  local fortitude = goblin:stats().fortitude
+  local dc = fortitude + 50
+
+  for i = 1, 20 do
+    local save = goblin:roll_save("fortitude", dc)
+    t:assert_eq(save:total(), save:roll() + fortitude)
+    t:assert_eq(save:kind(), "fortitude")
+
+    local margin = save:total() - dc
+    local expected = "fail"
+    if margin >= 50 then
+      expected = "crit_success"
+    elseif margin >= 0 then
+      expected = "success"
+    elseif margin >= -20 then
+      expected = "partial"
+    end
+    t:assert_eq(save:result(), expected)
+  end
+end
+
+function test_unknown_kind_errors(t)
+  local goblin = t:entity("goblin")
+
+  local ok, err = pcall(function() goblin:roll_save("luck", 10) end)
+  t:assert_true(not ok, "an unknown save kind is an error")
+  t:assert_true(string.find(tostring(err), "luck", 1, true) ~= nil)
+end
@@ -0,0 +1,70 @@
+-- Script tests for the schedules followed by entities outside the party when
+-- combat is inactive.  The villager is friendly, so combat never starts.
+
+fixture = farmhouse_fixture {
+  actors = {
+    { name = "villager", id = "npc_cragnik", x = 14, y = 13 },
+  },
+}
+
+-- the harness runs the game in fixed frames, so the villager is always at
+-- the same position after running schedules for the same time
+local function assert_at(t, entity, x, y, message)
+  t:assert_eq(entity:x(), x, message)
+  t:assert_eq(entity:y(), y, message)
+end
+
+function test_no_schedule(t)
+  local villager = t:entity("villager")
+  t:assert_false(villager:has_schedule())
+  t:assert_true(villager:schedule_dest() == nil)
+
+  t:run_schedules(1000)
+  assert_at(t, villager, 14, 13)
+end
+
+function test_walks_route(t)
+  local villager = t:entity("villager")
+  villager:set_schedule({ { x = 20, y = 13, wait = 1 }, { x = 14, y = 13 } }, "Once")
+  t:assert_true(villager:has_schedule())
+
+  t:run_schedules(1000)
+  t:assert_false(game:is_combat_active())
+  t:assert_eq(villager:schedule_dest().x, 20)
+  assert_at(t, villager, 19, 14, "reached the first waypoint")
+
+  -- waits for a round at the first waypoint before moving on
+  t:run_schedules(4000)
+  t:assert_eq(villager:schedule_dest().x, 20)
+  assert_at(t, villager, 19, 14, "waiting at the first waypoint")
+
+  t:run_schedules(1000)
+  t:assert_eq(villager:schedule_dest().x, 14)
+  assert_at(t, villager, 15, 14, "reached the last waypoint")
+end
+
+function test_hold(t)
+  local villager = t:entity("villager")
+  villager:set_schedule({ { x = 20, y = 13 } })
+  villager:hold_schedule(1)
+
+  t:run_schedules(1000)
+  assert_at(t, villager, 14, 13, "held in place")
+
+  villager:release_schedule()
+  t:run_schedules(1000)
+  assert_at(t, villager, 19, 14, "moved once released")
+
+  villager:clear_schedule()
+  t:assert_false(villager:has_schedule())
+end
+
+function test_walks_without_ap(t)
+  local villager = t:entity("villager")
+  villager:remove_ap(villager:stats().current_ap)
+  villager:set_schedule({ { x = 20, y = 13 } })
+
+  t:run_schedules(1000)
+  assert_at(t, villager, 19, 14, "moved with no AP")
+  t:assert_eq(villager:stats().current_ap, 0, "movement out of combat is free")
+end
@@ -0,0 +1,84 @@
+-- Script tests for detecting hidden entities.  The goblin is in view of the
+-- party, and checks its perception against the stealth of hidden party members.
+
+fixture = farmhouse_fixture {
+  actors = {
+    { name = "goblin", id = "goblin", x = 16, y = 13 },
+  },
+}
+
+local function hide(entity, stealth)
+  local effect = entity:create_effect("Hidden")
+  effect:add_hidden()
+  effect:add_num_bonus("stealth", stealth)
+  effect:apply()
+end
+
+function test_stealth_adds_to_margin(t)
+  local caster = t:entity("caster")
+  local goblin = t:entity("goblin")
+  local margin = caster:detection_margin(goblin)
+
+  hide(caster, 30)
+  t:assert_eq(caster:stats().stealth, 30)
+  t:assert_eq(caster:detection_margin(goblin), margin + 30)
+end
+
+function test_detected_entity_is_revealed(t)
+  local caster = t:entity("caster")
+  local goblin = t:entity("goblin")
+
+  hide(caster, 0)
+  t:assert_true(caster:stats().is_hidden)
+  t:assert_true(caster:detection_margin(goblin) < 0, "goblin notices caster")
+
+  t:assert_true(caster:check_detection())
+  t:update(16)
+  t:assert_false(caster:stats().is_hidden)
+end
+
+function test_high_stealth_stays_hidden(t)
+  local caster = t:entity("caster")
+
+  hide(caster, 1000)
+  t:assert_false(caster:check_detection())
+  t:advance_rounds(1)
+  t:assert_true(caster:stats().is_hidden)
+end
+
+function test_search_detects(t)
+  local caster = t:entity("caster")
+  local goblin = t:entity("goblin")
+
+  -- just enough stealth to remain hidden from the goblin until it searches
+  hide(caster, 1 - caster:detection_margin(goblin))
+  t:assert_eq(caster:detection_margin(goblin), 1)
+  t:assert_false(caster:check_detection())
+
+  goblin:search()
+  t:assert_true(goblin:has_effect_with_tag("searching"))
+  t:update(16)
+  t:assert_false(caster:stats().is_hidden)
+end
+
+function test_detection_ends_hide(t)
+  local caster = t:entity("caster")
+  local goblin = t:entity("goblin")
+  caster:add_ability("hide")
+  local ability = caster:get_ability("hide")
+
+  local cloak = caster:create_effect("Cloak")
+  cloak:add_num_bonus("stealth", 30)
+  cloak:apply()
+
+  caster:add_ap(4000)
+  t:assert_true(caster:use_ability(ability, true))
+  t:wait_for_animations()
+  t:update(16)
+  t:assert_true(ability:is_active_mode(caster), "hidden from the goblin")
+
+  goblin:search()
+  t:update(16)
+  t:assert_false(caster:stats().is_hidden)
+  t:assert_false(ability:is_active_mode(caster), "on_detected ends hide")
+end
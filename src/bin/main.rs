@@ -22,12 +22,15 @@ use std::cell::RefCell;
 
 use log::{error, info};
 
+use sulis_core::config::Config;
 use sulis_core::resource::ResourceSet;
-use sulis_core::io::{DisplayConfiguration, System, ControlFlowUpdater};
+use sulis_core::io::{
+    ControlFlowUpdater, DisplayConfiguration, HeadlessScript, InputAction, InputActionKind, System,
+};
 use sulis_core::ui::{self, Cursor, Widget};
 use sulis_core::util::{self, ActiveResources};
-use sulis_module::{Actor, Module};
-use sulis_state::{GameState, NextGameStep, SaveState};
+use sulis_module::{Actor, Difficulty, Module};
+use sulis_state::{GameState, NextGameStep, Replay, ReplayRecorder, SaveState};
 use sulis_view::{main_menu::{self, MainMenu}, RootView, trigger_activator};
 
 struct GameControlFlowUpdater {
@@ -39,6 +42,10 @@ struct GameControlFlowUpdater {
     exit: bool,
 
     next_step: Option<NextGameStep>,
+
+    recorder: Option<ReplayRecorder>,
+    replay: Option<Replay>,
+    exit_after_replay: bool,
 }
 
 #[derive(Clone)]
@@ -53,6 +60,18 @@ impl ControlFlowUpdater for GameControlFlowUpdater {
             self.handle_next_step(step);
         }
 
+        let millis = self.play_replay_frame(millis);
+        if let Some(recorder) = self.recorder.as_mut() {
+            if let Err(e) = recorder.record_frame(millis) {
+                error!(
+                    "Unable to write to replay {:?}, stopping recording.",
+                    recorder.path()
+                );
+                error!("{}", e);
+                self.recorder = None;
+            }
+        }
+
         self.update_mode(millis);
 
         if let Err(e) = Widget::update(&self.root, millis) {
@@ -65,6 +84,21 @@ impl ControlFlowUpdater for GameControlFlowUpdater {
         self.root()
     }
 
+    fn handle_input(&mut self, action: InputAction) {
+        if self.replay.is_some() {
+            // only allow the player to quit while a replay is running
+            if !matches!(action.kind, InputActionKind::Exit) {
+                return;
+            }
+        }
+
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record_input(action);
+        }
+
+        action.handle(&self.root);
+    }
+
     fn recreate_window(&mut self) -> bool {
         let recreate = self.recreate_window;
         self.recreate_window = false;
@@ -96,9 +130,72 @@ impl GameControlFlowUpdater {
             mode: UiMode::MainMenu(view),
             exit: false,
             next_step: None,
+            recorder: None,
+            replay: None,
+            exit_after_replay: false,
         }
     }
 
+    fn start_game_view(&mut self) {
+        let view = RootView::new();
+        self.root = ui::create_ui_tree(view.clone());
+        self.mode = UiMode::Game(view);
+    }
+
+    fn start_recording(&mut self) {
+        if !Config::debug().record_replay {
+            return;
+        }
+
+        match ReplayRecorder::start(ActiveResources::read()) {
+            Ok(recorder) => self.recorder = Some(recorder),
+            Err(e) => {
+                error!("Unable to start recording a replay.");
+                error!("{}", e);
+            }
+        }
+    }
+
+    fn start_replay(&mut self, mut replay: Replay, exit_after_replay: bool) {
+        info!("Starting replay.");
+        if let Err(e) = replay.start() {
+            error!("{}", e);
+            util::error_and_exit("There was a fatal error loading the replay.");
+        }
+
+        self.start_game_view();
+        self.replay = Some(replay);
+        self.exit_after_replay = exit_after_replay;
+    }
+
+    /// Dispatches the inputs of the next replay frame, if a replay is
+    /// running, and returns the elapsed millis recorded for that frame
+    fn play_replay_frame(&mut self, millis: u32) -> u32 {
+        let replay = match self.replay.as_mut() {
+            None => return millis,
+            Some(replay) => replay,
+        };
+
+        let frame = replay.next_frame();
+        if replay.is_complete() {
+            info!("Replay complete.");
+            self.replay = None;
+            if self.exit_after_replay {
+                self.exit = true;
+            }
+        }
+
+        let frame = match frame {
+            None => return millis,
+            Some(frame) => frame,
+        };
+
+        for action in frame.inputs {
+            action.handle(&self.root);
+        }
+        frame.millis
+    }
+
     fn main_menu(&mut self) {
         let view = main_menu::MainMenu::new(
             self.display_configurations.clone(),
@@ -108,16 +205,21 @@ impl GameControlFlowUpdater {
         self.mode = UiMode::MainMenu(view);
     }
 
-    fn new_campaign(&mut self, pc_actor: Rc<Actor>, party_actors: Vec<Rc<Actor>>, flags: HashMap<String, String>) {
+    fn new_campaign(
+        &mut self,
+        pc_actor: Rc<Actor>,
+        party_actors: Vec<Rc<Actor>>,
+        flags: HashMap<String, String>,
+        difficulty: Option<Difficulty>,
+    ) {
         info!("Initializing game state.");
-        if let Err(e) = GameState::init(pc_actor, party_actors, flags) {
+        if let Err(e) = GameState::init(pc_actor, party_actors, flags, difficulty) {
             error!("{}", e);
             util::error_and_exit("There was a fatal error creating the game state.");
         };
 
-        let view = RootView::new();
-        self.root = ui::create_ui_tree(view.clone());
-        self.mode = UiMode::Game(view);
+        self.start_game_view();
+        self.start_recording();
     }
 
     fn load_campaign(&mut self, save_state: SaveState) {
@@ -127,30 +229,36 @@ impl GameControlFlowUpdater {
             util::error_and_exit("There was a fatal error loading the game state.");
         };
 
-        let view = RootView::new();
-        self.root = ui::create_ui_tree(view.clone());
-        self.mode = UiMode::Game(view);
+        self.start_game_view();
+        self.start_recording();
     }
 
     fn handle_next_step(&mut self, step: NextGameStep) {
+        // any step ends the current session and its recording
+        self.recorder = None;
+
         use NextGameStep::*;
         match step {
             Exit => {
                 self.exit = true;
-            }, NewCampaign { pc_actor } => {
-                self.new_campaign(pc_actor, Vec::new(), HashMap::new());
+            }, NewCampaign { pc_actor, difficulty } => {
+                self.new_campaign(pc_actor, Vec::new(), HashMap::new(), Some(difficulty));
             }, LoadCampaign { save_state } => {
                 self.load_campaign(*save_state);
             }, LoadModuleAndNewCampaign { pc_actor, party_actors, flags, module_dir } => {
+                let difficulty_id = GameState::difficulty().id;
                 let mut active = ActiveResources::read();
                 active.campaign = Some(module_dir);
                 active.write();
-                load_resources();
-                self.new_campaign(pc_actor, party_actors, flags);
+                load_resources(&active);
+
+                // the party keeps its difficulty when moving on to the next module
+                let difficulty = Module::rules().difficulty(&difficulty_id).cloned();
+                self.new_campaign(pc_actor, party_actors, flags, difficulty);
             }, MainMenu => {
                 self.main_menu();
             }, MainMenuReloadResources => {
-                load_resources();
+                load_resources(&ActiveResources::read());
                 self.main_menu();
             }, RecreateIO => {
                 self.recreate_window = true;
@@ -183,6 +291,18 @@ impl GameControlFlowUpdater {
 fn create_io() -> System {
     Cursor::update_max();
 
+    match HeadlessScript::from_args() {
+        Err(e) => {
+            error!("{}", e);
+            util::error_and_exit("Unable to read the headless script.");
+        }
+        Ok(Some(script)) => {
+            info!("Setting up headless display adapter.");
+            return System::create_headless(script);
+        }
+        Ok(None) => (),
+    }
+
     info!("Setting up display adapter.");
     match System::create() {
         Ok(system) => system,
@@ -194,11 +314,9 @@ fn create_io() -> System {
     }
 }
 
-fn load_resources() {
+fn load_resources(active: &ActiveResources) {
     let start = std::time::Instant::now();
 
-    let active = ActiveResources::read();
-
     let dirs = active.directories();
 
     let start_main = std::time::Instant::now();
@@ -231,10 +349,25 @@ fn main() {
     info!("=========Initializing=========");
     info!("Setup Logger and read configuration from 'config.yml'");
 
-    load_resources();
+    let replay = match Replay::from_args() {
+        Err(e) => {
+            error!("{}", e);
+            util::error_and_exit("Unable to read the replay file.");
+            unreachable!();
+        }
+        Ok(replay) => replay,
+    };
+
+    match replay {
+        Some(ref replay) => load_resources(replay.resources()),
+        None => load_resources(&ActiveResources::read()),
+    }
 
     let system = create_io();
 
-    let flow_controller = GameControlFlowUpdater::new(&system);
+    let mut flow_controller = GameControlFlowUpdater::new(&system);
+    if let Some(replay) = replay {
+        flow_controller.start_replay(replay, system.is_headless());
+    }
     system.main_loop(Box::new(flow_controller));
 }
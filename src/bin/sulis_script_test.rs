@@ -0,0 +1,178 @@
+//  This file is part of Sulis, a turn based RPG written in Rust.
+//  Copyright 2018 Jared Stephen
+//
+//  Sulis is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Sulis is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Sulis.  If not, see <http://www.gnu.org/licenses/>
+
+//! Loads a campaign and any mods without creating a window, runs Lua script
+//! tests against it, and prints a JUnit XML report to stdout.
+//!
+//! Usage: `sulis_script_test [--campaign <dir>] [--mod <dir>]... <test file or dir>...`
+//!
+//! If no campaign is specified, the currently active campaign and mods are
+//! used.  Directories are searched for `.lua` test files.  Exits with a
+//! non-zero status if any test failed.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use sulis_core::resource::ResourceSet;
+use sulis_core::util::ActiveResources;
+use sulis_module::Module;
+use sulis_state::ScriptTestReport;
+
+/// Writes warnings and errors to stderr, keeping stdout for the report
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Warn
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("{} [{}] {}", record.level(), record.target(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+struct Args {
+    resources: ActiveResources,
+    paths: Vec<PathBuf>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut campaign = None;
+    let mut mods = Vec::new();
+    let mut paths = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--campaign" => match args.next() {
+                None => return Err("--campaign requires a directory".to_string()),
+                Some(dir) => campaign = Some(dir),
+            },
+            "--mod" => match args.next() {
+                None => return Err("--mod requires a directory".to_string()),
+                Some(dir) => mods.push(dir),
+            },
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+
+    if paths.is_empty() {
+        return Err("No test files specified".to_string());
+    }
+
+    let resources = match campaign {
+        None => ActiveResources::read(),
+        Some(campaign) => ActiveResources {
+            campaign: Some(campaign),
+            mods,
+        },
+    };
+
+    Ok(Args { resources, paths })
+}
+
+fn find_test_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let entries = fs::read_dir(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut found = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("{}: {}", path.display(), e))?;
+        let path = entry.path();
+        if path.is_dir() || path.extension().is_some_and(|ext| ext == "lua") {
+            found.push(path);
+        }
+    }
+
+    found.sort();
+    for path in found {
+        find_test_files(&path, files)?;
+    }
+    Ok(())
+}
+
+fn load_module(resources: &ActiveResources) -> Result<(), String> {
+    let dirs = resources.directories();
+    if dirs.len() < 2 {
+        return Err("No campaign directory specified".to_string());
+    }
+
+    let yaml = ResourceSet::load_resources(dirs.clone()).map_err(|e| format!("{}", e))?;
+    Module::load_resources(yaml, dirs).map_err(|e| format!("{}", e))
+}
+
+fn main() {
+    if log::set_logger(&StderrLogger).is_ok() {
+        log::set_max_level(LevelFilter::Warn);
+    }
+
+    let args = match parse_args() {
+        Err(e) => {
+            eprintln!("{}", e);
+            eprintln!(
+                "Usage: sulis_script_test [--campaign <dir>] [--mod <dir>]... <test file or dir>..."
+            );
+            process::exit(2);
+        }
+        Ok(args) => args,
+    };
+
+    let mut files = Vec::new();
+    for path in args.paths.iter() {
+        if let Err(e) = find_test_files(path, &mut files) {
+            eprintln!("Unable to read test files: {}", e);
+            process::exit(2);
+        }
+    }
+
+    if let Err(e) = load_module(&args.resources) {
+        eprintln!("Unable to load module: {}", e);
+        process::exit(2);
+    }
+
+    let mut report = ScriptTestReport::default();
+    for file in files.iter() {
+        report.run_file(file);
+    }
+
+    let mut xml = String::new();
+    if let Err(e) = report.write_junit_xml(&mut xml) {
+        eprintln!("Unable to write test report: {}", e);
+        process::exit(2);
+    }
+    print!("{}", xml);
+
+    eprintln!(
+        "{} tests, {} failures, {} errors",
+        report.tests(),
+        report.failures(),
+        report.errors()
+    );
+
+    if !report.is_success() {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,120 @@
+//  This file is part of Sulis, a turn based RPG written in Rust.
+//  Copyright 2018 Jared Stephen
+//
+//  Sulis is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Sulis is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Sulis.  If not, see <http://www.gnu.org/licenses/>
+
+//! Loads a campaign and any mods without creating a window, checks all
+//! cross references and scripts, and prints a JSON report to stdout.
+//!
+//! Usage: `sulis_validate [<campaign dir> [<mod dir>...]]`
+//!
+//! If no directories are specified, the currently active campaign and mods
+//! are used.  Exits with a non-zero status if any errors were found.
+
+use std::sync::Mutex;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use sulis_core::resource::ResourceSet;
+use sulis_core::serde_json;
+use sulis_core::util::ActiveResources;
+use sulis_module::Module;
+use sulis_state::ValidationReport;
+
+static LOG_WARNINGS: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+/// Collects warnings logged while loading, as these contain the details
+/// of why a given resource failed to load.
+struct WarningCollector;
+
+impl Log for WarningCollector {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Warn
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let mut warnings = LOG_WARNINGS.lock().unwrap();
+        warnings.push((record.target().to_string(), format!("{}", record.args())));
+    }
+
+    fn flush(&self) {}
+}
+
+fn active_resources() -> ActiveResources {
+    let mut args = std::env::args().skip(1);
+    match args.next() {
+        None => ActiveResources::read(),
+        Some(campaign) => ActiveResources {
+            campaign: Some(campaign),
+            mods: args.collect(),
+        },
+    }
+}
+
+fn validate(report: &mut ValidationReport) {
+    let dirs = report.dirs.clone();
+    if dirs.len() < 2 {
+        report.error(
+            "campaign",
+            "",
+            "No campaign directory specified".to_string(),
+        );
+        return;
+    }
+
+    let yaml = match ResourceSet::load_resources(dirs.clone()) {
+        Err(e) => {
+            report.error("resources", &dirs[0], format!("{}", e));
+            return;
+        }
+        Ok(yaml) => yaml,
+    };
+
+    let campaign_dir = dirs[1].to_string();
+    if let Err(e) = Module::load_resources(yaml, dirs) {
+        report.error("campaign", &campaign_dir, format!("{}", e));
+        return;
+    }
+
+    report.validate_module();
+}
+
+fn main() {
+    if log::set_logger(&WarningCollector).is_ok() {
+        log::set_max_level(LevelFilter::Warn);
+    }
+
+    let mut report = ValidationReport::new(active_resources().directories());
+    validate(&mut report);
+
+    for (target, message) in LOG_WARNINGS.lock().unwrap().drain(..) {
+        report.warning("log", &target, message);
+    }
+
+    match serde_json::to_string_pretty(&report) {
+        Ok(json) => println!("{}", json),
+        Err(e) => {
+            eprintln!("Unable to serialize validation report: {}", e);
+            std::process::exit(2);
+        }
+    }
+
+    if report.has_errors() {
+        std::process::exit(1);
+    }
+}
@@ -48,6 +48,9 @@ pub struct Config {
     pub logging: LoggingConfig,
     pub editor: EditorConfig,
 
+    #[serde(default)]
+    pub autosave: AutosaveConfig,
+
     #[serde(default)]
     pub debug: DebugConfig,
 }
@@ -125,6 +128,10 @@ impl Config {
         CONFIG.with(|c| c.borrow().debug.clone())
     }
 
+    pub fn autosave_slots() -> u32 {
+        CONFIG.with(|c| c.borrow().autosave.slots)
+    }
+
     pub fn audio_config() -> AudioConfig {
         CONFIG.with(|c| c.borrow().audio.clone())
     }
@@ -182,11 +189,32 @@ impl Config {
     }
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct AutosaveConfig {
+    pub slots: u32,
+}
+
+impl Default for AutosaveConfig {
+    fn default() -> Self {
+        AutosaveConfig { slots: 3 }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct DebugConfig {
     pub encounter_spawning: bool,
     pub limit_line_of_sight: bool,
+
+    #[serde(default)]
+    pub reseed_random_on_load: bool,
+
+    #[serde(default)]
+    pub hot_reload: bool,
+
+    #[serde(default)]
+    pub record_replay: bool,
 }
 
 impl Default for DebugConfig {
@@ -194,6 +222,9 @@ impl Default for DebugConfig {
         DebugConfig {
             encounter_spawning: true,
             limit_line_of_sight: true,
+            reseed_random_on_load: false,
+            hot_reload: false,
+            record_replay: false,
         }
     }
 }
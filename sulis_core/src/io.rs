@@ -22,6 +22,9 @@ pub use self::event::Event;
 
 mod glium_adapter;
 
+pub mod headless_adapter;
+pub use self::headless_adapter::{HeadlessRenderer, HeadlessScript, ScriptedInput};
+
 mod input_action;
 pub use self::input_action::{InputAction, InputActionKind, InputActionState};
 
@@ -57,6 +60,12 @@ pub struct Resolution {
 pub trait ControlFlowUpdater {
     fn update(&mut self, millis: u32) -> Rc<RefCell<Widget>>;
 
+    /// Called with each input action received since the last `update`.  By
+    /// default, the action is dispatched to the current UI tree.
+    fn handle_input(&mut self, action: InputAction) {
+        action.handle(&self.root());
+    }
+
     fn root(&self) -> Rc<RefCell<Widget>>;
 
     fn recreate_window(&mut self) -> bool;
@@ -330,21 +339,34 @@ pub struct Vertex {
 implement_vertex!(Vertex, position, tex_coords);
 
 pub enum System {
-    Glium(glium_adapter::GliumSystem),
+    Glium(Box<glium_adapter::GliumSystem>),
+    Headless(headless_adapter::HeadlessSystem),
 }
 
 impl System {
     pub fn create() -> Result<System, Error> {
-        // just always create glium for now
         let glium_system = glium_adapter::create_system()?;
 
-        Ok(System::Glium(glium_system))
+        Ok(System::Glium(Box::new(glium_system)))
+    }
+
+    /// Creates a system that does not require a display or GPU, driving the
+    /// `ControlFlowUpdater` from the inputs in the specified script
+    pub fn create_headless(script: HeadlessScript) -> System {
+        System::Headless(headless_adapter::create_system(script))
+    }
+
+    pub fn is_headless(&self) -> bool {
+        matches!(self, System::Headless(_))
     }
 
     pub fn main_loop(self, updater: Box<dyn ControlFlowUpdater>) {
         match self {
             System::Glium(glium_system) => {
-                glium_adapter::main_loop(glium_system, updater);
+                glium_adapter::main_loop(*glium_system, updater);
+            }
+            System::Headless(headless_system) => {
+                headless_adapter::main_loop(headless_system, updater);
             }
         }
     }
@@ -354,6 +376,7 @@ impl System {
             System::Glium(glium_system) => {
                 glium_system.io.get_display_configurations(&glium_system.event_loop)
             }
+            System::Headless(_) => headless_adapter::get_display_configurations(),
         }
     }
 }
@@ -540,7 +540,6 @@ pub(crate) fn main_loop(
     let mut io = system.io;
     let event_loop = system.event_loop;
     let mut audio = system.audio;
-    let mut root = updater.root();
 
     let mut scale = io.scale_factor;
     let (ui_x, ui_y) = Config::ui_size();
@@ -578,11 +577,11 @@ pub(crate) fn main_loop(
             Event::MainEventsCleared => {
                 // merge all mouse move events into at most one per frame
                 if let Some((mouse_x, mouse_y)) = mouse_move {
-                    InputAction::mouse_move(mouse_x, mouse_y).handle(&root);
+                    updater.handle_input(InputAction::mouse_move(mouse_x, mouse_y));
                 }
                 mouse_move = None;
 
-                root = updater.update(last_elapsed);
+                let root = updater.update(last_elapsed);
                 if updater.is_exit() {
                     *control_flow = ControlFlow::Exit;
                 } else if updater.recreate_window() {
@@ -631,7 +630,7 @@ pub(crate) fn main_loop(
                         }
                         _ => {
                             for action in process_window_event(event) {
-                                action.handle(&root);
+                                updater.handle_input(action);
                             }
                         }
                     }
@@ -0,0 +1,415 @@
+//  This file is part of Sulis, a turn based RPG written in Rust.
+//  Copyright 2018 Jared Stephen
+//
+//  Sulis is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Sulis is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Sulis.  If not, see <http://www.gnu.org/licenses/>
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+use crate::config::Config;
+use crate::io::*;
+use crate::resource::ResourceSet;
+use crate::ui::Cursor;
+use crate::util::{Point, Size};
+
+/// A single input action, fed to the UI tree at the start of the specified frame.
+#[derive(Debug, Deserialize, Serialize, Copy, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ScriptedInput {
+    pub frame: u32,
+    pub kind: InputActionKind,
+
+    #[serde(default = "started")]
+    pub state: InputActionState,
+}
+
+fn started() -> InputActionState {
+    InputActionState::Started
+}
+
+impl ScriptedInput {
+    pub fn action(&self) -> InputAction {
+        InputAction {
+            kind: self.kind,
+            state: self.state,
+        }
+    }
+}
+
+/// Describes a headless run: how many frames to run, the simulated
+/// time per frame, and the inputs to feed to the `ControlFlowUpdater`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct HeadlessScript {
+    pub frame_millis: u32,
+    pub max_frames: u32,
+
+    #[serde(default)]
+    pub inputs: Vec<ScriptedInput>,
+}
+
+impl HeadlessScript {
+    pub fn load(path: &Path) -> Result<HeadlessScript, Error> {
+        let data = fs::read_to_string(path)?;
+
+        let mut script: HeadlessScript = match serde_yaml::from_str(&data) {
+            Ok(script) => script,
+            Err(e) => return Err(Error::new(ErrorKind::InvalidData, format!("{}", e))),
+        };
+
+        if script.frame_millis == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Headless script frame_millis must be greater than zero",
+            ));
+        }
+
+        script.inputs.sort_by_key(|input| input.frame);
+        Ok(script)
+    }
+
+    /// Parses the `--headless <script>` command line argument, if present.
+    /// Returns `Ok(None)` if the argument was not specified.
+    pub fn from_args() -> Result<Option<HeadlessScript>, Error> {
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            if arg != "--headless" {
+                continue;
+            }
+
+            return match args.next() {
+                None => Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "--headless requires a script file argument",
+                )),
+                Some(file) => HeadlessScript::load(Path::new(&file)).map(Some),
+            };
+        }
+
+        Ok(None)
+    }
+}
+
+/// Summary statistics gathered by the `HeadlessRenderer` over a run.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct RenderStats {
+    pub frames: u32,
+    pub draw_calls: u64,
+    pub texture_draw_calls: u64,
+    pub vertices: u64,
+    pub missing_textures: u32,
+}
+
+/// A `GraphicsRenderer` that does not require a GPU.  Each `DrawList` drawn
+/// to the screen is recorded for the current frame, and summary statistics
+/// are kept over the whole run.
+#[derive(Default)]
+pub struct HeadlessRenderer {
+    textures: HashSet<String>,
+    missing: HashSet<String>,
+    frame: Vec<DrawList>,
+    scissor: Option<(Point, Size)>,
+    stats: RenderStats,
+}
+
+impl HeadlessRenderer {
+    pub fn new() -> HeadlessRenderer {
+        HeadlessRenderer::default()
+    }
+
+    /// The draw lists recorded for the most recently rendered frame
+    pub fn frame(&self) -> &[DrawList] {
+        &self.frame
+    }
+
+    pub fn stats(&self) -> RenderStats {
+        self.stats
+    }
+
+    pub fn scissor(&self) -> Option<(Point, Size)> {
+        self.scissor
+    }
+
+    fn begin_frame(&mut self) {
+        self.frame.clear();
+        self.scissor = None;
+    }
+
+    fn end_frame(&mut self) {
+        self.stats.frames += 1;
+    }
+
+    fn create_texture_if_missing(&mut self, draw_list: &DrawList) {
+        let id = &draw_list.texture;
+        if self.textures.contains(id) {
+            return;
+        }
+
+        let exists = match draw_list.kind {
+            DrawListKind::Sprite => ResourceSet::spritesheet(id).is_some(),
+            DrawListKind::Font => ResourceSet::font(id).is_some(),
+        };
+
+        if exists {
+            trace!("Creating headless texture for ID '{}'", id);
+            self.textures.insert(id.to_string());
+        } else if self.missing.insert(id.to_string()) {
+            warn!("Attempted to draw with non-existant texture '{}'", id);
+            self.stats.missing_textures += 1;
+        }
+    }
+}
+
+impl GraphicsRenderer for HeadlessRenderer {
+    fn draw(&mut self, draw_list: DrawList) {
+        if draw_list.texture.is_empty() {
+            return;
+        }
+        self.create_texture_if_missing(&draw_list);
+
+        self.stats.draw_calls += 1;
+        self.stats.vertices += draw_list.quads.len() as u64;
+        self.frame.push(draw_list);
+    }
+
+    fn draw_to_texture(&mut self, texture_id: &str, draw_list: DrawList) {
+        self.create_texture_if_missing(&draw_list);
+        if !self.textures.contains(texture_id) {
+            warn!("Attempted to draw to unregistered texture '{}'", texture_id);
+            return;
+        }
+
+        self.stats.texture_draw_calls += 1;
+        self.stats.vertices += draw_list.quads.len() as u64;
+    }
+
+    fn register_texture(
+        &mut self,
+        id: &str,
+        image: ImageBuffer<Rgba<u8>, Vec<u8>>,
+        _min_filter: TextureMinFilter,
+        _mag_filter: TextureMagFilter,
+    ) {
+        let dims = image.dimensions();
+        trace!("Registering headless texture '{}', {}x{}", id, dims.0, dims.1);
+        self.textures.insert(id.to_string());
+    }
+
+    fn clear_texture(&mut self, _id: &str) {}
+
+    fn clear_texture_region(&mut self, _id: &str, _min_x: i32, _min_y: i32, _max_x: i32, _max_y: i32) {}
+
+    fn has_texture(&self, id: &str) -> bool {
+        self.textures.contains(id)
+    }
+
+    fn set_scissor(&mut self, pos: Point, size: Size) {
+        self.scissor = Some((pos, size));
+    }
+
+    fn clear_scissor(&mut self) {
+        self.scissor = None;
+    }
+}
+
+pub struct HeadlessSystem {
+    pub script: HeadlessScript,
+}
+
+pub fn create_system(script: HeadlessScript) -> HeadlessSystem {
+    info!(
+        "Initialized headless adapter: {} frames at {} millis, {} scripted inputs",
+        script.max_frames,
+        script.frame_millis,
+        script.inputs.len()
+    );
+
+    HeadlessSystem { script }
+}
+
+pub(crate) fn get_display_configurations() -> Vec<DisplayConfiguration> {
+    let (width, height) = Config::display_resolution();
+
+    vec![DisplayConfiguration {
+        name: "Headless".to_string(),
+        index: 0,
+        resolutions: vec![Resolution {
+            width,
+            height,
+            fullscreen: false,
+            monitor_size: true,
+        }],
+    }]
+}
+
+/// Runs the updater for the number of frames specified in the script, or until
+/// it requests an exit.  Returns the statistics gathered over the run.
+pub fn main_loop(system: HeadlessSystem, mut updater: Box<dyn ControlFlowUpdater>) -> RenderStats {
+    let mut renderer = HeadlessRenderer::new();
+    let script = system.script;
+    let mut inputs = script.inputs.iter().peekable();
+
+    let (res_x, res_y) = Config::display_resolution();
+    let pixel_size = Point::new(res_x as i32, res_y as i32);
+
+    info!("Starting headless main loop.");
+    let mut total_elapsed = 0;
+    for frame in 0..script.max_frames {
+        while let Some(input) = inputs.next_if(|input| input.frame <= frame) {
+            updater.handle_input(input.action());
+        }
+
+        let root = updater.update(script.frame_millis);
+        if updater.is_exit() {
+            info!("Updater exited on frame {}", frame);
+            break;
+        }
+
+        // there is no window to recreate, just consume the request
+        updater.recreate_window();
+
+        total_elapsed += script.frame_millis;
+
+        renderer.begin_frame();
+        root.borrow().draw(&mut renderer, pixel_size, total_elapsed);
+        Cursor::draw(&mut renderer, total_elapsed);
+        renderer.end_frame();
+    }
+
+    let stats = renderer.stats();
+    info!(
+        "Headless run complete: {} frames, {} draw calls, {} texture draw calls, {} vertices",
+        stats.frames, stats.draw_calls, stats.texture_draw_calls, stats.vertices
+    );
+    if stats.missing_textures > 0 {
+        warn!("{} missing textures were referenced", stats.missing_textures);
+    }
+
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::Widget;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Records the frame on which each input arrives, and exits after
+    /// the specified number of updates, if any
+    struct TestUpdater {
+        root: Rc<RefCell<Widget>>,
+        updates: Rc<RefCell<Vec<u32>>>,
+        inputs: Rc<RefCell<Vec<(usize, String)>>>,
+        exit_after: Option<usize>,
+    }
+
+    impl ControlFlowUpdater for TestUpdater {
+        fn update(&mut self, millis: u32) -> Rc<RefCell<Widget>> {
+            self.updates.borrow_mut().push(millis);
+            Rc::clone(&self.root)
+        }
+
+        fn handle_input(&mut self, action: InputAction) {
+            let frame = self.updates.borrow().len();
+            self.inputs
+                .borrow_mut()
+                .push((frame, format!("{:?}", action.kind)));
+        }
+
+        fn root(&self) -> Rc<RefCell<Widget>> {
+            Rc::clone(&self.root)
+        }
+
+        fn recreate_window(&mut self) -> bool {
+            false
+        }
+
+        fn is_exit(&self) -> bool {
+            match self.exit_after {
+                None => false,
+                Some(frames) => self.updates.borrow().len() >= frames,
+            }
+        }
+    }
+
+    fn input(frame: u32, kind: InputActionKind) -> ScriptedInput {
+        ScriptedInput {
+            frame,
+            kind,
+            state: InputActionState::Started,
+        }
+    }
+
+    fn run(
+        script: HeadlessScript,
+        exit_after: Option<usize>,
+    ) -> (RenderStats, Vec<u32>, Vec<(usize, String)>) {
+        // widgets need the themes from the base resources
+        let data = concat!(env!("CARGO_MANIFEST_DIR"), "/../data").to_string();
+        ResourceSet::load_resources(vec![data]).unwrap();
+
+        let updates = Rc::new(RefCell::new(Vec::new()));
+        let inputs = Rc::new(RefCell::new(Vec::new()));
+        let updater = TestUpdater {
+            root: Widget::empty("root"),
+            updates: Rc::clone(&updates),
+            inputs: Rc::clone(&inputs),
+            exit_after,
+        };
+
+        let stats = main_loop(create_system(script), Box::new(updater));
+        let updates = updates.borrow().clone();
+        let inputs = inputs.borrow().clone();
+        (stats, updates, inputs)
+    }
+
+    #[test]
+    fn runs_frames_and_scripted_inputs() {
+        let script = HeadlessScript {
+            frame_millis: 20,
+            max_frames: 3,
+            inputs: vec![
+                input(0, InputActionKind::ToggleConsole),
+                input(2, InputActionKind::ToggleInventory),
+            ],
+        };
+
+        let (stats, updates, inputs) = run(script, None);
+        assert_eq!(stats.frames, 3);
+        assert_eq!(updates, vec![20, 20, 20]);
+        assert_eq!(
+            inputs,
+            vec![
+                (0, "ToggleConsole".to_string()),
+                (2, "ToggleInventory".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_when_the_updater_exits() {
+        let script = HeadlessScript {
+            frame_millis: 16,
+            max_frames: 10,
+            inputs: Vec::new(),
+        };
+
+        let (stats, updates, _) = run(script, Some(2));
+        assert_eq!(updates.len(), 2);
+        assert_eq!(stats.frames, 1, "the exit frame is not drawn");
+    }
+}
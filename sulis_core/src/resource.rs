@@ -30,6 +30,9 @@ pub use self::spritesheet::Spritesheet;
 mod font;
 pub use self::font::Font;
 
+mod resource_watcher;
+pub use self::resource_watcher::ResourceWatcher;
+
 pub mod yaml_resource_set;
 pub use self::yaml_resource_set::YamlResourceKind;
 pub use self::yaml_resource_set::YamlResourceSet;
@@ -57,6 +60,22 @@ use crate::util::{self, invalid_data_error};
 
 thread_local! {
     static RESOURCE_SET: RefCell<ResourceSet> = RefCell::new(ResourceSet::default());
+    static LOAD_ERRORS: RefCell<Vec<LoadError>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A resource that could not be created, and was therefore left out of
+/// its resource set.
+#[derive(Debug, Clone)]
+pub struct LoadError {
+    pub kind: String,
+    pub id: String,
+    pub error: String,
+}
+
+/// Returns all resources that have failed to load since the last call to
+/// `ResourceSet::load_resources`, clearing the list.
+pub fn take_load_errors() -> Vec<LoadError> {
+    LOAD_ERRORS.with(|errors| errors.replace(Vec::new()))
 }
 
 #[derive(Default)]
@@ -69,7 +88,7 @@ pub struct ResourceSet {
 }
 
 impl ResourceSet {
-    pub fn load_resources(mut dirs: Vec<String>) -> Result<YamlResourceSet, Error> {
+    pub fn load_resources(dirs: Vec<String>) -> Result<YamlResourceSet, Error> {
         if dirs.is_empty() {
             return Err(Error::new(
                 ErrorKind::InvalidInput,
@@ -78,15 +97,10 @@ impl ResourceSet {
             ));
         }
 
-        let yaml_start = std::time::Instant::now();
-        let root = dirs.remove(0);
-        let path = Path::new(&root);
-        let mut yaml = YamlResourceSet::new(path)?;
+        LOAD_ERRORS.with(|errors| errors.borrow_mut().clear());
 
-        for dir in dirs {
-            let path = Path::new(&dir);
-            yaml.append(path);
-        }
+        let yaml_start = std::time::Instant::now();
+        let mut yaml = ResourceSet::read_yaml(&dirs)?;
 
         let dir_val = serde_yaml::Value::String(yaml_resource_set::DIRECTORY_VAL_STR.to_string());
         let file_val = serde_yaml::Value::String(yaml_resource_set::FILE_VAL_STR.to_string());
@@ -115,6 +129,22 @@ impl ResourceSet {
         Ok(yaml)
     }
 
+    /// Reads the YAML and JSON data in the specified directories, without
+    /// building any of the resources in this set.  The first directory is
+    /// the root, with each subsequent directory appended to it.
+    pub fn read_yaml(dirs: &[String]) -> Result<YamlResourceSet, Error> {
+        if dirs.is_empty() {
+            return invalid_data_error("Must specify at least a root data directory");
+        }
+
+        let mut yaml = YamlResourceSet::new(Path::new(&dirs[0]))?;
+        for dir in dirs.iter().skip(1) {
+            yaml.append(Path::new(dir));
+        }
+
+        Ok(yaml)
+    }
+
     fn load_builders(builder_set: ResourceBuilderSet) -> Result<(), Error> {
         debug!("Creating resource set from parsed data.");
 
@@ -401,6 +431,14 @@ fn insert_if_ok_boxed<K: Eq + Hash + Display, V: ?Sized>(
 fn warn_on_insert<K: Display>(type_str: &str, key: K, error: Error) {
     warn!("Error in {} with id '{}'", type_str, key);
     warn!("{}", error);
+
+    LOAD_ERRORS.with(|errors| {
+        errors.borrow_mut().push(LoadError {
+            kind: type_str.to_string(),
+            id: key.to_string(),
+            error: error.to_string(),
+        })
+    });
 }
 
 pub fn subdirs<P: AsRef<Path>>(path: P) -> Result<Vec<PathBuf>, Error> {
@@ -0,0 +1,114 @@
+//  This file is part of Sulis, a turn based RPG written in Rust.
+//  Copyright 2018 Jared Stephen
+//
+//  Sulis is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Sulis is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Sulis.  If not, see <http://www.gnu.org/licenses/>
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+const CHECK_INTERVAL: Duration = Duration::from_millis(1000);
+
+/// Polls a set of resource directories for changes to data and script files.
+/// Used for reloading resources while the game is running.
+pub struct ResourceWatcher {
+    dirs: Vec<String>,
+    modified: HashMap<PathBuf, SystemTime>,
+    last_check: Instant,
+}
+
+impl ResourceWatcher {
+    pub fn new(dirs: Vec<String>) -> ResourceWatcher {
+        let mut modified = HashMap::new();
+        for dir in dirs.iter() {
+            scan(Path::new(dir), &mut modified);
+        }
+
+        info!("Watching {} resource files for changes", modified.len());
+
+        ResourceWatcher {
+            dirs,
+            modified,
+            last_check: Instant::now(),
+        }
+    }
+
+    pub fn dirs(&self) -> &[String] {
+        &self.dirs
+    }
+
+    /// Returns all files that have been added, modified, or removed since the
+    /// last check.  The directories are scanned at most once per second; this
+    /// returns an empty list on calls in between.
+    pub fn check(&mut self) -> Vec<PathBuf> {
+        if self.last_check.elapsed() < CHECK_INTERVAL {
+            return Vec::new();
+        }
+        self.last_check = Instant::now();
+
+        let mut modified = HashMap::new();
+        for dir in self.dirs.iter() {
+            scan(Path::new(dir), &mut modified);
+        }
+
+        let mut changed: Vec<PathBuf> = modified
+            .iter()
+            .filter(|(path, time)| self.modified.get(*path) != Some(*time))
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        for path in self.modified.keys() {
+            if !modified.contains_key(path) {
+                changed.push(path.clone());
+            }
+        }
+
+        self.modified = modified;
+        changed.sort();
+        changed
+    }
+}
+
+fn is_script(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "lua")
+}
+
+fn is_data(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "yml" || ext == "json")
+}
+
+fn scan(dir: &Path, modified: &mut HashMap<PathBuf, SystemTime>) {
+    let entries = match fs::read_dir(dir) {
+        Err(_) => return,
+        Ok(entries) => entries,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            scan(&path, modified);
+            continue;
+        }
+
+        if !is_script(&path) && !is_data(&path) {
+            continue;
+        }
+
+        if let Ok(time) = entry.metadata().and_then(|meta| meta.modified()) {
+            modified.insert(path, time);
+        }
+    }
+}
@@ -53,6 +53,7 @@ pub enum YamlResourceKind {
     Conversation,
     Cutscene,
     Encounter,
+    Faction,
     Item,
     ItemAdjective,
     LootList,
@@ -103,6 +104,7 @@ impl YamlResourceKind {
             "conversations" => Conversation,
             "cutscenes" => Cutscene,
             "encounters" => Encounter,
+            "factions" => Faction,
             "items" => Item,
             "item_adjectives" => ItemAdjective,
             "loot_lists" => LootList,
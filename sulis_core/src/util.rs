@@ -20,6 +20,7 @@ pub use self::point::{Offset, Point, Rect, Scale};
 pub mod size;
 pub use self::size::Size;
 
+use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::f32;
 use std::fmt;
@@ -39,6 +40,10 @@ use rand_pcg::Pcg64Mcg;
 use crate::config::{self, Config};
 use crate::resource::write_to_file;
 
+thread_local! {
+    static RAND: RefCell<Pcg64Mcg> = RefCell::new(Pcg64Mcg::new(rand::thread_rng().gen::<u64>() as u128));
+}
+
 const MAX_ULPS: i32 = 100;
 const MAX_DIFF: f32 = 2.0 * std::f32::EPSILON;
 
@@ -67,12 +72,33 @@ pub fn approx_eq(a: f32, b: f32) -> bool {
     i32::abs(a_int - b_int) <= MAX_ULPS
 }
 
-#[derive(Clone)]
+/// A seeded random number generator.  The full generator state may be
+/// serialized, so that a stream of rolls can be continued after a load.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ReproducibleRandom {
     seed: u128,
+
+    #[serde(with = "generator_state")]
     gen: Pcg64Mcg,
 }
 
+/// The generator state is a full 128 bit integer, which is stored as a string
+/// so it is not rounded when save files are read into a `serde_json::Value`.
+mod generator_state {
+    use rand_pcg::Pcg64Mcg;
+    use serde::{de::Error, ser, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(gen: &Pcg64Mcg, serializer: S) -> Result<S::Ok, S::Error> {
+        let state = serde_json::to_string(gen).map_err(ser::Error::custom)?;
+        serializer.serialize_str(&state)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pcg64Mcg, D::Error> {
+        let state = String::deserialize(deserializer)?;
+        serde_json::from_str(&state).map_err(D::Error::custom)
+    }
+}
+
 impl ReproducibleRandom {
     pub fn new(seed: Option<u128>) -> ReproducibleRandom {
         // TODO only seed with u64 for now because serde_yaml doesn't serialize u128 correctly
@@ -107,12 +133,21 @@ impl std::fmt::Debug for ReproducibleRandom {
     }
 }
 
+/// Reseeds the generator used by `gen_rand` and `shuffle`, so that a recorded
+/// session may be replayed exactly.  If no seed is specified, a new random seed
+/// is chosen.  Returns the seed that was used.
+pub fn seed_rand(seed: Option<u64>) -> u64 {
+    let seed = seed.unwrap_or_else(|| rand::thread_rng().gen());
+    RAND.with(|r| *r.borrow_mut() = Pcg64Mcg::new(seed as u128));
+    seed
+}
+
 pub fn shuffle<T>(values: &mut [T]) {
-    values.shuffle(&mut rand::thread_rng());
+    RAND.with(|r| values.shuffle(&mut *r.borrow_mut()));
 }
 
 pub fn gen_rand<T: SampleUniform + PartialOrd>(min: T, max: T) -> T {
-    rand::thread_rng().gen_range(min..max)
+    RAND.with(|r| r.borrow_mut().gen_range(min..max))
 }
 
 fn active_resources_file_path() -> PathBuf {
@@ -426,4 +461,23 @@ fn create_user_dirs() {
     let mut mods_dir = config::USER_DIR.clone();
     mods_dir.push(&res.mods_directory);
     config::create_dir_and_warn(&mods_dir);
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_gen_rand_repeats() {
+        let seed = seed_rand(None);
+        let first: Vec<u32> = (0..100).map(|_| gen_rand(0, 1000)).collect();
+
+        assert_eq!(seed_rand(Some(seed)), seed);
+        let second: Vec<u32> = (0..100).map(|_| gen_rand(0, 1000)).collect();
+        assert_eq!(first, second);
+
+        // the generator ignores the lowest bit of the seed
+        seed_rand(Some(!seed));
+        let third: Vec<u32> = (0..100).map(|_| gen_rand(0, 1000)).collect();
+        assert_ne!(first, third);
+    }
+}
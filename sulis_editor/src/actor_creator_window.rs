@@ -55,7 +55,7 @@ impl ActorCreatorWindow {
             selected_images: HashMap::new(),
             view_pane: Widget::empty("view_pane"),
             selected_hue: 0.0,
-            selected_faction: Faction::Neutral,
+            selected_faction: Faction::neutral(),
             selected_sex: Sex::Male,
             preview: None,
             id_field: InputField::new("creature01"),
@@ -101,7 +101,7 @@ impl ActorCreatorWindow {
             portrait: None,
             attributes: AttributeList::new(Module::rules().base_attribute as u8),
             conversation: None,
-            faction: Some(self.selected_faction),
+            faction: Some(self.selected_faction.clone()),
             images,
             hue: Some(self.selected_hue),
             hair_color: None,
@@ -348,9 +348,8 @@ impl WidgetKind for ActorCreatorWindow {
 
         let faction_pane = Widget::empty("faction_pane");
         if self.selected_race.is_some() {
-            for faction in Faction::iter() {
-                let faction = *faction;
-                let widget = Widget::with_theme(Button::empty(), &format!("{:?}", faction));
+            for faction in Faction::built_in() {
+                let widget = Widget::with_theme(Button::empty(), faction.id());
                 if faction == self.selected_faction {
                     widget.borrow_mut().state.set_active(true);
                 }
@@ -359,7 +358,7 @@ impl WidgetKind for ActorCreatorWindow {
                     .state
                     .add_callback(Callback::new(Rc::new(move |widget, _| {
                         let (parent, window) = Widget::parent_mut::<ActorCreatorWindow>(widget);
-                        window.selected_faction = faction;
+                        window.selected_faction = faction.clone();
                         parent.borrow_mut().invalidate_children();
                     })));
                 Widget::add_child_to(&faction_pane, widget);
@@ -32,7 +32,7 @@ pub struct AreaModel {
     pub config: EditorConfig,
 
     tiles: TilesModel,
-    actors: Vec<(Point, Rc<Actor>, Option<String>)>,
+    actors: Vec<(Point, Rc<Actor>, Option<String>, Vec<ScheduleEntry>)>,
     props: Vec<PropData>,
     encounters: Vec<EncounterData>,
     transitions: Vec<Transition>,
@@ -172,11 +172,12 @@ impl AreaModel {
             return;
         }
 
-        self.actors.push((Point::new(x, y), actor, None));
+        self.actors
+            .push((Point::new(x, y), actor, None, Vec::new()));
     }
 
     pub fn remove_actors_within(&mut self, x: i32, y: i32, width: i32, height: i32) {
-        self.actors.retain(|&(pos, ref actor, _)| {
+        self.actors.retain(|&(pos, ref actor, ..)| {
             !is_removal(
                 pos,
                 actor.race.size.width,
@@ -197,7 +198,7 @@ impl AreaModel {
         height: i32,
     ) -> Vec<(Point, Rc<Actor>)> {
         let mut actors = Vec::new();
-        for &(pos, ref actor, _) in self.actors.iter() {
+        for &(pos, ref actor, ..) in self.actors.iter() {
             if !is_removal(
                 pos,
                 actor.race.size.width,
@@ -399,7 +400,7 @@ impl AreaModel {
             renderer.draw(draw_list);
         }
 
-        for &(pos, ref actor, _) in self.actors.iter() {
+        for &(pos, ref actor, ..) in self.actors.iter() {
             let w = actor.race.size.width as f32 / 2.0;
             let h = actor.race.size.height as f32 / 2.0;
             actor.draw(
@@ -676,8 +677,12 @@ impl AreaModel {
                 Some(actor) => actor,
             };
 
-            self.actors
-                .push((actor_data.location, actor, actor_data.unique_id));
+            self.actors.push((
+                actor_data.location,
+                actor,
+                actor_data.unique_id,
+                actor_data.schedule,
+            ));
         }
     }
 
@@ -770,11 +775,12 @@ impl AreaModel {
 
         trace!("Saving actors.");
         let mut actors: Vec<ActorData> = Vec::new();
-        for &(pos, ref actor, ref unique_id) in self.actors.iter() {
+        for &(pos, ref actor, ref unique_id, ref schedule) in self.actors.iter() {
             actors.push(ActorData {
                 id: actor.id.to_string(),
                 unique_id: unique_id.clone(),
                 location: pos,
+                schedule: schedule.clone(),
             });
         }
 
@@ -21,7 +21,7 @@ use sulis_core::config::Config;
 use sulis_core::resource::ResourceSet;
 use sulis_core::ui;
 use sulis_core::util;
-use sulis_core::io::System;
+use sulis_core::io::{HeadlessScript, System};
 use sulis_module::Module;
 
 use sulis_editor::{EditorControlFlowUpdater, EditorView};
@@ -59,14 +59,27 @@ fn main() {
         util::error_and_exit("Fatal error settign up module.");
     }
 
-    info!("Setting up display adapter.");
-    let system = match System::create() {
-        Ok(system) => system,
+    let system = match HeadlessScript::from_args() {
         Err(e) => {
             error!("{}", e);
-            util::error_and_exit("Fatal error creating the display system.");
+            util::error_and_exit("Unable to read the headless script.");
             unreachable!();
         }
+        Ok(Some(script)) => {
+            info!("Setting up headless display adapter.");
+            System::create_headless(script)
+        }
+        Ok(None) => {
+            info!("Setting up display adapter.");
+            match System::create() {
+                Ok(system) => system,
+                Err(e) => {
+                    error!("{}", e);
+                    util::error_and_exit("Fatal error creating the display system.");
+                    unreachable!();
+                }
+            }
+        }
     };
 
     let root = ui::create_ui_tree(EditorView::new());
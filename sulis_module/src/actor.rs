@@ -26,65 +26,12 @@ use sulis_core::resource::ResourceSet;
 use sulis_core::ui::Color;
 use sulis_core::util::{unable_to_create_error, Offset, Scale};
 
+pub use crate::faction::Faction;
 use crate::{
     AITemplate, Ability, Class, Conversation, ImageLayer, ImageLayerSet, InventoryBuilder,
     LootList, Module, Race, RaceBuilder,
 };
 
-#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
-#[serde(deny_unknown_fields)]
-pub enum Faction {
-    Friendly,
-    Hostile,
-    Neutral,
-}
-
-impl Faction {
-    pub fn iter() -> impl Iterator<Item = &'static Faction> {
-        [Faction::Friendly, Faction::Hostile, Faction::Neutral].iter()
-    }
-
-    pub fn option_from_str(val: &str) -> Option<Faction> {
-        match val {
-            "Hostile" => Some(Faction::Hostile),
-            "Neutral" => Some(Faction::Neutral),
-            "Friendly" => Some(Faction::Friendly),
-            _ => None,
-        }
-    }
-
-    pub fn to_str(self) -> String {
-        match &self {
-            Faction::Hostile => "Hostile",
-            Faction::Neutral => "Neutral",
-            Faction::Friendly => "Friendly",
-        }
-        .to_owned()
-    }
-
-    pub fn is_hostile(self, other: Faction) -> bool {
-        if self == Faction::Neutral {
-            return false;
-        }
-        if other == Faction::Neutral {
-            return false;
-        }
-
-        self != other
-    }
-
-    pub fn is_friendly(self, other: Faction) -> bool {
-        if self == Faction::Neutral {
-            return false;
-        }
-        if other == Faction::Neutral {
-            return false;
-        }
-
-        self == other
-    }
-}
-
 #[derive(Debug, Clone)]
 pub struct Reward {
     pub xp: u32,
@@ -215,7 +162,7 @@ impl Actor {
         Actor {
             id: other.id.to_string(),
             name: other.name.to_string(),
-            faction: other.faction,
+            faction: other.faction.clone(),
             conversation: other.conversation.clone(),
             portrait: other.portrait.clone(),
             race: Rc::clone(&other.race),
@@ -237,6 +184,33 @@ impl Actor {
         }
     }
 
+    /// Creates a copy of this actor with its abilities and conversation looked
+    /// up again from the `Module`.  Used when module resources are reloaded.
+    pub fn reloaded(&self) -> Actor {
+        let mut actor = Actor::from(
+            self,
+            None,
+            self.xp,
+            Vec::new(),
+            Vec::new(),
+            self.inventory.clone(),
+        );
+
+        for owned in actor.abilities.iter_mut() {
+            if let Some(ability) = Module::ability(&owned.ability.id) {
+                owned.ability = ability;
+            }
+        }
+
+        if let Some(convo) = &self.conversation {
+            if let Some(convo) = Module::conversation(&convo.id) {
+                actor.conversation = Some(convo);
+            }
+        }
+
+        actor
+    }
+
     pub fn new(builder: ActorBuilder, resources: &mut Module) -> Result<Actor, Error> {
         let race = if let Some(race_id) = builder.race {
             match resources.races.get(&race_id) {
@@ -361,11 +335,17 @@ impl Actor {
             },
         };
 
+        let faction = builder.faction.unwrap_or_else(Faction::hostile);
+        if !resources.factions.contains_key(faction.id()) {
+            warn!("No faction found with id '{}'", faction.id());
+            return unable_to_create_error("actor", &builder.id);
+        }
+
         Ok(Actor {
             id: builder.id,
             name: builder.name,
             conversation,
-            faction: builder.faction.unwrap_or(Faction::Hostile),
+            faction,
             portrait,
             race,
             sex,
@@ -386,8 +366,8 @@ impl Actor {
         })
     }
 
-    pub fn faction(&self) -> Faction {
-        self.faction
+    pub fn faction(&self) -> &Faction {
+        &self.faction
     }
 
     pub fn levels(&self, other_class: &Rc<Class>) -> u32 {
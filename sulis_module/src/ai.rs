@@ -24,15 +24,119 @@ pub enum FuncKind {
     AfterAttack,
     BeforeDefense,
     OnRoundElapsed,
+    OnReaction,
+    OnDetected,
     AiAction,
 }
 
+/// Selects what decides the actions of an entity using a given `AITemplate`
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(deny_unknown_fields)]
+pub enum AIPlanner {
+    /// The `AiAction` hook of the template script is called for each action
+    #[default]
+    Script,
+
+    /// The built in tactical planner scores the available actions and plans
+    /// the AP spent over the whole turn.  The other script hooks are still called.
+    Native,
+}
+
+/// The part an entity plays within its AI group, shared with the other
+/// members of the group
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(deny_unknown_fields)]
+pub enum AIRole {
+    Striker,
+    Defender,
+    Caster,
+    Support,
+}
+
+impl AIRole {
+    pub fn unwrap_from_str(s: &str) -> AIRole {
+        match s {
+            "Striker" => AIRole::Striker,
+            "Defender" => AIRole::Defender,
+            "Caster" => AIRole::Caster,
+            "Support" => AIRole::Support,
+            _ => {
+                warn!("Invalid AI role string '{}'", s);
+                AIRole::Striker
+            }
+        }
+    }
+}
+
+/// How the AI controls a party member, when it does.  Each mode is mapped to
+/// an `AITemplate` by the `party_ai` rules.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(deny_unknown_fields)]
+pub enum PartyAIMode {
+    /// Closes with and attacks hostiles
+    #[default]
+    Aggressive,
+
+    /// Stays near its position, attacking hostiles that come within reach
+    Defensive,
+
+    /// Prefers healing and buffing its allies over attacking
+    Support,
+
+    /// Takes no actions, ending its turn immediately
+    Passive,
+}
+
+impl PartyAIMode {
+    /// All modes, in the order they are cycled through in the UI
+    pub const ALL: [PartyAIMode; 4] = [
+        PartyAIMode::Aggressive,
+        PartyAIMode::Defensive,
+        PartyAIMode::Support,
+        PartyAIMode::Passive,
+    ];
+
+    /// Returns the mode following `mode` when cycling through them, with
+    /// `None`, for control by the player, before the first mode and after the
+    /// last
+    pub fn cycle(mode: Option<PartyAIMode>) -> Option<PartyAIMode> {
+        match mode {
+            None => Some(PartyAIMode::ALL[0]),
+            Some(mode) => {
+                let index = PartyAIMode::ALL.iter().position(|m| *m == mode)?;
+                PartyAIMode::ALL.get(index + 1).copied()
+            }
+        }
+    }
+
+    pub fn unwrap_from_str(s: &str) -> PartyAIMode {
+        match s {
+            "Aggressive" => PartyAIMode::Aggressive,
+            "Defensive" => PartyAIMode::Defensive,
+            "Support" => PartyAIMode::Support,
+            "Passive" => PartyAIMode::Passive,
+            _ => {
+                warn!("Invalid party AI mode string '{}'", s);
+                PartyAIMode::Aggressive
+            }
+        }
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct AITemplate {
     pub id: String,
     pub script: String,
 
+    #[serde(default)]
+    pub planner: AIPlanner,
+
+    /// The role of entities using this template in their AI group.  If not
+    /// specified, it is chosen from the abilities of each entity.
+    #[serde(default)]
+    pub role: Option<AIRole>,
+
     #[serde(default)]
     pub hooks: HashMap<FuncKind, String>,
 
@@ -81,6 +81,75 @@ pub struct ActorData {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub unique_id: Option<String>,
+
+    /// Where the actor goes outside of combat.  The first entry covering the
+    /// current hour applies.  If none does, the actor returns to `location`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schedule: Vec<ScheduleEntry>,
+}
+
+/// A set of waypoints followed by an actor over some hours of the day
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduleEntry {
+    /// The first hour this entry applies, and the hour it stops applying.
+    /// The range wraps past midnight when the first hour is the larger.
+    /// An entry without hours applies at any time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hours: Option<(u32, u32)>,
+    pub waypoints: Vec<Waypoint>,
+
+    #[serde(default)]
+    pub route: ScheduleRoute,
+}
+
+impl ScheduleEntry {
+    pub fn applies_at(&self, hour: u32) -> bool {
+        match self.hours {
+            None => true,
+            Some((from, to)) if from <= to => hour >= from && hour < to,
+            Some((from, to)) => hour >= from || hour < to,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Waypoint {
+    pub location: Point,
+
+    /// The number of rounds to wait on arriving, before moving on
+    #[serde(default)]
+    pub wait: u32,
+}
+
+/// How an actor moves on once it reaches the last waypoint of an entry
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(deny_unknown_fields)]
+pub enum ScheduleRoute {
+    /// Start over from the first waypoint
+    #[default]
+    Loop,
+
+    /// Walk the waypoints in reverse, then forwards again
+    BackAndForth,
+
+    /// Stay at the last waypoint
+    Once,
+}
+
+impl ScheduleRoute {
+    pub fn unwrap_from_str(s: &str) -> ScheduleRoute {
+        match s {
+            "Loop" => ScheduleRoute::Loop,
+            "BackAndForth" => ScheduleRoute::BackAndForth,
+            "Once" => ScheduleRoute::Once,
+            _ => {
+                warn!("Invalid schedule route string '{}'", s);
+                ScheduleRoute::Loop
+            }
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -96,6 +96,10 @@ impl Conversation {
         })
     }
 
+    pub fn node_ids(&self) -> impl Iterator<Item = &String> {
+        self.nodes.keys()
+    }
+
     pub fn initial_nodes(&self) -> Iter<(String, Vec<OnTrigger>)> {
         self.initial_nodes.iter()
     }
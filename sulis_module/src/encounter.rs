@@ -21,7 +21,7 @@ use std::rc::Rc;
 use crate::{Actor, Module};
 use sulis_core::io::SoundSource;
 use sulis_core::resource::ResourceSet;
-use sulis_core::util::{gen_rand, unable_to_create_error};
+use sulis_core::util::{unable_to_create_error, ReproducibleRandom};
 
 struct Entry {
     actor: Rc<Actor>,
@@ -106,14 +106,18 @@ impl Encounter {
         })
     }
 
-    fn gen_actor(&self, count: &mut HashMap<String, u32>) -> Option<(Rc<Actor>, Option<String>)> {
+    fn gen_actor(
+        &self,
+        count: &mut HashMap<String, u32>,
+        rand: &mut ReproducibleRandom,
+    ) -> Option<(Rc<Actor>, Option<String>)> {
         if self.total_weight == 0 {
             return None;
         }
 
         // try to gen a maximum of 100 times
         for _ in 0..100 {
-            let index = match self.gen_roll() {
+            let index = match self.gen_roll(rand) {
                 None => continue,
                 Some(index) => index,
             };
@@ -135,8 +139,8 @@ impl Encounter {
         None
     }
 
-    fn gen_roll(&self) -> Option<usize> {
-        let roll = gen_rand(0, self.total_weight);
+    fn gen_roll(&self, rand: &mut ReproducibleRandom) -> Option<usize> {
+        let roll = rand.gen(0, self.total_weight);
         let mut cur_weight = 0;
         for (index, entry) in self.entries.iter().enumerate() {
             cur_weight += entry.weight;
@@ -148,16 +152,16 @@ impl Encounter {
         None
     }
 
-    pub fn gen_actors(&self) -> Vec<(Rc<Actor>, Option<String>)> {
+    pub fn gen_actors(&self, rand: &mut ReproducibleRandom) -> Vec<(Rc<Actor>, Option<String>)> {
         let mut actors = Vec::new();
 
-        let total_num = gen_rand(self.min_gen_actors, self.max_gen_actors + 1);
+        let total_num = rand.gen(self.min_gen_actors, self.max_gen_actors + 1);
 
         let mut count = HashMap::new();
         let mut cur_num = 0;
 
         while cur_num < total_num {
-            let actor = match self.gen_actor(&mut count) {
+            let actor = match self.gen_actor(&mut count, rand) {
                 None => {
                     warn!("Unable to generate actor for encounter '{}'", self.id);
                     return actors;
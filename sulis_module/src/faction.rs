@@ -0,0 +1,159 @@
+//  This file is part of Sulis, a turn based RPG written in Rust.
+//  Copyright 2018 Jared Stephen
+//
+//  Sulis is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Sulis is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Sulis.  If not, see <http://www.gnu.org/licenses/>
+
+//! Factions and the relationships between them.  The `Friendly`, `Hostile`,
+//! and `Neutral` factions are always defined; a module may define additional
+//! factions, or override the built in ones, with files in its `factions`
+//! directory.
+//!
+//! Each faction lists its relationship with other factions, from
+//! `MIN_RELATIONSHIP` to `MAX_RELATIONSHIP`.  Members of two factions are
+//! hostile to each other when their relationship is at or below
+//! `HOSTILE_THRESHOLD`, and friendly when it is at or above
+//! `FRIENDLY_THRESHOLD`.  The party belongs to the `Friendly` faction, and its
+//! relationship with every other faction is shifted by the reputation
+//! the party has with that faction in the current game.
+//!
+//! ```yaml
+//! id: town_guard
+//! name: "Town Guard"
+//! reputation: 20
+//! relationships:
+//!   Hostile: -100
+//!   bandits: -80
+//! ```
+
+use std::collections::HashMap;
+
+use crate::Module;
+
+pub const MIN_RELATIONSHIP: i32 = -100;
+pub const MAX_RELATIONSHIP: i32 = 100;
+pub const HOSTILE_THRESHOLD: i32 = -50;
+pub const FRIENDLY_THRESHOLD: i32 = 50;
+
+const FRIENDLY: &str = "Friendly";
+const HOSTILE: &str = "Hostile";
+const NEUTRAL: &str = "Neutral";
+
+/// The ID of the faction an actor belongs to
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct Faction(String);
+
+impl Faction {
+    /// The faction of the party
+    pub fn friendly() -> Faction {
+        Faction(FRIENDLY.to_string())
+    }
+
+    pub fn hostile() -> Faction {
+        Faction(HOSTILE.to_string())
+    }
+
+    pub fn neutral() -> Faction {
+        Faction(NEUTRAL.to_string())
+    }
+
+    /// The factions which are defined for every module
+    pub fn built_in() -> [Faction; 3] {
+        [Faction::friendly(), Faction::hostile(), Faction::neutral()]
+    }
+
+    /// Returns the faction with the specified ID, if it is defined
+    /// by the current module
+    pub fn option_from_str(val: &str) -> Option<Faction> {
+        Module::faction(val).map(|_| Faction(val.to_string()))
+    }
+
+    pub fn id(&self) -> &str {
+        &self.0
+    }
+
+    pub fn to_str(&self) -> String {
+        self.0.to_string()
+    }
+
+    pub fn is_friendly_faction(&self) -> bool {
+        self.0 == FRIENDLY
+    }
+
+    /// The display name of this faction, or its ID if it is not defined
+    pub fn name(&self) -> String {
+        match Module::faction(&self.0) {
+            None => self.0.to_string(),
+            Some(info) => info.name.to_string(),
+        }
+    }
+
+    /// The relationship between this faction and `other` as defined by the
+    /// module, without any reputation.  If both factions specify a value
+    /// for the other, the lower value is used.  Factions that do not
+    /// specify a value have a neutral relationship, except with
+    /// themselves, which is friendly.
+    pub fn base_relationship(&self, other: &Faction) -> i32 {
+        let value = |from: &Faction, to: &Faction| {
+            Module::faction(&from.0).and_then(|info| info.relationships.get(&to.0).copied())
+        };
+
+        let result = match (value(self, other), value(other, self)) {
+            (Some(a), Some(b)) => a.min(b),
+            (Some(a), None) | (None, Some(a)) => a,
+            (None, None) if self == other => MAX_RELATIONSHIP,
+            (None, None) => 0,
+        };
+
+        result.clamp(MIN_RELATIONSHIP, MAX_RELATIONSHIP)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct FactionInfo {
+    pub id: String,
+    pub name: String,
+
+    /// The relationship with each other faction, by ID
+    #[serde(default)]
+    pub relationships: HashMap<String, i32>,
+
+    /// The reputation of the party with this faction at the start of a game
+    #[serde(default)]
+    pub reputation: i32,
+}
+
+impl FactionInfo {
+    /// The definitions used for the built in factions when the module does
+    /// not override them.  These are hostile to each other, except for
+    /// `Neutral`, which is neither hostile nor friendly to anyone.
+    pub(crate) fn built_in() -> Vec<FactionInfo> {
+        let info = |id: &str, relationships: &[(&str, i32)]| FactionInfo {
+            id: id.to_string(),
+            name: id.to_string(),
+            relationships: relationships
+                .iter()
+                .map(|(id, value)| (id.to_string(), *value))
+                .collect(),
+            reputation: 0,
+        };
+
+        vec![
+            info(FRIENDLY, &[(HOSTILE, MIN_RELATIONSHIP)]),
+            info(HOSTILE, &[(FRIENDLY, MIN_RELATIONSHIP)]),
+            info(NEUTRAL, &[(NEUTRAL, 0)]),
+        ]
+    }
+}
@@ -45,6 +45,10 @@ pub struct ItemSaveState {
 
     #[serde(default)]
     pub variant: Option<usize>,
+
+    /// The current durability, only present for damaged items
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub durability: Option<u32>,
 }
 
 impl ItemSaveState {
@@ -56,10 +60,17 @@ impl ItemSaveState {
             .map(|adj| adj.id.clone())
             .collect();
 
+        let durability = if item.is_damaged() {
+            item.durability
+        } else {
+            None
+        };
+
         ItemSaveState {
             id: item.item.original_id.clone(),
             adjectives,
             variant: item.variant,
+            durability,
         }
     }
 }
@@ -142,7 +153,8 @@ impl InventoryBuilder {
                     None
                 }
                 Some(item) => {
-                    let state = ItemState::new(item, entry.item.variant);
+                    let state = ItemState::new(item, entry.item.variant)
+                        .with_durability(entry.item.durability);
                     Some((qty, state))
                 }
             }
@@ -170,7 +182,8 @@ impl InventoryBuilder {
                 return None;
             }
 
-            Some((slot, ItemState::new(item, item_save.variant)))
+            let item_state = ItemState::new(item, item_save.variant);
+            Some((slot, item_state.with_durability(item_save.durability)))
         })
     }
 
@@ -208,7 +221,8 @@ impl InventoryBuilder {
                 }
             }
 
-            Some((slot, ItemState::new(item, item_save.variant)))
+            let item_state = ItemState::new(item, item_save.variant);
+            Some((slot, item_state.with_durability(item_save.durability)))
         })
     }
 }
\ No newline at end of file
@@ -38,6 +38,10 @@ pub struct Equippable {
     pub blocks_slot: Option<Slot>,
     pub bonuses: BonusList,
     pub attack: Option<AttackBuilder>,
+
+    /// The maximum durability.  Items without durability never wear out.
+    #[serde(default)]
+    pub durability: Option<u32>,
 }
 
 #[derive(Debug, Clone)]
@@ -17,27 +17,34 @@
 use std::collections::hash_map::Iter;
 use std::rc::Rc;
 
-use crate::{ImageLayer, Item, Module};
+use crate::{ImageLayer, Item, ItemSaveState, Module};
 use sulis_core::image::Image;
 
 #[derive(Debug, Clone)]
 pub struct ItemState {
     pub item: Rc<Item>,
     pub variant: Option<usize>,
+
+    /// The current durability, or `None` if this item does not have durability
+    pub durability: Option<u32>,
 }
 
 impl PartialEq for ItemState {
     fn eq(&self, other: &ItemState) -> bool {
-        Rc::ptr_eq(&self.item, &other.item) && self.variant == other.variant
+        Rc::ptr_eq(&self.item, &other.item)
+            && self.variant == other.variant
+            && self.durability == other.durability
     }
 }
 
 impl ItemState {
     pub fn new(item: Rc<Item>, variant: Option<usize>) -> ItemState {
+        let durability = item.equippable.as_ref().and_then(|equip| equip.durability);
         match variant {
             None => ItemState {
                 item,
                 variant: None,
+                durability,
             },
             Some(idx) => {
                 if idx >= item.num_variants() {
@@ -45,18 +52,90 @@ impl ItemState {
                     ItemState {
                         item,
                         variant: None,
+                        durability,
                     }
                 } else {
-                    ItemState { item, variant }
+                    ItemState {
+                        item,
+                        variant,
+                        durability,
+                    }
                 }
             }
         }
     }
 
+    /// Sets the current durability, as read from a save.  `None` leaves this
+    /// item at full durability.
+    pub fn with_durability(mut self, durability: Option<u32>) -> ItemState {
+        if let (Some(max), Some(durability)) = (self.max_durability(), durability) {
+            self.durability = Some(durability.min(max));
+        }
+        self
+    }
+
+    pub fn max_durability(&self) -> Option<u32> {
+        self.item
+            .equippable
+            .as_ref()
+            .and_then(|equip| equip.durability)
+    }
+
+    /// Whether this item has worn out completely.  Broken items do not grant
+    /// any bonuses or attacks while equipped.
+    pub fn is_broken(&self) -> bool {
+        self.durability == Some(0)
+    }
+
+    /// Whether this item has less than its maximum durability
+    pub fn is_damaged(&self) -> bool {
+        match (self.durability, self.max_durability()) {
+            (Some(cur), Some(max)) => cur < max,
+            _ => false,
+        }
+    }
+
+    /// The fraction of the maximum durability that has been lost, from 0.0
+    /// for undamaged items to 1.0 for broken ones
+    pub fn damage_frac(&self) -> f32 {
+        match (self.durability, self.max_durability()) {
+            (Some(cur), Some(max)) if max > 0 => 1.0 - cur as f32 / max as f32,
+            _ => 0.0,
+        }
+    }
+
+    /// Reduces the durability by the specified amount.  Returns true if this
+    /// caused the item to break.
+    pub fn wear(&mut self, amount: u32) -> bool {
+        match self.durability {
+            None | Some(0) => false,
+            Some(cur) => {
+                let new = cur.saturating_sub(amount);
+                self.durability = Some(new);
+                new == 0
+            }
+        }
+    }
+
+    pub fn repair(&mut self) {
+        self.durability = self.max_durability();
+    }
+
     pub fn from(id: &str) -> Option<ItemState> {
         Module::item(id).map(|i| ItemState::new(i, None))
     }
 
+    /// Looks up this item again from the `Module`, recreating any adjectives.
+    /// Used when module resources are reloaded.  If the item no longer
+    /// exists, returns a copy of this state.
+    pub fn reloaded(&self) -> ItemState {
+        let save = ItemSaveState::new(self);
+        match Module::create_get_item(&save.id, &save.adjectives) {
+            None => self.clone(),
+            Some(item) => ItemState::new(item, save.variant).with_durability(save.durability),
+        }
+    }
+
     pub fn image_iter(&self) -> Iter<ImageLayer, Rc<dyn Image>> {
         self.item.image_iter(self.variant)
     }
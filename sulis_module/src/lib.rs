@@ -30,7 +30,6 @@ pub use self::ability_list::AbilityList;
 pub mod actor;
 pub use self::actor::Actor;
 pub use self::actor::ActorBuilder;
-pub use self::actor::Faction;
 pub use self::actor::Sex;
 
 pub mod ai;
@@ -59,6 +58,9 @@ pub use self::on_trigger::OnTrigger;
 pub mod encounter;
 pub use self::encounter::Encounter;
 
+pub mod faction;
+pub use self::faction::{Faction, FactionInfo};
+
 pub mod campaign;
 pub use self::campaign::Campaign;
 pub use self::campaign::CampaignGroup;
@@ -108,9 +110,10 @@ pub use self::race::Race;
 pub mod rules;
 pub use self::rules::bonus;
 pub use self::rules::{
-    AccuracyKind, Armor, ArmorKind, Attack, AttackBonuses, AttackKind, Attribute, AttributeList,
-    Bonus, BonusKind, BonusList, Damage, DamageKind, DamageList, HitFlags, HitKind, ItemKind,
-    QuickSlot, Resistance, Rules, Slot, StatList, Time, WeaponKind, WeaponStyle, ROUND_TIME_MILLIS,
+    AccuracyKind, Armor, ArmorKind, Attack, AttackBonuses, AttackKind, AttackRoll, Attribute,
+    AttributeList, Bonus, BonusKind, BonusList, Cover, Damage, DamageKind, DamageList, DamageRoll,
+    Difficulty, HitFlags, HitKind, ItemKind, QuickSlot, Resistance, Rules, SaveKind, SaveResult,
+    SaveRoll, Slot, StatList, Time, WeaponKind, WeaponStyle, ROUND_TIME_MILLIS,
 };
 
 use std::cell::RefCell;
@@ -165,6 +168,7 @@ pub struct Module {
     conversations: HashMap<String, Rc<Conversation>>,
     cutscenes: HashMap<String, Rc<Cutscene>>,
     encounters: HashMap<String, Rc<Encounter>>,
+    factions: HashMap<String, Rc<FactionInfo>>,
     items: HashMap<String, Rc<Item>>,
     item_adjectives: HashMap<String, Rc<ItemAdjective>>,
     loot_lists: HashMap<String, Rc<LootList>>,
@@ -430,6 +434,7 @@ impl Module {
             module.conversations.clear();
             module.cutscenes.clear();
             module.encounters.clear();
+            module.factions.clear();
             module.items.clear();
             module.item_adjectives.clear();
             module.loot_lists.clear();
@@ -461,6 +466,19 @@ impl Module {
                 );
             }
 
+            for faction in FactionInfo::built_in() {
+                module.factions.insert(faction.id.to_string(), Rc::new(faction));
+            }
+
+            for (id, faction) in builder_set.factions {
+                trace!(
+                    "Inserting resource of type faction with key {} \
+                     into module.",
+                    id
+                );
+                module.factions.insert(id, Rc::new(faction));
+            }
+
             for (id, quest) in builder_set.quests {
                 trace!(
                     "Inserting resource of type quest with key {} \
@@ -599,6 +617,75 @@ impl Module {
         Ok(())
     }
 
+    /// Reads the scripts from the specified directories again, replacing the
+    /// current scripts.  Returns the IDs of all scripts that were added,
+    /// changed, or removed.
+    pub fn reload_scripts(dirs: &[String]) -> Vec<String> {
+        let mut scripts = read_to_string(dirs, "scripts");
+        expand_include_directives(&mut scripts);
+
+        MODULE.with(|module| {
+            let mut module = module.borrow_mut();
+
+            let mut changed: Vec<String> = scripts
+                .iter()
+                .filter(|(id, script)| module.scripts.get(*id) != Some(*script))
+                .map(|(id, _)| id.to_string())
+                .collect();
+            for id in module.scripts.keys() {
+                if !scripts.contains_key(id) {
+                    changed.push(id.to_string());
+                }
+            }
+
+            module.scripts = scripts;
+            changed
+        })
+    }
+
+    /// Rebuilds the abilities, items, and conversations from the specified
+    /// data, leaving all other resources as they are.  A resource that fails
+    /// to rebuild keeps its previous definition.
+    pub fn reload_resources(mut yaml: YamlResourceSet) -> Result<(), Error> {
+        let ability_builders: HashMap<String, AbilityBuilder> =
+            read_builders(&mut yaml, YamlResourceKind::Ability)?;
+        let item_builders: HashMap<String, ItemBuilder> =
+            read_builders(&mut yaml, YamlResourceKind::Item)?;
+        let conversation_builders: HashMap<String, ConversationBuilder> =
+            read_builders(&mut yaml, YamlResourceKind::Conversation)?;
+
+        MODULE.with(|module| {
+            let mut module = module.borrow_mut();
+
+            let mut abilities = module.abilities.clone();
+            for (id, builder) in ability_builders {
+                insert_if_ok("ability", id, Ability::new(builder, &module), &mut abilities);
+            }
+            module.abilities = abilities;
+
+            // items with adjectives are created again on demand from the new base item
+            let mut items = module.items.clone();
+            items.retain(|_, item| item.id == item.original_id);
+            for (id, builder) in item_builders {
+                insert_if_ok("item", id, Item::new(builder, &module), &mut items);
+            }
+            module.items = items;
+
+            let mut conversations = module.conversations.clone();
+            for (id, builder) in conversation_builders {
+                insert_if_ok(
+                    "conversation",
+                    id,
+                    Conversation::new(builder, &module),
+                    &mut conversations,
+                );
+            }
+            module.conversations = conversations;
+        });
+
+        Ok(())
+    }
+
     fn load_tiles(module: &mut Module, tile_builders: HashMap<String, Tileset>) {
         let mut feature_builders = Vec::new();
         for (_, mut tiles_list) in tile_builders {
@@ -684,6 +771,18 @@ impl Module {
         MODULE.with(|m| Rc::clone(m.borrow().rules.as_ref().unwrap()))
     }
 
+    /// Modifies the rules of the currently loaded module in place, so that
+    /// script tests may enable optional rules.  Returns None if the rules are
+    /// currently in use elsewhere.
+    #[cfg(feature = "script_test")]
+    pub fn modify_rules<T, F: FnOnce(&mut Rules) -> T>(f: F) -> Option<T> {
+        MODULE.with(|m| {
+            let mut m = m.borrow_mut();
+            let rules = Rc::get_mut(m.rules.as_mut()?)?;
+            Some(f(rules))
+        })
+    }
+
     pub fn wall_rules() -> WallRules {
         MODULE.with(|m| m.borrow().wall_rules.as_ref().unwrap().clone())
     }
@@ -754,6 +853,7 @@ impl Module {
         conversation, conversations, Conversation;
         cutscene, cutscenes, Cutscene;
         encounter, encounters, Encounter;
+        faction, factions, FactionInfo;
         item, items, Item;
         item_adjective, item_adjectives, ItemAdjective;
         loot_list, loot_lists, LootList;
@@ -785,10 +885,22 @@ impl Module {
         })
     }
 
+    pub fn all_abilities() -> Vec<Rc<Ability>> {
+        MODULE.with(|r| all_resources(&r.borrow().abilities))
+    }
+
     pub fn all_actors() -> Vec<Rc<Actor>> {
         MODULE.with(|r| all_resources(&r.borrow().actors))
     }
 
+    pub fn all_ai_templates() -> Vec<Rc<AITemplate>> {
+        MODULE.with(|r| all_resources(&r.borrow().ai_templates))
+    }
+
+    pub fn all_areas() -> Vec<Rc<Area>> {
+        MODULE.with(|r| all_resources(&r.borrow().areas))
+    }
+
     pub fn all_object_sizes() -> Vec<Rc<ObjectSize>> {
         MODULE.with(|r| {
             r.borrow()
@@ -803,14 +915,34 @@ impl Module {
         MODULE.with(|r| all_resources(&r.borrow().classes))
     }
 
+    pub fn all_conversations() -> Vec<Rc<Conversation>> {
+        MODULE.with(|r| all_resources(&r.borrow().conversations))
+    }
+
+    pub fn all_cutscenes() -> Vec<Rc<Cutscene>> {
+        MODULE.with(|r| all_resources(&r.borrow().cutscenes))
+    }
+
     pub fn all_encounters() -> Vec<Rc<Encounter>> {
         MODULE.with(|r| all_resources(&r.borrow().encounters))
     }
 
+    pub fn all_factions() -> Vec<Rc<FactionInfo>> {
+        MODULE.with(|r| all_resources(&r.borrow().factions))
+    }
+
     pub fn all_features() -> Vec<Rc<Feature>> {
         MODULE.with(|r| all_resources(&r.borrow().features))
     }
 
+    pub fn all_items() -> Vec<Rc<Item>> {
+        MODULE.with(|r| all_resources(&r.borrow().items))
+    }
+
+    pub fn all_loot_lists() -> Vec<Rc<LootList>> {
+        MODULE.with(|r| all_resources(&r.borrow().loot_lists))
+    }
+
     pub fn all_props() -> Vec<Rc<Prop>> {
         MODULE.with(|r| all_resources(&r.borrow().props))
     }
@@ -838,6 +970,7 @@ struct ModuleBuilder {
     cutscene_builders: HashMap<String, CutsceneBuilder>,
     conversation_builders: HashMap<String, ConversationBuilder>,
     encounter_builders: HashMap<String, EncounterBuilder>,
+    factions: HashMap<String, FactionInfo>,
     item_builders: HashMap<String, ItemBuilder>,
     loot_builders: HashMap<String, LootListBuilder>,
     prop_builders: HashMap<String, PropBuilder>,
@@ -863,6 +996,7 @@ impl ModuleBuilder {
             conversation_builders: read_builders(resources, Conversation)?,
             cutscene_builders: read_builders(resources, Cutscene)?,
             encounter_builders: read_builders(resources, Encounter)?,
+            factions: read_builders(resources, Faction)?,
             item_builders: read_builders(resources, Item)?,
             item_adjectives: read_builders(resources, ItemAdjective)?,
             loot_builders: read_builders(resources, LootList)?,
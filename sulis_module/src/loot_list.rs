@@ -17,7 +17,7 @@
 use std::collections::HashMap;
 use std::io::Error;
 
-use sulis_core::util::{gen_rand, unable_to_create_error};
+use sulis_core::util::{unable_to_create_error, ReproducibleRandom};
 
 use crate::{ItemState, Module};
 
@@ -89,6 +89,13 @@ impl LootList {
             sub_lists.push(entry);
         }
 
+        // sort all entries so that generation from a given random seed
+        // does not depend on hash map iteration order
+        generate.sort_by_key(|entry| entry.num_items);
+        weighted_entries.sort_by(|a, b| a.id.cmp(&b.id));
+        probability_entries.sort_by(|a, b| a.id.cmp(&b.id));
+        sub_lists.sort_by(|a, b| a.id.cmp(&b.id));
+
         Ok(LootList {
             id: builder.id,
             generate,
@@ -195,6 +202,10 @@ impl LootList {
             variant.push((value, weight));
         }
 
+        adjective1.sort();
+        adjective2.sort();
+        variant.sort();
+
         Ok(Entry {
             id,
             weight: entry_in.weight,
@@ -208,20 +219,30 @@ impl LootList {
         })
     }
 
-    pub fn generate_with_chance(&self, chance: u32) -> Vec<(u32, ItemState)> {
-        let roll = gen_rand(1, 101);
+    /// The IDs of the loot lists referenced as sub lists.  These are
+    /// only looked up when generating loot.
+    pub fn sub_list_ids(&self) -> impl Iterator<Item = &String> {
+        self.sub_lists.iter().map(|entry| &entry.id)
+    }
+
+    pub fn generate_with_chance(
+        &self,
+        chance: u32,
+        rand: &mut ReproducibleRandom,
+    ) -> Vec<(u32, ItemState)> {
+        let roll = rand.gen(1, 101);
         if chance >= roll {
-            self.generate_internal(0)
+            self.generate_internal(0, rand)
         } else {
             Vec::new()
         }
     }
 
-    pub fn generate(&self) -> Vec<(u32, ItemState)> {
-        self.generate_internal(0)
+    pub fn generate(&self, rand: &mut ReproducibleRandom) -> Vec<(u32, ItemState)> {
+        self.generate_internal(0, rand)
     }
 
-    fn generate_internal(&self, depth: u32) -> Vec<(u32, ItemState)> {
+    fn generate_internal(&self, depth: u32, rand: &mut ReproducibleRandom) -> Vec<(u32, ItemState)> {
         if depth >= MAX_DEPTH {
             warn!(
                 "Exceeded maximum sub list depth of {}.  \
@@ -231,27 +252,27 @@ impl LootList {
             return Vec::new();
         }
 
-        let num_items = self.gen_num_items();
+        let num_items = self.gen_num_items(rand);
 
         let mut items = Vec::new();
         if num_items > 0 {
             for _ in 0..num_items {
-                if let Some(item) = self.gen_item() {
+                if let Some(item) = self.gen_item(rand) {
                     items.push(item);
                 }
             }
         }
 
         for entry in self.probability_entries.iter() {
-            let roll = gen_rand(0, 100);
+            let roll = rand.gen(0, 100);
             if roll < entry.weight {
                 let quantity = if entry.quantity[0] == entry.quantity[1] {
                     entry.quantity[0]
                 } else {
-                    gen_rand(entry.quantity[0], entry.quantity[1] + 1)
+                    rand.gen(entry.quantity[0], entry.quantity[1] + 1)
                 };
 
-                let adjectives = self.gen_adjectives(entry);
+                let adjectives = self.gen_adjectives(entry, rand);
                 let item = match Module::create_get_item(&entry.id, &adjectives) {
                     None => {
                         warn!(
@@ -262,7 +283,7 @@ impl LootList {
                     }
                     Some(item) => item,
                 };
-                let variant = self.gen_variant(entry);
+                let variant = self.gen_variant(entry, rand);
                 items.push((quantity, ItemState::new(item, variant)));
             }
         }
@@ -279,16 +300,16 @@ impl LootList {
                 Some(list) => list,
             };
 
-            let roll = gen_rand(0, 100);
+            let roll = rand.gen(0, 100);
             if roll < entry.weight {
                 let times = if entry.quantity[0] == entry.quantity[1] {
                     entry.quantity[0]
                 } else {
-                    gen_rand(entry.quantity[0], entry.quantity[1] + 1)
+                    rand.gen(entry.quantity[0], entry.quantity[1] + 1)
                 };
 
                 for _ in 0..times {
-                    let subitems = sub_list.generate_internal(depth + 1);
+                    let subitems = sub_list.generate_internal(depth + 1, rand);
                     for (quantity, item) in subitems {
                         items.push((quantity, item));
                     }
@@ -299,10 +320,10 @@ impl LootList {
         items
     }
 
-    fn gen_adjectives(&self, entry: &Entry) -> Vec<String> {
+    fn gen_adjectives(&self, entry: &Entry, rand: &mut ReproducibleRandom) -> Vec<String> {
         let mut result = Vec::new();
         if entry.adjective1_total_weight > 0 {
-            let roll = gen_rand(0, entry.adjective1_total_weight);
+            let roll = rand.gen(0, entry.adjective1_total_weight);
 
             let mut cur_weight = 0;
             for (id, weight) in entry.adjective1.iter() {
@@ -315,7 +336,7 @@ impl LootList {
         }
 
         if entry.adjective2_total_weight > 0 {
-            let roll = gen_rand(0, entry.adjective2_total_weight);
+            let roll = rand.gen(0, entry.adjective2_total_weight);
 
             let mut cur_weight = 0;
             for (id, weight) in entry.adjective2.iter() {
@@ -330,9 +351,9 @@ impl LootList {
         result
     }
 
-    fn gen_variant(&self, entry: &Entry) -> Option<usize> {
+    fn gen_variant(&self, entry: &Entry, rand: &mut ReproducibleRandom) -> Option<usize> {
         if entry.variant_total_weight > 0 {
-            let roll = gen_rand(0, entry.variant_total_weight);
+            let roll = rand.gen(0, entry.variant_total_weight);
             let mut cur_weight = 0;
             for (id, weight) in entry.variant.iter() {
                 cur_weight += weight;
@@ -345,8 +366,8 @@ impl LootList {
         None
     }
 
-    fn gen_item(&self) -> Option<(u32, ItemState)> {
-        let roll = gen_rand(0, self.total_entries_weight);
+    fn gen_item(&self, rand: &mut ReproducibleRandom) -> Option<(u32, ItemState)> {
+        let roll = rand.gen(0, self.total_entries_weight);
 
         let mut cur_weight = 0;
         for entry in self.weighted_entries.iter() {
@@ -355,10 +376,10 @@ impl LootList {
                 let quantity = if entry.quantity[0] == entry.quantity[1] {
                     entry.quantity[0]
                 } else {
-                    gen_rand(entry.quantity[0], entry.quantity[1] + 1)
+                    rand.gen(entry.quantity[0], entry.quantity[1] + 1)
                 };
 
-                let adjectives = self.gen_adjectives(entry);
+                let adjectives = self.gen_adjectives(entry, rand);
                 let item = match Module::create_get_item(&entry.id, &adjectives) {
                     None => {
                         warn!(
@@ -369,7 +390,7 @@ impl LootList {
                     }
                     Some(item) => item,
                 };
-                let variant = self.gen_variant(entry);
+                let variant = self.gen_variant(entry, rand);
                 return Some((quantity, ItemState::new(item, variant)));
             }
         }
@@ -377,12 +398,12 @@ impl LootList {
         None
     }
 
-    fn gen_num_items(&self) -> u32 {
+    fn gen_num_items(&self, rand: &mut ReproducibleRandom) -> u32 {
         if self.total_generate_weight == 0 {
             return 0;
         }
 
-        let roll = gen_rand(0, self.total_generate_weight);
+        let roll = rand.gen(0, self.total_generate_weight);
 
         let mut cur_gen_weight = 0;
         for generate in self.generate.iter() {
@@ -39,6 +39,11 @@ pub struct MerchantData {
 
     #[serde(default)]
     pub refresh_time: Time,
+
+    /// The fraction of an item's value charged to fully repair it.  Merchants
+    /// without this do not offer repairs.
+    #[serde(default)]
+    pub repair_frac: Option<f32>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -114,6 +119,13 @@ pub struct QuestStateData {
     pub state: QuestEntryState,
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ReputationData {
+    pub faction: String,
+    pub amount: i32,
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct ModuleLoadData {
@@ -153,6 +165,8 @@ pub enum OnTrigger {
     ShowMenu(MenuData),
     QuestState(QuestStateData),
     NotQuestState(QuestStateData),
+    Reputation(ReputationData), // add on activate, at least this much on match
+    NotReputation(ReputationData), // less than this much on match
     FadeOutIn,
     CheckEndTurn,
 }
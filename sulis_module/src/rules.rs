@@ -51,9 +51,10 @@ pub use self::resistance::Resistance;
 pub mod stat_list;
 pub use self::stat_list::StatList;
 
+use crate::ai::PartyAIMode;
 use crate::area::LocationKind;
 use sulis_core::ui::{color, Color};
-use sulis_core::util::{gen_rand, invalid_data_error};
+use sulis_core::util::{gen_rand, invalid_data_error, ReproducibleRandom};
 
 #[derive(Deserialize, Debug)]
 #[serde(deny_unknown_fields)]
@@ -118,9 +119,418 @@ pub struct Rules {
     pub hints: Vec<String>,
 
     pub main_menu_music: Option<String>,
+
+    #[serde(default)]
+    pub reactions: ReactionRules,
+
+    #[serde(default)]
+    pub cover: CoverRules,
+
+    #[serde(default)]
+    pub elevation: ElevationRules,
+
+    #[serde(default)]
+    pub encumbrance: EncumbranceRules,
+
+    #[serde(default)]
+    pub durability: DurabilityRules,
+
+    #[serde(default)]
+    pub saving_throws: SaveRules,
+
+    #[serde(default)]
+    pub stealth: StealthRules,
+
+    #[serde(default)]
+    pub party_ai: PartyAIRules,
+
+    /// The difficulty levels the player may choose from.  If empty, only the
+    /// default difficulty, with no modifiers, is available.
+    #[serde(default)]
+    pub difficulties: Vec<Difficulty>,
+
+    /// The ID of the difficulty used for new games and for saves that do not
+    /// specify one
+    #[serde(default)]
+    pub default_difficulty: String,
+}
+
+/// Controls reactions, such as attacks of opportunity, which entities may
+/// take outside of their own turn against enemies they threaten
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ReactionRules {
+    pub enabled: bool,
+
+    /// The number of reactions each entity may take per round, before bonuses
+    pub base_per_round: u32,
+
+    /// Whether moving out of a threatened square provokes reactions
+    pub provoked_by_movement: bool,
+
+    /// Activating an ability from one of these groups while threatened
+    /// provokes reactions
+    #[serde(default)]
+    pub provoked_by_ability_groups: Vec<String>,
+}
+
+/// Controls the bonuses a defender receives when part of its footprint is
+/// hidden from the attacker by walls, props, or other creatures
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct CoverRules {
+    pub enabled: bool,
+
+    /// Whether creatures standing between the attacker and defender provide cover
+    pub creatures_block: bool,
+
+    pub partial: CoverBonuses,
+    pub heavy: CoverBonuses,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct CoverBonuses {
+    /// The fraction of the defender's footprint, from 0.0 to 1.0, which must
+    /// be hidden for this level of cover to apply
+    pub min_blocked: f32,
+    pub defense: i32,
+    pub reflex: i32,
+    pub concealment: i32,
+}
+
+impl CoverRules {
+    /// Returns the level of cover for a defender with the specified fraction
+    /// of its footprint hidden from the attacker
+    pub fn cover(&self, blocked: f32) -> Cover {
+        if !self.enabled || blocked <= 0.0 {
+            Cover::None
+        } else if blocked >= self.heavy.min_blocked {
+            Cover::Heavy
+        } else if blocked >= self.partial.min_blocked {
+            Cover::Partial
+        } else {
+            Cover::None
+        }
+    }
+
+    pub fn bonuses(&self, cover: Cover) -> Option<&CoverBonuses> {
+        match cover {
+            Cover::None => None,
+            Cover::Partial => Some(&self.partial),
+            Cover::Heavy => Some(&self.heavy),
+        }
+    }
+}
+
+/// Controls the advantage an attacker gains from standing on higher ground
+/// than the defender, and the penalty for attacking uphill
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ElevationRules {
+    pub enabled: bool,
+
+    /// Differences in elevation beyond this many levels give no further
+    /// bonus or penalty
+    pub max_levels: u8,
+
+    /// Accuracy bonus for each level the attacker is above the defender
+    pub high_ground_accuracy: i32,
+
+    /// Accuracy bonus, usually negative, for each level the attacker is
+    /// below the defender
+    pub low_ground_accuracy: i32,
+
+    /// Additional reach for ranged attacks, in squares, for each level the
+    /// attacker is above the defender
+    pub high_ground_ranged_distance: f32,
+}
+
+impl ElevationRules {
+    /// Returns the number of levels the attacker is above the defender, which
+    /// is negative if the attacker is below
+    pub fn levels(&self, attacker: u8, defender: u8) -> i32 {
+        if !self.enabled {
+            return 0;
+        }
+
+        let max = self.max_levels as i32;
+        (attacker as i32 - defender as i32).clamp(-max, max)
+    }
+
+    pub fn accuracy_bonus(&self, attacker: u8, defender: u8) -> i32 {
+        let levels = self.levels(attacker, defender);
+        if levels > 0 {
+            levels * self.high_ground_accuracy
+        } else {
+            -levels * self.low_ground_accuracy
+        }
+    }
+
+    pub fn ranged_distance_bonus(&self, attacker: u8, defender: u8) -> f32 {
+        let levels = self.levels(attacker, defender);
+        if levels > 0 {
+            levels as f32 * self.high_ground_ranged_distance
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Controls how much weight each creature may carry, and the penalties for
+/// carrying more than that.  Weights are in the same units as item weights.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct EncumbranceRules {
+    pub enabled: bool,
+
+    /// The carrying capacity of a creature with Strength equal to the base
+    /// attribute, before bonuses
+    pub base_capacity: i32,
+
+    /// Capacity gained for each point of Strength above the base attribute,
+    /// or lost for each point below
+    pub capacity_per_strength: i32,
+
+    #[serde(default)]
+    pub levels: Vec<EncumbranceLevel>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct EncumbranceLevel {
+    pub name: String,
+
+    /// This level applies when the load is greater than this fraction of the
+    /// carrying capacity
+    pub min_load: f32,
+
+    /// The movement rate is multiplied by this value
+    pub movement_rate_multiplier: f32,
+
+    /// Bonus, usually negative, to the action points gained each round
+    pub action_points: i32,
+}
+
+impl EncumbranceRules {
+    pub fn capacity(&self, attributes: AttributeList, base_attr: i32) -> i32 {
+        let str_bonus = attributes.bonus(Attribute::Strength, base_attr);
+        self.base_capacity + str_bonus * self.capacity_per_strength
+    }
+
+    /// Returns the most severe encumbrance level for the specified load, or
+    /// `None` if the load is within the carrying capacity
+    pub fn level(&self, load: i32, capacity: i32) -> Option<&EncumbranceLevel> {
+        if !self.enabled {
+            return None;
+        }
+
+        let capacity = capacity.max(0) as f32;
+        self.levels
+            .iter()
+            .filter(|level| load as f32 > capacity * level.min_load)
+            .max_by(|a, b| a.min_load.total_cmp(&b.min_load))
+    }
+}
+
+/// Controls how quickly items with durability wear out.  Weapons wear when
+/// their attacks hit, and other equipped items wear when the wearer is
+/// damaged by an attack.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct DurabilityRules {
+    pub enabled: bool,
+
+    /// Durability lost by each equipped weapon when a weapon attack hits
+    pub weapon_wear_per_hit: u32,
+
+    /// Durability lost by each other equipped item when the wearer takes
+    /// damage from an attack
+    pub armor_wear_per_damage: u32,
+}
+
+/// Controls the outcome of saving throws, see `StatList::save_roll`.  The
+/// margin of a save is the roll plus the relevant defense, minus the DC.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SaveRules {
+    /// Failed saves within this many points of the DC are a partial success
+    pub partial_margin: i32,
+
+    /// Saves exceeding the DC by at least this many points are a critical
+    /// success
+    pub crit_margin: i32,
+}
+
+impl Default for SaveRules {
+    fn default() -> SaveRules {
+        SaveRules {
+            partial_margin: 20,
+            crit_margin: 50,
+        }
+    }
+}
+
+impl SaveRules {
+    pub fn result(&self, margin: i32) -> SaveResult {
+        if margin >= self.crit_margin {
+            SaveResult::CritSuccess
+        } else if margin >= 0 {
+            SaveResult::Success
+        } else if margin >= -self.partial_margin {
+            SaveResult::Partial
+        } else {
+            SaveResult::Fail
+        }
+    }
+}
+
+/// Controls whether hidden entities are detected by the hostile entities
+/// that can see them.  A hidden entity is detected when the sum of its
+/// stealth, concealment, light, cover, and distance bonuses is less than the
+/// observer's perception, multiplied by `perception_factor`.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct StealthRules {
+    pub enabled: bool,
+
+    pub perception_factor: i32,
+
+    /// Bonus for each square of distance between the observer and the hidden
+    /// entity beyond `break_even_distance`, or penalty for each square closer
+    pub distance_factor: i32,
+    pub break_even_distance: f32,
+
+    /// Bonus in complete darkness, scaled by the brightness of the area's
+    /// current color
+    pub darkness_bonus: i32,
+
+    pub partial_cover_bonus: i32,
+    pub heavy_cover_bonus: i32,
+
+    /// Bonus to the perception of an entity for the round after it searches
+    pub search_bonus: i8,
+
+    /// The action point cost of searching
+    pub search_ap: u32,
+}
+
+impl StealthRules {
+    pub fn cover_bonus(&self, cover: Cover) -> i32 {
+        match cover {
+            Cover::None => 0,
+            Cover::Partial => self.partial_cover_bonus,
+            Cover::Heavy => self.heavy_cover_bonus,
+        }
+    }
+
+    /// Returns the bonus for the specified area color, with brighter colors
+    /// giving a smaller bonus
+    pub fn darkness_bonus(&self, color: Color) -> i32 {
+        let brightness = ((color.r + color.g + color.b) / 3.0).clamp(0.0, 1.0);
+        ((1.0 - brightness) * self.darkness_bonus as f32).round() as i32
+    }
+}
+
+/// Controls the AI of party members, which may be given an AI mode
+/// individually or all be placed under AI control with auto-combat
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct PartyAIRules {
+    /// The ID of the `AITemplate` used for each mode
+    pub templates: HashMap<PartyAIMode, String>,
+
+    /// The mode used by party members without one of their own when
+    /// auto-combat is enabled
+    pub default_mode: PartyAIMode,
+}
+
+/// A difficulty level, modifying the stats of creatures hostile to the party
+/// and the experience gained from defeating them
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Difficulty {
+    pub id: String,
+    pub name: String,
+
+    #[serde(default)]
+    pub description: String,
+
+    pub enemy_hp_multiplier: f32,
+    pub enemy_damage_multiplier: f32,
+    pub enemy_accuracy_multiplier: f32,
+    pub xp_multiplier: f32,
+
+    /// Whether party members are injured when they fall in combat.  This is
+    /// checked by the campaign's party death script.
+    pub party_injuries: bool,
+}
+
+impl Default for Difficulty {
+    fn default() -> Difficulty {
+        Difficulty {
+            id: "normal".to_string(),
+            name: "Normal".to_string(),
+            description: String::new(),
+            enemy_hp_multiplier: 1.0,
+            enemy_damage_multiplier: 1.0,
+            enemy_accuracy_multiplier: 1.0,
+            xp_multiplier: 1.0,
+            party_injuries: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Default)]
+pub enum Cover {
+    #[default]
+    None,
+    Partial,
+    Heavy,
+}
+
+impl Cover {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Cover::None => "none",
+            Cover::Partial => "partial",
+            Cover::Heavy => "heavy",
+        }
+    }
 }
 
 impl Rules {
+    /// Returns the difficulty with the specified ID, if it exists
+    pub fn difficulty(&self, id: &str) -> Option<&Difficulty> {
+        self.difficulties
+            .iter()
+            .find(|difficulty| difficulty.id == id)
+    }
+
+    /// Returns the difficulty used for new games
+    pub fn default_difficulty(&self) -> Difficulty {
+        match self.difficulty(&self.default_difficulty) {
+            Some(difficulty) => difficulty.clone(),
+            None => self.difficulties.first().cloned().unwrap_or_default(),
+        }
+    }
+
+    /// Returns the difficulty following the one with the specified id,
+    /// wrapping around to the first.  Used for cycling through the
+    /// difficulties in the UI.
+    pub fn next_difficulty(&self, id: &str) -> Option<&Difficulty> {
+        if self.difficulties.is_empty() {
+            return None;
+        }
+
+        let index = self
+            .difficulties
+            .iter()
+            .position(|difficulty| difficulty.id == id)
+            .map_or(0, |index| index + 1);
+        self.difficulties.get(index % self.difficulties.len())
+    }
+
     pub fn play_main_menu_music(&self) {
         if let Some(music) = self.main_menu_music.as_ref() {
             sulis_core::io::Audio::play_music(music, 1.0);
@@ -256,7 +666,26 @@ impl Rules {
         armor: &Armor,
         resistance: &Resistance,
         multiplier: f32,
+        rand: &mut ReproducibleRandom,
     ) -> Vec<(DamageKind, u32)> {
+        self.roll_damage_details(damage, armor, resistance, multiplier, rand)
+            .into_iter()
+            .filter(|roll| roll.amount > 0)
+            .map(|roll| (roll.kind, roll.amount))
+            .collect()
+    }
+
+    /// Rolls damage as in `roll_damage`, returning each damage component with
+    /// the amounts before and after resistance and armor.  Unlike
+    /// `roll_damage`, components reduced to zero damage are included.
+    pub fn roll_damage_details(
+        &self,
+        damage: &DamageList,
+        armor: &Armor,
+        resistance: &Resistance,
+        multiplier: f32,
+        rand: &mut ReproducibleRandom,
+    ) -> Vec<DamageRoll> {
         debug!(
             "Rolling damage from {} to {} vs {} base armor",
             damage.min(),
@@ -273,7 +702,8 @@ impl Rules {
             let kind = damage.kind.unwrap();
 
             let resistance = (100 - resistance.amount(kind)) as f32 / 100.0;
-            let amount = damage.roll() as f32 * multiplier * resistance;
+            let rolled = damage.roll(rand);
+            let amount = rolled as f32 * multiplier * resistance;
 
             let armor = max(0, armor.amount(kind) as i32 - damage.ap as i32) as u32;
             let armor_max = self.armor_damage_reduction_cap(armor) as f32 * amount / 100.0;
@@ -282,10 +712,18 @@ impl Rules {
             let armor = if armor_max > armor { armor } else { armor_max };
             let armor = if armor > amount { amount } else { armor };
 
-            let amount = amount - armor;
-            if amount > 0.0 {
-                output.push((kind, amount.ceil() as u32));
-            }
+            let final_amount = amount - armor;
+            output.push(DamageRoll {
+                kind,
+                rolled,
+                resisted: amount,
+                armor,
+                amount: if final_amount > 0.0 {
+                    final_amount.ceil() as u32
+                } else {
+                    0
+                },
+            });
         }
 
         output
@@ -312,11 +750,11 @@ impl Rules {
         self.experience_for_level[(cur_level - 1) as usize]
     }
 
-    pub fn concealment_roll(&self, concealment: i32) -> bool {
+    pub fn concealment_roll(&self, concealment: i32, rand: &mut ReproducibleRandom) -> bool {
         if concealment == 0 {
             return true;
         }
-        let roll = gen_rand(1, 101);
+        let roll = rand.gen(1, 101);
         debug!("Concealment roll: {} against {}", roll, concealment);
         roll > concealment
     }
@@ -492,6 +930,35 @@ pub enum ItemKind {
     Other,
 }
 
+/// The result of a single attack roll, see `StatList::attack_roll`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttackRoll {
+    pub hit_kind: HitKind,
+
+    /// The random roll, from 1 to 100
+    pub roll: i32,
+    pub accuracy: i32,
+    pub defense: i32,
+}
+
+/// A single rolled damage component, see `Rules::roll_damage_details`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct DamageRoll {
+    pub kind: DamageKind,
+
+    /// The random roll for this component, before any modifiers
+    pub rolled: u32,
+
+    /// The damage after the multiplier and resistance, before armor
+    pub resisted: f32,
+
+    /// The amount the damage was reduced by armor
+    pub armor: f32,
+
+    /// The final damage amount applied
+    pub amount: u32,
+}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Default)]
 pub struct HitFlags {
     pub flanking: bool,
@@ -499,7 +966,7 @@ pub struct HitFlags {
     pub concealment: bool,
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord)]
 pub enum HitKind {
     Miss,
     Graze,
@@ -530,6 +997,77 @@ impl FromStr for HitKind {
     }
 }
 
+/// The defense used to resist a saving throw
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SaveKind {
+    Fortitude,
+    Reflex,
+    Will,
+}
+
+impl SaveKind {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            SaveKind::Fortitude => "fortitude",
+            SaveKind::Reflex => "reflex",
+            SaveKind::Will => "will",
+        }
+    }
+}
+
+impl FromStr for SaveKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let val = match s {
+            "fortitude" | "Fortitude" => SaveKind::Fortitude,
+            "reflex" | "Reflex" => SaveKind::Reflex,
+            "will" | "Will" => SaveKind::Will,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Unable to parse SaveKind from '{}'", s),
+                ));
+            }
+        };
+
+        Ok(val)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord)]
+pub enum SaveResult {
+    Fail,
+    Partial,
+    Success,
+    CritSuccess,
+}
+
+impl SaveResult {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            SaveResult::Fail => "fail",
+            SaveResult::Partial => "partial",
+            SaveResult::Success => "success",
+            SaveResult::CritSuccess => "crit_success",
+        }
+    }
+}
+
+/// The result of a single saving throw, see `StatList::save_roll`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SaveRoll {
+    pub kind: SaveKind,
+    pub result: SaveResult,
+
+    /// The random roll, from 1 to 100
+    pub roll: i32,
+
+    /// The defense added to the roll
+    pub bonus: i32,
+    pub dc: i32,
+}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Deserialize, Serialize)]
 pub enum WeaponStyle {
     Ranged,
@@ -251,7 +251,7 @@ pub enum AttackKind {
     Dummy,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub enum AccuracyKind {
     Melee,
     Ranged,
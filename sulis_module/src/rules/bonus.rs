@@ -16,6 +16,9 @@
 
 use std::mem;
 
+use serde::de::{self, Deserialize, Deserializer};
+
+use crate::area::LocationKind;
 use crate::rules::{
     ArmorKind, Attribute, Damage, DamageKind, HitKind, Slot, WeaponKind, WeaponStyle
 };
@@ -57,6 +60,9 @@ pub enum BonusKind {
     AttackCost(i32),
     FlankingAngle(i32),
     CasterLevel(i32),
+    Reactions(i32),
+    CarryCapacity(i32),
+    Stealth(i32),
     AbilityActionPointCost(i32),
     FreeAbilityGroupUse,
     MoveDisabled,
@@ -71,7 +77,7 @@ pub enum BonusKind {
     ClassStat { id: String, amount: i32 },
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(deny_unknown_fields)]
 pub enum Contingent {
     /// Bonuses that should always be applied
@@ -105,8 +111,68 @@ pub enum Contingent {
     /// Bonuses that are only applied to attacks with the specified base damage kind
     AttackWithDamageKind(DamageKind),
 
+    /// Bonuses that are only applied to attacks against a target with an
+    /// active effect with the specified tag
+    TargetHasEffect(String),
+
     /// Bonuses that only apply when the parent is threatened in melee
     Threatened,
+
+    /// Bonuses that only apply when the parent's hit points are below the
+    /// specified percentage of their maximum
+    HpBelow(u32),
+
+    /// Bonuses that only apply when the parent is in an area of the specified kind
+    LocationKind(LocationKind),
+
+    /// Bonuses that only apply from the start of the `from` hour until the start
+    /// of the `to` hour.  The range wraps past midnight if `from` is greater
+    /// than `to`.  `from` must be less than 24, and `to` at most 24.
+    TimeOfDay {
+        #[serde(deserialize_with = "de_from_hour")]
+        from: u32,
+        #[serde(deserialize_with = "de_to_hour")]
+        to: u32,
+    },
+
+    /// Bonuses that only apply while combat is active
+    InCombat,
+
+    /// Bonuses that only apply while combat is not active
+    OutOfCombat,
+
+    /// Bonuses that only apply when the parent is adjacent to a friendly creature
+    AdjacentToAlly,
+}
+
+/// The number of hours in a day, for `TimeOfDay` contingents
+const HOURS_PER_DAY: u32 = 24;
+
+/// Returns true if `from` and `to` are valid hours for a `TimeOfDay` contingent
+pub fn is_valid_time_of_day(from: u32, to: u32) -> bool {
+    from < HOURS_PER_DAY && to <= HOURS_PER_DAY
+}
+
+fn de_from_hour<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    let hour = u32::deserialize(deserializer)?;
+    if !is_valid_time_of_day(hour, 0) {
+        return Err(de::Error::custom(format!(
+            "Invalid time_of_day from hour {}, must be less than {}",
+            hour, HOURS_PER_DAY
+        )));
+    }
+    Ok(hour)
+}
+
+fn de_to_hour<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    let hour = u32::deserialize(deserializer)?;
+    if !is_valid_time_of_day(0, hour) {
+        return Err(de::Error::custom(format!(
+            "Invalid time_of_day to hour {}, must be at most {}",
+            hour, HOURS_PER_DAY
+        )));
+    }
+    Ok(hour)
 }
 
 impl Default for Contingent {
@@ -115,6 +181,61 @@ impl Default for Contingent {
     }
 }
 
+impl Contingent {
+    /// Returns whether this contingent applies in the given `state`.  This is
+    /// only meaningful for the contingents that depend on the state of the
+    /// parent, and returns None for all others.
+    pub fn applies(&self, state: &ContingentState) -> Option<bool> {
+        use self::Contingent::*;
+        let applies = match self {
+            Threatened => state.threatened,
+            HpBelow(percent) => state.hp_frac * 100.0 < *percent as f32,
+            LocationKind(kind) => state.location_kind == Some(*kind),
+            TimeOfDay { from, to } => {
+                if from <= to {
+                    state.hour >= *from && state.hour < *to
+                } else {
+                    state.hour >= *from || state.hour < *to
+                }
+            }
+            InCombat => state.in_combat,
+            OutOfCombat => !state.in_combat,
+            AdjacentToAlly => state.adjacent_to_ally,
+            _ => return None,
+        };
+        Some(applies)
+    }
+}
+
+/// The current conditions of a creature which determine whether its
+/// contingent bonuses apply.  See `Contingent::applies`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContingentState {
+    pub threatened: bool,
+
+    /// The current hit points as a fraction of the maximum
+    pub hp_frac: f32,
+    pub location_kind: Option<LocationKind>,
+
+    /// The current hour of the day
+    pub hour: u32,
+    pub in_combat: bool,
+    pub adjacent_to_ally: bool,
+}
+
+impl Default for ContingentState {
+    fn default() -> ContingentState {
+        ContingentState {
+            threatened: false,
+            hp_frac: 1.0,
+            location_kind: None,
+            hour: 0,
+            in_combat: false,
+            adjacent_to_ally: false,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialOrd, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct Bonus {
@@ -132,6 +253,10 @@ impl BonusList {
         self.0.iter()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     pub fn add(&mut self, bonus: Bonus) {
         self.0.push(bonus);
     }
@@ -222,6 +347,9 @@ fn apply_modifiers(bonus: &mut Bonus, neg: f32, pos: f32) {
         AttackCost(val) => get_mod!(AttackCost(val): i32, neg, pos),
         FlankingAngle(val) => get_mod!(FlankingAngle(val): i32, neg, pos),
         CasterLevel(val) => get_mod!(CasterLevel(val): i32, neg, pos),
+        Reactions(val) => get_mod!(Reactions(val): i32, neg, pos),
+        CarryCapacity(val) => get_mod!(CarryCapacity(val): i32, neg, pos),
+        Stealth(val) => get_mod!(Stealth(val): i32, neg, pos),
         AbilityActionPointCost(val) => get_mod!(AbilityActionPointCost(val): i32, neg, pos),
         Damage(damage) => Damage(damage.mult_f32(pos)),
         ClassStat { ref id, amount } => ClassStat {
@@ -316,7 +444,7 @@ pub fn merge_if_dup(first: &Bonus, sec: &Bonus) -> Option<Bonus> {
         return None;
     }
 
-    let when = first.when;
+    let when = first.when.clone();
     use self::BonusKind::*;
     match first.kind {
         Attribute { attribute, amount } => merge_dup!(Attribute { attribute, amount }: sec, when),
@@ -379,6 +507,9 @@ pub fn merge_if_dup(first: &Bonus, sec: &Bonus) -> Option<Bonus> {
         AttackCost(val) => merge_dup!(AttackCost(val): sec, when),
         FlankingAngle(val) => merge_dup!(FlankingAngle(val): sec, when),
         CasterLevel(val) => merge_dup!(CasterLevel(val): sec, when),
+        Reactions(val) => merge_dup!(Reactions(val): sec, when),
+        CarryCapacity(val) => merge_dup!(CarryCapacity(val): sec, when),
+        Stealth(val) => merge_dup!(Stealth(val): sec, when),
     }
 }
 
@@ -17,7 +17,7 @@
 use std::fmt::{self, Display};
 use std::slice::Iter;
 
-use sulis_core::util::gen_rand;
+use sulis_core::util::ReproducibleRandom;
 
 #[derive(Clone)]
 pub struct DamageList {
@@ -294,7 +294,7 @@ impl Damage {
         (self.min as f32 + self.max as f32) / 2.0
     }
 
-    pub fn roll(&self) -> u32 {
-        gen_rand(self.min, self.max + 1)
+    pub fn roll(&self, rand: &mut ReproducibleRandom) -> u32 {
+        rand.gen(self.min, self.max + 1)
     }
 }
\ No newline at end of file
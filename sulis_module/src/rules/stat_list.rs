@@ -17,14 +17,16 @@
 use std::collections::HashMap;
 use std::rc::Rc;
 
-use crate::rules::bonus::{AttackBonuses, AttackBuilder, Bonus, BonusKind, BonusList};
+use crate::rules::bonus::{
+    AttackBonuses, AttackBuilder, Bonus, BonusKind, BonusList, ContingentState,
+};
 use crate::rules::{
-    AccuracyKind, Armor, ArmorKind, Attack, AttributeList, Damage, HitKind, Resistance, Slot,
-    WeaponKind, WeaponStyle,
+    AccuracyKind, Armor, ArmorKind, Attack, AttackRoll, AttributeList, Damage, Difficulty, HitKind,
+    Resistance, SaveKind, SaveRoll, Slot, WeaponKind, WeaponStyle,
 };
 use crate::{Actor, Module};
 use sulis_core::image::Image;
-use sulis_core::util::{gen_rand, ExtInt};
+use sulis_core::util::{ExtInt, ReproducibleRandom};
 
 #[derive(Clone)]
 pub struct StatList {
@@ -39,6 +41,8 @@ pub struct StatList {
     pub contingent_bonuses: BonusList,
     // bonuses contingent on flanking that are only applied to some attacks
     pub flanking_bonuses: BonusList,
+    // bonuses contingent on the effects of the target of an attack
+    pub target_effect_bonuses: BonusList,
 
     // these bonuses are applied only to the attack itself of the given weaponkind
     pub attack_bonuses: Vec<Bonus>,
@@ -81,6 +85,15 @@ pub struct StatList {
     pub crit_immunity: bool,
     pub free_ability_group_use: bool,
     pub caster_level: i32,
+    pub reactions: i32,
+    pub carry_capacity: i32,
+    pub stealth: i32,
+
+    /// The weight carried, set by `apply_encumbrance`
+    pub load: i32,
+
+    /// The name of the encumbrance level for the current load, if any
+    pub encumbrance: Option<String>,
     has_shield: bool,
     group_uses_per_encounter: HashMap<String, ExtInt>,
     group_uses_per_day: HashMap<String, ExtInt>,
@@ -95,6 +108,7 @@ impl StatList {
             weapon_proficiencies: Vec::new(),
             contingent_bonuses: BonusList::default(),
             flanking_bonuses: BonusList::default(),
+            target_effect_bonuses: BonusList::default(),
             attack_bonuses: Vec::new(),
             bonus_ap: 0,
             bonus_ability_action_point_cost: 0,
@@ -136,6 +150,11 @@ impl StatList {
             crit_immunity: false,
             free_ability_group_use: false,
             caster_level: 0,
+            reactions: 0,
+            carry_capacity: 0,
+            stealth: 0,
+            load: 0,
+            encumbrance: None,
             has_shield: false,
             group_uses_per_encounter: HashMap::new(),
             group_uses_per_day: HashMap::new(),
@@ -180,32 +199,42 @@ impl StatList {
         self.weapon_proficiencies.contains(&prof)
     }
 
+    /// Rolls an attack with the specified accuracy against `defense`, returning
+    /// the kind of hit along with the roll and values it was computed from
     pub fn attack_roll(
         &self,
         accuracy_kind: AccuracyKind,
         crit_immunity: bool,
         defense: i32,
         bonuses: &AttackBonuses,
-    ) -> HitKind {
+        rand: &mut ReproducibleRandom,
+    ) -> AttackRoll {
         let accuracy = match accuracy_kind {
             AccuracyKind::Melee => self.melee_accuracy + bonuses.melee_accuracy,
             AccuracyKind::Ranged => self.ranged_accuracy + bonuses.ranged_accuracy,
             AccuracyKind::Spell => self.spell_accuracy + bonuses.spell_accuracy,
         };
-        let roll = gen_rand(1, 101);
+        let roll = rand.gen(1, 101);
         debug!(
             "Attack roll: {} with accuracy {} against {}",
             roll, accuracy, defense
         );
 
+        let attack_roll = |hit_kind| AttackRoll {
+            hit_kind,
+            roll,
+            accuracy,
+            defense,
+        };
+
         if roll + accuracy < defense {
-            return HitKind::Miss;
+            return attack_roll(HitKind::Miss);
         }
 
         let result = roll + accuracy - defense;
 
-        if !crit_immunity && (100 - roll) < self.crit_chance + bonuses.crit_chance {
-            let roll2 = gen_rand(1, 101);
+        let hit_kind = if !crit_immunity && (100 - roll) < self.crit_chance + bonuses.crit_chance {
+            let roll2 = rand.gen(1, 101);
             let result2 = roll2 + accuracy - defense;
             if result2 > self.graze_threshold + bonuses.graze_threshold {
                 HitKind::Crit
@@ -218,9 +247,85 @@ impl StatList {
             HitKind::Graze
         } else {
             HitKind::Miss
+        };
+        attack_roll(hit_kind)
+    }
+
+    /// Returns true if any contingent bonus depends on the `ContingentState`
+    /// of the parent
+    pub fn has_state_contingents(&self) -> bool {
+        let state = ContingentState::default();
+        self.contingent_bonuses
+            .iter()
+            .any(|bonus| bonus.when.applies(&state).is_some())
+    }
+
+    /// Returns true if any contingent bonus applies differently in the `new`
+    /// state than in the `old` state, meaning the stats must be recomputed
+    pub fn contingent_changed(&self, old: &ContingentState, new: &ContingentState) -> bool {
+        self.contingent_bonuses
+            .iter()
+            .any(|bonus| bonus.when.applies(old) != bonus.when.applies(new))
+    }
+
+    /// Rolls a saving throw of the specified kind against the `dc`, using
+    /// the matching defense as the bonus to the roll
+    pub fn save_roll(&self, kind: SaveKind, dc: i32, rand: &mut ReproducibleRandom) -> SaveRoll {
+        let bonus = match kind {
+            SaveKind::Fortitude => self.fortitude,
+            SaveKind::Reflex => self.reflex,
+            SaveKind::Will => self.will,
+        };
+        let roll = rand.gen(1, 101);
+        debug!(
+            "{:?} save roll: {} with bonus {} against {}",
+            kind, roll, bonus, dc
+        );
+
+        let result = Module::rules().saving_throws.result(roll + bonus - dc);
+        SaveRoll {
+            kind,
+            result,
+            roll,
+            bonus,
+            dc,
         }
     }
 
+    /// Sets the weight carried, applying the penalties for the resulting
+    /// encumbrance level, if any.  This should be called after `finalize`.
+    pub fn apply_encumbrance(&mut self, load: i32) {
+        self.load = load;
+
+        let rules = Module::rules();
+        let level = match rules.encumbrance.level(load, self.carry_capacity) {
+            None => return,
+            Some(level) => level,
+        };
+
+        self.movement_rate *= level.movement_rate_multiplier;
+        self.bonus_ap += level.action_points;
+        self.encumbrance = Some(level.name.to_string());
+    }
+
+    /// Applies the enemy multipliers of the difficulty to these stats.  This
+    /// should be called after `finalize`.
+    pub fn apply_difficulty(&mut self, difficulty: &Difficulty) {
+        let scale = |value: i32, multiplier: f32| (value as f32 * multiplier).round() as i32;
+
+        self.max_hp = scale(self.max_hp, difficulty.enemy_hp_multiplier).max(1);
+
+        let accuracy = difficulty.enemy_accuracy_multiplier;
+        self.melee_accuracy = scale(self.melee_accuracy, accuracy);
+        self.ranged_accuracy = scale(self.ranged_accuracy, accuracy);
+        self.spell_accuracy = scale(self.spell_accuracy, accuracy);
+
+        let damage = difficulty.enemy_damage_multiplier;
+        self.graze_multiplier *= damage;
+        self.hit_multiplier *= damage;
+        self.crit_multiplier *= damage;
+    }
+
     pub fn has_shield(&self) -> bool {
         self.has_shield
     }
@@ -318,9 +423,17 @@ impl StatList {
                     self.attack_bonuses.push(bonus.clone())
                 }
                 AttackWhenFlanking => self.flanking_bonuses.add(bonus.clone()),
-                WeaponEquipped(_) | ArmorEquipped { .. } | WeaponStyle(_) | Threatened => {
-                    self.contingent_bonuses.add(bonus.clone())
-                }
+                TargetHasEffect(_) => self.target_effect_bonuses.add(bonus.clone()),
+                WeaponEquipped(_)
+                | ArmorEquipped { .. }
+                | WeaponStyle(_)
+                | Threatened
+                | HpBelow(_)
+                | LocationKind(_)
+                | TimeOfDay { .. }
+                | InCombat
+                | OutOfCombat
+                | AdjacentToAlly => self.contingent_bonuses.add(bonus.clone()),
             }
         }
     }
@@ -374,6 +487,9 @@ impl StatList {
             AttackCost(amount) => self.attack_cost -= amount * times_i32,
             FlankingAngle(amount) => self.flanking_angle -= amount * times_i32,
             CasterLevel(amount) => self.caster_level += amount * times_i32,
+            Reactions(amount) => self.reactions += amount * times_i32,
+            CarryCapacity(amount) => self.carry_capacity += amount * times_i32,
+            Stealth(amount) => self.stealth += amount * times_i32,
             FreeAbilityGroupUse => self.free_ability_group_use = true,
             AbilitiesDisabled => self.abilities_disabled = true,
             MoveDisabled => self.move_disabled = true,
@@ -396,7 +512,7 @@ impl StatList {
         mut attacks: Vec<(&'a AttackBuilder, WeaponKind)>,
         equipped_armor: HashMap<Slot, ArmorKind>,
         weapon_style: WeaponStyle,
-        threatened: bool,
+        state: &ContingentState,
     ) {
         let rules = Module::rules();
 
@@ -410,7 +526,8 @@ impl StatList {
                 | AttackWithWeapon(_)
                 | AttackWhenHidden
                 | AttackWhenFlanking
-                | AttackWithDamageKind(_) => unreachable!(),
+                | AttackWithDamageKind(_)
+                | TargetHasEffect(_) => unreachable!(),
                 WeaponEquipped(weapon_kind) => {
                     for (_, attack_weapon_kind) in attacks.iter() {
                         if weapon_kind == *attack_weapon_kind {
@@ -430,8 +547,14 @@ impl StatList {
                         self.add_bonus(&bonus.kind, 1);
                     }
                 }
-                Threatened => {
-                    if threatened {
+                Threatened
+                | HpBelow(_)
+                | LocationKind(_)
+                | TimeOfDay { .. }
+                | InCombat
+                | OutOfCombat
+                | AdjacentToAlly => {
+                    if bonus.when.applies(state) == Some(true) {
                         self.add_bonus(&bonus.kind, 1);
                     }
                 }
@@ -516,6 +639,8 @@ impl StatList {
         }
 
         self.flanking_angle += rules.base_flanking_angle;
+        self.reactions += rules.reactions.base_per_round as i32;
+        self.carry_capacity += rules.encumbrance.capacity(self.attributes, base_attr);
         self.crit_chance += rules.crit_chance as i32;
         self.hit_threshold += rules.hit_percentile as i32;
         self.graze_threshold += rules.graze_percentile as i32;
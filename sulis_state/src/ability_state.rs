@@ -100,6 +100,25 @@ impl AbilityState {
         }
     }
 
+    /// Replaces the ability with an updated definition, keeping the current
+    /// duration and listeners.  Abilities that are no longer active are
+    /// left unchanged.
+    pub(crate) fn reload(&mut self, ability: &Rc<Ability>) {
+        if ability.active.is_none() {
+            return;
+        }
+
+        let state = AbilityState::new(ability);
+        self.ability = state.ability;
+        self.group = state.group;
+        self.combat_only = state.combat_only;
+        self.requires_active_mode = state.requires_active_mode;
+        self.requires_melee = state.requires_melee;
+        self.requires_ranged = state.requires_ranged;
+        self.requires_shield = state.requires_shield;
+        self.listeners.notify(self);
+    }
+
     pub fn update(&mut self, millis_elapsed: u32) {
         let cur_mod = self.cur_duration / ROUND_TIME_MILLIS;
         self.cur_duration += millis_elapsed;
@@ -27,8 +27,10 @@ use crate::{
 use sulis_core::image::{Image, LayeredImage};
 use sulis_core::io::GraphicsRenderer;
 use sulis_core::util::{invalid_data_error, ExtInt, Offset, Scale};
+use sulis_module::{
+    bonus::ContingentState, BonusKind, BonusList, ItemKind, ItemState, QuickSlot, Slot, StatList,
+};
 use sulis_module::{Ability, Actor, ActorBuilder, Faction, ImageLayer, Module};
-use sulis_module::{BonusList, ItemKind, ItemState, QuickSlot, Slot, StatList};
 
 pub struct ActorState {
     pub actor: Rc<Actor>,
@@ -42,6 +44,12 @@ pub struct ActorState {
     anim_image_layers: HashMap<ImageLayer, Rc<dyn Image>>,
     p_stats: PStats,
     started_turn_with_no_ap_for_actions: bool,
+
+    // this actor's share of the weight of the party stash
+    stash_load: i32,
+
+    // the conditions used to evaluate contingent bonuses
+    contingent_state: ContingentState,
 }
 
 impl ActorState {
@@ -115,6 +123,8 @@ impl ActorState {
             p_stats: save.p_stats,
             anim_image_layers: HashMap::new(),
             started_turn_with_no_ap_for_actions: false,
+            stash_load: 0,
+            contingent_state: ContingentState::default(),
         })
     }
 
@@ -152,6 +162,8 @@ impl ActorState {
             p_stats: PStats::new(&actor),
             anim_image_layers: HashMap::new(),
             started_turn_with_no_ap_for_actions: false,
+            stash_load: 0,
+            contingent_state: ContingentState::default(),
         };
 
         actor_state.compute_stats();
@@ -203,6 +215,88 @@ impl ActorState {
         }
     }
 
+    /// Sets this actor's share of the weight of the party stash, recomputing
+    /// stats if it has changed
+    pub(crate) fn set_stash_load(&mut self, load: i32) {
+        if self.stash_load == load {
+            return;
+        }
+
+        self.stash_load = load;
+        self.compute_stats();
+    }
+
+    pub fn contingent_state(&self) -> &ContingentState {
+        &self.contingent_state
+    }
+
+    /// Sets the conditions used to evaluate contingent bonuses, recomputing
+    /// stats if this changes whether any of them apply
+    pub(crate) fn set_contingent_state(&mut self, state: ContingentState) {
+        if self.contingent_state == state {
+            return;
+        }
+
+        let changed = self
+            .stats
+            .contingent_changed(&self.contingent_state, &state);
+        self.contingent_state = state;
+        if changed {
+            self.compute_stats();
+        }
+    }
+
+    /// Reduces the durability of this actor's equipped weapons, or of its
+    /// other equipped items, as specified by the module rules
+    pub(crate) fn wear_equipped(&mut self, weapons: bool) {
+        let rules = &Module::rules().durability;
+        if !rules.enabled {
+            return;
+        }
+
+        let amount = if weapons {
+            rules.weapon_wear_per_hit
+        } else {
+            rules.armor_wear_per_damage
+        };
+
+        let broken = self.inventory.wear(weapons, amount);
+        if broken.is_empty() {
+            return;
+        }
+
+        for name in broken {
+            info!("'{}' broke for '{}'", name, self.actor.name);
+        }
+        self.compute_stats();
+    }
+
+    /// Reduces the durability of the item equipped in `slot` by `amount`,
+    /// recomputing stats if the item breaks.  Does nothing if durability is
+    /// disabled by the module rules.
+    pub fn wear_item(&mut self, slot: Slot, amount: u32) {
+        if !Module::rules().durability.enabled {
+            return;
+        }
+
+        if self.inventory.wear_slot(slot, amount) {
+            info!("Item in '{:?}' broke for '{}'", slot, self.actor.name);
+            self.compute_stats();
+        }
+    }
+
+    /// Restores all of this actor's equipped and quick items to full durability
+    pub fn repair_all(&mut self) {
+        self.inventory.repair_all();
+        self.compute_stats();
+    }
+
+    /// The total weight carried by this actor, including its share of the
+    /// party stash
+    pub fn carried_weight(&self) -> i32 {
+        self.inventory.weight() + self.stash_load
+    }
+
     pub fn remove_anim_image_layers(&mut self, images: &HashMap<ImageLayer, Rc<dyn Image>>) {
         for layer in images.keys() {
             self.anim_image_layers.remove(layer);
@@ -228,6 +322,25 @@ impl ActorState {
         self.p_stats.is_threatened()
     }
 
+    pub fn threatened_by(&self) -> Vec<usize> {
+        self.p_stats.threatened_by().to_vec()
+    }
+
+    pub fn reactions_remaining(&self) -> u32 {
+        self.p_stats.reactions_remaining(&self.stats)
+    }
+
+    /// Uses one of this actor's reactions for the round, returning false
+    /// if none are remaining
+    pub fn use_reaction(&mut self) -> bool {
+        if self.reactions_remaining() == 0 {
+            return false;
+        }
+
+        self.p_stats.use_reaction();
+        true
+    }
+
     pub fn add_threatening(&mut self, index: usize) {
         self.p_stats.add_threatening(index);
     }
@@ -256,8 +369,8 @@ impl ActorState {
         }
     }
 
-    pub fn faction(&self) -> Faction {
-        self.p_stats.faction
+    pub fn faction(&self) -> &Faction {
+        &self.p_stats.faction
     }
 
     pub fn set_faction(&mut self, faction: Faction) {
@@ -486,6 +599,23 @@ impl ActorState {
         self.effects.iter().map(|(index, _)| index)
     }
 
+    /// Looks up this actor's abilities, conversation, and items again from
+    /// the `Module`, keeping all other state.  Used when module resources
+    /// are reloaded.
+    pub fn reload_resources(&mut self) {
+        for state in self.ability_states.values_mut() {
+            if let Some(ability) = Module::ability(&state.ability.id) {
+                state.reload(&ability);
+            }
+        }
+
+        self.inventory.reload_resources();
+        let actor = self.actor.reloaded();
+        self.replace_actor(actor);
+        self.texture_cache_invalid = true;
+        self.listeners.notify(self);
+    }
+
     pub fn replace_actor(&mut self, new_actor: Actor) {
         self.actor = Rc::new(new_actor);
 
@@ -657,17 +787,14 @@ impl ActorState {
             }
         };
 
-        debug!(
-            "Adding XP {} to '{}'",
-            reward.xp,
-            parent.borrow().actor.actor.id
-        );
+        let xp = (reward.xp as f32 * GameState::difficulty().xp_multiplier).round() as u32;
+        debug!("Adding XP {} to '{}'", xp, parent.borrow().actor.actor.id);
         if parent.borrow().is_party_member() {
             for member in GameState::party().iter() {
-                member.borrow_mut().add_xp(reward.xp);
+                member.borrow_mut().add_xp(xp);
             }
         } else {
-            parent.borrow_mut().add_xp(reward.xp);
+            parent.borrow_mut().add_xp(xp);
         }
 
         let loot = match reward.loot {
@@ -676,7 +803,8 @@ impl ActorState {
         };
 
         trace!("Checking for loot drop.");
-        let items = loot.generate_with_chance(reward.loot_chance);
+        let items =
+            GameState::with_rand(|rand| loot.generate_with_chance(reward.loot_chance, rand));
         if items.is_empty() {
             return;
         }
@@ -758,14 +886,33 @@ impl ActorState {
 
     pub(crate) fn remove_hp(&mut self, hp: u32) {
         self.p_stats.remove_hp(hp);
+        self.update_hp_contingent();
         self.listeners.notify(self);
     }
 
     pub(crate) fn add_hp(&mut self, hp: u32) {
         self.p_stats.add_hp(hp, self.stats.max_hp);
+        self.update_hp_contingent();
         self.listeners.notify(self);
     }
 
+    /// The current hit points as a fraction of the maximum
+    pub(crate) fn hp_frac(&self) -> f32 {
+        self.hp() as f32 / std::cmp::max(1, self.stats.max_hp) as f32
+    }
+
+    // hit points change outside of the events that update the other
+    // contingent conditions, so they are updated directly
+    fn update_hp_contingent(&mut self) {
+        if !self.stats.has_state_contingents() {
+            return;
+        }
+
+        let mut state = self.contingent_state.clone();
+        state.hp_frac = self.hp_frac();
+        self.set_contingent_state(state);
+    }
+
     pub(crate) fn remove_class_stat(&mut self, stat: &str, amount: u32) {
         self.p_stats.remove_class_stat(stat, amount);
         self.listeners.notify(self);
@@ -800,11 +947,50 @@ impl ActorState {
         self.compute_stats();
     }
 
+    pub(crate) fn update_effect_bonuses(&mut self, index: usize, bonuses: BonusList) {
+        for (effect_index, effect_bonuses) in self.effects.iter_mut() {
+            if *effect_index == index {
+                *effect_bonuses = bonuses;
+                break;
+            }
+        }
+        self.compute_stats();
+    }
+
     pub(crate) fn remove_effect(&mut self, index: usize) {
         self.effects.retain(|(i, _)| *i != index);
         self.compute_stats();
     }
 
+    /// Marks all effects granting the `Hidden` bonus for removal.  The effects
+    /// no longer apply to this actor's stats immediately, rather than after
+    /// the turn manager removes them.
+    pub(crate) fn remove_hidden_effects(&mut self) {
+        let hidden: Vec<usize> = self
+            .effects
+            .iter()
+            .filter(|(_, bonuses)| bonuses.iter().any(|bonus| bonus.kind == BonusKind::Hidden))
+            .map(|(index, _)| *index)
+            .collect();
+
+        if hidden.is_empty() {
+            return;
+        }
+
+        {
+            let mgr = GameState::turn_manager();
+            let mut mgr = mgr.borrow_mut();
+            for index in hidden.iter() {
+                if let Some(effect) = mgr.effect_mut_checked(*index) {
+                    effect.mark_for_removal();
+                }
+            }
+        }
+
+        self.effects.retain(|(index, _)| !hidden.contains(index));
+        self.compute_stats();
+    }
+
     pub fn init_day(&mut self) {
         self.p_stats.init_day(&self.stats);
         self.listeners.notify(self);
@@ -834,6 +1020,26 @@ impl ActorState {
         self.listeners.notify(self);
     }
 
+    /// Recomputes stats after the difficulty has changed, keeping the same
+    /// fraction of hit points
+    pub(crate) fn difficulty_changed(&mut self) {
+        let old_max = self.stats.max_hp;
+        self.compute_stats();
+        let new_max = self.stats.max_hp;
+
+        let hp = self.hp();
+        if old_max <= 0 || new_max == old_max || hp <= 0 {
+            return;
+        }
+
+        let new_hp = ((hp as i64 * new_max as i64 / old_max as i64) as i32).max(1);
+        if new_hp > hp {
+            self.add_hp((new_hp - hp) as u32);
+        } else {
+            self.remove_hp((hp - new_hp) as u32);
+        }
+    }
+
     pub fn compute_stats(&mut self) {
         debug!("Compute stats for '{}'", self.actor.name);
         self.stats = StatList::new(self.actor.attributes);
@@ -877,6 +1083,10 @@ impl ActorState {
 
         let mut attacks_list = Vec::new();
         for item_state in self.inventory.equipped_iter() {
+            if item_state.is_broken() {
+                continue;
+            }
+
             let equippable = match &item_state.item.equippable {
                 None => continue,
                 Some(equippable) => {
@@ -914,16 +1124,22 @@ impl ActorState {
         }
 
         let weapon_style = self.inventory.weapon_style();
-        let is_threatened = self.is_threatened();
+        self.contingent_state.threatened = self.is_threatened();
 
         self.stats.finalize(
             &self.actor,
             attacks_list,
             equipped_armor,
             weapon_style,
-            is_threatened,
+            &self.contingent_state,
         );
 
+        self.stats.apply_encumbrance(self.carried_weight());
+
+        if GameState::is_hostile_faction(self.faction(), &Faction::friendly()) {
+            self.stats.apply_difficulty(&GameState::difficulty());
+        }
+
         self.p_stats.recompute_level_up(&self.actor);
 
         self.listeners.notify(self);
@@ -17,10 +17,17 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use crate::ai_trace::{self, AIEndReason, AITraceStep};
 use crate::script::script_callback;
 use crate::{animation::Anim, EntityState, GameState, Script};
-use sulis_module::ai::FuncKind;
 use sulis_core::config::Config;
+use sulis_module::ai::{AIPlanner, FuncKind};
+
+mod blackboard;
+pub use self::blackboard::AIBlackboard;
+
+mod planner;
+use self::planner::Planner;
 
 pub struct AI {
     ai: Option<EntityAI>,
@@ -46,9 +53,18 @@ impl AI {
             return;
         }
 
+        // party members are controlled by the player unless they have been
+        // placed under AI control, and the player has not taken over the turn
         if entity.borrow().is_party_member() {
-            self.ai = None;
-            return;
+            let taken_over = GameState::turn_manager().borrow().is_turn_taken_over();
+            if taken_over || entity.borrow().ai_template().is_none() {
+                if let Some(ai) = self.ai.take() {
+                    if taken_over && Rc::ptr_eq(&ai.entity, &entity) {
+                        ai_trace::end_turn(AIEndReason::TakenOver);
+                    }
+                }
+                return;
+            }
         }
 
         let assign = match self.ai {
@@ -61,6 +77,8 @@ impl AI {
                 "Initialize round AI for '{}'",
                 entity.borrow().actor.actor.name
             );
+            let template = entity.borrow().ai_template();
+            ai_trace::begin_turn(&entity.borrow(), template.as_deref());
             self.ai = Some(EntityAI::new(&entity));
             self.next_state = State::Wait(20);
         }
@@ -84,13 +102,21 @@ fn end(ai: &mut EntityAI) -> State {
         "AI for '{}' is ending.",
         ai.entity.borrow().actor.actor.name
     );
+    let reason = match ai.end_reason.take() {
+        Some(reason) => reason,
+        None => ai.ap_end_reason(),
+    };
+    ai_trace::end_turn(reason);
+
     let turn_mgr = GameState::turn_manager();
     let cbs = turn_mgr.borrow_mut().next();
     script_callback::fire_round_elapsed(cbs);
     State::Run
 }
 
-#[derive(Clone, Copy, Debug)]
+/// What the AI does after an action: run another action, wait for the
+/// specified time, or end its turn
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum State {
     Run,
     Wait(u32),
@@ -106,6 +132,8 @@ struct EntityAI {
     entity: Rc<RefCell<EntityState>>,
     actions_taken_this_turn: u32,
     cur_wait_time: u32,
+    end_reason: Option<AIEndReason>,
+    planner: Planner,
 }
 
 impl EntityAI {
@@ -114,6 +142,20 @@ impl EntityAI {
             entity: Rc::clone(entity),
             actions_taken_this_turn: 0,
             cur_wait_time: 0,
+            end_reason: None,
+            planner: Planner::default(),
+        }
+    }
+
+    /// The reason the turn ended when the AI chose to end it
+    fn ap_end_reason(&self) -> AIEndReason {
+        let entity = self.entity.borrow();
+        let ap = entity.actor.ap();
+        let attack_ap = entity.actor.stats.attack_cost as u32;
+        if ap < attack_ap && ap < entity.actor.get_move_ap_cost(1) {
+            AIEndReason::OutOfAP
+        } else {
+            AIEndReason::NothingToDo
         }
     }
 
@@ -123,12 +165,17 @@ impl EntityAI {
             self.entity.borrow().actor.actor.name
         );
         self.cur_wait_time += time;
+        ai_trace::record(AITraceStep::Wait {
+            time,
+            total: self.cur_wait_time,
+        });
 
         if self.cur_wait_time > MAX_WAIT_TIME {
             warn!(
                 "Wait time for {} exceeded maximum",
                 self.entity.borrow().unique_id()
             );
+            self.end_reason = Some(AIEndReason::MaxWaitTime);
             return State::End;
         }
         let wait_time = Config::animation_base_time_millis() * time;
@@ -144,18 +191,39 @@ impl EntityAI {
                 "Action count for {} exceeded maximum",
                 self.entity.borrow().unique_id()
             );
+            self.end_reason = Some(AIEndReason::MaxActions);
             return State::End;
         }
 
-        let ai_template = match &self.entity.borrow().actor.actor.ai {
-            None => return State::End,
-            Some(template) => Rc::clone(template),
+        let ai_template = match self.entity.borrow().ai_template() {
+            None => {
+                self.end_reason = Some(AIEndReason::NoTemplate);
+                return State::End;
+            }
+            Some(template) => template,
         };
 
-        let func = ai_template.hooks.get(&FuncKind::AiAction).map(|f| f.as_str()).unwrap_or("ai_action");
-
         self.actions_taken_this_turn += 1;
+        ai_trace::record(AITraceStep::Action {
+            count: self.actions_taken_this_turn,
+        });
+
+        let state = if ai_template.planner == AIPlanner::Native {
+            self.planner.run(&self.entity, &ai_template)
+        } else {
+            let func = ai_template
+                .hooks
+                .get(&FuncKind::AiAction)
+                .map(|f| f.as_str())
+                .unwrap_or("ai_action");
+            ai_trace::record(AITraceStep::Script {
+                func: func.to_string(),
+            });
+
+            Script::ai(&self.entity, func)
+        };
 
-        Script::ai(&self.entity, func)
+        ai_trace::record(AITraceStep::State { state });
+        state
     }
 }
@@ -0,0 +1,168 @@
+//  This file is part of Sulis, a turn based RPG written in Rust.
+//  Copyright 2018 Jared Stephen
+//
+//  Sulis is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Sulis is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Sulis.  If not, see <http://www.gnu.org/licenses/>
+
+use std::collections::HashMap;
+
+use sulis_core::util::Point;
+use sulis_module::ability::{AIGroup, AIKind};
+use sulis_module::ai::AIRole;
+
+struct SupportClaim {
+    claimer: usize,
+    kind: AIKind,
+    group: AIGroup,
+    target: usize,
+    round: u32,
+}
+
+/// State shared between the members of an AI group over the course of an
+/// encounter, so that they can coordinate their actions.  Entities are
+/// referenced by index.  The blackboards are cleared when combat ends.
+#[derive(Default)]
+pub struct AIBlackboard {
+    focus: Option<usize>,
+    roles: HashMap<usize, AIRole>,
+    flanks: HashMap<usize, Point>,
+    retreat: bool,
+    support: Vec<SupportClaim>,
+}
+
+impl AIBlackboard {
+    /// The hostile entity the group is concentrating its attacks on
+    pub fn focus(&self) -> Option<usize> {
+        self.focus
+    }
+
+    pub fn set_focus(&mut self, focus: Option<usize>) {
+        self.focus = focus;
+    }
+
+    pub fn role(&self, entity: usize) -> Option<AIRole> {
+        self.roles.get(&entity).copied()
+    }
+
+    pub fn set_role(&mut self, entity: usize, role: AIRole) {
+        self.roles.insert(entity, role);
+    }
+
+    /// Returns the members of the group with the specified `role`
+    pub fn with_role(&self, role: AIRole) -> impl Iterator<Item = usize> + '_ {
+        self.roles
+            .iter()
+            .filter(move |(_, r)| **r == role)
+            .map(|(entity, _)| *entity)
+    }
+
+    /// The position the `entity` has claimed to attack its target from
+    pub fn flank(&self, entity: usize) -> Option<Point> {
+        self.flanks.get(&entity).copied()
+    }
+
+    /// Returns true if another member of the group than `entity` has claimed
+    /// the `point`
+    pub fn is_flank_claimed(&self, point: Point, entity: usize) -> bool {
+        self.flanks
+            .iter()
+            .any(|(claimer, claimed)| *claimer != entity && *claimed == point)
+    }
+
+    /// Claims the `point` for the `entity`, releasing any position it claimed
+    /// before.  Returns false if the point has already been claimed.
+    pub fn claim_flank(&mut self, entity: usize, point: Point) -> bool {
+        if self.is_flank_claimed(point, entity) {
+            return false;
+        }
+
+        self.flanks.insert(entity, point);
+        true
+    }
+
+    /// Returns the positions claimed by members of the group other than `entity`
+    pub fn other_flanks(&self, entity: usize) -> impl Iterator<Item = Point> + '_ {
+        self.flanks
+            .iter()
+            .filter(move |(claimer, _)| **claimer != entity)
+            .map(|(_, point)| *point)
+    }
+
+    pub fn release_flank(&mut self, entity: usize) {
+        self.flanks.remove(&entity);
+    }
+
+    pub fn is_retreating(&self) -> bool {
+        self.retreat
+    }
+
+    pub fn set_retreat(&mut self, retreat: bool) {
+        self.retreat = retreat;
+    }
+
+    /// Records that the `claimer` is healing or buffing the `target` this
+    /// `round`, with an ability of the specified `kind` and `group`.
+    pub fn claim_support(
+        &mut self,
+        claimer: usize,
+        kind: AIKind,
+        group: AIGroup,
+        target: usize,
+        round: u32,
+    ) {
+        self.support.retain(|claim| claim.round == round);
+        self.support.push(SupportClaim {
+            claimer,
+            kind,
+            group,
+            target,
+            round,
+        });
+    }
+
+    /// Returns true if another member of the group has already supported
+    /// allies this `round` in a way that makes the specified support from
+    /// `claimer` redundant.  Single target support conflicts with support of
+    /// the same kind on the same target, while support of multiple targets
+    /// conflicts with any other support of the same kind on multiple targets.
+    pub fn is_support_claimed(
+        &self,
+        claimer: usize,
+        kind: AIKind,
+        group: AIGroup,
+        target: usize,
+        round: u32,
+    ) -> bool {
+        self.support.iter().any(|claim| {
+            if claim.round != round || claim.claimer == claimer || claim.kind != kind {
+                return false;
+            }
+
+            match group {
+                AIGroup::Single => claim.group == AIGroup::Single && claim.target == target,
+                AIGroup::Multiple => claim.group == AIGroup::Multiple,
+            }
+        })
+    }
+
+    /// Removes all references to the entity with the specified `index`
+    pub(crate) fn remove_entity(&mut self, index: usize) {
+        if self.focus == Some(index) {
+            self.focus = None;
+        }
+        self.roles.remove(&index);
+        self.flanks.remove(&index);
+        self.support
+            .retain(|claim| claim.claimer != index && claim.target != index);
+    }
+}
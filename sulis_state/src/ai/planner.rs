@@ -0,0 +1,1151 @@
+//  This file is part of Sulis, a turn based RPG written in Rust.
+//  Copyright 2018 Jared Stephen
+//
+//  Sulis is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Sulis is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Sulis.  If not, see <http://www.gnu.org/licenses/>
+
+//! The native tactical planner, selected with `planner: Native` in an
+//! `AITemplate`.  Each time the entity may act, the planner scores every
+//! ability, usable item, and attack it could use on each target, and searches
+//! for the sequence of actions with the best total score that can be paid for
+//! with the entity's remaining AP, including the AP needed to move into range.
+//! The first action of that plan is performed, and the rest of the turn is
+//! planned again once it completes.
+//!
+//! The template `params` `AttackWeight` and `AbilityWeight` scale the scores
+//! of attacks and of abilities and items, as a percentage, and `SupportWeight`
+//! further scales those of heals and buffs.  All default to 100.  If the
+//! `MaxMove` param is set, actions needing the entity to move more than that
+//! many squares are not considered, and it does not close in on hostiles
+//! when it has nothing else to do.
+//!
+//! Members of an AI group coordinate through the group's `AIBlackboard`.  They
+//! concentrate their attacks on a shared focus target, defenders protect the
+//! casters and supporters of the group, melee attackers claim positions on
+//! opposite sides of their target, and heals and buffs are not duplicated
+//! within a round: single target support is not repeated on the same ally,
+//! and only one member uses support on multiple allies of each kind.  If the
+//! `RetreatPercent` param is set, the group retreats once its remaining hit
+//! points fall below that percentage of its total.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::State;
+use crate::ai_trace::{self, AITraceStep, TracedCandidate, TracedEntity, TracedSquare};
+use crate::script::{move_with_ai_group, ScriptItemKind};
+use crate::{
+    center, dist, stealth, AreaState, EntityState, GameState, Locatable, Script, TurnManager,
+};
+use sulis_core::util::Point;
+use sulis_module::ability::{AIData, AIGroup, AIKind, AIRange, AITarget};
+use sulis_module::ai::AIRole;
+use sulis_module::{AITemplate, Ability, QuickSlot};
+
+const WAIT_TIME: u32 = 10;
+const MAX_MOVE_LEN: u32 = 60;
+const MOVE_THRESHOLD: f32 = 0.1;
+const HEALING_FRAC: f32 = 0.5;
+const MIN_MULTIPLE_SCORE: f32 = 1.5;
+const SHORT_RANGE: f32 = 8.0;
+const FLEE_DIST: f32 = 12.0;
+const GROUP_DIST: f32 = 4.0;
+const FOCUS_BONUS: f32 = 0.5;
+const PROTECT_BONUS: f32 = 0.5;
+const PROTECT_DIST: f32 = 4.0;
+const MAX_CANDIDATES: usize = 12;
+const MAX_PLAN_LEN: usize = 3;
+const FLANK_DIRS: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+#[derive(Clone)]
+enum Action {
+    Ability(Rc<Ability>),
+    Item(QuickSlot),
+    Attack,
+}
+
+#[derive(Clone)]
+struct Candidate {
+    action: Action,
+    ai: Option<AIData>,
+    target: Rc<RefCell<EntityState>>,
+    range: f32,
+    squares: u32,
+    ap: u32,
+    score: f32,
+}
+
+impl Candidate {
+    fn key(&self) -> String {
+        let id = match &self.action {
+            Action::Ability(ability) => ability.id.to_string(),
+            Action::Item(slot) => format!("{:?}", slot),
+            Action::Attack => "attack".to_string(),
+        };
+        format!("{}:{}", id, self.target.borrow().index())
+    }
+
+    fn name(&self, parent: &EntityState) -> String {
+        match &self.action {
+            Action::Ability(ability) => ability.name.to_string(),
+            Action::Item(slot) => match parent.actor.inventory().quick(*slot) {
+                None => format!("{:?}", slot),
+                Some(item) => item.item.name.to_string(),
+            },
+            Action::Attack => "Attack".to_string(),
+        }
+    }
+
+    fn traced(&self, parent: &EntityState) -> TracedCandidate {
+        TracedCandidate {
+            action: self.name(parent),
+            target: TracedEntity::new(&self.target.borrow()),
+            ap: self.ap,
+            squares: self.squares,
+            score: self.score,
+        }
+    }
+
+    fn target_index(&self) -> usize {
+        self.target.borrow().index()
+    }
+
+    fn repeatable(&self) -> bool {
+        matches!(self.action, Action::Attack)
+    }
+}
+
+/// The entities the planner considers this action, and the weight of each
+/// one.  Hostiles have negative weights, friendlies positive.
+struct Targets {
+    group: Option<usize>,
+    hostiles: Vec<Rc<RefCell<EntityState>>>,
+    friendlies: Vec<Rc<RefCell<EntityState>>>,
+    weights: HashMap<usize, f32>,
+}
+
+impl Targets {
+    fn new(parent: &Rc<RefCell<EntityState>>) -> Targets {
+        let area_id = parent.borrow().location.area_id.to_string();
+        let mut hostiles = Vec::new();
+        let mut friendlies = Vec::new();
+
+        let mgr = GameState::turn_manager();
+        for entity in mgr.borrow().entity_iter() {
+            let other = entity.borrow();
+            if other.actor.is_dead() || !other.location.is_in_area_id(&area_id) {
+                continue;
+            }
+
+            let parent = parent.borrow();
+            if parent.is_hostile(&other) {
+                if !other.actor.stats.hidden {
+                    hostiles.push(Rc::clone(&entity));
+                }
+            } else if parent.is_friendly(&other) {
+                friendlies.push(Rc::clone(&entity));
+            }
+        }
+
+        let weights = hostiles
+            .iter()
+            .chain(friendlies.iter())
+            .map(|target| {
+                let target = target.borrow();
+                (target.index(), weight(&parent.borrow(), &target))
+            })
+            .collect();
+
+        Targets {
+            group: parent.borrow().ai_group(),
+            hostiles,
+            friendlies,
+            weights,
+        }
+    }
+
+    fn weight(&self, target: &EntityState) -> f32 {
+        *self.weights.get(&target.index()).unwrap_or(&0.0)
+    }
+
+    fn add_priority(&mut self, index: usize, amount: f32) {
+        if let Some(weight) = self.weights.get_mut(&index) {
+            *weight -= amount;
+        }
+    }
+
+    /// The hostile with the highest priority
+    fn best_hostile(&self) -> Option<&Rc<RefCell<EntityState>>> {
+        self.hostiles.iter().min_by(|a, b| {
+            let a = self.weight(&a.borrow());
+            let b = self.weight(&b.borrow());
+            a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+        })
+    }
+
+    /// The entities that an action of the given kind should be used on
+    fn for_kind(&self, kind: AIKind) -> &[Rc<RefCell<EntityState>>] {
+        match kind {
+            AIKind::Damage | AIKind::Debuff | AIKind::Summon => &self.hostiles,
+            AIKind::Heal | AIKind::Buff | AIKind::Special => &self.friendlies,
+        }
+    }
+}
+
+/// Plans the actions of a single entity over its current turn
+#[derive(Default)]
+pub(super) struct Planner {
+    failed: Vec<String>,
+    approached: bool,
+}
+
+impl Planner {
+    pub(super) fn run(
+        &mut self,
+        entity: &Rc<RefCell<EntityState>>,
+        template: &AITemplate,
+    ) -> State {
+        let mut targets = Targets::new(entity);
+
+        if has_effect_with_tag(entity, "fear") {
+            debug!("  Running away due to fear");
+            ai_trace::record_note("Running away due to fear");
+            flee(entity, &targets.hostiles);
+            return State::End;
+        }
+
+        if coordinate(entity, template, &mut targets) {
+            debug!("  Retreating on the orders of the group");
+            ai_trace::record_note("Retreating on the orders of the group");
+            flee(entity, &targets.hostiles);
+            return State::End;
+        }
+
+        // all hostiles are hidden, so look for them
+        if targets.hostiles.is_empty() {
+            if has_effect_with_tag(entity, "searching") {
+                return State::End;
+            }
+            ai_trace::record_note("Searching for hidden hostiles");
+            stealth::search(entity);
+            return State::Wait(WAIT_TIME);
+        }
+
+        loop {
+            let candidates = self.candidates(entity, template, &targets);
+            let plan = plan(&entity.borrow(), &candidates);
+            ai_trace::record(AITraceStep::Plan {
+                candidates: candidates
+                    .iter()
+                    .map(|c| c.traced(&entity.borrow()))
+                    .collect(),
+                chosen: plan.first().copied(),
+            });
+
+            let action = match plan.first() {
+                None => break,
+                Some(index) => &candidates[*index],
+            };
+
+            debug!(
+                "  Planned {} actions, performing '{}' with score {}",
+                plan.len(),
+                action.key(),
+                action.score
+            );
+
+            if perform(entity, action, &targets) {
+                claim_support(entity, action, &targets);
+                return State::Wait(WAIT_TIME);
+            }
+
+            debug!("  Unable to perform '{}'", action.key());
+            ai_trace::record(AITraceStep::Failed {
+                action: action.name(&entity.borrow()),
+                target: TracedEntity::new(&action.target.borrow()),
+            });
+            self.failed.push(action.key());
+        }
+
+        // nothing more can be done this turn, so close in on the best target
+        if !self.approached && !template.params.contains_key("MaxMove") {
+            self.approached = true;
+            if approach(entity, &targets) {
+                return State::Wait(WAIT_TIME);
+            }
+        }
+
+        State::End
+    }
+
+    fn candidates(
+        &self,
+        entity: &Rc<RefCell<EntityState>>,
+        template: &AITemplate,
+        targets: &Targets,
+    ) -> Vec<Candidate> {
+        let attack_weight = param(template, "AttackWeight");
+        let ability_weight = param(template, "AbilityWeight");
+        let support_weight = param(template, "SupportWeight");
+        let max_move = template
+            .params
+            .get("MaxMove")
+            .map(|max| (*max).max(0) as u32);
+
+        let area = match GameState::get_area_state(&entity.borrow().location.area_id) {
+            None => return Vec::new(),
+            Some(area) => area,
+        };
+
+        let parent = entity.borrow();
+        let mut candidates = Vec::new();
+
+        for (id, state) in parent.actor.ability_states.iter() {
+            if state.is_active_mode() || !parent.actor.can_activate(id) {
+                continue;
+            }
+
+            let active = match &state.ability.active {
+                None => continue,
+                Some(active) => active,
+            };
+
+            if active.ai.kind == AIKind::Special {
+                continue;
+            }
+
+            for target in usable_targets(entity, &active.ai, targets) {
+                candidates.push(Candidate {
+                    action: Action::Ability(Rc::clone(&state.ability)),
+                    ai: Some(active.ai.clone()),
+                    target,
+                    range: 0.0,
+                    squares: 0,
+                    ap: active.ap,
+                    score: ability_weight,
+                });
+            }
+        }
+
+        for slot in QuickSlot::usable_iter() {
+            let item = match parent.actor.inventory().quick(*slot) {
+                None => continue,
+                Some(item) => item,
+            };
+
+            let usable = match &item.item.usable {
+                None => continue,
+                Some(usable) => usable,
+            };
+
+            if usable.ai.kind == AIKind::Special || !parent.actor.can_use(item) {
+                continue;
+            }
+
+            for target in usable_targets(entity, &usable.ai, targets) {
+                candidates.push(Candidate {
+                    action: Action::Item(*slot),
+                    ai: Some(usable.ai.clone()),
+                    target,
+                    range: 0.0,
+                    squares: 0,
+                    ap: usable.ap,
+                    score: ability_weight,
+                });
+            }
+        }
+
+        if !parent.actor.stats.attack_disabled {
+            for target in targets.hostiles.iter() {
+                candidates.push(Candidate {
+                    action: Action::Attack,
+                    ai: None,
+                    target: Rc::clone(target),
+                    range: 0.0,
+                    squares: 0,
+                    ap: parent.actor.stats.attack_cost as u32,
+                    score: attack_weight,
+                });
+            }
+        }
+        drop(parent);
+
+        let mut candidates: Vec<_> = candidates
+            .into_iter()
+            .filter(|c| !self.failed.contains(&c.key()))
+            .filter(|c| !is_support_claimed(entity, c, targets))
+            .filter_map(|mut c| {
+                c.range = range(&area.borrow(), &entity.borrow(), &c);
+                c.squares = squares(&entity.borrow(), &c);
+                if max_move.is_some_and(|max| c.squares > max) {
+                    return None;
+                }
+
+                c.score *= score(&entity.borrow(), &c, targets);
+                if support_claim(&c).is_some() {
+                    c.score *= support_weight;
+                }
+                if c.score > 0.0 {
+                    Some(c)
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        candidates.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.key().cmp(&b.key()))
+        });
+        candidates.truncate(MAX_CANDIDATES);
+        candidates
+    }
+}
+
+fn param(template: &AITemplate, id: &str) -> f32 {
+    *template.params.get(id).unwrap_or(&100) as f32 / 100.0
+}
+
+fn has_effect_with_tag(entity: &Rc<RefCell<EntityState>>, tag: &str) -> bool {
+    let mgr = GameState::turn_manager();
+    let mgr = mgr.borrow();
+    let entity = entity.borrow();
+    let result = entity
+        .actor
+        .effects_iter()
+        .any(|index| mgr.effect(*index).tag == tag);
+    result
+}
+
+/// Returns the entities that an action with the specified `ai` data could
+/// be used on
+fn usable_targets(
+    parent: &Rc<RefCell<EntityState>>,
+    ai: &AIData,
+    targets: &Targets,
+) -> Vec<Rc<RefCell<EntityState>>> {
+    if ai.range == AIRange::Personal {
+        if ai.kind == AIKind::Heal && parent.borrow().actor.hp_frac() >= HEALING_FRAC {
+            return Vec::new();
+        }
+        return vec![Rc::clone(parent)];
+    }
+
+    targets
+        .for_kind(ai.kind)
+        .iter()
+        .filter(|target| ai.kind != AIKind::Heal || target.borrow().actor.hp_frac() < HEALING_FRAC)
+        .map(Rc::clone)
+        .collect()
+}
+
+/// The distance from which the `parent` can perform the action of the
+/// candidate on its target, within the `area` containing the parent
+fn range(area: &AreaState, parent: &EntityState, candidate: &Candidate) -> f32 {
+    let target = candidate.target.borrow();
+    let range = match &candidate.ai {
+        None => parent.attack_distance_to(&target),
+        Some(ai) => match ai.range {
+            AIRange::Personal => return f32::MAX,
+            AIRange::Touch => parent.actor.stats.touch_distance(),
+            AIRange::Attack => parent.attack_distance_to(&target),
+            AIRange::Short => SHORT_RANGE,
+            AIRange::Visible => area.area.area.vis_dist as f32 - 1.0,
+        },
+    };
+
+    // get close enough to see the target
+    if !area.has_visibility(parent, &target) {
+        return range.min(dist(parent, &*target) - 2.0);
+    }
+
+    range
+}
+
+/// The number of squares the `parent` must move before performing the action
+fn squares(parent: &EntityState, candidate: &Candidate) -> u32 {
+    let dist = dist(parent, &*candidate.target.borrow());
+    (dist - candidate.range).max(0.0).ceil() as u32
+}
+
+/// The score for performing the action of the candidate, based on the kind
+/// of action, its priority, and the weight of the target.
+fn score(parent: &EntityState, candidate: &Candidate, targets: &Targets) -> f32 {
+    let target = candidate.target.borrow();
+    let weight = targets.weight(&target);
+
+    let ai = match &candidate.ai {
+        None => return -weight,
+        Some(ai) => ai,
+    };
+
+    let value = match ai.kind {
+        AIKind::Damage | AIKind::Debuff => -weight,
+        AIKind::Heal => 2.0 * (1.0 - target.actor.hp_frac()),
+        AIKind::Buff => weight,
+        AIKind::Summon => 1.0,
+        AIKind::Special => 0.0,
+    };
+
+    // area abilities are worth more for each other entity that they catch
+    let value = if ai.group == AIGroup::Multiple && ai.range != AIRange::Personal {
+        let nearby = targets
+            .for_kind(ai.kind)
+            .iter()
+            .filter(|other| {
+                let other = other.borrow();
+                other.index() != target.index() && dist(&*target, &*other) < GROUP_DIST
+            })
+            .count();
+        value * (1.0 + 0.5 * nearby as f32)
+    } else {
+        value
+    };
+
+    // a priority of 20 is worth the same as a standard attack
+    let priority = 30.0 / (ai.priority as f32 + 10.0);
+
+    // friendly buffs are still worth more when a hostile is close
+    if ai.kind == AIKind::Buff && parent.actor.is_threatened() {
+        value * priority * 1.5
+    } else {
+        value * priority
+    }
+}
+
+/// Searches for the sequence of candidates with the highest total score whose
+/// AP, including for movement, can be paid for.  Returns the indices of the
+/// candidates in the order they are to be performed.
+fn plan(parent: &EntityState, candidates: &[Candidate]) -> Vec<usize> {
+    let mut used = Vec::new();
+    let (_, plan) = search(parent, candidates, parent.actor.ap(), None, &mut used);
+    plan
+}
+
+fn search(
+    parent: &EntityState,
+    candidates: &[Candidate],
+    ap: u32,
+    engaged: Option<(usize, f32)>,
+    used: &mut Vec<usize>,
+) -> (f32, Vec<usize>) {
+    let mut best = (0.0, Vec::new());
+    if used.len() == MAX_PLAN_LEN {
+        return best;
+    }
+
+    for (index, candidate) in candidates.iter().enumerate() {
+        if !candidate.repeatable() && used.contains(&index) {
+            continue;
+        }
+
+        // an earlier action in the plan may have moved us in range already
+        let target = candidate.target_index();
+        let squares = match engaged {
+            Some((engaged, range)) if engaged == target && candidate.range >= range => 0,
+            _ => candidate.squares,
+        };
+
+        let mut cost = candidate.ap;
+        if squares > 0 {
+            cost += parent.actor.get_move_ap_cost(squares);
+        }
+
+        if cost > ap {
+            continue;
+        }
+
+        let engaged = if squares > 0 {
+            Some((target, candidate.range))
+        } else {
+            engaged
+        };
+
+        used.push(index);
+        let (score, rest) = search(parent, candidates, ap - cost, engaged, used);
+        used.pop();
+
+        let score = score + candidate.score;
+        if score > best.0 {
+            let mut plan = vec![index];
+            plan.extend(rest);
+            best = (score, plan);
+        }
+    }
+
+    best
+}
+
+/// Records this entity in the blackboard of its AI group, orders a retreat
+/// if the group has taken too much damage, and adjusts the target weights to
+/// follow the group focus and protect its casters and supporters.  Returns
+/// true if the group is retreating.
+fn coordinate(
+    entity: &Rc<RefCell<EntityState>>,
+    template: &AITemplate,
+    targets: &mut Targets,
+) -> bool {
+    let group = match targets.group {
+        None => return false,
+        Some(group) => group,
+    };
+
+    let index = entity.borrow().index();
+    let mgr = GameState::turn_manager();
+    let mut mgr = mgr.borrow_mut();
+
+    let retreat = match template.params.get("RetreatPercent") {
+        None => false,
+        Some(percent) => group_hp_percent(&mgr, group) < *percent as f32,
+    };
+
+    let members: Vec<_> = mgr
+        .entity_iter()
+        .filter(|member| {
+            let member = member.borrow();
+            member.ai_group() == Some(group) && !member.actor.is_dead()
+        })
+        .collect();
+
+    let alive = |index: usize| {
+        targets
+            .hostiles
+            .iter()
+            .any(|hostile| hostile.borrow().index() == index)
+    };
+
+    let board = mgr.ai_blackboard(group);
+    if board.role(index).is_none() {
+        board.set_role(index, default_role(&entity.borrow(), template));
+    }
+
+    if retreat && !board.is_retreating() {
+        info!("AI group {} ordered to retreat", group);
+        board.set_retreat(true);
+    }
+
+    if board.is_retreating() {
+        return true;
+    }
+
+    if !board.focus().is_some_and(alive) {
+        let focus = targets.best_hostile().map(|t| t.borrow().index());
+        board.set_focus(focus);
+    }
+
+    if let Some(focus) = board.focus() {
+        targets.add_priority(focus, FOCUS_BONUS);
+    }
+
+    if board.role(index) != Some(AIRole::Defender) {
+        return false;
+    }
+
+    // defenders go after hostiles that are threatening the vulnerable members
+    let protected: Vec<usize> = board
+        .with_role(AIRole::Caster)
+        .chain(board.with_role(AIRole::Support))
+        .collect();
+    let protected: Vec<_> = members
+        .iter()
+        .filter(|member| protected.contains(&member.borrow().index()))
+        .collect();
+
+    let threats: Vec<usize> = targets
+        .hostiles
+        .iter()
+        .filter(|hostile| {
+            let hostile = hostile.borrow();
+            protected
+                .iter()
+                .any(|member| dist(&*member.borrow(), &*hostile) < PROTECT_DIST)
+        })
+        .map(|hostile| hostile.borrow().index())
+        .collect();
+
+    for threat in threats {
+        targets.add_priority(threat, PROTECT_BONUS);
+    }
+
+    false
+}
+
+/// The role used for an entity that has not been assigned one
+fn default_role(parent: &EntityState, template: &AITemplate) -> AIRole {
+    if let Some(role) = template.role {
+        return role;
+    }
+
+    let supports =
+        parent.actor.ability_states.values().any(|state| {
+            state.ability.active.as_ref().is_some_and(|active| {
+                active.ai.kind == AIKind::Heal || active.ai.kind == AIKind::Buff
+            })
+        });
+
+    if supports {
+        AIRole::Support
+    } else if parent.actor.stats.caster_level > 0 {
+        AIRole::Caster
+    } else {
+        AIRole::Striker
+    }
+}
+
+/// The remaining hit points of the members of the `group`, as a percentage
+/// of their total
+fn group_hp_percent(mgr: &TurnManager, group: usize) -> f32 {
+    let (mut hp, mut max_hp) = (0, 0);
+    for member in mgr.entity_iter() {
+        let member = member.borrow();
+        if member.ai_group() != Some(group) {
+            continue;
+        }
+
+        hp += member.actor.hp().max(0);
+        max_hp += member.actor.stats.max_hp;
+    }
+
+    if max_hp <= 0 {
+        return 100.0;
+    }
+
+    hp as f32 * 100.0 / max_hp as f32
+}
+
+/// Returns the blackboard key for a heal or buff candidate, if it is one
+fn support_claim(candidate: &Candidate) -> Option<(AIKind, AIGroup)> {
+    match &candidate.ai {
+        Some(ai) if ai.kind == AIKind::Heal || ai.kind == AIKind::Buff => Some((ai.kind, ai.group)),
+        _ => None,
+    }
+}
+
+/// Returns true if another member of the group has already given the
+/// support of this candidate this round
+fn is_support_claimed(
+    entity: &Rc<RefCell<EntityState>>,
+    candidate: &Candidate,
+    targets: &Targets,
+) -> bool {
+    let (group, (kind, ai_group)) = match (targets.group, support_claim(candidate)) {
+        (Some(group), Some(claim)) => (group, claim),
+        _ => return false,
+    };
+
+    let mgr = GameState::turn_manager();
+    let mgr = mgr.borrow();
+    let round = mgr.current_round();
+    mgr.ai_blackboard_checked(group).is_some_and(|board| {
+        board.is_support_claimed(
+            entity.borrow().index(),
+            kind,
+            ai_group,
+            candidate.target_index(),
+            round,
+        )
+    })
+}
+
+fn claim_support(entity: &Rc<RefCell<EntityState>>, candidate: &Candidate, targets: &Targets) {
+    let (group, (kind, ai_group)) = match (targets.group, support_claim(candidate)) {
+        (Some(group), Some(claim)) => (group, claim),
+        _ => return,
+    };
+
+    let mgr = GameState::turn_manager();
+    let mut mgr = mgr.borrow_mut();
+    let round = mgr.current_round();
+    mgr.ai_blackboard(group).claim_support(
+        entity.borrow().index(),
+        kind,
+        ai_group,
+        candidate.target_index(),
+        round,
+    );
+}
+
+/// Moves to a position adjacent to the `target` and within `range` of it that
+/// no other member of the group has claimed, preferring positions on the
+/// opposite side of the target from the other members.  Returns false if no
+/// position could be reached.
+fn move_to_flank(
+    entity: &Rc<RefCell<EntityState>>,
+    target: &Rc<RefCell<EntityState>>,
+    range: f32,
+    group: usize,
+) -> bool {
+    let index = entity.borrow().index();
+    let area_state = GameState::area_state();
+    let mgr = GameState::turn_manager();
+
+    let best = {
+        let parent = entity.borrow();
+        let target = target.borrow();
+        let area_state = area_state.borrow();
+        let mut mgr = mgr.borrow_mut();
+        let board = mgr.ai_blackboard(group);
+
+        let (cx, cy) = center(&*target);
+        let (pw, ph) = (parent.size.width as f32, parent.size.height as f32);
+        let others: Vec<(f32, f32)> = board
+            .other_flanks(index)
+            .map(|p| (p.x as f32 + pw / 2.0 - cx, p.y as f32 + ph / 2.0 - cy))
+            .filter(|(x, y)| x.hypot(*y) < GROUP_DIST + target.size.diagonal)
+            .collect();
+
+        let mut squares = Vec::new();
+        let mut best: Option<(Point, f32)> = None;
+        for (dx, dy) in FLANK_DIRS {
+            // the position touching the target on this side
+            let x = match dx {
+                -1 => target.location.x - parent.size.width,
+                1 => target.location.x + target.size.width,
+                _ => target.location.x + (target.size.width - parent.size.width) / 2,
+            };
+            let y = match dy {
+                -1 => target.location.y - parent.size.height,
+                1 => target.location.y + target.size.height,
+                _ => target.location.y + (target.size.height - parent.size.height) / 2,
+            };
+            let point = Point::new(x, y);
+
+            if board.is_flank_claimed(point, index) {
+                continue;
+            }
+
+            // corners may be out of reach.  the position is located as the
+            // single square at the center of the parent
+            let pos = (x as f32 + (pw - 1.0) / 2.0, y as f32 + (ph - 1.0) / 2.0);
+            if dist(&pos, &*target) > range {
+                continue;
+            }
+
+            if !area_state.is_passable_for_entity(&parent, x, y) {
+                continue;
+            }
+
+            let (fx, fy) = (dx as f32, dy as f32);
+            let len = fx.hypot(fy);
+            let opposition: f32 = others
+                .iter()
+                .map(|(ox, oy)| -(fx * ox + fy * oy) / (len * ox.hypot(*oy).max(1.0)))
+                .sum();
+            let (px, py) = parent.pos();
+            let travel = (x as f32 - px).hypot(y as f32 - py);
+            let score = opposition - 0.1 * travel;
+            squares.push(TracedSquare { x, y, score });
+
+            let better = match best {
+                None => true,
+                Some((_, best)) => score > best,
+            };
+            if better {
+                best = Some((point, score));
+            }
+        }
+
+        let chosen = best.and_then(|(point, _)| {
+            squares
+                .iter()
+                .position(|square| square.x == point.x && square.y == point.y)
+        });
+        ai_trace::record(AITraceStep::Squares {
+            target: TracedEntity::new(&target),
+            squares,
+            chosen,
+        });
+
+        match best {
+            None => return false,
+            Some((point, _)) => {
+                board.claim_flank(index, point);
+                (point.x as f32 + pw / 2.0, point.y as f32 + ph / 2.0)
+            }
+        }
+    };
+
+    // the path finder accepts any position within a square of the
+    // destination, so target the exact center of the parent at the position
+    let mut dest = GameState::get_point_dest(&entity.borrow(), best.0, best.1);
+    dest.w = 0.0;
+    dest.h = 0.0;
+    dest.max_path_len = Some(MAX_MOVE_LEN);
+    if move_with_ai_group(entity, dest) {
+        return true;
+    }
+
+    mgr.borrow_mut().ai_blackboard(group).release_flank(index);
+    false
+}
+
+/// Performs the action of the candidate, moving in range first if needed.
+/// Returns false if nothing could be done.
+fn perform(entity: &Rc<RefCell<EntityState>>, candidate: &Candidate, targets: &Targets) -> bool {
+    if candidate.squares > 0 {
+        let melee = entity.borrow().actor.stats.attack_is_melee();
+        if let (Action::Attack, true, Some(group)) = (&candidate.action, melee, targets.group) {
+            if move_to_flank(entity, &candidate.target, candidate.range, group) {
+                return true;
+            }
+        }
+
+        let mut dest = GameState::get_target_dest(&entity.borrow(), &candidate.target.borrow());
+        dest.dist = candidate.range - MOVE_THRESHOLD;
+        dest.max_path_len = Some(MAX_MOVE_LEN);
+        return move_with_ai_group(entity, dest);
+    }
+
+    let ap = entity.borrow().actor.ap();
+
+    match &candidate.action {
+        Action::Attack => {
+            EntityState::attack(entity, &candidate.target, None, true);
+        }
+        Action::Ability(ability) => {
+            let index = entity.borrow().index();
+            Script::ability_on_activate(index, on_activate_fn(candidate), ability);
+            handle_targeter(entity, candidate, targets);
+        }
+        Action::Item(slot) => {
+            let kind = ScriptItemKind::Quick(*slot);
+            Script::item_on_activate(entity, on_activate_fn(candidate), kind);
+            handle_targeter(entity, candidate, targets);
+        }
+    }
+
+    // an action that spent no AP was not performed
+    entity.borrow().actor.ap() < ap
+}
+
+fn on_activate_fn(candidate: &Candidate) -> String {
+    match candidate
+        .ai
+        .as_ref()
+        .and_then(|ai| ai.on_activate_fn.as_ref())
+    {
+        None => "on_activate".to_string(),
+        Some(func) => func.to_string(),
+    }
+}
+
+/// Selects the best position for the targeter created by the candidate's
+/// action, if there is one, and activates it.
+fn handle_targeter(entity: &Rc<RefCell<EntityState>>, candidate: &Candidate, targets: &Targets) {
+    let targeter = match GameState::area_state().borrow().targeter() {
+        None => return,
+        Some(targeter) => targeter,
+    };
+    let mut targeter = targeter.borrow_mut();
+    if targeter.cancel() || !Rc::ptr_eq(targeter.parent(), entity) {
+        return;
+    }
+
+    let ai = match &candidate.ai {
+        None => return,
+        Some(ai) => ai,
+    };
+
+    let target = candidate.target.borrow();
+    let positions: Vec<(i32, i32)> = if ai.kind == AIKind::Heal {
+        vec![(target.location.x, target.location.y)]
+    } else if ai.kind == AIKind::Summon {
+        grid_around(&target, 5, 2)
+    } else if !targeter.is_free_select() {
+        targeter
+            .selectable()
+            .iter()
+            .map(|e| (e.borrow().location.x, e.borrow().location.y))
+            .collect()
+    } else if ai.target == AITarget::Entity {
+        targets
+            .for_kind(ai.kind)
+            .iter()
+            .map(|e| (e.borrow().location.x, e.borrow().location.y))
+            .collect()
+    } else {
+        targets
+            .for_kind(ai.kind)
+            .iter()
+            .flat_map(|e| grid_around(&e.borrow(), 4, 2))
+            .collect()
+    };
+    drop(target);
+
+    // want hostiles and don't want friendlies for damage or debuff, the opposite for others
+    let modifier = match ai.kind {
+        AIKind::Damage | AIKind::Debuff | AIKind::Summon => -1.0,
+        _ => 1.0,
+    };
+
+    let mut best: Option<((i32, i32), f32)> = None;
+    for (x, y) in positions {
+        targeter.on_mouse_move(x, y);
+        if !targeter.is_valid_to_activate() {
+            continue;
+        }
+
+        // heals and summons go to the first valid position
+        if ai.kind == AIKind::Heal || ai.kind == AIKind::Summon {
+            best = Some(((x, y), 1.0));
+            break;
+        }
+
+        let score: f32 = targeter
+            .cur_affected()
+            .iter()
+            .map(|e| targets.weight(&e.borrow()) * modifier)
+            .sum();
+
+        if score > best.map_or(0.0, |(_, score)| score) {
+            best = Some(((x, y), score));
+        }
+    }
+
+    match best {
+        Some(_) if ai.group == AIGroup::Multiple && below_multiple(best) => {
+            debug!("  Minimum score for multi-target ability not reached");
+            targeter.on_cancel();
+        }
+        Some(((x, y), _)) => {
+            targeter.on_mouse_move(x, y);
+            targeter.on_activate();
+        }
+        None => targeter.on_cancel(),
+    }
+}
+
+fn below_multiple(best: Option<((i32, i32), f32)>) -> bool {
+    match best {
+        None => true,
+        Some((_, score)) => score < MIN_MULTIPLE_SCORE,
+    }
+}
+
+fn grid_around(target: &EntityState, radius: i32, step: usize) -> Vec<(i32, i32)> {
+    let mut points = Vec::new();
+    for y in (-radius..=radius).step_by(step) {
+        for x in (-radius..=radius).step_by(step) {
+            points.push((target.location.x + x, target.location.y + y));
+        }
+    }
+    points
+}
+
+/// Moves towards the best hostile target, for when no action could be
+/// planned this turn
+fn approach(entity: &Rc<RefCell<EntityState>>, targets: &Targets) -> bool {
+    let target = match targets.best_hostile() {
+        None => return false,
+        Some(target) => target,
+    };
+
+    if entity.borrow().actor.stats.move_disabled {
+        return false;
+    }
+
+    ai_trace::record_note(&format!("Approaching {}", target.borrow().actor.actor.name));
+    let mut dest = GameState::get_target_dest(&entity.borrow(), &target.borrow());
+    dest.max_path_len = Some(MAX_MOVE_LEN);
+    move_with_ai_group(entity, dest)
+}
+
+/// Moves the entity directly away from the average position of the hostiles
+fn flee(entity: &Rc<RefCell<EntityState>>, hostiles: &[Rc<RefCell<EntityState>>]) {
+    if hostiles.is_empty() {
+        return;
+    }
+
+    let (x, y) = {
+        let parent = entity.borrow();
+        let (mut dx, mut dy) = (0.0, 0.0);
+        for hostile in hostiles {
+            let hostile = hostile.borrow();
+            let angle = ((parent.location.y - hostile.location.y) as f32)
+                .atan2((parent.location.x - hostile.location.x) as f32);
+            dx += angle.cos();
+            dy += angle.sin();
+        }
+        let angle = dy.atan2(dx);
+        (
+            parent.location.x as f32 + angle.cos() * FLEE_DIST,
+            parent.location.y as f32 + angle.sin() * FLEE_DIST,
+        )
+    };
+
+    // if unable, try moving in the general direction with less and less accuracy
+    for thresh in 1..=10 {
+        let mut dest = GameState::get_point_dest(&entity.borrow(), x, y);
+        dest.dist = thresh as f32;
+        if move_with_ai_group(entity, dest) {
+            return;
+        }
+    }
+}
+
+/// The priority of `target` for `parent`, positive for friendlies and
+/// negative for hostiles.  Follows `compute_weight` in `ai_basic.lua`.
+fn weight(parent: &EntityState, target: &EntityState) -> f32 {
+    let base = if parent.is_hostile(target) { -1.0 } else { 1.0 };
+    let stats = &target.actor.stats;
+    let mut modifiers = 0.0;
+
+    // threatening hostiles are higher priority
+    if parent.actor.p_stats().is_threatened_by(target.index()) {
+        modifiers += 0.35;
+    }
+
+    // closer targets are higher priority
+    modifiers += (20.0 - dist(parent, target)) / 60.0;
+
+    // weaker hostiles and stronger friendlies are higher priority
+    if base > 0.0 {
+        if stats.caster_level > 0 {
+            modifiers += stats.spell_accuracy as f32 / 100.0;
+        } else {
+            if let Some(attack) = stats.attacks.first() {
+                let damage = (attack.damage.min() + attack.damage.max()) as f32 / 2.0;
+                modifiers += damage / 100.0;
+            }
+
+            if stats.attack_is_melee() {
+                modifiers += stats.melee_accuracy as f32 / 200.0;
+            } else {
+                modifiers += stats.ranged_accuracy as f32 / 200.0;
+            }
+        }
+    } else {
+        modifiers -= stats.armor.base() as f32 / 100.0;
+        modifiers -= target.actor.hp() as f32 / 400.0;
+    }
+
+    // hostiles that have hurt us are higher priority, and those that are
+    // difficult to damage with our regular attack lower priority
+    let id = target.unique_id();
+    modifiers += parent.get_num_flag(&format!("__damage_taken_from_{}", id));
+    modifiers -= parent.get_num_flag(&format!("__hard_target_for_{}", id));
+
+    base * (1.0 + modifiers)
+}
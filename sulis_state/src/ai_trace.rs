@@ -0,0 +1,408 @@
+//  This file is part of Sulis, a turn based RPG written in Rust.
+//  Copyright 2018 Jared Stephen
+//
+//  Sulis is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Sulis is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Sulis.  If not, see <http://www.gnu.org/licenses/>
+
+//! A structured trace of the decisions the AI makes on each turn it takes:
+//! the candidate actions considered by the native planner or reported by the
+//! AI script and their scores, the action chosen, the positions scored when
+//! moving to flank, the script functions called, the state returned after
+//! each action, the actions and waits of the turn, and why it ended.
+//! The most recent turns are kept in memory for the AI trace window and its
+//! overlay on the area, and may be exported to a JSON file in the user folder.
+//! The trace is not saved, and is cleared whenever a game is started or loaded.
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{BufWriter, Error, ErrorKind};
+use std::path::PathBuf;
+
+use chrono::prelude::*;
+
+use sulis_core::config;
+use sulis_core::serde_json;
+use sulis_module::{AITemplate, Time};
+
+use crate::{AIState, ChangeListener, ChangeListenerList, EntityState, GameState};
+
+/// The maximum number of turns held in memory.  Older turns are discarded
+/// as new ones begin.
+pub const MAX_TURNS: usize = 100;
+
+thread_local! {
+    static TRACE: RefCell<AITrace> = RefCell::new(AITrace::default());
+    static OVERLAY: Cell<bool> = const { Cell::new(false) };
+}
+
+#[derive(Default)]
+struct AITrace {
+    turns: VecDeque<AITurnTrace>,
+    listeners: ChangeListenerList<AITurnTrace>,
+}
+
+/// The decisions made by the AI over a single turn of one entity
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct AITurnTrace {
+    /// The game time when the turn began
+    pub time: Time,
+    pub entity: TracedEntity,
+
+    /// The ID of the AI template in use, if any
+    pub template: Option<String>,
+    pub steps: Vec<AITraceStep>,
+
+    /// Why the turn ended, or None if it is still in progress
+    pub end: Option<AIEndReason>,
+}
+
+impl AITurnTrace {
+    /// The most recent plan made this turn, if any
+    pub fn last_plan(&self) -> Option<(&[TracedCandidate], Option<usize>)> {
+        self.steps.iter().rev().find_map(|step| match step {
+            AITraceStep::Plan { candidates, chosen } => Some((candidates.as_slice(), *chosen)),
+            _ => None,
+        })
+    }
+
+    /// The most recent set of positions scored this turn, if any
+    pub fn last_squares(&self) -> Option<(&[TracedSquare], Option<usize>)> {
+        self.steps.iter().rev().find_map(|step| match step {
+            AITraceStep::Squares {
+                squares, chosen, ..
+            } => Some((squares.as_slice(), *chosen)),
+            _ => None,
+        })
+    }
+}
+
+/// An entity referenced by the trace, along with its position at the time
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct TracedEntity {
+    pub index: usize,
+    pub id: String,
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl TracedEntity {
+    pub(crate) fn new(entity: &EntityState) -> TracedEntity {
+        TracedEntity {
+            index: entity.index(),
+            id: entity.unique_id().to_string(),
+            name: entity.actor.actor.name.to_string(),
+            x: entity.location.x,
+            y: entity.location.y,
+            width: entity.size.width,
+            height: entity.size.height,
+        }
+    }
+}
+
+/// An action the planner considered, and the score it was given
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct TracedCandidate {
+    pub action: String,
+    pub target: TracedEntity,
+    pub ap: u32,
+
+    /// The number of squares to move before the action can be performed
+    pub squares: u32,
+    pub score: f32,
+}
+
+/// A position scored when moving to attack a target
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct TracedSquare {
+    pub x: i32,
+    pub y: i32,
+    pub score: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub enum AITraceStep {
+    /// The start of an action, counting from 1
+    Action { count: u32 },
+
+    /// The AI waited for the specified time, in units of the animation
+    /// base time, bringing its total wait this turn to `total`
+    Wait { time: u32, total: u32 },
+
+    /// The AI script function called for the action
+    Script { func: String },
+
+    /// The state returned by the AI script or planner at the end of the action
+    State { state: AIState },
+
+    /// The candidates scored by the native planner, best first, and the
+    /// index of the one it chose to perform, if any.  For AI scripts, the
+    /// candidates reported by the script, in the order reported.
+    Plan {
+        candidates: Vec<TracedCandidate>,
+        chosen: Option<usize>,
+    },
+
+    /// A planned action that could not be performed, and will not be
+    /// considered again this turn
+    Failed {
+        action: String,
+        target: TracedEntity,
+    },
+
+    /// The positions scored when moving to flank the target, and the index
+    /// of the one chosen, if any
+    Squares {
+        target: TracedEntity,
+        squares: Vec<TracedSquare>,
+        chosen: Option<usize>,
+    },
+
+    /// A behavior of the planner outside of the scored candidates, such as
+    /// fleeing or searching
+    Note { text: String },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AIEndReason {
+    /// Not enough AP remained to attack or move
+    OutOfAP,
+
+    /// The AI chose to end the turn with AP remaining
+    NothingToDo,
+
+    /// The AI waited longer than the maximum allowed in a turn
+    MaxWaitTime,
+
+    /// The AI took more than the maximum actions allowed in a turn
+    MaxActions,
+
+    /// The entity has no AI template
+    NoTemplate,
+
+    /// The player took over the turn of a party member
+    TakenOver,
+
+    /// The turn ended without the AI ending it, for example when combat ended
+    Interrupted,
+}
+
+impl AIEndReason {
+    pub fn to_str(self) -> &'static str {
+        use AIEndReason::*;
+        match self {
+            OutOfAP => "out of AP",
+            NothingToDo => "nothing to do",
+            MaxWaitTime => "maximum wait time",
+            MaxActions => "maximum actions",
+            NoTemplate => "no AI template",
+            TakenOver => "taken over",
+            Interrupted => "interrupted",
+        }
+    }
+}
+
+fn notify_last(trace: &AITrace) {
+    if let Some(turn) = trace.turns.back() {
+        trace.listeners.notify(turn);
+    }
+}
+
+/// Begins the trace of a new turn for the `entity`.  A previous turn that
+/// was not ended is marked as interrupted.
+pub(crate) fn begin_turn(entity: &EntityState, template: Option<&AITemplate>) {
+    let turn = AITurnTrace {
+        time: GameState::turn_manager().borrow().current_time(),
+        entity: TracedEntity::new(entity),
+        template: template.map(|template| template.id.to_string()),
+        steps: Vec::new(),
+        end: None,
+    };
+
+    TRACE.with(|trace| {
+        let mut trace = trace.borrow_mut();
+        if let Some(last) = trace.turns.back_mut() {
+            if last.end.is_none() {
+                last.end = Some(AIEndReason::Interrupted);
+            }
+        }
+        if trace.turns.len() >= MAX_TURNS {
+            trace.turns.pop_front();
+        }
+        trace.turns.push_back(turn);
+        notify_last(&trace);
+    });
+}
+
+/// Adds a step to the turn in progress
+pub(crate) fn record(step: AITraceStep) {
+    trace!("AI trace: {:?}", step);
+    TRACE.with(|trace| {
+        let mut trace = trace.borrow_mut();
+        match trace.turns.back_mut() {
+            Some(turn) if turn.end.is_none() => turn.steps.push(step),
+            _ => return,
+        }
+        notify_last(&trace);
+    });
+}
+
+/// Adds the `candidate` to the plan of the action in progress, starting a new
+/// plan if this action does not have one yet
+pub(crate) fn record_candidate(candidate: TracedCandidate) {
+    trace!("AI trace candidate: {:?}", candidate);
+    TRACE.with(|trace| {
+        let mut trace = trace.borrow_mut();
+        let turn = match trace.turns.back_mut() {
+            Some(turn) if turn.end.is_none() => turn,
+            _ => return,
+        };
+
+        match current_plan(turn) {
+            Some((candidates, _)) => candidates.push(candidate),
+            None => turn.steps.push(AITraceStep::Plan {
+                candidates: vec![candidate],
+                chosen: None,
+            }),
+        }
+        notify_last(&trace);
+    });
+}
+
+/// Marks the candidate of the current plan with the specified `action` and
+/// target entity `index` as chosen.  Returns false if there is no such
+/// candidate.
+pub(crate) fn choose_candidate(action: &str, index: usize) -> bool {
+    TRACE.with(|trace| {
+        let mut trace = trace.borrow_mut();
+        let turn = match trace.turns.back_mut() {
+            Some(turn) if turn.end.is_none() => turn,
+            _ => return false,
+        };
+
+        let (candidates, chosen) = match current_plan(turn) {
+            None => return false,
+            Some(plan) => plan,
+        };
+
+        let position = candidates
+            .iter()
+            .position(|c| c.action == action && c.target.index == index);
+        match position {
+            None => false,
+            Some(position) => {
+                *chosen = Some(position);
+                notify_last(&trace);
+                true
+            }
+        }
+    })
+}
+
+/// The plan recorded since the start of the current action, if any
+fn current_plan(turn: &mut AITurnTrace) -> Option<(&mut Vec<TracedCandidate>, &mut Option<usize>)> {
+    for step in turn.steps.iter_mut().rev() {
+        match step {
+            AITraceStep::Plan { candidates, chosen } => return Some((candidates, chosen)),
+            AITraceStep::Action { .. } => return None,
+            _ => (),
+        }
+    }
+    None
+}
+
+pub(crate) fn record_note(text: &str) {
+    record(AITraceStep::Note {
+        text: text.to_string(),
+    });
+}
+
+/// Ends the turn in progress, if there is one, for the specified `reason`
+pub(crate) fn end_turn(reason: AIEndReason) {
+    debug!("AI turn ended: {:?}", reason);
+    TRACE.with(|trace| {
+        let mut trace = trace.borrow_mut();
+        match trace.turns.back_mut() {
+            Some(turn) if turn.end.is_none() => turn.end = Some(reason),
+            _ => return,
+        }
+        notify_last(&trace);
+    });
+}
+
+/// Returns all turns currently held, oldest first
+pub fn turns() -> Vec<AITurnTrace> {
+    TRACE.with(|trace| trace.borrow().turns.iter().cloned().collect())
+}
+
+/// Returns the most recent turn, which may still be in progress
+pub fn latest() -> Option<AITurnTrace> {
+    TRACE.with(|trace| trace.borrow().turns.back().cloned())
+}
+
+/// Adds a listener which is notified of the turn being traced each time it
+/// changes
+pub fn add_change_listener(listener: ChangeListener<AITurnTrace>) {
+    TRACE.with(|trace| trace.borrow_mut().listeners.add(listener));
+}
+
+/// Removes all turns.  Listeners are kept.
+pub fn clear() {
+    TRACE.with(|trace| trace.borrow_mut().turns.clear());
+}
+
+/// Whether the scores of the most recent turn are drawn over the area
+pub fn is_overlay_shown() -> bool {
+    OVERLAY.with(|overlay| overlay.get())
+}
+
+pub fn set_overlay_shown(shown: bool) {
+    OVERLAY.with(|overlay| overlay.set(shown));
+}
+
+fn get_ai_trace_dir() -> PathBuf {
+    let mut path = config::USER_DIR.clone();
+    path.push("ai_trace");
+    path
+}
+
+/// Writes all turns currently held to a new JSON file in the AI trace
+/// directory of the user folder, returning the path of the file.
+pub fn export() -> Result<PathBuf, Error> {
+    let dir = get_ai_trace_dir();
+    if !dir.is_dir() {
+        fs::create_dir_all(&dir)?;
+    }
+
+    let mut path = dir;
+    path.push(format!(
+        "ai_trace_{}.json",
+        Utc::now().format("%Y%m%d-%H%M%S%.3f")
+    ));
+
+    let file = BufWriter::new(File::create(&path)?);
+    TRACE
+        .with(|trace| serde_json::to_writer_pretty(file, &trace.borrow().turns))
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+    info!("Exported AI trace to {:?}", path);
+    Ok(path)
+}
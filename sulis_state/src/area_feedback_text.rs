@@ -23,7 +23,7 @@ use sulis_core::io::{DrawList, GraphicsRenderer};
 use sulis_core::resource::{Font, ResourceSet};
 use sulis_core::ui::{animation_state, Color, LineRenderer};
 use sulis_core::util::{self, Offset, Point, Rect, Scale};
-use sulis_module::{DamageKind, HitFlags, HitKind};
+use sulis_module::{DamageKind, HitFlags, HitKind, SaveResult};
 
 use crate::{AreaState, EntityState};
 
@@ -155,6 +155,24 @@ impl AreaFeedbackText {
         text
     }
 
+    pub fn with_save(
+        target: &EntityState,
+        area: &AreaState,
+        result: SaveResult,
+    ) -> AreaFeedbackText {
+        let mut text = AreaFeedbackText::with_target(target, area);
+
+        let (entry, color) = match result {
+            SaveResult::Fail => ("Failed Save", ColorKind::Hit),
+            SaveResult::Partial => ("Partial Save", ColorKind::Info),
+            SaveResult::Success => ("Save", ColorKind::Miss),
+            SaveResult::CritSuccess => ("Critical Save", ColorKind::Miss),
+        };
+        text.add_entry(entry.to_string(), color);
+
+        text
+    }
+
     pub fn with_target(target: &EntityState, area: &AreaState) -> AreaFeedbackText {
         let move_rate = 3.0;
         let mut area_pos = target.location.to_point();
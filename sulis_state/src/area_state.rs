@@ -28,9 +28,9 @@ use crate::script::AreaTargeter;
 use crate::*;
 use sulis_core::io::Audio;
 use sulis_core::config::Config;
-use sulis_core::util::{self, gen_rand, invalid_data_error, Point, Size};
+use sulis_core::util::{self, invalid_data_error, Point, Size};
 use sulis_module::area::{Transition, TriggerKind, Trigger};
-use sulis_module::{Actor, Area, LootList, Module, ObjectSize, Time};
+use sulis_module::{Actor, Area, Cover, LootList, Module, ObjectSize, Time};
 
 pub struct TriggerState {
     pub(crate) fired: bool,
@@ -261,6 +261,12 @@ impl AreaState {
             let location = Location::from_point(actor_data.location, &area);
             debug!("Adding actor '{}' at '{:?}'", actor.id, location);
             match self.add_actor(actor, location, Some(unique_id), false, None) {
+                Ok(index) if !actor_data.schedule.is_empty() => {
+                    let schedule =
+                        ScheduleState::new(actor_data.location, actor_data.schedule.clone());
+                    let entity = GameState::turn_manager().borrow().entity(index);
+                    entity.borrow_mut().set_schedule(Some(schedule));
+                }
                 Ok(_) => (),
                 Err(e) => {
                     warn!("Error adding actor to area: {}", e);
@@ -299,6 +305,7 @@ impl AreaState {
         loot_list: &Rc<LootList>,
         buy_frac: f32,
         sell_frac: f32,
+        repair_frac: Option<f32>,
         refresh_time: Time,
     ) -> &mut MerchantState {
         let mut index = None;
@@ -311,13 +318,21 @@ impl AreaState {
 
         match index {
             Some(i) => {
+                self.merchants[i].repair_frac = repair_frac;
                 self.merchants[i].check_refresh();
                 &mut self.merchants[i]
             }
             None => {
                 info!("Creating merchant '{}'", id);
                 let len = self.merchants.len();
-                let merchant = MerchantState::new(id, loot_list, buy_frac, sell_frac, refresh_time);
+                let merchant = MerchantState::new(
+                    id,
+                    loot_list,
+                    buy_frac,
+                    sell_frac,
+                    repair_frac,
+                    refresh_time,
+                );
                 self.merchants.push(merchant);
                 &mut self.merchants[len]
             }
@@ -483,7 +498,7 @@ impl AreaState {
             }
             let encounter = &enc_data.encounter;
             (
-                encounter.gen_actors(),
+                GameState::with_rand(|rand| encounter.gen_actors(rand)),
                 enc_data.location,
                 enc_data.size,
                 ai_group,
@@ -520,7 +535,7 @@ impl AreaState {
             return None;
         }
 
-        let roll = gen_rand(0, available.len());
+        let roll = GameState::with_rand(|rand| rand.gen(0, available.len()));
 
         let point = available[roll];
         let location = Location::from_point(point, &self.area.area);
@@ -660,6 +675,30 @@ impl AreaState {
         has_visibility(&self.area, self.props.entire_vis_grid(), parent, target)
     }
 
+    /// Returns the cover that `target` has against attacks from `parent`, based on
+    /// how much of the target is hidden from the parent
+    pub fn cover(&self, parent: &EntityState, target: &EntityState) -> Cover {
+        let rules = &Module::rules().cover;
+        if !rules.enabled {
+            return Cover::None;
+        }
+
+        let entity_grid = if rules.creatures_block {
+            Some(&self.entity_grid[..])
+        } else {
+            None
+        };
+
+        let blocked = blocked_fraction(
+            &self.area,
+            self.props.entire_vis_grid(),
+            entity_grid,
+            parent,
+            target,
+        );
+        rules.cover(blocked)
+    }
+
     pub fn compute_pc_visibility(
         &mut self,
         entity: &Rc<RefCell<EntityState>>,
@@ -956,6 +995,7 @@ impl AreaState {
         entity.borrow_mut().actor.compute_stats();
 
         entity.borrow_mut().location = location;
+        self.update_entity_elevation(entity);
         self.entities.push(index);
 
         let mgr = GameState::turn_manager();
@@ -984,17 +1024,36 @@ impl AreaState {
     ) -> bool {
         let old_x = entity.borrow().location.x;
         let old_y = entity.borrow().location.y;
+        let threatened_by = entity.borrow().actor.threatened_by();
         if !entity.borrow_mut().move_to(x, y, squares) {
             return false;
         }
 
         let mgr = GameState::turn_manager();
+        let mut mgr = mgr.borrow_mut();
 
-        self.update_entity_position(entity, old_x, old_y, &mut mgr.borrow_mut());
+        self.update_entity_position(entity, old_x, old_y, &mut mgr);
+
+        // squares is zero for pushes and teleports, which do not provoke
+        if squares > 0 {
+            reaction_handler::provoke_on_move(&mut mgr, &entity.borrow(), &threatened_by);
+        }
 
         true
     }
 
+    fn update_entity_elevation(&self, entity: &Rc<RefCell<EntityState>>) {
+        let mut entity = entity.borrow_mut();
+        let x = entity.location.x + entity.size.width / 2;
+        let y = entity.location.y + entity.size.height / 2;
+        let elevation = if self.area.area.coords_valid(x, y) {
+            self.area.layer_set.elevation(x, y)
+        } else {
+            0
+        };
+        entity.set_elevation(elevation);
+    }
+
     pub(crate) fn update_entity_position(
         &mut self,
         entity: &Rc<RefCell<EntityState>>,
@@ -1005,6 +1064,7 @@ impl AreaState {
         let d_x = old_x - entity.borrow().location.x;
         let d_y = old_y - entity.borrow().location.y;
 
+        self.update_entity_elevation(entity);
         let entity_index = entity.borrow().index();
 
         let aura_indices = mgr.auras_for(entity_index);
@@ -1063,6 +1123,9 @@ impl AreaState {
         }
 
         mgr.fire_on_moved_next_update(entity_index);
+        if entity.borrow().actor.stats.hidden {
+            mgr.queue_stealth_check(entity_index);
+        }
         mgr.check_ai_activation(entity, self);
     }
 
@@ -0,0 +1,77 @@
+//  This file is part of Sulis, a turn based RPG written in Rust.
+//  Copyright 2018 Jared Stephen
+//
+//  Sulis is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Sulis is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Sulis.  If not, see <http://www.gnu.org/licenses/>
+
+//! Automatic saves.  Requests are made at the points in the game where an
+//! autosave is wanted, and the save is written on a later update once it is
+//! safe to do so, i.e. outside of combat and with no pending UI callbacks.
+
+use std::cell::Cell;
+
+use sulis_core::config::Config;
+
+use crate::{save_file, GameState};
+
+thread_local! {
+    static PENDING: Cell<Option<AutosaveTrigger>> = const { Cell::new(None) };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutosaveTrigger {
+    AreaTransition,
+    Rest,
+    CombatEnd,
+}
+
+/// Requests an autosave as soon as the game state allows it.  Multiple
+/// requests before the save is written result in a single save.
+pub fn request(trigger: AutosaveTrigger) {
+    PENDING.with(|p| {
+        if p.get().is_none() {
+            p.set(Some(trigger));
+        }
+    });
+}
+
+/// Discards any pending autosave request
+pub fn clear() {
+    PENDING.with(|p| p.set(None));
+}
+
+pub(crate) fn update() {
+    let trigger = match PENDING.with(|p| p.get()) {
+        None => return,
+        Some(trigger) => trigger,
+    };
+
+    if GameState::is_combat_active()
+        || GameState::is_modal_locked()
+        || GameState::has_pending_ui_callbacks()
+    {
+        return;
+    }
+
+    clear();
+
+    if GameState::player().borrow().actor.is_dead() {
+        return;
+    }
+
+    info!("Autosaving after {:?}", trigger);
+    if let Err(e) = save_file::create_autosave(Config::autosave_slots()) {
+        error!("Error writing autosave");
+        error!("{}", e);
+    }
+}
@@ -0,0 +1,366 @@
+//  This file is part of Sulis, a turn based RPG written in Rust.
+//  Copyright 2018 Jared Stephen
+//
+//  Sulis is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Sulis is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Sulis.  If not, see <http://www.gnu.org/licenses/>
+
+//! A structured log of the combat events of the current session: attack
+//! rolls and the damage they dealt, effects applied, abilities used, and
+//! deaths.  The most recent entries are kept in memory for the combat log
+//! window, and may be exported to a JSON file in the user folder for
+//! balance analysis.  The log is not saved, and is cleared whenever a game
+//! is started or loaded.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{BufWriter, Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use chrono::prelude::*;
+
+use sulis_core::config;
+use sulis_core::serde_json;
+use sulis_core::util::ExtInt;
+use sulis_module::{
+    AccuracyKind, AttackRoll, Cover, DamageRoll, HitFlags, HitKind, SaveKind, SaveResult, SaveRoll,
+    Time,
+};
+
+use crate::{ChangeListener, ChangeListenerList, EntityState, GameState};
+
+/// The maximum number of entries held in memory.  Older entries are
+/// discarded as new ones are recorded.
+pub const MAX_ENTRIES: usize = 1000;
+
+thread_local! {
+    static LOG: RefCell<CombatLog> = RefCell::new(CombatLog::default());
+}
+
+#[derive(Default)]
+struct CombatLog {
+    entries: VecDeque<CombatLogEntry>,
+    listeners: ChangeListenerList<CombatLogEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct CombatLogEntry {
+    /// The game time when the event occurred
+    pub time: Time,
+    pub event: CombatEvent,
+}
+
+/// An entity involved in a combat event
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct LoggedEntity {
+    pub id: String,
+    pub name: String,
+}
+
+impl LoggedEntity {
+    fn new(entity: &EntityState) -> LoggedEntity {
+        LoggedEntity {
+            id: entity.unique_id().to_string(),
+            name: entity.actor.actor.name.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub enum CombatEvent {
+    /// An attack roll, and the damage dealt if it hit
+    Attack {
+        attacker: LoggedEntity,
+        target: LoggedEntity,
+        accuracy_kind: AccuracyKind,
+        roll: i32,
+        accuracy: i32,
+        defense: i32,
+        hit_kind: HitKind,
+        flanking: bool,
+        sneak_attack: bool,
+        cover: Cover,
+        damage: Vec<DamageRoll>,
+    },
+
+    /// An attack that missed due to the target's concealment, before
+    /// any attack roll was made
+    Concealed {
+        attacker: LoggedEntity,
+        target: LoggedEntity,
+        concealment: i32,
+    },
+
+    /// A hidden entity noticed by a hostile observer.  The margin is the
+    /// amount by which the observer's perception exceeded the entity's stealth.
+    Detected {
+        entity: LoggedEntity,
+        observer: LoggedEntity,
+        margin: i32,
+    },
+
+    /// A saving throw against a DC, rolled by a script
+    Save {
+        entity: LoggedEntity,
+        kind: SaveKind,
+        roll: i32,
+        bonus: i32,
+        dc: i32,
+        result: SaveResult,
+    },
+
+    /// Damage dealt directly by a script, without an attack roll
+    Damage {
+        attacker: LoggedEntity,
+        target: LoggedEntity,
+        damage: Vec<DamageRoll>,
+    },
+
+    Effect {
+        target: LoggedEntity,
+        name: String,
+
+        /// The duration in rounds
+        duration: ExtInt,
+    },
+
+    Ability {
+        entity: LoggedEntity,
+        id: String,
+        name: String,
+    },
+
+    Death {
+        entity: LoggedEntity,
+        killer: LoggedEntity,
+    },
+}
+
+/// The categories that events may be filtered by in the combat log window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CombatEventKind {
+    Attack,
+    Effect,
+    Ability,
+    Death,
+}
+
+impl CombatEventKind {
+    pub fn iter() -> impl Iterator<Item = &'static CombatEventKind> {
+        use CombatEventKind::*;
+        [Attack, Effect, Ability, Death].iter()
+    }
+
+    pub fn to_str(self) -> &'static str {
+        use CombatEventKind::*;
+        match self {
+            Attack => "attack",
+            Effect => "effect",
+            Ability => "ability",
+            Death => "death",
+        }
+    }
+}
+
+impl CombatEvent {
+    pub fn kind(&self) -> CombatEventKind {
+        use CombatEvent::*;
+        match self {
+            Attack { .. } | Concealed { .. } | Detected { .. } | Save { .. } | Damage { .. } => {
+                CombatEventKind::Attack
+            }
+            Effect { .. } => CombatEventKind::Effect,
+            Ability { .. } => CombatEventKind::Ability,
+            Death { .. } => CombatEventKind::Death,
+        }
+    }
+}
+
+fn record(time: Time, event: CombatEvent) {
+    debug!("Combat log: {:?}", event);
+    let entry = CombatLogEntry { time, event };
+
+    LOG.with(|log| {
+        let mut log = log.borrow_mut();
+        if log.entries.len() >= MAX_ENTRIES {
+            log.entries.pop_front();
+        }
+        log.listeners.notify(&entry);
+        log.entries.push_back(entry);
+    });
+}
+
+fn current_time() -> Time {
+    GameState::turn_manager().borrow().current_time()
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn record_attack(
+    attacker: &EntityState,
+    target: &EntityState,
+    accuracy_kind: AccuracyKind,
+    roll: AttackRoll,
+    flags: HitFlags,
+    cover: Cover,
+    damage: Vec<DamageRoll>,
+) {
+    record(
+        current_time(),
+        CombatEvent::Attack {
+            attacker: LoggedEntity::new(attacker),
+            target: LoggedEntity::new(target),
+            accuracy_kind,
+            roll: roll.roll,
+            accuracy: roll.accuracy,
+            defense: roll.defense,
+            hit_kind: roll.hit_kind,
+            flanking: flags.flanking,
+            sneak_attack: flags.sneak_attack,
+            cover,
+            damage,
+        },
+    );
+}
+
+pub(crate) fn record_concealed(attacker: &EntityState, target: &EntityState, concealment: i32) {
+    record(
+        current_time(),
+        CombatEvent::Concealed {
+            attacker: LoggedEntity::new(attacker),
+            target: LoggedEntity::new(target),
+            concealment,
+        },
+    );
+}
+
+pub(crate) fn record_detected(entity: &EntityState, observer: &EntityState, margin: i32) {
+    record(
+        current_time(),
+        CombatEvent::Detected {
+            entity: LoggedEntity::new(entity),
+            observer: LoggedEntity::new(observer),
+            margin,
+        },
+    );
+}
+
+pub(crate) fn record_save(entity: &EntityState, roll: SaveRoll) {
+    record(
+        current_time(),
+        CombatEvent::Save {
+            entity: LoggedEntity::new(entity),
+            kind: roll.kind,
+            roll: roll.roll,
+            bonus: roll.bonus,
+            dc: roll.dc,
+            result: roll.result,
+        },
+    );
+}
+
+pub(crate) fn record_damage(attacker: &EntityState, target: &EntityState, damage: Vec<DamageRoll>) {
+    record(
+        current_time(),
+        CombatEvent::Damage {
+            attacker: LoggedEntity::new(attacker),
+            target: LoggedEntity::new(target),
+            damage,
+        },
+    );
+}
+
+/// Records an effect applied to the target.  The time is passed in as this
+/// is called while the turn manager is mutably borrowed.
+pub(crate) fn record_effect(time: Time, target: &EntityState, name: &str, duration: ExtInt) {
+    record(
+        time,
+        CombatEvent::Effect {
+            target: LoggedEntity::new(target),
+            name: name.to_string(),
+            duration,
+        },
+    );
+}
+
+pub(crate) fn record_ability(entity: &EntityState, id: &str, name: &str) {
+    record(
+        current_time(),
+        CombatEvent::Ability {
+            entity: LoggedEntity::new(entity),
+            id: id.to_string(),
+            name: name.to_string(),
+        },
+    );
+}
+
+pub(crate) fn record_death(entity: &EntityState, killer: &EntityState) {
+    record(
+        current_time(),
+        CombatEvent::Death {
+            entity: LoggedEntity::new(entity),
+            killer: LoggedEntity::new(killer),
+        },
+    );
+}
+
+/// Returns all entries currently held, oldest first
+pub fn entries() -> Vec<CombatLogEntry> {
+    LOG.with(|log| log.borrow().entries.iter().cloned().collect())
+}
+
+/// Adds a listener which is notified of each new entry as it is recorded
+pub fn add_change_listener(listener: ChangeListener<CombatLogEntry>) {
+    LOG.with(|log| log.borrow_mut().listeners.add(listener));
+}
+
+/// Removes all entries.  Listeners are kept.
+pub fn clear() {
+    LOG.with(|log| log.borrow_mut().entries.clear());
+}
+
+fn get_combat_log_dir() -> PathBuf {
+    let mut path = config::USER_DIR.clone();
+    path.push("combat_log");
+    path
+}
+
+/// Writes all entries currently held to a new JSON file in the combat log
+/// directory of the user folder, returning the path of the file.
+pub fn export() -> Result<PathBuf, Error> {
+    let dir = get_combat_log_dir();
+    if !dir.is_dir() {
+        fs::create_dir_all(&dir)?;
+    }
+
+    let mut path = dir;
+    path.push(format!(
+        "combat_log_{}.json",
+        Utc::now().format("%Y%m%d-%H%M%S%.3f")
+    ));
+
+    export_to(&path)?;
+    Ok(path)
+}
+
+/// Writes all entries currently held to the JSON file at the specified `path`
+pub fn export_to(path: &Path) -> Result<(), Error> {
+    let file = BufWriter::new(File::create(path)?);
+    LOG.with(|log| serde_json::to_writer_pretty(file, &log.borrow().entries))
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+    info!("Exported combat log to {:?}", path);
+    Ok(())
+}
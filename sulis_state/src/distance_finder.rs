@@ -110,8 +110,8 @@ pub fn is_within(parent: &impl Locatable, target: &impl Locatable, max_dist: f32
     dist(parent, target) <= max_dist
 }
 
-pub fn is_within_attack_dist<T: Locatable>(parent: &EntityState, target: &T) -> bool {
-    let dist = parent.actor.stats.attack_distance();
+pub fn is_within_attack_dist(parent: &EntityState, target: &EntityState) -> bool {
+    let dist = parent.attack_distance_to(target);
     is_within(parent, target, dist)
 }
 
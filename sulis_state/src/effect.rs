@@ -42,6 +42,29 @@ pub struct Icon {
     pub text: String,
 }
 
+/// Determines what happens when an effect is applied to an entity which
+/// already has an effect with the same tag
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
+pub enum StackPolicy {
+    /// Each application is a separate effect
+    #[default]
+    Independent,
+
+    /// The existing effect's remaining duration is extended to the new
+    /// effect's duration if that is longer, and the new effect is discarded
+    Refresh,
+
+    /// The existing effect gains a stack, up to `max`, and its remaining
+    /// duration is extended as for `Refresh`.  Its bonuses are multiplied by
+    /// the number of stacks.
+    Stack { max: u32 },
+
+    /// The new effect replaces the existing effect if it is at least as strong,
+    /// and is otherwise discarded
+    ReplaceIfStronger { strength: f32 },
+}
+
 pub struct Effect {
     pub name: String,
     pub tag: String,
@@ -56,6 +79,8 @@ pub struct Effect {
     pub(crate) entity: Option<usize>,
     pub(crate) callbacks: Vec<Rc<CallbackData>>,
     pub(crate) icon: Option<Icon>,
+    pub(crate) stack_policy: StackPolicy,
+    pub(crate) stacks: u32,
 
     squares_moved: HashMap<usize, u32>,
 
@@ -108,6 +133,8 @@ impl Effect {
             surface,
             entity: data.entity,
             icon: data.icon,
+            stack_policy: data.stack_policy,
+            stacks: data.stacks,
 
             squares_moved: HashMap::new(),
             callbacks,
@@ -137,6 +164,8 @@ impl Effect {
             surface: None,
             entity: None,
             icon: None,
+            stack_policy: StackPolicy::Independent,
+            stacks: 1,
             squares_moved: HashMap::new(),
         }
     }
@@ -183,6 +212,40 @@ impl Effect {
         self.icon = Some(Icon { icon, text });
     }
 
+    pub fn stack_policy(&self) -> StackPolicy {
+        self.stack_policy
+    }
+
+    pub fn set_stack_policy(&mut self, policy: StackPolicy) {
+        self.stack_policy = policy;
+    }
+
+    /// Returns the number of times this effect has been stacked, which is
+    /// 1 for effects that have not been stacked
+    pub fn stacks(&self) -> u32 {
+        self.stacks
+    }
+
+    /// Returns the bonuses of this effect multiplied by its number of stacks
+    pub fn stacked_bonuses(&self) -> BonusList {
+        let mut bonuses = self.bonuses.clone();
+        if self.stacks > 1 {
+            let multiplier = self.stacks as f32;
+            bonuses.apply_modifiers(multiplier, multiplier);
+        }
+        bonuses
+    }
+
+    /// Resets the remaining duration of this effect to `duration`
+    pub(crate) fn refresh_duration(&mut self, duration: ExtInt) {
+        self.total_duration = duration + ExtInt::Int(self.cur_duration);
+        self.listeners.notify(self);
+    }
+
+    pub(crate) fn is_expired(&self) -> bool {
+        !self.total_duration.greater_than(self.cur_duration)
+    }
+
     pub fn set_owning_entity(&mut self, entity: usize) {
         self.entity = Some(entity);
     }
@@ -18,9 +18,9 @@ use std::cell::RefCell;
 use std::rc::Rc;
 
 use sulis_core::io::Audio;
-use crate::{center, is_threat, ActorState, EntityState, GameState};
-use sulis_module::{AccuracyKind, Attack, AttackKind, DamageKind, HitFlags, HitKind, Module,
-    OnTrigger};
+use crate::{center, combat_log, is_threat, ActorState, EntityState, GameState};
+use sulis_module::{AccuracyKind, Attack, AttackKind, Cover, DamageKind, HitFlags, HitKind, Module,
+    OnTrigger, SaveKind, SaveRoll, bonus::{BonusList, Contingent}};
 
 fn is_sneak_attack(parent: &EntityState, target: &EntityState) -> bool {
     parent.actor.stats.hidden && !target.actor.stats.sneak_attack_immunity
@@ -76,6 +76,45 @@ fn is_flanking(parent: &EntityState, target: &EntityState) -> bool {
     false
 }
 
+/// Returns the bonuses of the parent which apply to attacks against the
+/// target due to the target's active effects
+fn target_effect_bonuses(parent: &EntityState, target: &EntityState) -> BonusList {
+    let mut result = BonusList::default();
+    let bonuses = &parent.actor.stats.target_effect_bonuses;
+    if bonuses.is_empty() {
+        return result;
+    }
+
+    let mgr = GameState::turn_manager();
+    let mgr = mgr.borrow();
+    for bonus in bonuses.iter() {
+        let tag = match &bonus.when {
+            Contingent::TargetHasEffect(tag) => tag,
+            _ => continue,
+        };
+
+        if target
+            .actor
+            .effects_iter()
+            .any(|index| mgr.effect(*index).tag == *tag)
+        {
+            result.add(bonus.clone());
+        }
+    }
+    result
+}
+
+fn cover(parent: &EntityState, target: &EntityState) -> Cover {
+    if parent.location.area_id != target.location.area_id {
+        return Cover::None;
+    }
+
+    match GameState::get_area_state(&parent.location.area_id) {
+        None => Cover::None,
+        Some(area) => area.borrow().cover(parent, target),
+    }
+}
+
 type AttackResult = Vec<(HitKind, HitFlags, Vec<(DamageKind, u32)>)>;
 
 pub fn weapon_attack(
@@ -96,8 +135,10 @@ pub fn weapon_attack(
 
     let is_flanking = is_flanking(&parent.borrow(), &target.borrow());
     let is_sneak_attack = is_sneak_attack(&parent.borrow(), &target.borrow());
+    let target_bonuses = target_effect_bonuses(&parent.borrow(), &target.borrow());
 
     let mut had_crit = false;
+    let mut had_hit = false;
     let mut result = Vec::new();
     for attack in attacks {
         let mut attack = if is_flanking {
@@ -106,6 +147,10 @@ pub fn weapon_attack(
             attack
         };
 
+        if !target_bonuses.is_empty() {
+            attack = Attack::from(&attack, &target_bonuses);
+        }
+
         let (hit_kind, hit_flags, damage) =
             attack_internal(parent, target, &mut attack, is_flanking, is_sneak_attack);
 
@@ -113,6 +158,10 @@ pub fn weapon_attack(
             had_crit = true;
         }
 
+        if hit_kind != HitKind::Miss {
+            had_hit = true;
+        }
+
         let sound = attack.sounds.sound(hit_kind);
         if let Some(sound_id) = sound {
             Audio::play_sfx(sound_id, 1.0);
@@ -125,10 +174,31 @@ pub fn weapon_attack(
         GameState::add_ui_callback(vec![OnTrigger::ScreenShake], parent, target);
     }
 
+    if had_hit {
+        parent.borrow_mut().actor.wear_equipped(true);
+    }
+
     ActorState::check_death(parent, target);
     result
 }
 
+/// Rolls a saving throw of the specified kind for the `target` against the
+/// `dc`, recording the result in the combat log
+pub fn saving_throw(target: &Rc<RefCell<EntityState>>, kind: SaveKind, dc: i32) -> SaveRoll {
+    let roll = GameState::with_rand(|rand| target.borrow().actor.stats.save_roll(kind, dc, rand));
+
+    info!(
+        "'{}' rolls a {:?} save against {}: {:?}",
+        target.borrow().actor.actor.name,
+        kind,
+        dc,
+        roll.result
+    );
+    combat_log::record_save(&target.borrow(), roll);
+
+    roll
+}
+
 pub fn attack(
     parent: &Rc<RefCell<EntityState>>,
     target: &Rc<RefCell<EntityState>>,
@@ -147,6 +217,11 @@ pub fn attack(
     let is_flanking = is_flanking(&parent.borrow(), &target.borrow());
     let is_sneak_attack = is_sneak_attack(&parent.borrow(), &target.borrow());
 
+    let target_bonuses = target_effect_bonuses(&parent.borrow(), &target.borrow());
+    if !target_bonuses.is_empty() {
+        *attack = Attack::from(attack, &target_bonuses);
+    }
+
     let (hit_kind, hit_flags, damage) =
         attack_internal(parent, target, attack, is_flanking, is_sneak_attack);
 
@@ -164,13 +239,22 @@ fn attack_internal(
 ) -> (HitKind, HitFlags, Vec<(DamageKind, u32)>) {
     let rules = Module::rules();
 
+    let cover = cover(&parent.borrow(), &target.borrow());
+    let cover_bonuses = rules.cover.bonuses(cover);
+    if cover != Cover::None {
+        debug!("Target has {} cover", cover.to_str());
+    }
+
     let concealment = std::cmp::max(
         0,
-        target.borrow().actor.stats.concealment - parent.borrow().actor.stats.concealment_ignore,
+        target.borrow().actor.stats.concealment
+            + cover_bonuses.map_or(0, |bonuses| bonuses.concealment)
+            - parent.borrow().actor.stats.concealment_ignore,
     );
 
-    if !rules.concealment_roll(concealment) {
+    if !GameState::with_rand(|rand| rules.concealment_roll(concealment, rand)) {
         debug!("Concealment miss");
+        combat_log::record_concealed(&parent.borrow(), &target.borrow(), concealment);
         return (
             HitKind::Miss,
             HitFlags {
@@ -183,12 +267,16 @@ fn attack_internal(
 
     let (accuracy_kind, defense) = {
         let target_stats = &target.borrow().actor.stats;
+        let cover_defense = cover_bonuses.map_or(0, |bonuses| bonuses.defense);
+        let cover_reflex = cover_bonuses.map_or(0, |bonuses| bonuses.reflex);
         match attack.kind {
             AttackKind::Fortitude { accuracy } => (accuracy, target_stats.fortitude),
-            AttackKind::Reflex { accuracy } => (accuracy, target_stats.reflex),
+            AttackKind::Reflex { accuracy } => (accuracy, target_stats.reflex + cover_reflex),
             AttackKind::Will { accuracy } => (accuracy, target_stats.will),
-            AttackKind::Melee { .. } => (AccuracyKind::Melee, target_stats.defense),
-            AttackKind::Ranged { .. } => (AccuracyKind::Ranged, target_stats.defense),
+            AttackKind::Melee { .. } => (AccuracyKind::Melee, target_stats.defense + cover_defense),
+            AttackKind::Ranged { .. } => {
+                (AccuracyKind::Ranged, target_stats.defense + cover_defense)
+            }
             AttackKind::Dummy => {
                 return (HitKind::Hit, HitFlags::default(), Vec::new());
             }
@@ -196,6 +284,16 @@ fn attack_internal(
     };
     let crit_immunity = target.borrow().actor.stats.crit_immunity;
 
+    let elevation_bonus = rules
+        .elevation
+        .accuracy_bonus(parent.borrow().elevation(), target.borrow().elevation());
+    if elevation_bonus != 0 {
+        debug!("Elevation accuracy bonus {}", elevation_bonus);
+        attack.bonuses.melee_accuracy += elevation_bonus;
+        attack.bonuses.ranged_accuracy += elevation_bonus;
+        attack.bonuses.spell_accuracy += elevation_bonus;
+    }
+
     if flanking {
         attack.bonuses.melee_accuracy += rules.flanking_accuracy_bonus;
         attack.bonuses.ranged_accuracy += rules.flanking_accuracy_bonus;
@@ -212,13 +310,23 @@ fn attack_internal(
         concealment: false,
     };
 
-    let (hit_kind, damage_multiplier) = {
+    let (roll, damage_multiplier) = {
         let parent_stats = &parent.borrow().actor.stats;
-        let hit_kind =
-            parent_stats.attack_roll(accuracy_kind, crit_immunity, defense, &attack.bonuses);
-        let damage_multiplier = match hit_kind {
+        let roll = GameState::with_rand(|rand| {
+            parent_stats.attack_roll(accuracy_kind, crit_immunity, defense, &attack.bonuses, rand)
+        });
+        let damage_multiplier = match roll.hit_kind {
             HitKind::Miss => {
                 debug!("Miss");
+                combat_log::record_attack(
+                    &parent.borrow(),
+                    &target.borrow(),
+                    accuracy_kind,
+                    roll,
+                    hit_flags,
+                    cover,
+                    Vec::new(),
+                );
                 return (HitKind::Miss, hit_flags, Vec::new());
             }
             HitKind::Graze => parent_stats.graze_multiplier + attack.bonuses.graze_multiplier,
@@ -226,14 +334,37 @@ fn attack_internal(
             HitKind::Crit => parent_stats.crit_multiplier + attack.bonuses.crit_multiplier,
             HitKind::Auto => panic!(),
         };
-        (hit_kind, damage_multiplier)
+        (roll, damage_multiplier)
     };
+    let hit_kind = roll.hit_kind;
 
-    let damage = {
+    let rolls = {
         let target = &target.borrow().actor.stats;
         let damage = &attack.damage;
-        rules.roll_damage(damage, &target.armor, &target.resistance, damage_multiplier)
+        GameState::with_rand(|rand| {
+            rules.roll_damage_details(
+                damage,
+                &target.armor,
+                &target.resistance,
+                damage_multiplier,
+                rand,
+            )
+        })
     };
+    let damage: Vec<_> = rolls
+        .iter()
+        .filter(|roll| roll.amount > 0)
+        .map(|roll| (roll.kind, roll.amount))
+        .collect();
+    combat_log::record_attack(
+        &parent.borrow(),
+        &target.borrow(),
+        accuracy_kind,
+        roll,
+        hit_flags,
+        cover,
+        rolls,
+    );
 
     debug!("{:?}. {:?} damage", hit_kind, damage);
 
@@ -244,6 +375,7 @@ fn attack_internal(
         }
 
         EntityState::remove_hp(target, parent, hit_kind, damage.clone());
+        target.borrow_mut().actor.wear_equipped(false);
     }
 
     (hit_kind, hit_flags, damage)
@@ -24,7 +24,9 @@ use std::usize;
 use sulis_core::config::Config;
 
 use crate::animation::{self, Anim};
+use crate::combat_log;
 use crate::save_state::EntitySaveState;
+use crate::schedule::ScheduleState;
 use crate::script::{self, CallbackData, ScriptEntitySet};
 use crate::{
     entity_attack_handler::weapon_attack, entity_texture_cache::Slot, is_within_attack_dist,
@@ -36,12 +38,21 @@ use sulis_core::ui::{color, Color};
 use sulis_core::util::{invalid_data_error, Offset, Scale, Size, Point};
 use sulis_module::area::MAX_AREA_SIZE;
 use sulis_module::{
-    actor::Faction, ai, Actor, DamageKind, HitKind, Module, ObjectSize, ObjectSizeIterator,
+    actor::Faction,
+    ai::{self, PartyAIMode},
+    AITemplate, Actor, DamageKind, HitKind, Module, ObjectSize, ObjectSizeIterator,
 };
 
 enum AIState {
-    Player { vis: Vec<bool>, show_portrait: bool },
-    AI { group: Option<usize>, active: bool },
+    Player {
+        vis: Vec<bool>,
+        show_portrait: bool,
+        ai_mode: Option<PartyAIMode>,
+    },
+    AI {
+        group: Option<usize>,
+        active: bool,
+    },
 }
 
 pub struct EntityState {
@@ -65,6 +76,12 @@ pub struct EntityState {
     unique_id: String, // assigned when setting the index and persisted on save
 
     collapsed_groups: Vec<String>,
+
+    schedule: Option<ScheduleState>,
+
+    // elevation of the area at the center of this entity, updated by the area
+    // state whenever this entity is placed or moved
+    elevation: u8,
 }
 
 impl PartialEq for EntityState {
@@ -88,6 +105,7 @@ impl EntityState {
                 AIState::Player {
                     vis: vec![false; dim],
                     show_portrait: save.show_portrait,
+                    ai_mode: save.party_ai_mode,
                 }
             }
         };
@@ -125,6 +143,8 @@ impl EntityState {
             texture_cache_slot: None,
             custom_flags: save.custom_flags,
             collapsed_groups: save.collapsed_groups,
+            schedule: save.schedule,
+            elevation: 0,
         })
     }
 
@@ -140,6 +160,7 @@ impl EntityState {
             AIState::Player {
                 vis: vec![false; dim],
                 show_portrait: true,
+                ai_mode: None,
             }
         } else {
             AIState::AI {
@@ -173,9 +194,33 @@ impl EntityState {
             texture_cache_slot: None,
             custom_flags: HashMap::new(),
             collapsed_groups: Vec::new(),
+            schedule: None,
+            elevation: 0,
         }
     }
 
+    /// Returns the elevation of the area at the center of this entity
+    pub fn elevation(&self) -> u8 {
+        self.elevation
+    }
+
+    pub(crate) fn set_elevation(&mut self, elevation: u8) {
+        self.elevation = elevation;
+    }
+
+    /// Returns the maximum distance at which this entity can attack the
+    /// specified `target`, including any range bonus for ranged attacks from
+    /// higher ground
+    pub fn attack_distance_to(&self, target: &EntityState) -> f32 {
+        let dist = self.actor.stats.attack_distance();
+        if self.actor.stats.attack_is_melee() {
+            return dist;
+        }
+
+        let rules = &Module::rules().elevation;
+        dist + rules.ranged_distance_bonus(self.elevation, target.elevation)
+    }
+
     pub fn add_collapsed_group(&mut self, group: String) {
         self.collapsed_groups.push(group);
     }
@@ -214,6 +259,8 @@ impl EntityState {
                     ai::FuncKind::OnRoundElapsed => {
                         cbs.add_func(script::FuncKind::OnRoundElapsed, func)
                     },
+                    ai::FuncKind::OnReaction => cbs.add_func(script::FuncKind::OnReaction, func),
+                    ai::FuncKind::OnDetected => cbs.add_func(script::FuncKind::OnDetected, func),
                     ai::FuncKind::AiAction => (), // this is handled specially when running the AI
                 }
             }
@@ -320,10 +367,12 @@ impl EntityState {
     }
 
     pub fn add_to_party(&mut self, show_portrait: bool) {
+        self.schedule = None;
         let dim = (MAX_AREA_SIZE * MAX_AREA_SIZE) as usize;
         self.ai_state = AIState::Player {
             vis: vec![false; dim],
             show_portrait,
+            ai_mode: None,
         };
     }
 
@@ -334,6 +383,68 @@ impl EntityState {
         };
     }
 
+    /// The schedule this entity follows outside of combat, if any
+    pub fn schedule(&self) -> Option<&ScheduleState> {
+        self.schedule.as_ref()
+    }
+
+    pub fn schedule_mut(&mut self) -> Option<&mut ScheduleState> {
+        self.schedule.as_mut()
+    }
+
+    pub fn set_schedule(&mut self, schedule: Option<ScheduleState>) {
+        self.schedule = schedule;
+    }
+
+    /// The mode the AI uses to control this party member, if it has been
+    /// given one.  Always `None` for entities outside the party.
+    pub fn party_ai_mode(&self) -> Option<PartyAIMode> {
+        match self.ai_state {
+            AIState::Player { ai_mode, .. } => ai_mode,
+            AIState::AI { .. } => None,
+        }
+    }
+
+    pub fn set_party_ai_mode(&mut self, mode: Option<PartyAIMode>) {
+        if let AIState::Player {
+            ref mut ai_mode, ..
+        } = self.ai_state
+        {
+            *ai_mode = mode;
+            self.actor.listeners.notify(&self.actor);
+        }
+    }
+
+    /// The AI template controlling this entity on its turn.  Party members
+    /// are controlled with the template of their AI mode, or of the default
+    /// mode while auto-combat is on, and are otherwise controlled by the player.
+    pub fn ai_template(&self) -> Option<Rc<AITemplate>> {
+        let mode = match self.ai_state {
+            AIState::AI { .. } => return self.actor.actor.ai.clone(),
+            AIState::Player { ai_mode, .. } => ai_mode,
+        };
+
+        let rules = &Module::rules().party_ai;
+        let mode = match mode {
+            Some(mode) => mode,
+            None if GameState::is_auto_combat() => rules.default_mode,
+            None => return None,
+        };
+
+        let template = match rules.templates.get(&mode) {
+            None => {
+                warn!("No AI template specified for party AI mode {:?}", mode);
+                return None;
+            }
+            Some(id) => Module::ai_template(id),
+        };
+
+        if template.is_none() {
+            warn!("Invalid AI template for party AI mode {:?}", mode);
+        }
+        template
+    }
+
     pub fn is_party_member(&self) -> bool {
         match self.ai_state {
             AIState::Player { .. } => true,
@@ -392,17 +503,22 @@ impl EntityState {
     }
 
     pub fn is_hostile(&self, other: &EntityState) -> bool {
-        let self_faction = self.actor.faction();
-        let other_faction = other.actor.faction();
-
-        self_faction.is_hostile(other_faction)
+        GameState::is_hostile_faction(self.actor.faction(), other.actor.faction())
     }
 
     pub fn is_friendly(&self, other: &EntityState) -> bool {
-        let self_faction = self.actor.faction();
-        let other_faction = other.actor.faction();
+        GameState::is_friendly_faction(self.actor.faction(), other.actor.faction())
+    }
 
-        self_faction.is_friendly(other_faction)
+    /// Whether this entity is hostile to the party's faction
+    pub fn is_hostile_to_party(&self) -> bool {
+        GameState::is_hostile_faction(self.actor.faction(), &Faction::friendly())
+    }
+
+    /// Whether this entity is hidden from, and not yet detected by, the party.
+    /// Such entities are not shown in the area view.
+    pub fn is_hidden_from_party(&self) -> bool {
+        self.actor.stats.hidden && self.is_hostile_to_party()
     }
 
     pub(crate) fn is_marked_for_removal(&self) -> bool {
@@ -491,6 +607,7 @@ impl EntityState {
         damage: Vec<(DamageKind, u32)>,
     ) {
         let hp_amount = damage.iter().map(|(_, amount)| amount).sum();
+        let was_alive = entity.borrow().actor.hp() > 0;
         entity.borrow_mut().actor.remove_hp(hp_amount);
 
         let targets = ScriptEntitySet::from_pair(entity, attacker);
@@ -507,6 +624,9 @@ impl EntityState {
                 "Entity '{}' has zero hit points.  Playing death animation",
                 entity.borrow().actor.actor.name
             );
+            if was_alive {
+                combat_log::record_death(&entity.borrow(), &attacker.borrow());
+            }
             let anim = Anim::new_entity_death(entity);
             GameState::add_animation(anim);
         } else {
@@ -637,12 +757,8 @@ impl AreaDrawable for EntityState {
         color: Color,
     ) {
         // don't draw invisible hostiles
-        if self.actor.stats.hidden {
-            match self.actor.faction() {
-                Faction::Hostile => return,
-                Faction::Neutral => (),
-                Faction::Friendly => (),
-            }
+        if self.is_hidden_from_party() {
+            return;
         }
 
         let offset_x = (self.scale - 1.0) * self.size.width as f32 / 2.0;
@@ -21,19 +21,26 @@ use std::rc::Rc;
 
 use sulis_core::config::Config;
 use sulis_core::io::{GraphicsRenderer};
-use sulis_core::util::{invalid_data_error, ExtInt, Offset, Point, Scale};
+use sulis_core::util::{invalid_data_error, ExtInt, Offset, Point, ReproducibleRandom, Scale};
 use sulis_module::on_trigger::QuestEntryState;
 use sulis_module::{
     area::{Destination, PathFinder, Trigger, TriggerKind},
-    Actor, ItemState, Module, OnTrigger, Time, MOVE_TO_THRESHOLD,
+    faction::{FRIENDLY_THRESHOLD, HOSTILE_THRESHOLD, MAX_RELATIONSHIP, MIN_RELATIONSHIP},
+    Actor, Difficulty, Faction, ItemState, Module, OnTrigger, Time, MOVE_TO_THRESHOLD,
 };
 
+use crate::ai_trace;
 use crate::animation::{particle_generator::Param, Anim, AnimSaveState, AnimState};
+use crate::autosave;
+use crate::combat_log;
+use crate::reaction_handler;
+use crate::schedule;
 use crate::script::{script_cache, script_callback, Script, ScriptCallback, ScriptEntity};
+use crate::stealth;
 use crate::{
-    path_finder, transition_handler, AreaState, ChangeListener, ChangeListenerList, Effect,
-    EntityState, Formation, ItemList, Location, PartyStash, QuestStateSet, SaveState, TurnManager,
-    UICallback, WorldMapState, AI,
+    is_within_touch_dist, path_finder, transition_handler, AreaState, ChangeListener,
+    ChangeListenerList, Effect, EntityState, Formation, ItemList, Location, PartyStash,
+    QuestStateSet, SaveState, TurnManager, UICallback, WorldMapState, AI,
 };
 
 thread_local! {
@@ -45,6 +52,15 @@ thread_local! {
     static ANIMATIONS: RefCell<AnimState> = RefCell::new(AnimState::new());
     static ANIMS_TO_ADD: RefCell<Vec<Anim>> = RefCell::new(Vec::new());
     static COMBAT_INACTIVE_TIME: Cell<u32> = Cell::new(0);
+    static RANDOM: RefCell<ReproducibleRandom> = RefCell::new(ReproducibleRandom::new(None));
+    static REPUTATION: RefCell<HashMap<String, i32>> = RefCell::new(HashMap::new());
+
+    /// The resolved relationship between each pair of factions, by faction ID.
+    /// Cleared whenever reputation or the module factions change.
+    static RELATIONSHIPS: RefCell<HashMap<String, HashMap<String, i32>>> =
+        RefCell::new(HashMap::new());
+    static DIFFICULTY: RefCell<Difficulty> = RefCell::new(Difficulty::default());
+    static STASH_LOAD_INVALID: Cell<bool> = const { Cell::new(true) };
 }
 
 pub struct GameState {
@@ -58,6 +74,7 @@ pub struct GameState {
     party_formation: Rc<RefCell<Formation>>,
     party_coins: i32,
     party_stash: Rc<RefCell<PartyStash>>,
+    party_auto_combat: bool,
 
     // listener returns the first selected party member
     party_listeners: ChangeListenerList<Option<Rc<RefCell<EntityState>>>>,
@@ -73,6 +90,30 @@ const MAX_ZOOM: f32 = 2.0;
 
 impl GameState {
     pub fn load(save_state: SaveState) -> Result<(), Error> {
+        let rand = match save_state.rand {
+            Some(ref rand) if !Config::debug().reseed_random_on_load => rand.clone(),
+            _ => ReproducibleRandom::new(None),
+        };
+        info!("Loaded campaign random stream with seed {}", rand.seed());
+        RANDOM.with(|r| *r.borrow_mut() = rand);
+        REPUTATION.with(|r| *r.borrow_mut() = save_state.reputation.clone());
+        clear_relationships();
+
+        let rules = Module::rules();
+        let difficulty = match rules.difficulty(&save_state.difficulty) {
+            Some(difficulty) => difficulty.clone(),
+            None => {
+                if !save_state.difficulty.is_empty() {
+                    warn!(
+                        "Invalid difficulty '{}' in save, using default",
+                        save_state.difficulty
+                    );
+                }
+                rules.default_difficulty()
+            }
+        };
+        DIFFICULTY.with(|d| *d.borrow_mut() = difficulty);
+
         TURN_MANAGER.with(|mgr| {
             mgr.borrow_mut().load(save_state.total_elapsed_millis);
         });
@@ -82,6 +123,9 @@ impl GameState {
         MODAL_LOCKED.with(|c| c.set(false));
         ANIMS_TO_ADD.with(|anims| anims.borrow_mut().clear());
         AI.with(|ai| *ai.borrow_mut() = AI::new());
+        autosave::clear();
+        combat_log::clear();
+        ai_trace::clear();
         script_cache::setup().map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
 
         let game_state: Result<GameState, Error> = {
@@ -130,7 +174,11 @@ impl GameState {
                 }
             }
 
-            for entity in entities.values() {
+            // load entities in their saved order, so they are given the same
+            // relative indices each time a save is loaded
+            let mut indices: Vec<usize> = entities.keys().copied().collect();
+            indices.sort_unstable();
+            for entity in indices.iter().map(|index| &entities[index]) {
                 let area_state = match areas.get(&entity.borrow().location.area_id) {
                     Some(state) => state,
                     None => unreachable!(),
@@ -176,7 +224,7 @@ impl GameState {
 
                     let new_idx =
                         mgr.borrow_mut()
-                            .add_effect(effect, &entity, Vec::new(), Vec::new());
+                            .load_effect(effect, &entity, Vec::new(), Vec::new());
                     assert!(new_index == new_idx);
                     effects.insert(old_index, new_index);
                     continue;
@@ -230,7 +278,8 @@ impl GameState {
                     Some(item) => Ok(item),
                 }?;
 
-                let item = ItemState::new(item, item_save.item.variant);
+                let item = ItemState::new(item, item_save.item.variant)
+                    .with_durability(item_save.item.durability);
 
                 stash.add_quantity(item_save.quantity, item);
             }
@@ -252,7 +301,8 @@ impl GameState {
                 user_zoom: save_state.zoom,
                 party_formation: Rc::new(RefCell::new(formation)),
                 party_coins,
-                party_stash: Rc::new(RefCell::new(PartyStash::new(stash))),
+                party_stash: create_party_stash(stash),
+                party_auto_combat: save_state.auto_combat,
                 party_listeners: ChangeListenerList::default(),
                 party_death_listeners: ChangeListenerList::default(),
                 ui_callbacks: Vec::new(),
@@ -275,16 +325,32 @@ impl GameState {
         Ok(())
     }
 
+    /// Starts a new game with the specified party.  The game is played on the
+    /// specified `difficulty`, or the rules default difficulty if it is None.
     pub fn init(
         pc_actor: Rc<Actor>,
         party_actors: Vec<Rc<Actor>>,
         flags: HashMap<String, String>,
+        difficulty: Option<Difficulty>,
     ) -> Result<(), Error> {
         ANIMATIONS.with(|anims| anims.borrow_mut().clear());
         CLEAR_ANIMS.with(|c| c.set(false));
         MODAL_LOCKED.with(|c| c.set(false));
         ANIMS_TO_ADD.with(|anims| anims.borrow_mut().clear());
         AI.with(|ai| *ai.borrow_mut() = AI::new());
+        autosave::clear();
+        combat_log::clear();
+        ai_trace::clear();
+
+        let rand = ReproducibleRandom::new(None);
+        info!("Created campaign random stream with seed {}", rand.seed());
+        RANDOM.with(|r| *r.borrow_mut() = rand);
+        REPUTATION.with(|r| r.borrow_mut().clear());
+        clear_relationships();
+
+        let difficulty = difficulty.unwrap_or_else(|| Module::rules().default_difficulty());
+        info!("Starting new game on difficulty '{}'", difficulty.id);
+        DIFFICULTY.with(|d| *d.borrow_mut() = difficulty);
 
         TURN_MANAGER.with(|mgr| {
             let rules = Module::rules();
@@ -422,7 +488,8 @@ impl GameState {
             party,
             party_formation: Rc::new(RefCell::new(Formation::default())),
             party_coins,
-            party_stash: Rc::new(RefCell::new(PartyStash::new(party_stash))),
+            party_stash: create_party_stash(party_stash),
+            party_auto_combat: false,
             party_listeners: ChangeListenerList::default(),
             party_death_listeners: ChangeListenerList::default(),
             ui_callbacks: Vec::new(),
@@ -527,6 +594,21 @@ impl GameState {
         STATE.with(|state| state.borrow().as_ref().unwrap().user_zoom)
     }
 
+    /// Calls the specified function with the campaign random number stream.  All
+    /// gameplay rolls should use this stream, so that results are reproducible
+    /// after loading a save.  The function must not call `with_rand` itself.
+    pub fn with_rand<T, F: FnOnce(&mut ReproducibleRandom) -> T>(f: F) -> T {
+        RANDOM.with(|r| f(&mut r.borrow_mut()))
+    }
+
+    pub fn rand_state() -> ReproducibleRandom {
+        RANDOM.with(|r| r.borrow().clone())
+    }
+
+    pub(crate) fn set_rand_state(rand: ReproducibleRandom) {
+        RANDOM.with(|r| *r.borrow_mut() = rand);
+    }
+
     pub fn turn_manager() -> Rc<RefCell<TurnManager>> {
         TURN_MANAGER.with(|m| Rc::clone(m))
     }
@@ -610,6 +692,10 @@ impl GameState {
             state.party_listeners.notify(&entity);
         });
 
+        // no longer carrying any of the stash
+        entity.borrow_mut().actor.set_stash_load(0);
+        invalidate_stash_load();
+
         let area_state = GameState::area_state();
         area_state.borrow_mut().update_view_visibility();
         area_state.borrow_mut().pc_vis_full_redraw();
@@ -725,6 +811,78 @@ impl GameState {
         }
     }
 
+    /// Splits the weight of the party stash evenly among the party members,
+    /// if the stash or the party has changed since this was last done
+    fn update_stash_load() {
+        if !STASH_LOAD_INVALID.with(|invalid| invalid.replace(false)) {
+            return;
+        }
+
+        let party = GameState::party();
+        if party.is_empty() {
+            return;
+        }
+
+        let weight = GameState::party_stash().borrow().weight();
+        let share = weight / party.len() as i32;
+        for member in party {
+            member.borrow_mut().actor.set_stash_load(share);
+        }
+    }
+
+    /// Updates the conditions used to evaluate contingent bonuses for the
+    /// entities in the current area that have any bonuses depending on them.
+    /// This is only done after an event that may change the conditions, such
+    /// as a move, a new round, or an effect being added or removed.
+    fn update_contingent_states() {
+        let area_state = GameState::area_state();
+        let mgr = GameState::turn_manager();
+        if !mgr.borrow_mut().take_contingent_update() {
+            return;
+        }
+
+        let location_kind = area_state.borrow().area.area.location_kind;
+        let (hour, in_combat, entities) = {
+            let mgr = mgr.borrow();
+            let entities: Vec<_> = area_state
+                .borrow()
+                .entity_iter()
+                .map(|index| mgr.entity(*index))
+                .filter(|entity| !entity.borrow().actor.is_dead())
+                .collect();
+            (mgr.current_time().hour, mgr.is_combat_active(), entities)
+        };
+
+        for entity in entities.iter() {
+            let state = {
+                let entity_ref = entity.borrow();
+                let actor = &entity_ref.actor;
+                if !actor.stats.has_state_contingents() {
+                    continue;
+                }
+
+                let adjacent_to_ally = entities.iter().any(|other| {
+                    if Rc::ptr_eq(entity, other) {
+                        return false;
+                    }
+
+                    let other = other.borrow();
+                    entity_ref.is_friendly(&other) && is_within_touch_dist(&entity_ref, &*other)
+                });
+
+                let mut state = actor.contingent_state().clone();
+                state.hp_frac = actor.hp_frac();
+                state.location_kind = Some(location_kind);
+                state.hour = hour;
+                state.in_combat = in_combat;
+                state.adjacent_to_ally = adjacent_to_ally;
+                state
+            };
+
+            entity.borrow_mut().actor.set_contingent_state(state);
+        }
+    }
+
     pub fn has_party_member(id: &str) -> bool {
         for entity in GameState::party() {
             if entity.borrow().actor.actor.id == id {
@@ -756,6 +914,7 @@ impl GameState {
             let entity = state.selected.first().map(Rc::clone);
             state.party_listeners.notify(&entity);
         });
+        invalidate_stash_load();
 
         let area_state = GameState::area_state();
         area_state.borrow_mut().update_view_visibility();
@@ -895,6 +1054,29 @@ impl GameState {
         })
     }
 
+    /// Looks up the resources used by all entities and the party stash
+    /// again from the `Module`.  Called after module resources are reloaded.
+    pub fn reload_resources() {
+        let mgr = GameState::turn_manager();
+        let entities: Vec<_> = mgr.borrow().entity_iter().collect();
+        for entity in entities {
+            entity.borrow_mut().actor.reload_resources();
+        }
+
+        GameState::party_stash().borrow_mut().reload_resources();
+        clear_relationships();
+    }
+
+    /// The UI callbacks that have been added but not yet returned by `update`,
+    /// in the order they were added
+    pub fn pending_ui_callbacks() -> Vec<UICallback> {
+        STATE.with(|s| s.borrow().as_ref().unwrap().ui_callbacks.clone())
+    }
+
+    pub fn has_pending_ui_callbacks() -> bool {
+        STATE.with(|s| !s.borrow().as_ref().unwrap().ui_callbacks.is_empty())
+    }
+
     pub fn is_modal_locked() -> bool {
         MODAL_LOCKED.with(|c| c.get())
     }
@@ -960,6 +1142,12 @@ impl GameState {
         let cbs = mgr.borrow_mut().update_entity_move_callbacks();
         script_callback::fire_on_moved(cbs);
 
+        let reactions = mgr.borrow_mut().drain_reactions();
+        reaction_handler::fire_reactions(reactions);
+
+        let stealth_checks = mgr.borrow_mut().drain_stealth_checks();
+        stealth::check_detection(stealth_checks);
+
         {
             let area_state = GameState::area_state();
             let mut area_state = area_state.borrow_mut();
@@ -1005,7 +1193,13 @@ impl GameState {
         };
         COMBAT_INACTIVE_TIME.with(|c| c.set(inactive_time));
 
+        if !mgr.borrow().is_combat_active() && !GameState::is_modal_locked() {
+            schedule::update();
+        }
+
         GameState::handle_disabled_party_members();
+        GameState::update_stash_load();
+        GameState::update_contingent_states();
 
         let campaign = Module::campaign();
         if let Some(script_data) = &campaign.on_tick_script {
@@ -1015,6 +1209,11 @@ impl GameState {
             script_cache::set_report_enabled(true);
         }
 
+        // wait until the view has handled the callback before autosaving
+        if ui_cb.is_none() {
+            autosave::update();
+        }
+
         ui_cb
     }
 
@@ -1100,7 +1299,7 @@ impl GameState {
     }
 
     pub fn get_target_dest(entity: &EntityState, target: &EntityState) -> Destination {
-        let dist = entity.actor.stats.attack_distance();
+        let dist = entity.attack_distance_to(target);
         let x = target.location.x as f32;
         let y = target.location.y as f32;
         let w = target.size.width as f32;
@@ -1142,6 +1341,27 @@ impl GameState {
         entities_to_ignore: &[usize],
         dest: Destination,
         cb: Option<Box<dyn ScriptCallback>>,
+    ) -> bool {
+        GameState::move_towards(entity, entities_to_ignore, dest, cb, true)
+    }
+
+    /// Moves the entity towards the dest without requiring it to have the AP
+    /// for a step.  Only useful out of combat, where movement is free
+    pub fn move_ignore_ap(
+        entity: &Rc<RefCell<EntityState>>,
+        entities_to_ignore: &[usize],
+        dest: Destination,
+        cb: Option<Box<dyn ScriptCallback>>,
+    ) -> bool {
+        GameState::move_towards(entity, entities_to_ignore, dest, cb, false)
+    }
+
+    fn move_towards(
+        entity: &Rc<RefCell<EntityState>>,
+        entities_to_ignore: &[usize],
+        dest: Destination,
+        cb: Option<Box<dyn ScriptCallback>>,
+        check_ap: bool,
     ) -> bool {
         let anim = STATE.with(|s| {
             let mut state = s.borrow_mut();
@@ -1155,6 +1375,7 @@ impl GameState {
                 entities_to_ignore,
                 dest,
                 cb,
+                check_ap,
             )
         });
 
@@ -1219,6 +1440,175 @@ impl GameState {
         STATE.with(|s| s.borrow_mut().as_mut().unwrap().party_coins += amount);
     }
 
+    /// Whether party members without an AI mode of their own are controlled
+    /// by the AI in combat, see `EntityState::ai_template`
+    pub fn is_auto_combat() -> bool {
+        STATE.with(|s| s.borrow().as_ref().is_some_and(|s| s.party_auto_combat))
+    }
+
+    pub fn set_auto_combat(auto_combat: bool) {
+        info!("Set party auto-combat to {}", auto_combat);
+        STATE.with(|s| s.borrow_mut().as_mut().unwrap().party_auto_combat = auto_combat);
+    }
+
+    /// Gives the player control of the current turn, if it belongs to a
+    /// party member under AI control
+    pub fn take_over_turn() {
+        let mgr = GameState::turn_manager();
+        let current = match mgr.borrow().current() {
+            None => return,
+            Some(current) => current,
+        };
+
+        if !current.borrow().is_party_member() || current.borrow().ai_template().is_none() {
+            return;
+        }
+
+        if !mgr.borrow().is_turn_taken_over() {
+            info!(
+                "Player took over the turn of '{}'",
+                current.borrow().actor.actor.name
+            );
+            mgr.borrow_mut().take_over_turn();
+        }
+    }
+
+    /// The cost to repair all damaged items equipped by the party or in the
+    /// party stash, at the specified fraction of each item's value
+    pub fn party_repair_price(repair_frac: f32) -> i32 {
+        let price = |item_state: &ItemState| {
+            let value = item_state.item.value as f32 * item_state.damage_frac() * repair_frac;
+            value.ceil() as i32
+        };
+
+        let mut total = 0;
+        for member in GameState::party() {
+            let member = member.borrow();
+            total += member
+                .actor
+                .inventory()
+                .damaged_iter()
+                .map(price)
+                .sum::<i32>();
+        }
+
+        let stash = GameState::party_stash();
+        let stash = stash.borrow();
+        total += stash
+            .items()
+            .iter()
+            .map(|(qty, item_state)| *qty as i32 * price(item_state))
+            .sum::<i32>();
+        total
+    }
+
+    /// Repairs all damaged items equipped by the party or in the party stash,
+    /// paying with party coins.  Returns false if there is nothing to repair
+    /// or the party cannot afford it.
+    pub fn repair_party_items(repair_frac: f32) -> bool {
+        let price = GameState::party_repair_price(repair_frac);
+        if price == 0 || GameState::party_coins() < price {
+            return false;
+        }
+
+        info!("Repairing party items for {} coins", price);
+        GameState::add_party_coins(-price);
+        for member in GameState::party() {
+            member.borrow_mut().actor.repair_all();
+        }
+        GameState::party_stash().borrow_mut().repair_all();
+        true
+    }
+
+    /// The reputation of the party with the specified faction.  This starts at
+    /// the value defined for the faction and is changed by scripts and triggers.
+    pub fn reputation(faction: &Faction) -> i32 {
+        match REPUTATION.with(|r| r.borrow().get(faction.id()).copied()) {
+            Some(value) => value,
+            None => Module::faction(faction.id()).map_or(0, |info| info.reputation),
+        }
+    }
+
+    /// The party's reputation with every faction it has been changed for
+    pub fn reputations() -> HashMap<String, i32> {
+        REPUTATION.with(|r| r.borrow().clone())
+    }
+
+    /// Sets the reputation of the party with the specified faction.  Any
+    /// members of that faction who are now hostile and can see the party
+    /// will enter combat.
+    pub fn set_reputation(faction: &Faction, value: i32) {
+        let max = MAX_RELATIONSHIP - MIN_RELATIONSHIP;
+        let value = value.clamp(-max, max);
+        info!("Set party reputation with '{}' to {}", faction.id(), value);
+        REPUTATION.with(|r| r.borrow_mut().insert(faction.id().to_string(), value));
+        clear_relationships();
+
+        let mgr = GameState::turn_manager();
+        let area_state = GameState::area_state();
+        mgr.borrow_mut()
+            .check_ai_activation_for_party(&mut area_state.borrow_mut());
+    }
+
+    pub fn add_reputation(faction: &Faction, amount: i32) {
+        GameState::set_reputation(faction, GameState::reputation(faction) + amount);
+    }
+
+    /// The difficulty of the current game
+    pub fn difficulty() -> Difficulty {
+        DIFFICULTY.with(|d| d.borrow().clone())
+    }
+
+    /// Sets the difficulty of the current game and recomputes the stats of
+    /// all entities.  New games are instead given their difficulty in `init`.
+    pub fn set_difficulty(difficulty: Difficulty) {
+        info!("Set difficulty to '{}'", difficulty.id);
+        DIFFICULTY.with(|d| *d.borrow_mut() = difficulty);
+
+        let mgr = GameState::turn_manager();
+        let entities: Vec<_> = mgr.borrow().entity_iter().collect();
+        for entity in entities {
+            entity.borrow_mut().actor.difficulty_changed();
+        }
+    }
+
+    /// The relationship between the two factions, including the reputation of
+    /// the party if exactly one of the factions is the party's faction
+    pub fn faction_relationship(a: &Faction, b: &Faction) -> i32 {
+        let cached = RELATIONSHIPS.with(|r| {
+            r.borrow()
+                .get(a.id())
+                .and_then(|values| values.get(b.id()))
+                .copied()
+        });
+        if let Some(value) = cached {
+            return value;
+        }
+
+        let mut value = a.base_relationship(b);
+        if a.is_friendly_faction() != b.is_friendly_faction() {
+            let other = if a.is_friendly_faction() { b } else { a };
+            value += GameState::reputation(other);
+        }
+        let value = value.clamp(MIN_RELATIONSHIP, MAX_RELATIONSHIP);
+
+        RELATIONSHIPS.with(|r| {
+            r.borrow_mut()
+                .entry(a.id().to_string())
+                .or_default()
+                .insert(b.id().to_string(), value)
+        });
+        value
+    }
+
+    pub fn is_hostile_faction(a: &Faction, b: &Faction) -> bool {
+        GameState::faction_relationship(a, b) <= HOSTILE_THRESHOLD
+    }
+
+    pub fn is_friendly_faction(a: &Faction, b: &Faction) -> bool {
+        GameState::faction_relationship(a, b) >= FRIENDLY_THRESHOLD
+    }
+
     pub fn party_formation() -> Rc<RefCell<Formation>> {
         STATE.with(|s| {
             let state = s.borrow();
@@ -1228,3 +1618,23 @@ impl GameState {
         })
     }
 }
+
+fn clear_relationships() {
+    RELATIONSHIPS.with(|r| r.borrow_mut().clear());
+}
+
+fn invalidate_stash_load() {
+    STASH_LOAD_INVALID.with(|invalid| invalid.set(true));
+}
+
+/// Creates the party stash, which invalidates the load it places on the
+/// party each time its contents change
+fn create_party_stash(items: ItemList) -> Rc<RefCell<PartyStash>> {
+    invalidate_stash_load();
+    let stash = Rc::new(RefCell::new(PartyStash::new(items)));
+    stash.borrow_mut().listeners.add(ChangeListener::new(
+        "stash_load",
+        Box::new(|_| invalidate_stash_load()),
+    ));
+    stash
+}
@@ -0,0 +1,111 @@
+//  This file is part of Sulis, a turn based RPG written in Rust.
+//  Copyright 2018 Jared Stephen
+//
+//  Sulis is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Sulis is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Sulis.  If not, see <http://www.gnu.org/licenses/>
+
+//! Reloads Lua scripts and module data while a game is in progress, so that
+//! changes can be tested without restarting.  Enabled with the `hot_reload`
+//! debug option.  Scripts are rebuilt in the script cache, while abilities,
+//! items, and conversations are rebuilt in the `Module` and then looked up
+//! again by all entities in the current `GameState`.
+
+use std::path::{Path, PathBuf};
+
+use sulis_core::resource::{take_load_errors, ResourceSet, ResourceWatcher};
+use sulis_core::util::ActiveResources;
+use sulis_module::Module;
+
+use crate::script::script_cache;
+use crate::GameState;
+
+#[derive(Debug, Default)]
+pub struct ReloadReport {
+    pub scripts: Vec<String>,
+    pub data_reloaded: bool,
+    pub errors: Vec<String>,
+}
+
+pub struct HotReloader {
+    watcher: ResourceWatcher,
+}
+
+impl HotReloader {
+    pub fn new() -> HotReloader {
+        HotReloader {
+            watcher: ResourceWatcher::new(ActiveResources::read().directories()),
+        }
+    }
+
+    /// Checks the resource directories for changes, reloading any changed
+    /// resources.  Returns `None` if nothing has changed.
+    pub fn update(&mut self) -> Option<ReloadReport> {
+        let changed = self.watcher.check();
+        if changed.is_empty() {
+            return None;
+        }
+
+        info!("Resource files changed: {:?}", changed);
+        Some(self.reload(&changed))
+    }
+
+    fn reload(&self, changed: &[PathBuf]) -> ReloadReport {
+        let mut report = ReloadReport::default();
+        let dirs = self.watcher.dirs();
+
+        if changed.iter().any(|path| has_extension(path, "lua")) {
+            report.scripts = Module::reload_scripts(dirs);
+            for (id, e) in script_cache::reload(&report.scripts) {
+                report.errors.push(format!("Script '{}': {}", id, e));
+            }
+        }
+
+        if changed
+            .iter()
+            .any(|path| has_extension(path, "yml") || has_extension(path, "json"))
+        {
+            take_load_errors();
+            let result = ResourceSet::read_yaml(dirs).and_then(Module::reload_resources);
+
+            match result {
+                Err(e) => report.errors.push(format!("Data: {}", e)),
+                Ok(()) => {
+                    GameState::reload_resources();
+                    report.data_reloaded = true;
+                }
+            }
+
+            for error in take_load_errors() {
+                report
+                    .errors
+                    .push(format!("{} '{}': {}", error.kind, error.id, error.error));
+            }
+        }
+
+        for error in report.errors.iter() {
+            warn!("Reload error: {}", error);
+        }
+
+        report
+    }
+}
+
+impl Default for HotReloader {
+    fn default() -> Self {
+        HotReloader::new()
+    }
+}
+
+fn has_extension(path: &Path, ext: &str) -> bool {
+    path.extension().is_some_and(|e| e == ext)
+}
@@ -59,7 +59,7 @@ impl Inventory {
             let variant = item.variant;
             let item_state = match Module::create_get_item(&item.id, &item.adjectives) {
                 None => invalid_data_error(&format!("No item with ID '{}'", item.id)),
-                Some(item) => Ok(ItemState::new(item, variant)),
+                Some(i) => Ok(ItemState::new(i, variant).with_durability(item.durability)),
             }?;
 
             {
@@ -102,7 +102,7 @@ impl Inventory {
             let variant = item.variant;
             let item_state = match Module::create_get_item(&item.id, &item.adjectives) {
                 None => invalid_data_error(&format!("No item with ID '{}'", item.id)),
-                Some(item) => Ok(ItemState::new(item, variant)),
+                Some(i) => Ok(ItemState::new(i, variant).with_durability(item.durability)),
             }?;
 
             self.quick.insert(quick_slot, item_state);
@@ -160,6 +160,13 @@ impl Inventory {
         self.weapon_style_internal(self.equipped(Slot::HeldMain), self.equipped(Slot::HeldOff))
     }
 
+    /// Looks up all items in this inventory again from the `Module`
+    pub fn reload_resources(&mut self) {
+        for item in self.equipped.values_mut().chain(self.quick.values_mut()) {
+            *item = item.reloaded();
+        }
+    }
+
     pub fn quick(&self, slot: QuickSlot) -> Option<&ItemState> {
         self.quick.get(&slot)
     }
@@ -178,6 +185,57 @@ impl Inventory {
         }
     }
 
+    /// The total weight of all equipped and quick items
+    pub fn weight(&self) -> i32 {
+        self.equipped
+            .values()
+            .chain(self.quick.values())
+            .map(|item_state| item_state.item.weight)
+            .sum()
+    }
+
+    /// Reduces the durability of equipped weapons, if `weapons` is true, or
+    /// all other equipped items otherwise.  Returns the names of any items
+    /// which broke as a result.
+    pub fn wear(&mut self, weapons: bool, amount: u32) -> Vec<String> {
+        let mut broken = Vec::new();
+        for item_state in self.equipped.values_mut() {
+            let is_weapon = matches!(item_state.item.kind, ItemKind::Weapon { .. });
+            if is_weapon != weapons {
+                continue;
+            }
+
+            if item_state.wear(amount) {
+                broken.push(item_state.item.name.to_string());
+            }
+        }
+        broken
+    }
+
+    /// Reduces the durability of the item in the specified slot.  Returns
+    /// true if the item broke as a result.
+    pub fn wear_slot(&mut self, slot: Slot, amount: u32) -> bool {
+        match self.equipped.get_mut(&slot) {
+            None => false,
+            Some(item_state) => item_state.wear(amount),
+        }
+    }
+
+    /// Iterates over all damaged equipped and quick items
+    pub fn damaged_iter(&self) -> impl Iterator<Item = &ItemState> {
+        self.equipped
+            .values()
+            .chain(self.quick.values())
+            .filter(|item_state| item_state.is_damaged())
+    }
+
+    /// Restores all equipped and quick items to full durability
+    pub fn repair_all(&mut self) {
+        for item_state in self.equipped.values_mut().chain(self.quick.values_mut()) {
+            item_state.repair();
+        }
+    }
+
     pub fn swap_weapon_set(&mut self) {
         let cur_main = self.equipped.remove(&Slot::HeldMain);
         let cur_off = self.equipped.remove(&Slot::HeldOff);
@@ -36,6 +36,13 @@ impl ItemList {
         self.items.clear();
     }
 
+    /// Looks up all items in this list again from the `Module`
+    pub fn reload_resources(&mut self) {
+        for (_, item) in self.items.iter_mut() {
+            *item = item.reloaded();
+        }
+    }
+
     pub fn iter(&self) -> Iter<(u32, ItemState)> {
         self.items.iter()
     }
@@ -22,7 +22,10 @@ extern crate log;
 extern crate serde_derive;
 
 mod ai;
-pub use self::ai::AI;
+pub use self::ai::{State as AIState, AI};
+
+pub mod ai_trace;
+pub use self::ai_trace::{AIEndReason, AITraceStep, AITurnTrace};
 
 pub mod ability_state;
 pub use self::ability_state::AbilityState;
@@ -38,6 +41,12 @@ pub use self::area_feedback_text::AreaFeedbackText;
 pub mod area_state;
 pub use self::area_state::AreaState;
 
+pub mod autosave;
+pub use self::autosave::AutosaveTrigger;
+
+pub mod combat_log;
+pub use self::combat_log::{CombatEvent, CombatEventKind, CombatLogEntry};
+
 mod change_listener;
 pub use self::change_listener::ChangeListener;
 pub use self::change_listener::ChangeListenerList;
@@ -50,6 +59,7 @@ pub use self::distance_finder::{
 
 mod effect;
 pub use self::effect::Effect;
+pub use self::effect::StackPolicy;
 
 mod entity_attack_handler;
 
@@ -70,6 +80,9 @@ pub use self::game_state::GameState;
 mod generated_area;
 pub use self::generated_area::{GeneratedArea, PregenOutput};
 
+pub mod hot_reload;
+pub use self::hot_reload::HotReloader;
+
 pub mod inventory;
 pub use self::inventory::Inventory;
 
@@ -80,12 +93,16 @@ mod location;
 pub use self::location::Location;
 
 mod los_calculator;
+pub use self::los_calculator::blocked_fraction;
 pub use self::los_calculator::calculate_los;
 pub use self::los_calculator::has_visibility;
 
 mod merchant_state;
 pub use self::merchant_state::MerchantState;
 
+pub mod module_validator;
+pub use self::module_validator::ValidationReport;
+
 mod path_finder;
 
 mod party_bump_handler;
@@ -106,6 +123,12 @@ pub use self::quest_state::QuestStateSet;
 mod range_indicator;
 pub use self::range_indicator::{RangeIndicator, RangeIndicatorHandler, RangeIndicatorImageSet};
 
+mod reaction_handler;
+pub use self::reaction_handler::ReactionKind;
+
+pub mod replay;
+pub use self::replay::{Replay, ReplayRecorder};
+
 pub mod save_file;
 pub use self::save_file::SaveFile;
 pub use self::save_file::SaveFileMetaData;
@@ -113,8 +136,15 @@ pub use self::save_file::SaveFileMetaData;
 mod save_state;
 pub use self::save_state::SaveState;
 
+pub mod schedule;
+pub use self::schedule::ScheduleState;
+
 pub mod script;
 pub use self::script::{Script, ScriptCallback, ScriptState};
+#[cfg(feature = "script_test")]
+pub use self::script::ScriptTestReport;
+
+mod stealth;
 
 mod transition_handler;
 
@@ -125,13 +155,14 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
 
-use sulis_module::{Actor, Module, OnTrigger};
+use sulis_module::{Actor, Difficulty, Module, OnTrigger};
 
 #[derive(Debug)]
 pub enum NextGameStep {
     Exit,
     NewCampaign {
         pc_actor: Rc<Actor>,
+        difficulty: Difficulty,
     },
     LoadCampaign {
         save_state: Box<SaveState>,
@@ -147,6 +178,7 @@ pub enum NextGameStep {
     RecreateIO,
 }
 
+#[derive(Clone)]
 pub struct UICallback {
     pub on_trigger: Vec<OnTrigger>,
     pub parent: Rc<RefCell<EntityState>>,
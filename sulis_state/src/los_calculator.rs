@@ -103,87 +103,104 @@ fn check_vis(
     end_y: i32,
     src_elev: u8,
 ) -> bool {
+    match vis_elevation(area, start_x, start_y, end_x, end_y, src_elev) {
+        None => false,
+        Some(elev) => cast_ray(start_x, start_y, end_x, end_y, &|x, y| {
+            check(area, prop_vis_grid, x, y, elev)
+        }),
+    }
+}
+
+/// Returns the elevation that a ray between the specified points must stay at or
+/// below, or `None` if the points are too far apart to see each other at all
+fn vis_elevation(
+    area: &GeneratedArea,
+    start_x: i32,
+    start_y: i32,
+    end_x: i32,
+    end_y: i32,
+    src_elev: u8,
+) -> Option<u8> {
     let dist_squared =
         (start_x - end_x) * (start_x - end_x) + (start_y - end_y) * (start_y - end_y);
 
     if dist_squared < area.area.vis_dist_up_one_squared {
-        cast_ray(
-            area,
-            prop_vis_grid,
-            start_x,
-            start_y,
-            end_x,
-            end_y,
-            src_elev + 1,
-        )
+        Some(src_elev + 1)
     } else if dist_squared < area.area.vis_dist_squared {
-        cast_ray(
-            area,
-            prop_vis_grid,
-            start_x,
-            start_y,
-            end_x,
-            end_y,
-            src_elev,
-        )
+        Some(src_elev)
     } else {
-        false
+        None
     }
 }
 
-fn cast_ray(
+/// Returns the fraction, from 0.0 to 1.0, of the `target`'s footprint which is
+/// hidden from the center of `entity`.  Walls, elevation, and props which block
+/// sight hide a point.  If `entity_grid` is specified, any creature other than
+/// the two entities standing between them also hides the point.  Points beyond
+/// vision distance are not counted.
+pub fn blocked_fraction(
     area: &GeneratedArea,
     prop_vis_grid: &[bool],
+    entity_grid: Option<&[Vec<usize>]>,
+    entity: &EntityState,
+    target: &EntityState,
+) -> f32 {
+    let start_x = entity.location.x + entity.size.width / 2;
+    let start_y = entity.location.y + entity.size.height / 2;
+    let src_elev = area.layer_set.elevation(start_x, start_y);
+
+    let ignore = [entity.index(), target.index()];
+    let is_clear = |x: i32, y: i32| match entity_grid {
+        None => true,
+        Some(grid) => grid[(x + y * area.width) as usize]
+            .iter()
+            .all(|index| ignore.contains(index)),
+    };
+
+    let mut total = 0;
+    let mut blocked = 0;
+    for p in target.location_points() {
+        let elev = match vis_elevation(area, start_x, start_y, p.x, p.y, src_elev) {
+            None => continue,
+            Some(elev) => elev,
+        };
+        total += 1;
+
+        let visible = cast_ray(start_x, start_y, p.x, p.y, &|x, y| {
+            check(area, prop_vis_grid, x, y, elev) && is_clear(x, y)
+        });
+
+        if !visible {
+            blocked += 1;
+        }
+    }
+
+    if total == 0 {
+        0.0
+    } else {
+        blocked as f32 / total as f32
+    }
+}
+
+fn cast_ray<F: Fn(i32, i32) -> bool>(
     start_x: i32,
     start_y: i32,
     end_x: i32,
     end_y: i32,
-    src_elev: u8,
+    check: &F,
 ) -> bool {
     #[allow(clippy::collapsible_else_if)] // this block is logically easier to read when not collapsed
     if (end_y - start_y).abs() < (end_x - start_x).abs() {
         if start_x > end_x {
-            cast_low(
-                area,
-                prop_vis_grid,
-                end_x,
-                end_y,
-                start_x,
-                start_y,
-                src_elev,
-            )
+            cast_low(end_x, end_y, start_x, start_y, check)
         } else {
-            cast_low(
-                area,
-                prop_vis_grid,
-                start_x,
-                start_y,
-                end_x,
-                end_y,
-                src_elev,
-            )
+            cast_low(start_x, start_y, end_x, end_y, check)
         }
     } else {
         if start_y > end_y {
-            cast_high(
-                area,
-                prop_vis_grid,
-                end_x,
-                end_y,
-                start_x,
-                start_y,
-                src_elev,
-            )
+            cast_high(end_x, end_y, start_x, start_y, check)
         } else {
-            cast_high(
-                area,
-                prop_vis_grid,
-                start_x,
-                start_y,
-                end_x,
-                end_y,
-                src_elev,
-            )
+            cast_high(start_x, start_y, end_x, end_y, check)
         }
     }
 }
@@ -196,14 +213,12 @@ fn check(area: &GeneratedArea, prop_vis_grid: &[bool], x: i32, y: i32, src_elev:
         && area.layer_set.elevation_index(index) <= src_elev
 }
 
-fn cast_high(
-    area: &GeneratedArea,
-    prop_vis_grid: &[bool],
+fn cast_high<F: Fn(i32, i32) -> bool>(
     start_x: i32,
     start_y: i32,
     end_x: i32,
     end_y: i32,
-    src_elev: u8,
+    check: &F,
 ) -> bool {
     let mut delta_x = end_x - start_x;
     let delta_y = end_y - start_y;
@@ -222,7 +237,7 @@ fn cast_high(
     for y in start_y..end_y {
         if first {
             first = false;
-        } else if !check(area, prop_vis_grid, x, y, src_elev) {
+        } else if !check(x, y) {
             return false;
         }
 
@@ -236,14 +251,12 @@ fn cast_high(
     true
 }
 
-fn cast_low(
-    area: &GeneratedArea,
-    prop_vis_grid: &[bool],
+fn cast_low<F: Fn(i32, i32) -> bool>(
     start_x: i32,
     start_y: i32,
     end_x: i32,
     end_y: i32,
-    src_elev: u8,
+    check: &F,
 ) -> bool {
     let delta_x = end_x - start_x;
     let mut delta_y = end_y - start_y;
@@ -262,7 +275,7 @@ fn cast_low(
     for x in start_x..end_x {
         if first {
             first = false;
-        } else if !check(area, prop_vis_grid, x, y, src_elev) {
+        } else if !check(x, y) {
             return false;
         }
 
@@ -26,6 +26,7 @@ pub struct MerchantState {
     pub id: String,
     pub buy_frac: f32,
     pub sell_frac: f32,
+    pub repair_frac: Option<f32>,
     pub listeners: ChangeListenerList<MerchantState>,
     items: ItemList,
 
@@ -40,12 +41,14 @@ impl MerchantState {
         for item_save in save.items {
             let item = item_save.item;
             let variant = item.variant;
+            let durability = item.durability;
             let item = match Module::create_get_item(&item.id, &item.adjectives) {
                 None => invalid_data_error(&format!("No item with ID '{}'", item.id)),
                 Some(item) => Ok(item),
             }?;
 
-            items.add_quantity(item_save.quantity, ItemState::new(item, variant));
+            let item_state = ItemState::new(item, variant).with_durability(durability);
+            items.add_quantity(item_save.quantity, item_state);
         }
 
         Ok(MerchantState {
@@ -53,6 +56,7 @@ impl MerchantState {
             loot_list_id: save.loot_list_id,
             buy_frac: save.buy_frac,
             sell_frac: save.sell_frac,
+            repair_frac: save.repair_frac,
             listeners: ChangeListenerList::default(),
             items,
             refresh_rate_millis: save.refresh_rate_millis,
@@ -65,6 +69,7 @@ impl MerchantState {
         loot_list: &Rc<LootList>,
         buy_frac: f32,
         sell_frac: f32,
+        repair_frac: Option<f32>,
         refresh_time: Time,
     ) -> MerchantState {
         let mgr = GameState::turn_manager();
@@ -73,7 +78,7 @@ impl MerchantState {
 
         let mut items = ItemList::default();
 
-        for (qty, item) in loot_list.generate() {
+        for (qty, item) in GameState::with_rand(|rand| loot_list.generate(rand)) {
             items.add_quantity(qty, item);
         }
 
@@ -82,6 +87,7 @@ impl MerchantState {
             loot_list_id: Some(loot_list.id.to_string()),
             buy_frac,
             sell_frac,
+            repair_frac,
             items,
             listeners: ChangeListenerList::default(),
             last_refresh_millis,
@@ -124,7 +130,7 @@ impl MerchantState {
         };
 
         self.items.clear();
-        for (qty, item) in loot_list.generate() {
+        for (qty, item) in GameState::with_rand(|rand| loot_list.generate(rand)) {
             self.items.add_quantity(qty, item);
         }
     }
@@ -137,6 +143,12 @@ impl MerchantState {
         ((item_state.item.value as f32) * self.sell_frac).floor() as i32
     }
 
+    /// Returns the cost to repair all of the party's damaged items, or None
+    /// if this merchant does not offer repairs
+    pub fn get_repair_price(&self) -> Option<i32> {
+        self.repair_frac.map(GameState::party_repair_price)
+    }
+
     pub fn add(&mut self, item_state: ItemState) {
         self.items.add(item_state);
 
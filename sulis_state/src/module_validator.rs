@@ -0,0 +1,458 @@
+//  This file is part of Sulis, a turn based RPG written in Rust.
+//  Copyright 2018 Jared Stephen
+//
+//  Sulis is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Sulis is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Sulis.  If not, see <http://www.gnu.org/licenses/>
+
+//! Offline checks of a loaded module.  Resources that failed to load are
+//! collected, then every cross reference between the remaining resources
+//! is resolved and every Lua script is loaded and checked for the functions
+//! that the module data expects it to define.
+
+use std::collections::{HashMap, HashSet};
+
+use sulis_core::resource::take_load_errors;
+use sulis_module::ability::AIData;
+use sulis_module::ai::{AIPlanner, FuncKind};
+use sulis_module::area::{ActorData, OnRest, ToKind};
+use sulis_module::on_trigger::{Kind, QuestStateData, ScriptData};
+use sulis_module::prop::Interactive;
+use sulis_module::{Area, Module, OnTrigger};
+
+use crate::script::script_cache;
+
+const ON_ACTIVATE: &str = "on_activate";
+const AI_ACTION: &str = "ai_action";
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ValidationIssue {
+    /// The kind of resource containing the issue, i.e. "conversation"
+    pub kind: String,
+    pub id: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ValidationReport {
+    pub dirs: Vec<String>,
+    pub errors: Vec<ValidationIssue>,
+    pub warnings: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub fn new(dirs: Vec<String>) -> ValidationReport {
+        ValidationReport {
+            dirs,
+            ..Default::default()
+        }
+    }
+
+    pub fn has_errors(&self) -> bool {
+        !self.errors.is_empty()
+    }
+
+    pub fn error(&mut self, kind: &str, id: &str, message: String) {
+        self.errors.push(ValidationIssue {
+            kind: kind.to_string(),
+            id: id.to_string(),
+            message,
+        });
+    }
+
+    pub fn warning(&mut self, kind: &str, id: &str, message: String) {
+        self.warnings.push(ValidationIssue {
+            kind: kind.to_string(),
+            id: id.to_string(),
+            message,
+        });
+    }
+
+    /// Checks the currently loaded `Module`, adding any issues found to
+    /// this report.  The module should have been loaded with
+    /// `Module::load_resources` immediately prior to calling this.
+    pub fn validate_module(&mut self) {
+        for error in take_load_errors() {
+            self.error(&error.kind, &error.id, error.error);
+        }
+
+        if !Module::is_initialized() {
+            self.error("campaign", "", "The module was not loaded".to_string());
+            return;
+        }
+
+        let mut checker = Checker {
+            report: self,
+            functions: HashMap::new(),
+        };
+        checker.check_scripts();
+        checker.check_campaign();
+        checker.check_ai_templates();
+        checker.check_abilities();
+        checker.check_items();
+        checker.check_loot_lists();
+        checker.check_factions();
+        checker.check_conversations();
+        checker.check_cutscenes();
+        checker.check_props();
+        checker.check_areas();
+
+        self.errors
+            .sort_by(|a, b| (&a.kind, &a.id).cmp(&(&b.kind, &b.id)));
+    }
+}
+
+struct Checker<'a> {
+    report: &'a mut ValidationReport,
+
+    /// The global functions defined by each script that loaded without error
+    functions: HashMap<String, HashSet<String>>,
+}
+
+impl<'a> Checker<'a> {
+    fn check_scripts(&mut self) {
+        let mut ids = Module::all_scripts();
+        ids.sort();
+
+        for id in ids {
+            match script_cache::defined_functions(&id) {
+                Err(e) => self.report.error("script", &id, format!("{}", e)),
+                Ok(funcs) => {
+                    self.functions.insert(id, funcs);
+                }
+            }
+        }
+    }
+
+    fn check_func(&mut self, kind: &str, id: &str, script: &str, func: &str) {
+        if Module::script(script).is_none() {
+            self.report
+                .error(kind, id, format!("Script '{}' does not exist", script));
+            return;
+        }
+
+        // scripts that failed to load have already been reported
+        let funcs = match self.functions.get(script) {
+            None => return,
+            Some(funcs) => funcs,
+        };
+
+        if !funcs.contains(func) {
+            self.report.error(
+                kind,
+                id,
+                format!("Function '{}' is not defined in script '{}'", func, script),
+            );
+        }
+    }
+
+    fn check_script_data(&mut self, kind: &str, id: &str, data: &ScriptData) {
+        self.check_func(kind, id, &data.id, &data.func);
+    }
+
+    fn check_on_activate(&mut self, kind: &str, id: &str, script: &str, ai: &AIData) {
+        self.check_func(kind, id, script, ON_ACTIVATE);
+        if let Some(func) = &ai.on_activate_fn {
+            self.check_func(kind, id, script, func);
+        }
+    }
+
+    fn check_campaign(&mut self) {
+        let campaign = Module::campaign();
+        let id = &campaign.id;
+
+        self.check_script_data("campaign", id, &campaign.on_party_death_script);
+        if let Some(data) = &campaign.on_tick_script {
+            self.check_script_data("campaign", id, data);
+        }
+        if let Some(data) = &campaign.on_round_elapsed_script {
+            self.check_script_data("campaign", id, data);
+        }
+
+        if Module::area(&campaign.starting_area).is_none() {
+            self.report.error(
+                "campaign",
+                id,
+                format!("Starting area '{}' does not exist", campaign.starting_area),
+            );
+        }
+
+        for location in campaign.world_map.locations.iter() {
+            if let Some(area) = &location.linked_area {
+                if Module::area(area).is_none() {
+                    self.report.error(
+                        "campaign",
+                        id,
+                        format!(
+                            "World map location '{}' links to invalid area '{}'",
+                            location.id, area
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_ai_templates(&mut self) {
+        for ai in Module::all_ai_templates() {
+            let scripted = ai.planner == AIPlanner::Script;
+            if scripted && !ai.hooks.contains_key(&FuncKind::AiAction) {
+                self.check_func("ai_template", &ai.id, &ai.script, AI_ACTION);
+            }
+
+            for func in ai.hooks.values() {
+                self.check_func("ai_template", &ai.id, &ai.script, func);
+            }
+        }
+    }
+
+    fn check_abilities(&mut self) {
+        for ability in Module::all_abilities() {
+            if let Some(active) = &ability.active {
+                self.check_on_activate("ability", &ability.id, &active.script, &active.ai);
+            }
+        }
+    }
+
+    fn check_items(&mut self) {
+        for item in Module::all_items() {
+            if let Some(usable) = &item.usable {
+                self.check_on_activate("item", &item.id, &usable.script, &usable.ai);
+            }
+        }
+    }
+
+    fn check_loot_lists(&mut self) {
+        for list in Module::all_loot_lists() {
+            for sub_list in list.sub_list_ids() {
+                if Module::loot_list(sub_list).is_none() {
+                    self.report.error(
+                        "loot_list",
+                        &list.id,
+                        format!("Sub list '{}' does not exist", sub_list),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_factions(&mut self) {
+        for faction in Module::all_factions() {
+            for other in faction.relationships.keys() {
+                if Module::faction(other).is_none() {
+                    self.missing("faction", &faction.id, "faction", other);
+                }
+            }
+        }
+    }
+
+    fn check_conversations(&mut self) {
+        for convo in Module::all_conversations() {
+            let id = &convo.id;
+            for (_, on_view) in convo.initial_nodes() {
+                self.check_triggers("conversation", id, on_view);
+            }
+
+            for node in convo.node_ids() {
+                self.check_triggers("conversation", id, convo.on_view(node));
+                for response in convo.responses(node) {
+                    self.check_triggers("conversation", id, &response.on_select);
+                    self.check_triggers("conversation", id, &response.to_view);
+                }
+            }
+        }
+    }
+
+    fn check_cutscenes(&mut self) {
+        for cutscene in Module::all_cutscenes() {
+            self.check_triggers("cutscene", &cutscene.id, &cutscene.on_end);
+        }
+    }
+
+    fn check_props(&mut self) {
+        for prop in Module::all_props() {
+            if let Interactive::Door { on_activate, .. } = &prop.interactive {
+                self.check_triggers("prop", &prop.id, on_activate);
+            }
+        }
+    }
+
+    fn check_areas(&mut self) {
+        for area in Module::all_areas() {
+            let id = &area.id;
+            for trigger in area.triggers.iter() {
+                self.check_triggers("area", id, &trigger.on_activate);
+            }
+
+            if let OnRest::FireScript { id: script, func } = &area.on_rest {
+                self.check_func("area", id, script, func);
+            }
+
+            for actor in area.actors.iter() {
+                if Module::actor(&actor.id).is_none() {
+                    self.report
+                        .error("area", id, format!("Actor '{}' does not exist", actor.id));
+                }
+                self.check_schedule(&area, actor);
+            }
+
+            for transition in area.transitions.iter() {
+                if let ToKind::Area { id: to, .. } = &transition.to {
+                    if Module::area(to).is_none() {
+                        self.report.error(
+                            "area",
+                            id,
+                            format!("Transition to invalid area '{}'", to),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_schedule(&mut self, area: &Area, actor: &ActorData) {
+        let hours_per_day = Module::rules().hours_per_day;
+        for entry in actor.schedule.iter() {
+            if let Some((from, to)) = entry.hours {
+                if from >= hours_per_day || to >= hours_per_day {
+                    self.report.error(
+                        "area",
+                        &area.id,
+                        format!("Invalid schedule hours for actor '{}'", actor.id),
+                    );
+                }
+            }
+
+            if entry.waypoints.is_empty() {
+                self.report.warning(
+                    "area",
+                    &area.id,
+                    format!("Schedule entry without waypoints for actor '{}'", actor.id),
+                );
+            }
+
+            for waypoint in entry.waypoints.iter() {
+                let p = waypoint.location;
+                if p.x < 0 || p.y < 0 || p.x >= area.width || p.y >= area.height {
+                    self.report.error(
+                        "area",
+                        &area.id,
+                        format!(
+                            "Schedule waypoint {},{} outside the area for actor '{}'",
+                            p.x, p.y, actor.id
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_triggers(&mut self, kind: &str, id: &str, triggers: &[OnTrigger]) {
+        for trigger in triggers {
+            self.check_trigger(kind, id, trigger);
+        }
+    }
+
+    fn check_trigger(&mut self, kind: &str, id: &str, trigger: &OnTrigger) {
+        use OnTrigger::*;
+        match trigger {
+            PartyMember(actor) if Module::actor(actor).is_none() => {
+                self.missing(kind, id, "actor", actor)
+            }
+            PartyItem(item) if Module::item(item).is_none() => self.missing(kind, id, "item", item),
+            PlayerAbility(ability) if Module::ability(ability).is_none() => {
+                self.missing(kind, id, "ability", ability)
+            }
+            ShowMerchant(data) if Module::loot_list(&data.loot_list).is_none() => {
+                self.missing(kind, id, "loot list", &data.loot_list)
+            }
+            ShowCutscene(cutscene) if Module::cutscene(cutscene).is_none() => {
+                self.missing(kind, id, "cutscene", cutscene)
+            }
+            StartConversation(convo) if Module::conversation(convo).is_none() => {
+                self.missing(kind, id, "conversation", convo)
+            }
+            FireScript(data) => self.check_script_data(kind, id, data),
+            ShowConfirm(data) => {
+                if let Some(data) = &data.on_accept {
+                    self.check_script_data(kind, id, data);
+                }
+            }
+            ShowMenu(data) => self.check_menu_callback(kind, id, &data.cb_kind, &data.cb_func),
+            QuestState(data) | NotQuestState(data) => self.check_quest(kind, id, data),
+            Reputation(data) | NotReputation(data) if Module::faction(&data.faction).is_none() => {
+                self.missing(kind, id, "faction", &data.faction)
+            }
+            _ => (),
+        }
+    }
+
+    fn check_menu_callback(&mut self, kind: &str, id: &str, cb_kind: &Kind, func: &str) {
+        match cb_kind {
+            Kind::Ability(ability_id) => match Module::ability(ability_id) {
+                None => self.missing(kind, id, "ability", ability_id),
+                Some(ability) => match &ability.active {
+                    None => self.report.error(
+                        kind,
+                        id,
+                        format!("Ability '{}' has no script", ability_id),
+                    ),
+                    Some(active) => self.check_func(kind, id, &active.script, func),
+                },
+            },
+            Kind::Item(item_id) => match Module::item(item_id) {
+                None => self.missing(kind, id, "item", item_id),
+                Some(item) => match &item.usable {
+                    None => {
+                        self.report
+                            .error(kind, id, format!("Item '{}' has no script", item_id))
+                    }
+                    Some(usable) => self.check_func(kind, id, &usable.script, func),
+                },
+            },
+            Kind::Script(script) => self.check_func(kind, id, script, func),
+            // entity callbacks are resolved against the AI script of whichever
+            // entity is the parent at runtime
+            Kind::Entity => (),
+        }
+    }
+
+    fn check_quest(&mut self, kind: &str, id: &str, data: &QuestStateData) {
+        let quest = match Module::quest(&data.quest) {
+            None => {
+                self.missing(kind, id, "quest", &data.quest);
+                return;
+            }
+            Some(quest) => quest,
+        };
+
+        if let Some(entry) = &data.entry {
+            if !quest.entries.contains_key(entry) {
+                self.report.error(
+                    kind,
+                    id,
+                    format!("Quest '{}' has no entry '{}'", data.quest, entry),
+                );
+            }
+        }
+    }
+
+    fn missing(&mut self, kind: &str, id: &str, ref_kind: &str, ref_id: &str) {
+        self.report.error(
+            kind,
+            id,
+            format!("Referenced {} '{}' does not exist", ref_kind, ref_id),
+        );
+    }
+}
@@ -34,6 +34,9 @@ pub struct PStats {
     #[serde(default)]
     inventory_locked: bool,
 
+    #[serde(default)]
+    reactions_used: u32,
+
     #[serde(skip)] // will be computed on load anyway
     threatened_by: Vec<usize>,
 
@@ -63,12 +66,13 @@ impl PStats {
             xp: actor.xp,
             has_level_up: false,
             inventory_locked: false,
+            reactions_used: 0,
             threatened_by: Vec::new(),
             threatening: Vec::new(),
             current_group_uses_per_encounter: HashMap::new(),
             current_group_uses_per_day: HashMap::new(),
             current_class_stats: HashMap::new(),
-            faction: actor.faction(),
+            faction: actor.faction().clone(),
             disabled: false,
             base_class: Some(actor.base_class()),
         }
@@ -153,6 +157,20 @@ impl PStats {
         !self.threatened_by.is_empty()
     }
 
+    /// The indices of all entities currently threatening the parent
+    pub fn threatened_by(&self) -> &[usize] {
+        &self.threatened_by
+    }
+
+    /// The number of reactions the parent may still take this round
+    pub fn reactions_remaining(&self, stats: &StatList) -> u32 {
+        (stats.reactions.max(0) as u32).saturating_sub(self.reactions_used)
+    }
+
+    pub fn use_reaction(&mut self) {
+        self.reactions_used += 1;
+    }
+
     pub fn add_threatening(&mut self, index: usize) {
         if !self.threatening.contains(&index) {
             self.threatening.push(index);
@@ -344,6 +362,7 @@ impl PStats {
         }
 
         self.ap = ap;
+        self.reactions_used = 0;
     }
 
     pub fn end_turn(&mut self) {
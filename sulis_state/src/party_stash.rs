@@ -44,6 +44,48 @@ impl PartyStash {
         &self.items
     }
 
+    /// The total weight of all items in the stash, which is carried by the
+    /// party members
+    pub fn weight(&self) -> i32 {
+        self.items
+            .iter()
+            .map(|(qty, item_state)| *qty as i32 * item_state.item.weight)
+            .sum()
+    }
+
+    /// Restores all damaged items in the stash to full durability
+    pub(crate) fn repair_all(&mut self) {
+        let mut repaired = Vec::new();
+        let mut index = 0;
+        while index < self.items.len() {
+            let damaged = match self.items.get(index) {
+                None => false,
+                Some((_, item_state)) => item_state.is_damaged(),
+            };
+
+            if damaged {
+                repaired.extend(self.items.remove_all_at(index));
+            } else {
+                index += 1;
+            }
+        }
+
+        if repaired.is_empty() {
+            return;
+        }
+
+        for (qty, mut item_state) in repaired {
+            item_state.repair();
+            self.items.add_quantity(qty, item_state);
+        }
+        self.listeners.notify(self);
+    }
+
+    pub(crate) fn reload_resources(&mut self) {
+        self.items.reload_resources();
+        self.listeners.notify(self);
+    }
+
     pub fn add_item(&mut self, quantity: u32, item_state: ItemState) -> Option<usize> {
         if quantity == 0 {
             return None;
@@ -123,6 +123,7 @@ pub fn move_towards_point(
     entities_to_ignore: &[usize],
     dest: Destination,
     cb: Option<Box<dyn ScriptCallback>>,
+    check_ap: bool,
 ) -> Option<Anim> {
     let path = match find_path(
         finder,
@@ -130,7 +131,7 @@ pub fn move_towards_point(
         &entity.borrow(),
         entities_to_ignore,
         dest,
-        true,
+        check_ap,
     ) {
         None => return None,
         Some(path) => path,
@@ -74,6 +74,7 @@ impl PropState {
             let quantity = item_save.quantity;
             let item = &item_save.item;
             let variant = item.variant;
+            let durability = item.durability;
             let item = match Module::create_get_item(&item.id, &item.adjectives) {
                 None => {
                     warn!(
@@ -84,7 +85,8 @@ impl PropState {
                 }
                 Some(item) => item,
             };
-            items.add_quantity(quantity, ItemState::new(item, variant));
+            let item_state = ItemState::new(item, variant).with_durability(durability);
+            items.add_quantity(quantity, item_state);
         }
 
         let mut anim_state = AnimationState::default();
@@ -170,6 +172,7 @@ impl PropState {
                 for item_save_state in items {
                     let item = &item_save_state.item;
                     let variant = item.variant;
+                    let durability = item.durability;
                     let item = match Module::create_get_item(&item.id, &item.adjectives) {
                         None => invalid_data_error(&format!(
                             "No item with ID '{}'",
@@ -178,7 +181,8 @@ impl PropState {
                         Some(item) => Ok(item),
                     }?;
 
-                    item_list.add_quantity(item_save_state.quantity, ItemState::new(item, variant));
+                    let item_state = ItemState::new(item, variant).with_durability(durability);
+                    item_list.add_quantity(item_save_state.quantity, item_state);
                 }
 
                 let loot = match loot_to_generate {
@@ -304,7 +308,7 @@ impl PropState {
                 };
 
                 info!("Generating loot for prop from '{}'", loot.id);
-                let generated_items = loot.generate();
+                let generated_items = GameState::with_rand(|rand| loot.generate(rand));
                 for (qty, item) in generated_items {
                     items.add_quantity(qty, item);
                 }
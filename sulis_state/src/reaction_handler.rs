@@ -0,0 +1,174 @@
+//  This file is part of Sulis, a turn based RPG written in Rust.
+//  Copyright 2019 Jared Stephen
+//
+//  Sulis is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Sulis is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Sulis.  If not, see <http://www.gnu.org/licenses/>
+
+//! Reactions are actions, such as attacks of opportunity, that an entity takes
+//! outside of its own turn against an enemy it threatens.  Moving out of a
+//! threatened square, activating an ability from one of the groups listed in
+//! the `reactions` rules, or a script calling `provoke_reactions` will provoke a
+//! reaction from each threatening entity with a reaction remaining this round.
+//!
+//! Reactions are queued and resolved on the next update, so that they are never
+//! fired while the turn manager or the provoking script is in use.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::area_feedback_text::ColorKind;
+use crate::script::{ScriptCallback, ScriptEntitySet};
+use crate::{AreaFeedbackText, EntityState, GameState, TurnManager};
+use sulis_module::{Ability, Module};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReactionKind {
+    Movement,
+    Ability,
+    Script,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Reaction {
+    reactor: usize,
+    provoker: usize,
+    kind: ReactionKind,
+}
+
+/// Queues a reaction against `provoker` from each of the `reactors`
+pub(crate) fn provoke(
+    mgr: &mut TurnManager,
+    provoker: usize,
+    reactors: &[usize],
+    kind: ReactionKind,
+) {
+    if !Module::rules().reactions.enabled || !mgr.is_combat_active() {
+        return;
+    }
+
+    for reactor in reactors.iter().filter(|index| **index != provoker) {
+        mgr.queue_reaction(Reaction {
+            reactor: *reactor,
+            provoker,
+            kind,
+        });
+    }
+}
+
+/// Provokes reactions from any entities that threatened `mover`, which
+/// were the `threatened_by` list prior to the move, but no longer do.
+pub(crate) fn provoke_on_move(mgr: &mut TurnManager, mover: &EntityState, threatened_by: &[usize]) {
+    if !Module::rules().reactions.provoked_by_movement {
+        return;
+    }
+
+    let left: Vec<usize> = threatened_by
+        .iter()
+        .filter(|index| !mover.actor.p_stats().is_threatened_by(**index))
+        .copied()
+        .collect();
+
+    provoke(mgr, mover.index(), &left, ReactionKind::Movement);
+}
+
+/// Provokes reactions from all entities threatening `entity` if the
+/// ability is in one of the groups that provokes reactions.
+pub(crate) fn provoke_on_ability(entity: &Rc<RefCell<EntityState>>, ability: &Ability) {
+    let group = match &ability.active {
+        None => return,
+        Some(active) => active.group.name(),
+    };
+
+    if !Module::rules()
+        .reactions
+        .provoked_by_ability_groups
+        .contains(&group)
+    {
+        return;
+    }
+
+    let entity = entity.borrow();
+    let mgr = GameState::turn_manager();
+    provoke(
+        &mut mgr.borrow_mut(),
+        entity.index(),
+        &entity.actor.threatened_by(),
+        ReactionKind::Ability,
+    );
+}
+
+pub(crate) fn fire_reactions(reactions: Vec<Reaction>) {
+    for reaction in reactions {
+        fire(reaction);
+    }
+}
+
+fn fire(reaction: Reaction) {
+    let mgr = GameState::turn_manager();
+    let (reactor, provoker) = {
+        let mgr = mgr.borrow();
+        match (
+            mgr.entity_checked(reaction.reactor),
+            mgr.entity_checked(reaction.provoker),
+        ) {
+            (Some(reactor), Some(provoker)) => (reactor, provoker),
+            _ => return,
+        }
+    };
+
+    if !can_react(&reactor.borrow(), &provoker.borrow()) {
+        return;
+    }
+
+    if !reactor.borrow_mut().actor.use_reaction() {
+        return;
+    }
+
+    info!(
+        "'{}' reacts to '{}' ({:?})",
+        reactor.borrow().actor.actor.name,
+        provoker.borrow().actor.actor.name,
+        reaction.kind
+    );
+
+    let area_state = GameState::area_state();
+    let mut text = AreaFeedbackText::with_target(&reactor.borrow(), &area_state.borrow());
+    text.add_entry("Reaction".to_string(), ColorKind::Info);
+    area_state.borrow_mut().add_feedback_text(text);
+
+    let targets = ScriptEntitySet::from_pair(&reactor, &provoker);
+    let cbs = reactor.borrow().callbacks(&mgr.borrow());
+    cbs.iter().for_each(|cb| cb.on_reaction(&targets));
+
+    // the provoker may have been killed by an on_reaction callback
+    if !provoker.borrow().actor.is_dead() {
+        EntityState::attack(&reactor, &provoker, None, false);
+    }
+}
+
+fn can_react(reactor: &EntityState, provoker: &EntityState) -> bool {
+    if reactor.actor.is_dead() || reactor.actor.is_disabled() || provoker.actor.is_dead() {
+        return false;
+    }
+
+    if reactor.location.area_id != provoker.location.area_id {
+        return false;
+    }
+
+    let stats = &reactor.actor.stats;
+    if !stats.attack_is_melee() || stats.attack_disabled {
+        return false;
+    }
+
+    reactor.is_hostile(provoker)
+}
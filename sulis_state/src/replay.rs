@@ -0,0 +1,324 @@
+//  This file is part of Sulis, a turn based RPG written in Rust.
+//  Copyright 2018 Jared Stephen
+//
+//  Sulis is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Sulis is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Sulis.  If not, see <http://www.gnu.org/licenses/>
+
+//! Records every input of a game session, along with the state and random
+//! number generator seeds at the start of the session, so that the session
+//! can be reproduced exactly from a bug report.
+//!
+//! A replay file consists of lines of JSON.  The first line is the
+//! `ReplayHeader`, and each following line is a `ReplayFrame`, holding the
+//! inputs handled before a single frame update and the elapsed time passed
+//! to that update.  Frames are flushed as they are written, so a replay is
+//! usable even if the game crashes while recording.
+
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::prelude::*;
+
+use sulis_core::config;
+use sulis_core::io::InputAction;
+use sulis_core::serde_json;
+use sulis_core::util::{self, invalid_data_error, ActiveResources};
+use sulis_module::OnTrigger;
+
+use crate::save_file::SAVE_SCHEMA_VERSION;
+use crate::script::entity_with_id;
+use crate::{GameState, SaveState};
+
+/// The state of the game and resources at the start of a recorded session
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReplayHeader {
+    /// The save format version of `state`.  Replays are not upgraded, so
+    /// only replays recorded with the current version may be played.
+    pub schema_version: u32,
+
+    /// The campaign and mods that were loaded when recording
+    pub resources: ActiveResources,
+
+    /// The seed used for the `sulis_core::util::gen_rand` stream.  The
+    /// campaign random stream is saved as part of `state`.
+    pub util_seed: u64,
+
+    pub state: SaveState,
+
+    /// UI callbacks, such as the conversation started when beginning a new
+    /// campaign, which had not yet been shown when recording began
+    #[serde(default)]
+    pub ui_callbacks: Vec<PendingUICallback>,
+}
+
+/// A `UICallback` with its entities referenced by unique ID
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PendingUICallback {
+    pub on_trigger: Vec<OnTrigger>,
+    pub parent: String,
+    pub target: String,
+}
+
+/// The inputs handled before a single update of the UI and game state
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ReplayFrame {
+    pub millis: u32,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<InputAction>,
+}
+
+fn get_replay_dir() -> PathBuf {
+    let mut path = config::USER_DIR.clone();
+    path.push("replay");
+    path
+}
+
+fn to_json_line<T: serde::Serialize>(value: &T) -> Result<String, Error> {
+    serde_json::to_string(value).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+/// Writes the inputs of the current game session to a replay file
+pub struct ReplayRecorder {
+    path: PathBuf,
+    out: BufWriter<File>,
+    inputs: Vec<InputAction>,
+    frames: u32,
+}
+
+impl ReplayRecorder {
+    /// Begins recording from the current game state to a new file in the
+    /// replay directory of the user folder.  This reseeds the
+    /// `sulis_core::util::gen_rand` stream, and should be called once the
+    /// game state and UI for the session have been created.
+    pub fn start(resources: ActiveResources) -> Result<ReplayRecorder, Error> {
+        let dir = get_replay_dir();
+        if !dir.is_dir() {
+            fs::create_dir_all(&dir)?;
+        }
+
+        let mut path = dir;
+        path.push(format!(
+            "replay_{}.jsonl",
+            Utc::now().format("%Y%m%d-%H%M%S%.3f")
+        ));
+
+        ReplayRecorder::start_at(path, resources)
+    }
+
+    /// Begins recording from the current game state to the file at the
+    /// specified `path`, as with `start`
+    pub fn start_at(path: PathBuf, resources: ActiveResources) -> Result<ReplayRecorder, Error> {
+        let util_seed = util::seed_rand(None);
+        let state = SaveState::create();
+        let ui_callbacks = GameState::pending_ui_callbacks()
+            .into_iter()
+            .map(|cb| PendingUICallback {
+                on_trigger: cb.on_trigger,
+                parent: cb.parent.borrow().unique_id().to_string(),
+                target: cb.target.borrow().unique_id().to_string(),
+            })
+            .collect();
+        info!(
+            "Recording replay to {:?}, campaign seed {}, util seed {}",
+            path,
+            GameState::rand_state().seed(),
+            util_seed
+        );
+
+        let header = ReplayHeader {
+            schema_version: SAVE_SCHEMA_VERSION,
+            resources,
+            util_seed,
+            state,
+            ui_callbacks,
+        };
+
+        let mut out = BufWriter::new(File::create(&path)?);
+        writeln!(out, "{}", to_json_line(&header)?)?;
+        out.flush()?;
+
+        Ok(ReplayRecorder {
+            path,
+            out,
+            inputs: Vec::new(),
+            frames: 0,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Records an input, to be written with the next frame
+    pub fn record_input(&mut self, action: InputAction) {
+        self.inputs.push(action);
+    }
+
+    /// Writes a frame with the specified elapsed time and all inputs recorded
+    /// since the previous frame
+    pub fn record_frame(&mut self, millis: u32) -> Result<(), Error> {
+        let frame = ReplayFrame {
+            millis,
+            inputs: std::mem::take(&mut self.inputs),
+        };
+
+        writeln!(self.out, "{}", to_json_line(&frame)?)?;
+        self.out.flush()?;
+        self.frames += 1;
+        Ok(())
+    }
+}
+
+impl Drop for ReplayRecorder {
+    fn drop(&mut self) {
+        info!("Recorded {} frames to replay {:?}", self.frames, self.path);
+    }
+}
+
+/// A recorded session, read from a replay file
+pub struct Replay {
+    header: Option<ReplayHeader>,
+    resources: ActiveResources,
+    util_seed: Option<u64>,
+    frames: VecDeque<ReplayFrame>,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> Result<Replay, Error> {
+        let file = BufReader::new(File::open(path)?);
+        let mut lines = file.lines();
+
+        let header: ReplayHeader = match lines.next() {
+            None => return invalid_data_error("Replay file is empty"),
+            Some(line) => parse_line(&line?, 1)?,
+        };
+
+        if header.schema_version != SAVE_SCHEMA_VERSION {
+            return invalid_data_error(&format!(
+                "Replay was recorded with save schema version {}, but only version {} is supported",
+                header.schema_version, SAVE_SCHEMA_VERSION
+            ));
+        }
+
+        let mut frames = VecDeque::new();
+        for (index, line) in lines.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match parse_line(&line, index + 2) {
+                Ok(frame) => frames.push_back(frame),
+                Err(e) => {
+                    // the last line may be incomplete if the game crashed while writing
+                    warn!("{}", e);
+                    warn!("Ignoring the remainder of the replay");
+                    break;
+                }
+            }
+        }
+
+        info!("Read replay {:?} with {} frames", path, frames.len());
+        Ok(Replay {
+            resources: header.resources.clone(),
+            util_seed: Some(header.util_seed),
+            header: Some(header),
+            frames,
+        })
+    }
+
+    /// Parses the `--replay <file>` command line argument, if present.
+    /// Returns `Ok(None)` if the argument was not specified.
+    pub fn from_args() -> Result<Option<Replay>, Error> {
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            if arg != "--replay" {
+                continue;
+            }
+
+            return match args.next() {
+                None => Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "--replay requires a replay file argument",
+                )),
+                Some(file) => Replay::load(Path::new(&file)).map(Some),
+            };
+        }
+
+        Ok(None)
+    }
+
+    /// The campaign and mods that must be loaded before calling `start`
+    pub fn resources(&self) -> &ActiveResources {
+        &self.resources
+    }
+
+    /// Loads the game state recorded at the start of the session, including
+    /// the campaign random stream.
+    pub fn start(&mut self) -> Result<(), Error> {
+        let header = match self.header.take() {
+            None => return invalid_data_error("Replay has already been started"),
+            Some(header) => header,
+        };
+
+        let rand = header.state.rand.clone();
+        GameState::load(header.state)?;
+
+        // the saved stream is used even if the config specifies a reseed on load
+        if let Some(rand) = rand {
+            info!("Starting replay with campaign seed {}", rand.seed());
+            GameState::set_rand_state(rand);
+        }
+
+        for cb in header.ui_callbacks {
+            match (entity_with_id(cb.parent), entity_with_id(cb.target)) {
+                (Some(parent), Some(target)) => {
+                    GameState::add_ui_callback(cb.on_trigger, &parent, &target)
+                }
+                _ => warn!("Unable to find the entities for a recorded UI callback"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the next recorded frame, or `None` if the replay is complete.
+    /// The `sulis_core::util::gen_rand` stream is reseeded when the first
+    /// frame is returned, as the recording was started after the UI for the
+    /// session was created.
+    pub fn next_frame(&mut self) -> Option<ReplayFrame> {
+        if let Some(seed) = self.util_seed.take() {
+            util::seed_rand(Some(seed));
+        }
+        self.frames.pop_front()
+    }
+
+    /// Whether all recorded frames have been returned
+    pub fn is_complete(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+fn parse_line<T: serde::de::DeserializeOwned>(line: &str, line_num: usize) -> Result<T, Error> {
+    serde_json::from_str(line).map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("Invalid replay data on line {}: {}", line_num, e),
+        )
+    })
+}
@@ -14,6 +14,9 @@
 //  You should have received a copy of the GNU General Public License
 //  along with Sulis.  If not, see <http://www.gnu.org/licenses/>
 
+mod migrations;
+pub use self::migrations::SAVE_SCHEMA_VERSION;
+
 use std::fs::{self, File};
 use std::io::{Error, Read};
 use std::path::{Path, PathBuf};
@@ -22,11 +25,13 @@ use std::time;
 use chrono::prelude::*;
 
 use crate::{GameState, SaveState};
-use sulis_core::resource::{read_single_resource_path, write_json_to_file};
+use sulis_core::resource::write_json_to_file;
 use sulis_core::util::invalid_data_error;
 use sulis_core::{config, serde_json, util};
 use sulis_module::Module;
 
+const AUTOSAVE_PREFIX: &str = "autosave_";
+
 #[derive(Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct SaveFile {
@@ -35,8 +40,8 @@ pub struct SaveFile {
 }
 
 impl SaveFile {
-    fn from_json(data: &str) -> Result<Self, Error> {
-        let resource: Result<SaveFile, serde_json::Error> = serde_json::from_str(data);
+    fn from_json(data: serde_json::Value) -> Result<Self, Error> {
+        let resource: Result<SaveFile, serde_json::Error> = serde_json::from_value(data);
 
         match resource {
             Ok(resource) => Ok(resource),
@@ -48,8 +53,18 @@ impl SaveFile {
 #[derive(Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct SaveFileMetaData {
+    /// The version of the save format.  Older saves are upgraded to the
+    /// current `SAVE_SCHEMA_VERSION` when read.
+    #[serde(default)]
+    pub schema_version: u32,
+
     pub player_name: String,
 
+    /// Whether this save was written automatically into one of the
+    /// rotating autosave slots
+    #[serde(default)]
+    pub autosave: bool,
+
     pub level: Option<u32>,
     pub class: Option<String>,
 
@@ -61,6 +76,18 @@ pub struct SaveFileMetaData {
 
     #[serde(skip)]
     pub error: Option<String>,
+
+    #[serde(skip)]
+    incompatible: bool,
+}
+
+impl SaveFileMetaData {
+    /// Whether this save could not be read because its format version could
+    /// not be upgraded to the current version, rather than because the file
+    /// is corrupt.
+    pub fn is_incompatible(&self) -> bool {
+        self.incompatible
+    }
 }
 
 fn get_save_dir() -> PathBuf {
@@ -77,18 +104,89 @@ pub fn delete_save(save_file: &SaveFileMetaData) -> Result<(), Error> {
 
 pub fn load_state(save_file: &SaveFileMetaData) -> Result<SaveState, Error> {
     let path = save_file.path.as_path();
-    let save_file: SaveFile = read_single_resource_path(path)?;
+    let save_file = read_save_file(path)?;
 
     Ok(save_file.state)
 }
 
 pub fn create_save() -> Result<(), Error> {
-    let start_time = time::Instant::now();
-    info!("Start save");
-
     let utc = Utc::now();
     let filename = format!("save_{}.json", utc.format("%Y%m%d-%H%M%S%.3f"));
 
+    write_save(filename, utc, false)
+}
+
+/// Writes an autosave into the oldest of the specified number of rotating
+/// slots, and removes any autosaves in slots beyond that number.
+pub fn create_autosave(slots: u32) -> Result<(), Error> {
+    prune_autosaves(slots)?;
+    if slots == 0 {
+        return Ok(());
+    }
+
+    let dir = get_save_dir();
+    let mut slot = 0;
+    let mut oldest = None;
+    for index in 0..slots {
+        let mut path = dir.clone();
+        path.push(autosave_filename(index));
+
+        let modified = match fs::metadata(&path).and_then(|meta| meta.modified()) {
+            Err(_) => {
+                // use an empty slot if there is one
+                slot = index;
+                break;
+            }
+            Ok(modified) => modified,
+        };
+
+        let older = match oldest {
+            None => true,
+            Some(oldest) => modified < oldest,
+        };
+        if older {
+            oldest = Some(modified);
+            slot = index;
+        }
+    }
+
+    write_save(autosave_filename(slot), Utc::now(), true)
+}
+
+fn autosave_filename(slot: u32) -> String {
+    format!("{}{}.json", AUTOSAVE_PREFIX, slot)
+}
+
+fn autosave_slot(path: &Path) -> Option<u32> {
+    let stem = path.file_stem()?.to_str()?;
+    let slot = stem.strip_prefix(AUTOSAVE_PREFIX)?;
+    slot.parse().ok()
+}
+
+fn prune_autosaves(slots: u32) -> Result<(), Error> {
+    let dir = get_save_dir();
+    if !dir.is_dir() {
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        match autosave_slot(&path) {
+            Some(slot) if slot >= slots => {
+                info!("Removing old autosave {}", path.to_string_lossy());
+                fs::remove_file(path)?;
+            }
+            _ => (),
+        }
+    }
+
+    Ok(())
+}
+
+fn write_save(filename: String, utc: DateTime<Utc>, autosave: bool) -> Result<(), Error> {
+    let start_time = time::Instant::now();
+    info!("Start save to {}", filename);
+
     let mut path = get_save_dir();
     if !path.is_dir() {
         trace!("Save dir '{:?}' not found, attempting to create it.", path);
@@ -97,7 +195,7 @@ pub fn create_save() -> Result<(), Error> {
 
     path.push(filename);
 
-    let meta = create_meta_data(utc.format("%c").to_string());
+    let meta = create_meta_data(utc.format("%c").to_string(), autosave);
 
     info!(
         "  Filename and meta data creation complete in {} secs",
@@ -123,20 +221,23 @@ pub fn create_save() -> Result<(), Error> {
     result
 }
 
-fn create_meta_data(datetime: String) -> SaveFileMetaData {
+fn create_meta_data(datetime: String, autosave: bool) -> SaveFileMetaData {
     let cur_area = GameState::area_state();
     let cur_area = cur_area.borrow();
     let player = GameState::player();
     let player = player.borrow();
 
     SaveFileMetaData {
+        schema_version: SAVE_SCHEMA_VERSION,
         player_name: player.actor.actor.name.to_string(),
+        autosave,
         level: Some(player.actor.actor.total_level),
         class: Some(player.actor.actor.base_class().name.to_string()),
         datetime,
         current_area_name: cur_area.area.area.name.to_string(),
         path: Default::default(),
         error: None,
+        incompatible: false,
     }
 }
 
@@ -177,16 +278,62 @@ pub fn has_available_save_files() -> bool {
     false
 }
 
-fn read_save_file(path: &Path) -> Result<SaveFile, Error> {
+fn read_save_json(path: &Path) -> Result<serde_json::Value, Error> {
     let mut file = File::open(path)?;
 
     let mut file_data = String::new();
     file.read_to_string(&mut file_data)?;
 
-    SaveFile::from_json(&file_data)
+    match serde_json::from_str(&file_data) {
+        Ok(value) => Ok(value),
+        Err(error) => invalid_data_error(&format!("{}", error)),
+    }
+}
+
+fn read_save_file(path: &Path) -> Result<SaveFile, Error> {
+    read_upgraded_save_file(path).map_err(|failure| failure.error)
+}
+
+struct ReadFailure {
+    error: Error,
+
+    /// Whether the save could not be upgraded to the current version
+    incompatible: bool,
 }
 
-fn create_error_meta(path: PathBuf, error: Error) -> SaveFileMetaData {
+fn read_upgraded_save_file(path: &Path) -> Result<SaveFile, ReadFailure> {
+    let corrupt = |error| ReadFailure {
+        error,
+        incompatible: false,
+    };
+
+    let mut save = read_save_json(path).map_err(corrupt)?;
+    if let Err(error) = migrations::migrate(&mut save) {
+        return Err(ReadFailure {
+            error,
+            incompatible: true,
+        });
+    }
+    SaveFile::from_json(save).map_err(corrupt)
+}
+
+/// Reads only the meta data of a save, as it was written, without upgrading
+/// the remainder of the file
+fn read_original_meta(path: &Path) -> Option<SaveFileMetaData> {
+    let mut save = read_save_json(path).ok()?;
+    let meta = save.get_mut("meta")?.take();
+    serde_json::from_value(meta).ok()
+}
+
+fn create_error_meta(path: PathBuf, failure: ReadFailure) -> SaveFileMetaData {
+    let error = failure.error;
+    if let Some(mut meta) = read_original_meta(&path) {
+        meta.path = path;
+        meta.error = Some(error.to_string());
+        meta.incompatible = failure.incompatible;
+        return meta;
+    }
+
     let time = match fs::metadata(&path) {
         Err(e) => {
             warn!("Unable to get metadata for invalid save file at {:?}", path);
@@ -209,13 +356,34 @@ fn create_error_meta(path: PathBuf, error: Error) -> SaveFileMetaData {
     let datetime = time.format("%c").to_string();
 
     SaveFileMetaData {
+        schema_version: SAVE_SCHEMA_VERSION,
         player_name: "Unknown Player".to_string(),
+        autosave: autosave_slot(&path).is_some(),
         level: None,
         class: None,
         datetime,
         current_area_name: "Unknown Area".to_string(),
         path,
         error: Some(error.to_string()),
+        // the version is unknown, so report the file as corrupt rather than incompatible
+        incompatible: false,
+    }
+}
+
+/// Reads the meta data of the specified save, which describes why the save
+/// can't be loaded if it is not valid
+fn read_meta(path: PathBuf) -> SaveFileMetaData {
+    match read_upgraded_save_file(&path) {
+        Ok(save_file) => {
+            let mut meta = save_file.meta;
+            meta.path = path;
+            meta
+        }
+        Err(failure) => {
+            warn!("Unable to read save file: {}", path.to_string_lossy());
+            warn!("{}", failure.error);
+            create_error_meta(path, failure)
+        }
     }
 }
 
@@ -249,22 +417,7 @@ pub fn get_available_save_files() -> Result<Vec<SaveFileMetaData>, Error> {
             continue;
         }
 
-        let path_buf = path.to_path_buf();
-
-        let save_file: SaveFile = match read_save_file(&path_buf) {
-            Ok(save_file) => save_file,
-            Err(e) => {
-                warn!("Unable to read save file: {}", path_buf.to_string_lossy());
-                warn!("{}", e);
-                results.push(create_error_meta(path_buf, e));
-                continue;
-            }
-        };
-
-        let mut meta = save_file.meta;
-        meta.path = path_buf;
-
-        results.push(meta);
+        results.push(read_meta(path.to_path_buf()));
     }
 
     results.sort_by(|f1, f2| {
@@ -288,3 +441,59 @@ fn time_modified(data: &SaveFileMetaData) -> time::SystemTime {
         Err(_) => time::UNIX_EPOCH,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V0_META: &str = r#""meta": {
+        "player_name": "Tester",
+        "level": 1,
+        "class": "Fighter",
+        "datetime": "Thu Jan  1 00:00:00 2026",
+        "current_area_name": "Farmhouse"
+    }"#;
+
+    fn read_test_save(name: &str, contents: &str) -> SaveFileMetaData {
+        let mut path = std::env::temp_dir();
+        path.push(format!(
+            "sulis_save_file_{}_{}.json",
+            name,
+            std::process::id()
+        ));
+        fs::write(&path, contents).unwrap();
+
+        let meta = read_meta(path.clone());
+        fs::remove_file(&path).unwrap();
+        meta
+    }
+
+    #[test]
+    fn corrupt_v0_save_is_not_incompatible() {
+        let contents = format!(r#"{{ {}, "state": {{ "unknown_field": 1 }} }}"#, V0_META);
+        let meta = read_test_save("corrupt_v0", &contents);
+
+        assert!(meta.error.is_some());
+        assert_eq!(meta.schema_version, 0);
+        assert_eq!(meta.player_name, "Tester");
+        assert!(!meta.is_incompatible());
+    }
+
+    #[test]
+    fn newer_save_is_incompatible() {
+        let contents = format!(
+            r#"{{ "meta": {{
+                "schema_version": {},
+                "player_name": "Tester",
+                "datetime": "Thu Jan  1 00:00:00 2026",
+                "current_area_name": "Farmhouse"
+            }}, "state": {{}} }}"#,
+            SAVE_SCHEMA_VERSION + 1
+        );
+        let meta = read_test_save("newer", &contents);
+
+        assert!(meta.error.is_some());
+        assert_eq!(meta.schema_version, SAVE_SCHEMA_VERSION + 1);
+        assert!(meta.is_incompatible());
+    }
+}
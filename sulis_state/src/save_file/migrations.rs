@@ -0,0 +1,113 @@
+//  This file is part of Sulis, a turn based RPG written in Rust.
+//  Copyright 2018 Jared Stephen
+//
+//  Sulis is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Sulis is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Sulis.  If not, see <http://www.gnu.org/licenses/>
+
+//! Upgrades the JSON of save files written by older versions to the current
+//! save format.  When a change to any of the save structs would prevent an
+//! older save from deserializing, increment `SAVE_SCHEMA_VERSION` and append
+//! a migration from the previous version to `MIGRATIONS`.
+
+use std::io::Error;
+
+use sulis_core::serde_json::{Map, Value};
+use sulis_core::util::invalid_data_error;
+
+/// The version of the save format written by this build.  Saves without a
+/// version are from before versioning was introduced, and are version 0.
+pub const SAVE_SCHEMA_VERSION: u32 = 1;
+
+type Migration = fn(&mut Value) -> Result<(), Error>;
+
+/// The migration at each index upgrades a save from that version to the next
+const MIGRATIONS: [Migration; SAVE_SCHEMA_VERSION as usize] = [remove_on_swap_weapons];
+
+/// Returns the schema version recorded in the meta data of the specified save
+pub fn schema_version(save: &Value) -> Result<u32, Error> {
+    match save.pointer("/meta/schema_version") {
+        None => Ok(0),
+        Some(Value::Number(num)) => match num.as_u64() {
+            Some(version) if version <= u32::MAX as u64 => Ok(version as u32),
+            _ => invalid_data_error(&format!("Invalid save schema version {}", num)),
+        },
+        Some(value) => invalid_data_error(&format!("Invalid save schema version {}", value)),
+    }
+}
+
+/// Applies all migrations needed to bring the specified save up to the
+/// current `SAVE_SCHEMA_VERSION`.
+pub fn migrate(save: &mut Value) -> Result<(), Error> {
+    let version = schema_version(save)?;
+
+    if version > SAVE_SCHEMA_VERSION {
+        return invalid_data_error(&format!(
+            "Save schema version {} is newer than the supported version {}",
+            version, SAVE_SCHEMA_VERSION
+        ));
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        info!(
+            "Upgrading save from schema version {} to {}",
+            index,
+            index + 1
+        );
+        if let Err(e) = migration(save) {
+            return invalid_data_error(&format!(
+                "Unable to upgrade save from schema version {}: {}",
+                index, e
+            ));
+        }
+    }
+
+    match save.get_mut("meta") {
+        Some(Value::Object(meta)) => {
+            meta.insert(
+                "schema_version".to_string(),
+                Value::from(SAVE_SCHEMA_VERSION),
+            );
+            Ok(())
+        }
+        _ => invalid_data_error("Save file has no meta data"),
+    }
+}
+
+/// Calls `f` on every JSON object in the tree, depth first
+fn for_each_object(value: &mut Value, f: &mut dyn FnMut(&mut Map<String, Value>)) {
+    match value {
+        Value::Object(map) => {
+            f(map);
+            for child in map.values_mut() {
+                for_each_object(child, f);
+            }
+        }
+        Value::Array(values) => {
+            for child in values.iter_mut() {
+                for_each_object(child, f);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Version 0 to 1.  Callbacks could hold an `OnSwapWeapons` function, which
+/// was replaced by `OnHeldChanged` and has not been called since.
+fn remove_on_swap_weapons(save: &mut Value) -> Result<(), Error> {
+    for_each_object(save, &mut |map| {
+        if let Some(Value::Object(funcs)) = map.get_mut("funcs") {
+            funcs.remove("OnSwapWeapons");
+        }
+    });
+    Ok(())
+}
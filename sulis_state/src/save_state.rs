@@ -20,14 +20,16 @@ use std::io::Error;
 use std::rc::Rc;
 use std::u64;
 
-use sulis_core::util::{ExtInt, Point};
+use sulis_core::util::{ExtInt, Point, ReproducibleRandom};
 use sulis_module::{
     actor::{ActorBuilder, RewardBuilder},
+    ai::PartyAIMode,
     BonusList, ItemListEntrySaveState, ItemSaveState, QuickSlot, Slot,
 };
 
 use crate::animation::AnimSaveState;
 use crate::area_state::TriggerState;
+use crate::schedule::ScheduleState;
 use crate::script::CallbackData;
 use crate::{
     effect, prop_state::Interactive, turn_manager::EncounterRef, ActorState, Effect, EntityState,
@@ -54,6 +56,19 @@ pub struct SaveState {
 
     #[serde(default)]
     pub(crate) total_elapsed_millis: usize,
+
+    #[serde(default)]
+    pub(crate) rand: Option<ReproducibleRandom>,
+
+    #[serde(default)]
+    pub(crate) reputation: HashMap<String, i32>,
+
+    /// The ID of the difficulty.  If empty, the default difficulty is used.
+    #[serde(default)]
+    pub(crate) difficulty: String,
+
+    #[serde(default)]
+    pub(crate) auto_combat: bool,
 }
 
 fn default_zoom() -> f32 {
@@ -116,6 +131,10 @@ impl SaveState {
             world_map: GameState::world_map(),
             quests: quest_state,
             total_elapsed_millis,
+            rand: Some(GameState::rand_state()),
+            reputation: GameState::reputations(),
+            difficulty: GameState::difficulty().id,
+            auto_combat: GameState::is_auto_combat(),
         }
     }
 
@@ -193,12 +212,22 @@ pub struct EffectSaveState {
 
     #[serde(default = "default_true")]
     pub(crate) ui_visible: bool,
+
+    #[serde(default)]
+    pub(crate) stack_policy: effect::StackPolicy,
+
+    #[serde(default = "default_stacks")]
+    pub(crate) stacks: u32,
 }
 
 fn default_true() -> bool {
     true
 }
 
+fn default_stacks() -> u32 {
+    1
+}
+
 impl EffectSaveState {
     pub fn new(effect: &Effect, index: usize) -> EffectSaveState {
         let mut callbacks: Vec<CallbackData> = Vec::new();
@@ -220,6 +249,8 @@ impl EffectSaveState {
             callbacks,
             icon: effect.icon.clone(),
             ui_visible: effect.ui_visible,
+            stack_policy: effect.stack_policy,
+            stacks: effect.stacks,
         }
     }
 }
@@ -389,6 +420,9 @@ pub struct MerchantSaveState {
 
     #[serde(default)]
     pub(crate) loot_list_id: Option<String>,
+
+    #[serde(default)]
+    pub(crate) repair_frac: Option<f32>,
 }
 
 impl MerchantSaveState {
@@ -403,6 +437,7 @@ impl MerchantSaveState {
             id: merchant.id.to_string(),
             loot_list_id: merchant.loot_list_id.clone(),
             buy_frac: merchant.buy_frac,
+            repair_frac: merchant.repair_frac,
             sell_frac: merchant.sell_frac,
             items,
             refresh_rate_millis: merchant.refresh_rate_millis,
@@ -431,6 +466,12 @@ pub struct EntitySaveState {
     #[serde(default = "serde_true")]
     pub(crate) show_portrait: bool,
 
+    #[serde(default)]
+    pub(crate) party_ai_mode: Option<PartyAIMode>,
+
+    #[serde(default)]
+    pub(crate) schedule: Option<ScheduleState>,
+
     #[serde(default)]
     pub(crate) collapsed_groups: Vec<String>,
 }
@@ -473,7 +514,7 @@ impl EntitySaveState {
                 portrait: actor.portrait.as_ref().map(|p| p.id()),
                 attributes: actor.attributes,
                 conversation: actor.conversation.as_ref().map(|c| c.id.to_string()),
-                faction: Some(actor.faction()),
+                faction: Some(actor.faction().clone()),
                 images: actor.builder_images.clone(),
                 hue: actor.hue,
                 hair_color: actor.hair_color,
@@ -504,6 +545,8 @@ impl EntitySaveState {
             ai_group: entity.ai_group(),
             ai_active: entity.is_ai_active(),
             show_portrait: entity.show_portrait(),
+            party_ai_mode: entity.party_ai_mode(),
+            schedule: entity.schedule().cloned(),
             actor_base,
             collapsed_groups: entity.collapsed_groups(),
         }
@@ -0,0 +1,299 @@
+//  This file is part of Sulis, a turn based RPG written in Rust.
+//  Copyright 2018 Jared Stephen
+//
+//  Sulis is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Sulis is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Sulis.  If not, see <http://www.gnu.org/licenses/>
+
+//! Out of combat schedules for entities outside the party.  Each frame while
+//! combat is inactive, scheduled entities in the current area walk in real
+//! time between the waypoints of the schedule entry covering the current hour,
+//! or back to their home location when no entry does.  The progress of each
+//! entity through its schedule is saved with the entity.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use sulis_core::util::Point;
+use sulis_module::area::{ScheduleEntry, ScheduleRoute};
+use sulis_module::ROUND_TIME_MILLIS;
+
+use crate::{EntityState, GameState};
+
+/// How long to wait before trying again after failing to find a path
+const RETRY_MILLIS: usize = 2_000;
+
+/// The number of rounds a scheduled entity stays put when spoken to, both
+/// when the conversation starts and after it ends
+pub const CONVERSATION_HOLD_ROUNDS: u32 = 1;
+
+/// The state of an entity following a schedule
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduleState {
+    home: Point,
+    entries: Vec<ScheduleEntry>,
+
+    #[serde(default)]
+    entry: Option<usize>,
+
+    #[serde(default)]
+    waypoint: usize,
+
+    #[serde(default)]
+    reverse: bool,
+
+    /// The game time, in total elapsed millis, at which the entity moves on
+    /// from the waypoint it is waiting at
+    #[serde(default)]
+    wait_until: Option<usize>,
+
+    #[serde(default)]
+    hold_until: usize,
+
+    #[serde(skip)]
+    retry_at: usize,
+}
+
+impl ScheduleState {
+    pub fn new(home: Point, entries: Vec<ScheduleEntry>) -> ScheduleState {
+        ScheduleState {
+            home,
+            entries,
+            entry: None,
+            waypoint: 0,
+            reverse: false,
+            wait_until: None,
+            hold_until: 0,
+            retry_at: 0,
+        }
+    }
+
+    /// The location the entity returns to when no entry applies
+    pub fn home(&self) -> Point {
+        self.home
+    }
+
+    pub fn entries(&self) -> &[ScheduleEntry] {
+        &self.entries
+    }
+
+    /// The index of the entry currently being followed, if any
+    pub fn current_entry(&self) -> Option<usize> {
+        self.entry
+    }
+
+    /// The index of the waypoint being walked to or waited at
+    pub fn current_waypoint(&self) -> usize {
+        self.waypoint
+    }
+
+    /// The location the schedule currently leads to: the current waypoint of
+    /// the entry being followed, or home
+    pub fn destination(&self) -> Point {
+        let waypoints = match self.entry {
+            None => return self.home,
+            Some(index) => &self.entries[index].waypoints,
+        };
+
+        match waypoints.get(self.waypoint) {
+            None => self.home,
+            Some(waypoint) => waypoint.location,
+        }
+    }
+
+    /// Returns true if the schedule is held at the game time `now`
+    pub fn is_held(&self, now: usize) -> bool {
+        now < self.hold_until
+    }
+
+    /// Stops following the schedule until the game time `until`
+    pub fn hold(&mut self, until: usize) {
+        self.hold_until = self.hold_until.max(until);
+    }
+
+    pub fn release(&mut self) {
+        self.hold_until = 0;
+    }
+
+    /// Returns the point the entity at `pos` should move to at the game time
+    /// `now` and `hour`, or None if it should stay where it is.
+    fn next_destination(&mut self, pos: Point, now: usize, hour: u32) -> Option<Point> {
+        if self.is_held(now) || now < self.retry_at {
+            return None;
+        }
+
+        let entry = self.entries.iter().position(|e| e.applies_at(hour));
+        if entry != self.entry {
+            self.entry = entry;
+            self.waypoint = 0;
+            self.reverse = false;
+            self.wait_until = None;
+        }
+
+        let (waypoints, route) = match entry {
+            Some(index) if !self.entries[index].waypoints.is_empty() => {
+                let entry = &self.entries[index];
+                (&entry.waypoints, entry.route)
+            }
+            _ => {
+                return if is_near(pos, self.home) {
+                    None
+                } else {
+                    Some(self.home)
+                };
+            }
+        };
+
+        self.waypoint = self.waypoint.min(waypoints.len() - 1);
+        let waypoint = waypoints[self.waypoint];
+        if !is_near(pos, waypoint.location) {
+            self.wait_until = None;
+            return Some(waypoint.location);
+        }
+
+        let until = match self.wait_until {
+            None => {
+                let until = now + waypoint.wait as usize * ROUND_TIME_MILLIS as usize;
+                self.wait_until = Some(until);
+                until
+            }
+            Some(until) => until,
+        };
+
+        if now < until {
+            return None;
+        }
+
+        let len = waypoints.len();
+        let next = next_waypoint(self.waypoint, len, route, &mut self.reverse);
+        if next == self.waypoint {
+            return None;
+        }
+
+        self.waypoint = next;
+        self.wait_until = None;
+        Some(waypoints[next].location)
+    }
+}
+
+fn next_waypoint(cur: usize, len: usize, route: ScheduleRoute, reverse: &mut bool) -> usize {
+    if len < 2 {
+        return 0;
+    }
+
+    match route {
+        ScheduleRoute::Loop => (cur + 1) % len,
+        ScheduleRoute::Once => (cur + 1).min(len - 1),
+        ScheduleRoute::BackAndForth => {
+            if *reverse && cur == 0 {
+                *reverse = false;
+            } else if !*reverse && cur == len - 1 {
+                *reverse = true;
+            }
+
+            if *reverse {
+                cur - 1
+            } else {
+                cur + 1
+            }
+        }
+    }
+}
+
+fn is_near(pos: Point, dest: Point) -> bool {
+    (pos.x - dest.x).abs() <= 1 && (pos.y - dest.y).abs() <= 1
+}
+
+/// Stops the `entity` where it is and holds its schedule for the specified
+/// number of `rounds`, for example so that it may be spoken to.  Does nothing
+/// if the entity has no schedule.
+pub fn hold(entity: &Rc<RefCell<EntityState>>, rounds: u32) {
+    let now = GameState::turn_manager().borrow().total_elapsed_millis();
+    {
+        let mut entity = entity.borrow_mut();
+        match entity.schedule_mut() {
+            None => return,
+            Some(schedule) => schedule.hold(now + rounds as usize * ROUND_TIME_MILLIS as usize),
+        }
+    }
+
+    GameState::remove_blocking_animations(entity);
+}
+
+/// Moves all scheduled entities in the current area towards their next
+/// destination.  Called each frame while combat is inactive.
+pub(crate) fn update() {
+    let (entities, now, hour) = {
+        let mgr = GameState::turn_manager();
+        let mgr = mgr.borrow();
+        let area_id = GameState::area_state().borrow().area.area.id.to_string();
+        let entities: Vec<_> = mgr
+            .entity_iter()
+            .filter(|entity| {
+                let entity = entity.borrow();
+                entity.schedule().is_some()
+                    && !entity.is_party_member()
+                    && entity.location.is_in_area_id(&area_id)
+            })
+            .collect();
+        (
+            entities,
+            mgr.total_elapsed_millis(),
+            mgr.current_time().hour,
+        )
+    };
+
+    for entity in entities {
+        update_entity(&entity, now, hour);
+    }
+}
+
+fn update_entity(entity: &Rc<RefCell<EntityState>>, now: usize, hour: u32) {
+    if GameState::has_blocking_animations(entity) {
+        return;
+    }
+
+    let dest = {
+        let mut entity = entity.borrow_mut();
+        if entity.actor.is_dead() || entity.is_ai_active() {
+            return;
+        }
+
+        let pos = entity.location.to_point();
+        let schedule = entity.schedule_mut().unwrap();
+        match schedule.next_destination(pos, now, hour) {
+            None => return,
+            Some(dest) => dest,
+        }
+    };
+
+    trace!(
+        "Schedule moving '{}' to {},{}",
+        entity.borrow().unique_id(),
+        dest.x,
+        dest.y
+    );
+
+    let to_ignore = vec![entity.borrow().index()];
+    let dest = GameState::get_point_dest(&entity.borrow(), dest.x as f32, dest.y as f32);
+    // schedules only run out of combat, where movement is free
+    if !GameState::move_ignore_ap(entity, &to_ignore, dest, None) {
+        debug!(
+            "Unable to find schedule path for '{}'",
+            entity.borrow().unique_id()
+        );
+        if let Some(schedule) = entity.borrow_mut().schedule_mut() {
+            schedule.retry_at = now + RETRY_MILLIS;
+        }
+    }
+}
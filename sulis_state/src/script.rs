@@ -49,11 +49,19 @@ pub use self::module_export::ModuleExport;
 mod script_ability;
 pub use self::script_ability::{ScriptAbility, ScriptAbilitySet};
 
+mod script_ai_blackboard;
+pub use self::script_ai_blackboard::ScriptAIBlackboard;
+
 pub mod script_cache;
 
+#[cfg(feature = "script_test")]
+pub mod script_test;
+#[cfg(feature = "script_test")]
+pub use self::script_test::ScriptTestReport;
+
 pub mod script_callback;
 pub use self::script_callback::{
-    CallbackData, FuncKind, ScriptCallback, ScriptHitKind, TriggeredCallback,
+    CallbackData, FuncKind, ScriptCallback, ScriptHitKind, ScriptSaveRoll, TriggeredCallback,
 };
 
 mod script_effect;
@@ -63,6 +71,7 @@ pub use self::script_effect::{
 
 mod script_entity;
 pub use self::script_entity::ScriptEntity;
+pub(crate) use self::script_entity::move_with_ai_group;
 
 mod script_entity_set;
 pub use self::script_entity_set::ScriptEntitySet;
@@ -98,14 +107,16 @@ pub mod targeter;
 pub use self::targeter::TargeterData;
 
 use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 use std::time;
 
-use rlua::{self, FromLuaMulti, Function, Lua, ToLuaMulti};
+use rlua::{self, FromLuaMulti, Function, Lua, ToLuaMulti, Value};
 
 use crate::{ai, EntityState, GameState};
-use sulis_core::{config::Config, util::Point};
+use sulis_core::config::Config;
+use sulis_core::util::{gen_rand, Point};
 use sulis_module::{Ability, DamageKind, HitKind, Module, QuickSlot};
 
 pub type Result<T> = std::result::Result<T, rlua::Error>;
@@ -341,6 +352,11 @@ impl Default for ScriptState {
                     warn!("{}", e);
                 }
             }
+
+            if let Err(e) = set_math_random(lua) {
+                warn!("Error replacing Lua math.random");
+                warn!("{}", e);
+            }
         });
 
         let instructions = Arc::new(Mutex::new(InstructionState {
@@ -414,6 +430,34 @@ impl ScriptState {
             .context(|lua| lua.load(&script).set_name(&id)?.exec())
     }
 
+    /// Removes the instruction and time limits from this state.  Script tests
+    /// drive the game from Lua, and so run for far longer than normal scripts.
+    #[cfg(feature = "script_test")]
+    pub(in crate::script) fn remove_limits(&self) {
+        self.lua.remove_hook();
+    }
+
+    #[cfg(feature = "script_test")]
+    pub(in crate::script) fn global<T>(&self, name: &str) -> Result<T>
+    where
+        T: for<'a> rlua::FromLua<'a>,
+    {
+        self.lua.context(|lua| lua.globals().get(name))
+    }
+
+    /// Returns the names of all functions in the global table of this state
+    pub(in crate::script) fn global_functions(&self) -> Result<HashSet<String>> {
+        self.lua.context(|lua| {
+            let mut funcs = HashSet::new();
+            for pair in lua.globals().pairs::<Value, Value>() {
+                if let (Value::String(name), Value::Function(_)) = pair? {
+                    funcs.insert(name.to_str()?.to_string());
+                }
+            }
+            Ok(funcs)
+        })
+    }
+
     pub(in crate::script) fn exec_func<Args, Ret>(
         &self,
         function: &str,
@@ -485,6 +529,65 @@ fn get_targeter() -> Result<Rc<RefCell<AreaTargeter>>> {
     }
 }
 
+/// Replaces the standard `math.random` with an equivalent function drawing from
+/// the campaign random stream, so script rolls are reproducible from a save.
+/// `math.cosmetic_random` takes the same arguments, but draws from a separate
+/// stream, so that purely visual rolls do not change later gameplay rolls.
+fn set_math_random(lua: rlua::Context) -> Result<()> {
+    let math: rlua::Table = lua.globals().get("math")?;
+    let random = random_function(lua, RandomStream::Campaign)?;
+    math.set("random", random)?;
+    let cosmetic_random = random_function(lua, RandomStream::Cosmetic)?;
+    math.set("cosmetic_random", cosmetic_random)
+}
+
+#[derive(Copy, Clone)]
+enum RandomStream {
+    Campaign,
+    Cosmetic,
+}
+
+impl RandomStream {
+    fn gen_float(self) -> f64 {
+        match self {
+            RandomStream::Campaign => GameState::with_rand(|rand| rand.gen(0.0, 1.0)),
+            RandomStream::Cosmetic => gen_rand(0.0, 1.0),
+        }
+    }
+
+    fn gen_int(self, min: i64, end: i64) -> i64 {
+        match self {
+            RandomStream::Campaign => GameState::with_rand(|rand| rand.gen(min, end)),
+            RandomStream::Cosmetic => gen_rand(min, end),
+        }
+    }
+}
+
+fn random_function(lua: rlua::Context, stream: RandomStream) -> Result<Function> {
+    lua.create_function(move |_, (m, n): (Option<i64>, Option<i64>)| {
+        let (min, max) = match (m, n) {
+            (None, _) => {
+                return Ok(rlua::Value::Number(stream.gen_float()));
+            }
+            (Some(max), None) => (1, max),
+            (Some(min), Some(max)) => (min, max),
+        };
+
+        if min > max {
+            return Err(rlua::Error::RuntimeError(
+                "bad argument to 'random' (interval is empty)".to_string(),
+            ));
+        }
+
+        let end = max.checked_add(1).ok_or_else(|| {
+            rlua::Error::RuntimeError(
+                "bad argument to 'random' (interval is too large)".to_string(),
+            )
+        })?;
+        Ok(rlua::Value::Integer(stream.gen_int(min, end)))
+    })
+}
+
 fn get_rlua_std_lib() -> rlua::StdLib {
     use rlua::StdLib;
 
@@ -21,7 +21,10 @@ use std::rc::Rc;
 use rlua::{self, Context, UserData, UserDataMethods};
 
 use crate::script::{CallbackData, ScriptEntity};
-use crate::{area_feedback_text::ColorKind, AreaFeedbackText, EntityState, GameState};
+use crate::{
+    area_feedback_text::ColorKind, combat_log, reaction_handler, AreaFeedbackText, EntityState,
+    GameState,
+};
 use sulis_module::{
     ability::{self, AIData, Range},
     Ability, Module,
@@ -475,5 +478,7 @@ fn activate(
         .borrow_mut()
         .actor
         .activate_ability_state(&ability.id);
+    combat_log::record_ability(&entity.borrow(), &ability.id, &ability.name);
+    reaction_handler::provoke_on_ability(&entity, &ability);
     Ok(())
 }
@@ -0,0 +1,256 @@
+//  This file is part of Sulis, a turn based RPG written in Rust.
+//  Copyright 2018 Jared Stephen
+//
+//  Sulis is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Sulis is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Sulis.  If not, see <http://www.gnu.org/licenses/>
+
+use rlua::{Result, UserData, UserDataMethods};
+
+use crate::script::ScriptEntity;
+use crate::GameState;
+use sulis_core::util::Point;
+use sulis_module::ability::{AIGroup, AIKind};
+use sulis_module::ai::AIRole;
+
+/// The blackboard shared by the members of an AI group, used by the native
+/// AI planner and the basic AI script to coordinate the group.  Scripts may
+/// read it, or write to it to direct the group.  Obtained with
+/// `game:ai_group_blackboard(entity)`.  The blackboard is cleared when combat
+/// ends.
+///
+/// # `group() -> Int`
+/// Returns the index of the AI group this blackboard belongs to.
+///
+/// # `focus() -> ScriptEntity`
+/// Returns the hostile entity that the group is concentrating its attacks
+/// on, or nil if there is none.
+///
+/// # `set_focus(target: ScriptEntity)`
+/// Sets the entity the group will concentrate its attacks on.
+///
+/// # `clear_focus()`
+/// Clears the focus target.  The native AI picks a new one on its next action.
+///
+/// # `role(entity: ScriptEntity) -> String`
+/// Returns the role of the `entity` within the group, one of `Striker`,
+/// `Defender`, `Caster`, or `Support`, or nil if no role has been assigned.
+/// Defenders give priority to hostiles near the casters and supporters of
+/// their group.
+///
+/// # `set_role(entity: ScriptEntity, role: String)`
+/// Sets the role of the `entity` within the group.
+///
+/// # `flank(entity: ScriptEntity) -> Table`
+/// Returns the position claimed by the `entity` to attack its target from,
+/// as a table with `x` and `y` entries, or nil if it has claimed none.
+///
+/// # `claim_flank(entity: ScriptEntity, x: Int, y: Int) -> Bool`
+/// Claims the specified position for the `entity`, releasing any it held
+/// before.  Returns false if another member has already claimed the position.
+///
+/// # `release_flank(entity: ScriptEntity)`
+/// Releases the position claimed by the `entity`, if any.
+///
+/// # `is_support_claimed(entity: ScriptEntity, kind: String, group: String,
+/// target: ScriptEntity) -> Bool`
+/// Returns true if another member of the group has already healed or buffed
+/// allies this round in a way that makes the same support from the `entity`
+/// redundant.  `kind` and `group` are the `kind` and `group` of the ability's
+/// `ai_data`.
+///
+/// # `claim_support(entity: ScriptEntity, kind: String, group: String,
+/// target: ScriptEntity)`
+/// Records that the `entity` is healing or buffing the `target` this round,
+/// so that other members of the group do not repeat it.
+///
+/// # `is_retreating() -> Bool`
+/// Returns true if the group has been ordered to retreat.
+///
+/// # `order_retreat()`
+/// Orders the members of the group to run away from their hostiles.
+///
+/// # `cancel_retreat()`
+/// Cancels a retreat order.
+#[derive(Clone)]
+pub struct ScriptAIBlackboard {
+    group: usize,
+}
+
+impl ScriptAIBlackboard {
+    pub fn new(group: usize) -> ScriptAIBlackboard {
+        ScriptAIBlackboard { group }
+    }
+}
+
+impl UserData for ScriptAIBlackboard {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("group", |_, board, ()| Ok(board.group));
+
+        methods.add_method("focus", |_, board, ()| {
+            let mgr = GameState::turn_manager();
+            let mgr = mgr.borrow();
+            let focus = match mgr.ai_blackboard_checked(board.group) {
+                None => return Ok(None),
+                Some(blackboard) => blackboard.focus(),
+            };
+
+            match focus.and_then(|index| mgr.entity_checked(index)) {
+                None => Ok(None),
+                Some(entity) => {
+                    if entity.borrow().actor.is_dead() {
+                        Ok(None)
+                    } else {
+                        Ok(Some(ScriptEntity::from(&entity)))
+                    }
+                }
+            }
+        });
+
+        methods.add_method("set_focus", |_, board, target: ScriptEntity| {
+            let index = target.try_unwrap_index()?;
+            let mgr = GameState::turn_manager();
+            mgr.borrow_mut()
+                .ai_blackboard(board.group)
+                .set_focus(Some(index));
+            Ok(())
+        });
+
+        methods.add_method("clear_focus", |_, board, ()| {
+            let mgr = GameState::turn_manager();
+            mgr.borrow_mut().ai_blackboard(board.group).set_focus(None);
+            Ok(())
+        });
+
+        methods.add_method("role", |_, board, entity: ScriptEntity| {
+            let index = entity.try_unwrap_index()?;
+            let mgr = GameState::turn_manager();
+            let mgr = mgr.borrow();
+            let role = mgr
+                .ai_blackboard_checked(board.group)
+                .and_then(|blackboard| blackboard.role(index));
+            Ok(role.map(|role| format!("{:?}", role)))
+        });
+
+        methods.add_method(
+            "set_role",
+            |_, board, (entity, role): (ScriptEntity, String)| {
+                let index = entity.try_unwrap_index()?;
+                let role = AIRole::unwrap_from_str(&role);
+                let mgr = GameState::turn_manager();
+                mgr.borrow_mut()
+                    .ai_blackboard(board.group)
+                    .set_role(index, role);
+                Ok(())
+            },
+        );
+
+        methods.add_method("flank", |lua, board, entity: ScriptEntity| {
+            let index = entity.try_unwrap_index()?;
+            let mgr = GameState::turn_manager();
+            let flank = mgr
+                .borrow()
+                .ai_blackboard_checked(board.group)
+                .and_then(|blackboard| blackboard.flank(index));
+
+            match flank {
+                None => Ok(None),
+                Some(point) => {
+                    let table = lua.create_table()?;
+                    table.set("x", point.x)?;
+                    table.set("y", point.y)?;
+                    Ok(Some(table))
+                }
+            }
+        });
+
+        methods.add_method(
+            "claim_flank",
+            |_, board, (entity, x, y): (ScriptEntity, i32, i32)| {
+                let index = entity.try_unwrap_index()?;
+                let mgr = GameState::turn_manager();
+                let mut mgr = mgr.borrow_mut();
+                Ok(mgr
+                    .ai_blackboard(board.group)
+                    .claim_flank(index, Point::new(x, y)))
+            },
+        );
+
+        methods.add_method("release_flank", |_, board, entity: ScriptEntity| {
+            let index = entity.try_unwrap_index()?;
+            let mgr = GameState::turn_manager();
+            mgr.borrow_mut()
+                .ai_blackboard(board.group)
+                .release_flank(index);
+            Ok(())
+        });
+
+        methods.add_method("is_support_claimed", |_, board, args: SupportArgs| {
+            let (index, kind, group, target) = support_claim(args)?;
+            let mgr = GameState::turn_manager();
+            let mgr = mgr.borrow();
+            let round = mgr.current_round();
+            Ok(mgr
+                .ai_blackboard_checked(board.group)
+                .is_some_and(|blackboard| {
+                    blackboard.is_support_claimed(index, kind, group, target, round)
+                }))
+        });
+
+        methods.add_method("claim_support", |_, board, args: SupportArgs| {
+            let (index, kind, group, target) = support_claim(args)?;
+            let mgr = GameState::turn_manager();
+            let mut mgr = mgr.borrow_mut();
+            let round = mgr.current_round();
+            mgr.ai_blackboard(board.group)
+                .claim_support(index, kind, group, target, round);
+            Ok(())
+        });
+
+        methods.add_method("is_retreating", |_, board, ()| {
+            let mgr = GameState::turn_manager();
+            let mgr = mgr.borrow();
+            Ok(mgr
+                .ai_blackboard_checked(board.group)
+                .is_some_and(|blackboard| blackboard.is_retreating()))
+        });
+
+        methods.add_method("order_retreat", |_, board, ()| {
+            let mgr = GameState::turn_manager();
+            mgr.borrow_mut()
+                .ai_blackboard(board.group)
+                .set_retreat(true);
+            Ok(())
+        });
+
+        methods.add_method("cancel_retreat", |_, board, ()| {
+            let mgr = GameState::turn_manager();
+            mgr.borrow_mut()
+                .ai_blackboard(board.group)
+                .set_retreat(false);
+            Ok(())
+        });
+    }
+}
+
+/// The entity, ability kind and group, and target of a support claim
+type SupportArgs = (ScriptEntity, String, String, ScriptEntity);
+
+fn support_claim(args: SupportArgs) -> Result<(usize, AIKind, AIGroup, usize)> {
+    let (entity, kind, group, target) = args;
+    Ok((
+        entity.try_unwrap_index()?,
+        AIKind::unwrap_from_str(&kind),
+        AIGroup::unwrap_from_str(&group),
+        target.try_unwrap_index()?,
+    ))
+}
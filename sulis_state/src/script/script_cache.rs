@@ -15,7 +15,7 @@
 //  along with Sulis.  If not, see <http://www.gnu.org/licenses/>
 
 use std::cell::{Cell, RefCell};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use std::time::{Duration, Instant};
 
@@ -31,6 +31,7 @@ use sulis_module::{ai::AITemplate, Ability, Item, Module};
 thread_local! {
     static SCRIPT_CACHE: RefCell<HashMap<String, Rc<ScriptState>>> = RefCell::new(HashMap::new());
     static REPORTING: Cell<bool> = Cell::new(true);
+    static ERRORS: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
 }
 
 pub fn setup() -> Result<()> {
@@ -55,10 +56,75 @@ pub fn setup() -> Result<()> {
     Ok(())
 }
 
+/// Rebuilds the cached states of the specified scripts from the current
+/// `Module`, removing any scripts that no longer exist.  If a script fails to
+/// load, its previous state is kept, and the error is returned.
+pub fn reload(ids: &[String]) -> Vec<(String, rlua::Error)> {
+    let mut errors = Vec::new();
+
+    for id in ids {
+        if Module::script(id).is_none() {
+            SCRIPT_CACHE.with(|cache| cache.borrow_mut().remove(id));
+            continue;
+        }
+
+        let mut state = ScriptState::default();
+        match get_script_from_id(id).and_then(|script| state.load(id, &script)) {
+            Err(e) => errors.push((id.to_string(), e)),
+            Ok(()) => {
+                SCRIPT_CACHE.with(|cache| cache.borrow_mut().insert(id.to_string(), Rc::new(state)));
+            }
+        }
+    }
+
+    errors
+}
+
+/// Loads the script with the specified ID into a new Lua state, without
+/// touching the cache, and returns the names of the global functions it
+/// defines.  This checks the script for syntax errors and any errors in its
+/// top level code.
+pub fn defined_functions(id: &str) -> Result<HashSet<String>> {
+    let script = get_script_from_id(id)?;
+    let mut state = ScriptState::default();
+    state.load(id, &script)?;
+    state.global_functions()
+}
+
 pub fn set_report_enabled(enabled: bool) {
     REPORTING.with(|r| r.set(enabled));
 }
 
+/// Starts or stops recording the errors returned by script functions.  These
+/// errors are normally only logged, so this allows callers such as the script
+/// test runner to find out about them.
+pub fn set_error_capture(enabled: bool) {
+    ERRORS.with(|errors| {
+        let mut errors = errors.borrow_mut();
+        match (enabled, errors.is_some()) {
+            (true, false) => *errors = Some(Vec::new()),
+            (false, true) => *errors = None,
+            _ => (),
+        }
+    });
+}
+
+/// Returns all errors recorded since the last call, if error capture is enabled
+pub fn take_errors() -> Vec<String> {
+    ERRORS.with(|errors| match errors.borrow_mut().as_mut() {
+        None => Vec::new(),
+        Some(errors) => std::mem::take(errors),
+    })
+}
+
+fn capture_error(id: &str, func: &str, error: &rlua::Error) {
+    ERRORS.with(|errors| {
+        if let Some(errors) = errors.borrow_mut().as_mut() {
+            errors.push(format!("{}:{}: {}", id, func, error));
+        }
+    });
+}
+
 fn parse_traceback_line_num(traceback: &str) -> Option<i32> {
     // find first line with a line number, this should be the right
     // place on the call stack
@@ -151,9 +217,13 @@ where
                 "Script Error:\n{}\n{}.lua:{} Called '{}'\n{}",
                 cause, state.id, line_num, func, output
             );
+            capture_error(&state.id, func, &cause);
             Err(CallbackError { traceback, cause })
         }
-        Err(e) => Err(e),
+        Err(e) => {
+            capture_error(&state.id, func, &e);
+            Err(e)
+        }
     }
 }
 
@@ -301,13 +371,13 @@ where
 fn get_script_data_from_entity(entity: &Rc<RefCell<EntityState>>) -> Result<Rc<AITemplate>> {
     let entity = entity.borrow();
     let id = entity.unique_id();
-    match &entity.actor.actor.ai {
+    match entity.ai_template() {
         None => Err(rlua::Error::ToLuaConversionError {
             from: "Entity",
             to: "Script",
             message: Some(format!("Script called for entity '{}' with no AI", id)),
         }),
-        Some(ai) => Ok(Rc::clone(ai)),
+        Some(ai) => Ok(ai),
     }
 }
 
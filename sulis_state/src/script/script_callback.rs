@@ -28,7 +28,7 @@ use crate::script::{
 };
 use crate::{EntityState, GameState, Script};
 use sulis_core::util::invalid_data_error;
-use sulis_module::{on_trigger::Kind, Ability, DamageKind, HitKind, Module};
+use sulis_module::{on_trigger::Kind, Ability, DamageKind, HitKind, Module, SaveResult, SaveRoll};
 
 pub fn fire_round_elapsed(cbs: Vec<Rc<CallbackData>>) {
     for cb in cbs {
@@ -130,9 +130,6 @@ pub fn fire_on_moved(cbs: Vec<Rc<CallbackData>>) {
 #[derive(Serialize, Deserialize, Clone, Copy, PartialOrd, Ord, Hash, PartialEq, Eq, Debug)]
 #[serde(deny_unknown_fields)]
 pub enum FuncKind {
-    /// Save compatibility only.  TODO remove this after a while
-    OnSwapWeapons,
-
     /// Called when an entity swaps their weapon set
     OnHeldChanged,
 
@@ -196,6 +193,15 @@ pub enum FuncKind {
 
     /// Called whena an ability mode is deactivated
     OnDeactivated,
+
+    /// Called on an entity when it takes a reaction, such as an attack of
+    /// opportunity, against an enemy it threatens.  The targets are the reacting
+    /// entity and the entity that provoked the reaction.
+    OnReaction,
+
+    /// Called on a hidden entity when it is detected by a hostile entity.  The
+    /// targets are the detected entity and the entity that detected it.
+    OnDetected,
 }
 
 /// A trait representing a callback that will fire a script when called.  In lua scripts,
@@ -252,6 +258,10 @@ pub trait ScriptCallback {
     fn on_entered_surface(&self, _target: usize) {}
 
     fn on_exited_surface(&self, _target: usize) {}
+
+    fn on_reaction(&self, _targets: &ScriptEntitySet) {}
+
+    fn on_detected(&self, _targets: &ScriptEntitySet) {}
 }
 
 /// A callback that can be passed to various functions to be executed later.
@@ -290,6 +300,8 @@ pub trait ScriptCallback {
 /// # `set_on_moved_in_surface_fn(func: String)`
 /// # `set_on_entered_surface_fn(func: String)`
 /// # `set_on_exited_surface_fn(func: String)`
+/// # `set_on_reaction_fn(func: String)`
+/// # `set_on_detected_fn(func: String)`
 /// Each of these methods causes a specified lua `func` to be called when the condition is met,
 /// as described in `FuncKind`.  Multiple of these methods may be added to one
 /// Callback.
@@ -593,6 +605,14 @@ impl ScriptCallback for CallbackData {
         self.exec_standard_script(self.get_or_create_targets(), FuncKind::OnMoved);
     }
 
+    fn on_reaction(&self, targets: &ScriptEntitySet) {
+        self.exec_standard_script(self.get_targets(targets), FuncKind::OnReaction);
+    }
+
+    fn on_detected(&self, targets: &ScriptEntitySet) {
+        self.exec_standard_script(self.get_targets(targets), FuncKind::OnDetected);
+    }
+
     /// when called, this computes the current target set and sends it to
     /// the lua function based on the surface state
     fn on_surface_round_elapsed(&self) {
@@ -836,6 +856,69 @@ impl UserData for CallbackData {
             cb.add_func(FuncKind::OnExitedSurface, func);
             Ok(())
         });
+        methods.add_method_mut("set_on_reaction_fn", |_, cb, func: String| {
+            cb.add_func(FuncKind::OnReaction, func);
+            Ok(())
+        });
+        methods.add_method_mut("set_on_detected_fn", |_, cb, func: String| {
+            cb.add_func(FuncKind::OnDetected, func);
+            Ok(())
+        });
+    }
+}
+
+/// ScriptSaveRoll stores the result of a saving throw for lua.
+///
+/// # `is_fail() -> Bool`
+/// Whether the save was failed
+///
+/// # `is_partial() -> Bool`
+/// Whether the save was failed, but close enough to the DC for a partial
+/// success
+///
+/// # `is_success() -> Bool`
+/// Whether the save was successful, including critical successes
+///
+/// # `is_crit_success() -> Bool`
+/// Whether the save was a critical success
+///
+/// # `roll() -> Int`
+/// The random roll, from 1 to 100
+///
+/// # `total() -> Int`
+/// The roll plus the defense of the entity making the save, which is
+/// compared against the DC
+///
+/// # `result() -> String`
+/// The result of the save.  One of `fail`, `partial`, `success`, or
+/// `crit_success`.
+///
+/// # `kind() -> String`
+/// The kind of save that was rolled.  One of `fortitude`, `reflex`, or
+/// `will`.
+#[derive(Clone)]
+pub struct ScriptSaveRoll {
+    pub roll: SaveRoll,
+}
+
+impl UserData for ScriptSaveRoll {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("is_fail", |_, save, ()| {
+            Ok(save.roll.result == SaveResult::Fail)
+        });
+        methods.add_method("is_partial", |_, save, ()| {
+            Ok(save.roll.result == SaveResult::Partial)
+        });
+        methods.add_method("is_success", |_, save, ()| {
+            Ok(save.roll.result >= SaveResult::Success)
+        });
+        methods.add_method("is_crit_success", |_, save, ()| {
+            Ok(save.roll.result == SaveResult::CritSuccess)
+        });
+        methods.add_method("roll", |_, save, ()| Ok(save.roll.roll));
+        methods.add_method("total", |_, save, ()| Ok(save.roll.roll + save.roll.bonus));
+        methods.add_method("result", |_, save, ()| Ok(save.roll.result.to_str()));
+        methods.add_method("kind", |_, save, ()| Ok(save.roll.kind.to_str()));
     }
 }
 
@@ -21,6 +21,7 @@ use rlua::{Context, UserData, UserDataMethods};
 
 use sulis_core::util::{ExtInt, Point};
 use sulis_module::{
+    area::LocationKind,
     bonus::{self, Contingent},
     ArmorKind, Attribute, Bonus, BonusKind, BonusList, Damage, DamageKind, Slot, WeaponKind,
     WeaponStyle, ROUND_TIME_MILLIS,
@@ -32,7 +33,7 @@ use crate::script::{
     ScriptCallback, ScriptColorAnimation, ScriptEntity, ScriptImageLayerAnimation,
     ScriptParticleGenerator, ScriptScaleAnimation, ScriptSubposAnimation,
 };
-use crate::{effect, Effect, GameState};
+use crate::{effect, Effect, GameState, StackPolicy};
 
 /// Represents a surface that already exists, and is being passed into
 /// a Lua script.  Not used during effect creation
@@ -97,6 +98,10 @@ impl UserData for ScriptMenuSelection {
 /// # `tag() -> String`
 /// Returns the user defined tag of this effect
 ///
+/// # `stacks() -> Int`
+/// Returns the number of times this effect has been stacked.  This is 1 unless
+/// the effect was created with the `stack` policy, see `set_stacking`
+///
 /// # `surface_points() -> Table`
 /// Returns a table of all the affected points for this effect.  Only works
 /// on surfaces.
@@ -119,6 +124,7 @@ impl UserData for ScriptMenuSelection {
 /// `reflex`, `will`, `concealment`, `concealment_ignore`, `crit_chance`,
 /// `hit_threshold`, `graze_threshold`, `graze_multiplier`, `hit_multiplier`,
 /// `crit_multiplier`, `movement_rate`, `move_anim_rate`, `attack_cost`, `ability_ap_cost`,
+/// `caster_level`, `reactions`, `carry_capacity`, `stealth`, `flanking_angle`,
 /// `hidden`, `free_ability_group_use`, abilities_disabled`, `move_disabled`,
 /// `attack_disabled`, `flanked_immunity`, `sneak_attack_immunity`, `crit_immunity`
 ///
@@ -185,6 +191,9 @@ fn check_for_bonus(effect: &ScriptAppliedEffect, kind: String) -> bool {
         "move_anim_rate" => MoveAnimRate(0.0),
         "attack_cost" => AttackCost(0),
         "caster_level" => CasterLevel(0),
+        "reactions" => Reactions(0),
+        "carry_capacity" => CarryCapacity(0),
+        "stealth" => Stealth(0),
         "flanking_angle" => FlankingAngle(0),
         "hidden" => Hidden,
         "free_ability_group_use" => FreeAbilityGroupUse,
@@ -202,7 +211,7 @@ fn check_for_bonus(effect: &ScriptAppliedEffect, kind: String) -> bool {
 
     for bonus in effect.bonuses.iter() {
         let check_bonus = Bonus {
-            when: bonus.when,
+            when: bonus.when.clone(),
             kind: kind.clone(),
         };
 
@@ -220,6 +229,14 @@ impl UserData for ScriptAppliedEffect {
 
         methods.add_method("tag", |_, effect, ()| Ok(effect.tag.to_string()));
 
+        methods.add_method("stacks", |_, effect, ()| {
+            let mgr = GameState::turn_manager();
+            let mgr = mgr.borrow();
+            Ok(mgr
+                .effect_checked(effect.index)
+                .map_or(0, |effect| effect.stacks()))
+        });
+
         methods.add_method("surface_points", |_, effect, ()| {
             let mgr = GameState::turn_manager();
             let mgr = mgr.borrow();
@@ -344,6 +361,20 @@ impl UserData for ScriptAppliedEffect {
 /// Sets a tag to identify this effect as being of a particular type to other scripts.
 /// Most notably, this is used when calling `remove_effects_with_tag` on a `ScriptEntity`
 ///
+/// # `set_stacking(policy: String, value: Float (Optional))`
+/// Sets what happens when this effect is applied to an entity that already has an effect
+/// with the same tag.  The effect must be given a tag with `set_tag`; untagged effects
+/// are always independent.  Valid policies are:
+/// - `independent`: the default.  Each application is a separate effect.
+/// - `refresh`: the existing effect's remaining duration is reset to this effect's
+///   duration, and this effect is discarded.
+/// - `stack`: the existing effect gains a stack, up to `value` stacks, and its remaining
+///   duration is reset as for `refresh`.  Its bonuses are multiplied by the number of
+///   stacks.
+/// - `replace_if_stronger`: this effect replaces the existing effect if its strength,
+///   `value`, is at least that of the existing effect, and is otherwise discarded.
+/// Any other policy is an error.
+///
 /// # `add_num_bonus(kind: String, amount: Float, when: String (Optional))`
 /// Adds a numeric bonus that is applied to the parent entity when this effect is active.
 /// Positive values are bonuses, while negative values are penalties.  `when` is optional
@@ -351,14 +382,17 @@ impl UserData for ScriptAppliedEffect {
 /// the bonus is always applied.  Valid values are `always`, `attack_when_hidden`,
 /// `attack_when_flanking`, `weapon_equipped <WEAPON_KIND>`,
 /// `armor_equipped <ARMOR_KIND> <INVENTORY_SLOT>`, `weapon_style <WEAPON_STYLE>`,
-/// `attack_with_weapon <WEAPON_KIND>`, `attack_with_damage_kind <DAMAGE_KIND>`
+/// `attack_with_weapon <WEAPON_KIND>`, `attack_with_damage_kind <DAMAGE_KIND>`,
+/// `threatened`, `in_combat`, `out_of_combat`, `adjacent_to_ally`,
+/// `hp_below <PERCENT>`, `target_has_effect <TAG>`, `location_kind <LOCATION_KIND>`,
+/// `time_of_day <FROM_HOUR> <TO_HOUR>`
 ///
 /// Bonus kinds include `armor`, `ap`, `reach`, `range`, `initiative`, `hit_points`,
 /// `melee_accuracy`, `ranged_accuracy`, `spell_accuracy`, `defense`, `fortitude`,
 /// `reflex`, `will`, `concealment`, `concealment_ignore`, `crit_chance`,
 /// `hit_threshold`, `graze_threshold`, `graze_multiplier`, `hit_multiplier`,
 /// `crit_multiplier`, `movement_rate`, `move_anim_rate`, `attack_cost`, `ability_ap_cost`,
-/// `caster_level`, `flanking_angle`
+/// `caster_level`, `reactions`, `carry_capacity`, `stealth`, `flanking_angle`
 ///
 /// # `add_damage(min: Float, max: Float, ap: Float (Optional), when: String (Optional))`
 /// Adds a damage bonus of the specified amount (from `min` to `max` randomly, with `ap`
@@ -415,6 +449,7 @@ pub struct ScriptEffect {
     deactivate_with_ability: Option<String>,
     pub bonuses: BonusList,
     icon: Option<effect::Icon>,
+    stack_policy: StackPolicy,
     callbacks: Vec<CallbackData>,
     pgens: Vec<ScriptParticleGenerator>,
     image_layer_anims: Vec<ScriptImageLayerAnimation>,
@@ -437,6 +472,7 @@ impl ScriptEffect {
             deactivate_with_ability: None,
             duration,
             icon: None,
+            stack_policy: StackPolicy::Independent,
             bonuses: BonusList::default(),
             callbacks: Vec::new(),
             pgens: Vec::new(),
@@ -456,6 +492,7 @@ impl ScriptEffect {
             deactivate_with_ability: None,
             duration,
             icon: None,
+            stack_policy: StackPolicy::Independent,
             bonuses: BonusList::default(),
             callbacks: Vec::new(),
             pgens: Vec::new(),
@@ -535,6 +572,29 @@ impl UserData for ScriptEffect {
             effect.tag = tag;
             Ok(())
         });
+        methods.add_method_mut(
+            "set_stacking",
+            |_, effect, (policy, value): (String, Option<f32>)| {
+                effect.stack_policy = match policy.as_ref() {
+                    "independent" => StackPolicy::Independent,
+                    "refresh" => StackPolicy::Refresh,
+                    "stack" => StackPolicy::Stack {
+                        max: value.unwrap_or(1.0).max(1.0) as u32,
+                    },
+                    "replace_if_stronger" => StackPolicy::ReplaceIfStronger {
+                        strength: value.unwrap_or(0.0),
+                    },
+                    _ => {
+                        return Err(rlua::Error::FromLuaConversionError {
+                            from: "String",
+                            to: "StackPolicy",
+                            message: Some(format!("Invalid stacking policy '{}'", policy)),
+                        });
+                    }
+                };
+                Ok(())
+            },
+        );
         methods.add_method_mut("set_ui_visible", |_, effect, vis: bool| {
             effect.ui_visible = vis;
             Ok(())
@@ -676,6 +736,9 @@ fn add_bonus_to_effect(effect: &mut ScriptEffect, bonus_kind: BonusKind, when: O
                 "attack_when_hidden" => Contingent::AttackWhenHidden,
                 "attack_when_flanking" => Contingent::AttackWhenFlanking,
                 "threatened" => Contingent::Threatened,
+                "in_combat" => Contingent::InCombat,
+                "out_of_combat" => Contingent::OutOfCombat,
+                "adjacent_to_ally" => Contingent::AdjacentToAlly,
                 _ => {
                     warn!(
                         "Unable to parse contingent '{}'.  May need an additional arg.",
@@ -751,6 +814,61 @@ fn add_bonus_to_effect(effect: &mut ScriptEffect, bonus_kind: BonusKind, when: O
 
                     Contingent::AttackWithDamageKind(DamageKind::unwrap_from_str(split[1]))
                 }
+                "hp_below" => {
+                    if split.len() != 2 {
+                        warn!("Need 2 args for hp_below from '{}'", when);
+                        return;
+                    }
+
+                    match split[1].parse::<u32>() {
+                        Ok(percent) => Contingent::HpBelow(percent),
+                        Err(_) => {
+                            warn!("Invalid percentage for hp_below from '{}'", when);
+                            return;
+                        }
+                    }
+                }
+                "target_has_effect" => {
+                    if split.len() != 2 {
+                        warn!("Need 2 args for target_has_effect from '{}'", when);
+                        return;
+                    }
+
+                    Contingent::TargetHasEffect(split[1].to_string())
+                }
+                "location_kind" => {
+                    if split.len() != 2 {
+                        warn!("Need 2 args for location_kind from '{}'", when);
+                        return;
+                    }
+
+                    let kind = match split[1] {
+                        "outdoors" | "Outdoors" => LocationKind::Outdoors,
+                        "indoors" | "Indoors" => LocationKind::Indoors,
+                        "underground" | "Underground" => LocationKind::Underground,
+                        _ => {
+                            warn!("Invalid location kind from '{}'", when);
+                            return;
+                        }
+                    };
+                    Contingent::LocationKind(kind)
+                }
+                "time_of_day" => {
+                    if split.len() != 3 {
+                        warn!("Need 3 args for time_of_day from '{}'", when);
+                        return;
+                    }
+
+                    match (split[1].parse::<u32>(), split[2].parse::<u32>()) {
+                        (Ok(from), Ok(to)) if bonus::is_valid_time_of_day(from, to) => {
+                            Contingent::TimeOfDay { from, to }
+                        }
+                        _ => {
+                            warn!("Invalid hours for time_of_day from '{}'", when);
+                            return;
+                        }
+                    }
+                }
                 _ => {
                     warn!(
                         "Unable to parse contingent '{}'.  Unknown kind / too many args.",
@@ -808,6 +926,9 @@ fn add_num_bonus(
         "move_anim_rate" => MoveAnimRate(amount),
         "attack_cost" => AttackCost(amount_int),
         "caster_level" => CasterLevel(amount_int),
+        "reactions" => Reactions(amount_int),
+        "carry_capacity" => CarryCapacity(amount_int),
+        "stealth" => Stealth(amount_int),
         "flanking_angle" => FlankingAngle(amount_int),
         _ => {
             warn!("Attempted to add num bonus with invalid type '{}'", name);
@@ -835,6 +956,7 @@ fn apply(effect_data: &ScriptEffect) -> Result<()> {
         effect_data.deactivate_with_ability.clone(),
     );
     effect.ui_visible = effect_data.ui_visible;
+    effect.set_stack_policy(effect_data.stack_policy);
     if let Some(icon) = &effect_data.icon {
         effect.set_icon(icon.icon.clone(), icon.text.clone());
     }
@@ -22,16 +22,22 @@ use std::{self, f32, u32};
 
 use rlua::{self, Context, UserData, UserDataMethods};
 
+use crate::AreaFeedbackText;
 use crate::{ability_state::DisabledReason, dist, is_within_attack_dist, is_within_touch_dist};
-use crate::{ai, animation, entity_attack_handler, script::*, AreaFeedbackText};
-use crate::{area_feedback_text::ColorKind, EntityState, GameState, Location};
+use crate::{
+    ai, animation, combat_log, entity_attack_handler, reaction_handler, script::*, stealth,
+};
+use crate::{area_feedback_text::ColorKind, EntityState, GameState, Location, ReactionKind};
+use crate::{schedule, ScheduleState};
 use sulis_core::config::Config;
 use sulis_core::resource::ResourceSet;
-use sulis_core::util::ExtInt;
+use sulis_core::util::{ExtInt, Point};
 use sulis_module::{
     ability::AIData, Actor, Attack, AttackKind, Attribute, DamageKind, Faction, HitFlags, HitKind,
-    ImageLayer, InventoryBuilder, MOVE_TO_THRESHOLD, area::Destination,
+    ImageLayer, InventoryBuilder, SaveKind, MOVE_TO_THRESHOLD, area::Destination,
 };
+use sulis_module::ai::PartyAIMode;
+use sulis_module::area::{ScheduleEntry, ScheduleRoute, Waypoint};
 
 /// Represents a single entity for Lua scripts.  Also can represent an invalid,
 /// non-existant entity in some cases.  Many script functions pass a parent
@@ -100,9 +106,10 @@ use sulis_module::{
 /// Returns the ID of the faction that this entity currently belongs to
 ///
 /// # `set_faction(faction: String)`
-/// Sets this entity to the specified `faction`.  Valid factions are currently
-/// `Hostile`, `Neutral`, or `Friendly`.  Hostiles will attack the player and
-/// friendlies on sight, but will not engage neutrals.
+/// Sets this entity to the specified `faction`.  Valid factions are the built in
+/// `Hostile`, `Neutral`, and `Friendly`, as well as any factions defined by the
+/// module.  Entities will attack those whose faction they are hostile to on sight.
+/// See `game:faction_relationship`.
 ///
 /// # `set_flag(flag: String, value: String (Optional))`
 /// Sets a `flag` to be stored on this entity.  This value will persist as part of the
@@ -143,6 +150,15 @@ use sulis_module::{
 /// Returns true if this entity is a member of the player's party (or if it is the player),
 /// false otherwise.
 ///
+/// # `party_ai_mode() -> String`
+/// Returns the AI mode of this party member, one of `Aggressive`, `Defensive`,
+/// `Support`, or `Passive`, or nil if it is controlled by the player.
+///
+/// # `set_party_ai_mode(mode: String (Optional))`
+/// Sets the AI mode of this party member, placing it under AI control in combat.
+/// If `mode` is not specified, control is returned to the player.  Has no effect
+/// on entities outside the party.
+///
 /// # `use_ability(ability: ScriptAbility, allow_invalid: Bool (Optional)) -> Bool`
 /// The parent entity attempts to use the `ability`.  Returns true if the ability use was
 /// successful, false if it was not.  After activating, the script will often need to handle
@@ -281,9 +297,24 @@ use sulis_module::{
 /// Returns whether this entity is close enough to attack the target with
 /// its current weapon.
 ///
+/// # `attack_distance_to(target: ScriptEntity) -> Float`
+/// Returns the maximum distance at which this entity can attack the `target`
+/// with its current weapon, including the bonus reach of ranged attacks from
+/// higher ground.
+///
+/// # `elevation_accuracy_bonus(target: ScriptEntity) -> Int`
+/// Returns the accuracy bonus this entity has when attacking the `target`
+/// due to the difference in elevation between them.  This is negative when
+/// attacking from lower ground.
+///
 /// # `has_visibility(target: ScriptEntity) -> Bool`
 /// Returns true if this entity can see the `target`, false otherwise.
 ///
+/// # `cover_of(target: ScriptEntity) -> String`
+/// Returns the cover that `target` has against attacks from this entity, based
+/// on how much of the `target` is hidden by walls, props, and other creatures.
+/// One of `none`, `partial`, or `heavy`.
+///
 /// # `can_move() -> Bool`
 /// Returns true if this entity can move at all (even 1 square), false otherwise.
 ///
@@ -297,6 +328,12 @@ use sulis_module::{
 /// entities stats vs the defender. Returns the hit type, one of crit, hit,
 /// graze, or miss.
 ///
+/// # `roll_save(kind: String, dc: Int) -> ScriptSaveRoll`
+/// Immediately rolls a saving throw for this entity against the `dc`.  The
+/// `kind` is one of `fortitude`, `reflex`, or `will`, and determines which of
+/// this entity's defenses is added to the roll.  The result is shown as
+/// feedback text over this entity and recorded in the combat log.
+///
 /// # 'anim_weapon_attack(target: ScriptEntity, callback: CallbackData (Optional),
 /// use_ap: Bool (Optional))`
 /// Attempts to perform a standard weapon attack against the `target`.  The attack
@@ -404,7 +441,9 @@ use sulis_module::{
 ///
 /// # `stats() -> Table`
 /// Creates and returns a stats table for this entity.  This includes all stats shown on the
-/// character sheet.
+/// character sheet, as well as the `elevation` of the ground this entity is standing on.
+/// `load` is the weight carried, including this entity's share of the party stash, and
+/// `encumbrance` is the name of the encumbrance level, if the load exceeds `carry_capacity`.
 ///
 /// # `inventory() -> ScriptInventory`
 /// Returns a `ScriptInventory` object representing this entity's inventory.
@@ -454,6 +493,63 @@ use sulis_module::{
 /// # `is_threatened_by(target: ScriptEntity) -> Bool`
 /// Returns true if this entity is threatened by the speciied target with its
 /// melee weapon, false otherwise
+///
+/// # `provoke_reactions()`
+/// Provokes a reaction from each hostile currently threatening this entity,
+/// as when it moves out of a threatened square.  Each hostile with a reaction
+/// remaining this round fires its `OnReaction` callbacks and makes a free
+/// weapon attack against this entity.  Does nothing outside of combat or if
+/// reactions are disabled in the module rules.
+///
+/// # `reactions_remaining() -> Int`
+/// Returns the number of reactions this entity may still take this round
+///
+/// # `use_reaction() -> Bool`
+/// Uses one of this entity's reactions for the round, for abilities that grant
+/// special reactions.  Returns false if no reactions are remaining, true otherwise.
+///
+/// # `detection_margin(observer: ScriptEntity) -> Int`
+/// Returns the margin by which this entity remains hidden from the `observer`,
+/// combining this entity's stealth and concealment with the light, cover, and
+/// distance between them, less the observer's perception.  This entity is
+/// detected by the observer if the margin is negative.
+///
+/// # `check_detection() -> Bool`
+/// Checks whether this entity, if hidden, is detected by any hostile that can
+/// see it.  If so, returns true and the detection is resolved on the next
+/// update, firing this entity's `OnDetected` callbacks and removing the
+/// effects that hide it.  Hidden entities are also checked automatically
+/// each round and each time they move.
+///
+/// # `search()`
+/// This entity spends action points searching, gaining a bonus to its
+/// perception for one round and checking each hidden entity hostile to it
+/// for detection.  The cost and bonus are set in the `stealth` rules.
+///
+/// # `has_schedule() -> Bool`
+/// Returns true if this entity follows a schedule outside of combat.
+///
+/// # `set_schedule(waypoints: Table, route: String (Optional))`
+/// Replaces the schedule of this entity with one walking between the
+/// `waypoints` at all hours.  Each waypoint is a table of the form
+/// `{ x: x_coord, y: y_coord, wait: rounds (Optional) }`.  The `route` is one
+/// of `Loop` (the default), `BackAndForth`, or `Once`.  The current location
+/// of the entity becomes its home.  Party members do not follow schedules.
+///
+/// # `clear_schedule()`
+/// Removes the schedule of this entity, leaving it where it is.
+///
+/// # `schedule_dest() -> Table`
+/// Returns the location the schedule of this entity currently leads to, as a
+/// table of the form `{ x: x_coord, y: y_coord }`, or nil if it has no
+/// schedule.  This is the home of the entity while no entry applies.
+///
+/// # `hold_schedule(rounds: Int)`
+/// Stops this entity where it is, and holds its schedule for the specified
+/// number of `rounds`.
+///
+/// # `release_schedule()`
+/// Releases a hold on the schedule of this entity.
 #[derive(Clone, Debug)]
 pub struct ScriptEntity {
     pub index: Option<usize>,
@@ -763,6 +859,19 @@ impl UserData for ScriptEntity {
             Ok(is_member)
         });
 
+        methods.add_method("party_ai_mode", |_, entity, ()| {
+            let entity = entity.try_unwrap()?;
+            let mode = entity.borrow().party_ai_mode();
+            Ok(mode.map(|mode| format!("{:?}", mode)))
+        });
+
+        methods.add_method("set_party_ai_mode", |_, entity, mode: Option<String>| {
+            let entity = entity.try_unwrap()?;
+            let mode = mode.map(|mode| PartyAIMode::unwrap_from_str(&mode));
+            entity.borrow_mut().set_party_ai_mode(mode);
+            Ok(())
+        });
+
         methods.add_method(
             "use_ability",
             |_, entity, (ability, allow_invalid): (ScriptAbility, Option<bool>)| {
@@ -1063,11 +1172,30 @@ impl UserData for ScriptEntity {
             |_, entity, target: ScriptEntity| {
                 let parent = entity.try_unwrap()?;
                 let target = target.try_unwrap()?;
-                let result = is_within_attack_dist(&*parent.borrow(), &*target.borrow());
+                let result = is_within_attack_dist(&parent.borrow(), &target.borrow());
                 Ok(result)
             },
         );
 
+        methods.add_method("attack_distance_to", |_, entity, target: ScriptEntity| {
+            let parent = entity.try_unwrap()?;
+            let target = target.try_unwrap()?;
+            let dist = parent.borrow().attack_distance_to(&target.borrow());
+            Ok(dist)
+        });
+
+        methods.add_method(
+            "elevation_accuracy_bonus",
+            |_, entity, target: ScriptEntity| {
+                let parent = entity.try_unwrap()?;
+                let target = target.try_unwrap()?;
+                let bonus = Module::rules()
+                    .elevation
+                    .accuracy_bonus(parent.borrow().elevation(), target.borrow().elevation());
+                Ok(bonus)
+            },
+        );
+
         methods.add_method("is_within_touch_dist", |_, entity, target: ScriptEntity| {
             let parent = entity.try_unwrap()?;
             let target = target.try_unwrap()?;
@@ -1084,6 +1212,15 @@ impl UserData for ScriptEntity {
             Ok(result)
         });
 
+        methods.add_method("cover_of", |_, entity, target: ScriptEntity| {
+            let parent = entity.try_unwrap()?;
+            let target = target.try_unwrap()?;
+            let area_state = GameState::area_state();
+            let area_state = area_state.borrow();
+            let cover = area_state.cover(&parent.borrow(), &target.borrow());
+            Ok(cover.to_str())
+        });
+
         methods.add_method("can_move", |_, entity, ()| {
             let parent = entity.try_unwrap()?;
             let result = parent.borrow().can_move();
@@ -1156,6 +1293,28 @@ impl UserData for ScriptEntity {
             Ok(hit_kind)
         });
 
+        methods.add_method("roll_save", |_, entity, (kind, dc): (String, i32)| {
+            let parent = entity.try_unwrap()?;
+            let kind = match SaveKind::from_str(&kind) {
+                Err(e) => {
+                    return Err(rlua::Error::FromLuaConversionError {
+                        from: "String",
+                        to: "SaveKind",
+                        message: Some(format!("{}", e)),
+                    });
+                }
+                Ok(kind) => kind,
+            };
+
+            let roll = entity_attack_handler::saving_throw(&parent, kind, dc);
+
+            let area_state = GameState::area_state();
+            let feedback =
+                AreaFeedbackText::with_save(&parent.borrow(), &area_state.borrow(), roll.result);
+            area_state.borrow_mut().add_feedback_text(feedback);
+            Ok(ScriptSaveRoll { roll })
+        });
+
         methods.add_method("anim_weapon_attack", |_, entity, (target, callback, use_ap):
                            (ScriptEntity, Option<CallbackData>, Option<bool>)| {
             entity.check_not_equal(&target)?;
@@ -1281,7 +1440,7 @@ impl UserData for ScriptEntity {
 
                 let min_damage = min_damage as u32;
                 let max_damage = max_damage as u32;
-                let damage = {
+                let rolls = {
                     let parent = &parent.borrow().actor.stats;
                     let attack = Attack::special(
                         parent,
@@ -1292,8 +1451,22 @@ impl UserData for ScriptEntity {
                         AttackKind::Dummy,
                     );
                     let damage = &attack.damage;
-                    rules.roll_damage(damage, &parent.armor, &parent.resistance, 1.0)
+                    GameState::with_rand(|rand| {
+                        rules.roll_damage_details(
+                            damage,
+                            &parent.armor,
+                            &parent.resistance,
+                            1.0,
+                            rand,
+                        )
+                    })
                 };
+                let damage: Vec<_> = rolls
+                    .iter()
+                    .filter(|roll| roll.amount > 0)
+                    .map(|roll| (roll.kind, roll.amount))
+                    .collect();
+                combat_log::record_damage(&attacker.borrow(), &parent.borrow(), rolls);
 
                 if !damage.is_empty() {
                     EntityState::remove_hp(&parent, &attacker, HitKind::Hit, damage.clone());
@@ -1627,11 +1800,166 @@ impl UserData for ScriptEntity {
             let target = target.index.unwrap_or(std::usize::MAX);
             Ok(entity.actor.p_stats().is_threatened_by(target))
         });
+
+        methods.add_method("provoke_reactions", |_, entity, ()| {
+            let entity = entity.try_unwrap()?;
+            let entity = entity.borrow();
+            let mgr = GameState::turn_manager();
+            reaction_handler::provoke(
+                &mut mgr.borrow_mut(),
+                entity.index(),
+                &entity.actor.threatened_by(),
+                ReactionKind::Script,
+            );
+            Ok(())
+        });
+
+        methods.add_method("reactions_remaining", |_, entity, ()| {
+            let entity = entity.try_unwrap()?;
+            let entity = entity.borrow();
+            Ok(entity.actor.reactions_remaining())
+        });
+
+        methods.add_method("use_reaction", |_, entity, ()| {
+            let entity = entity.try_unwrap()?;
+            let result = entity.borrow_mut().actor.use_reaction();
+            Ok(result)
+        });
+
+        methods.add_method("detection_margin", |_, entity, observer: ScriptEntity| {
+            let entity = entity.try_unwrap()?;
+            let observer = observer.try_unwrap()?;
+            let entity = entity.borrow();
+            let area_id = &entity.location.area_id;
+            let area = GameState::get_area_state(area_id).ok_or_else(|| {
+                rlua::Error::ToLuaConversionError {
+                    from: "ScriptEntity",
+                    to: "AreaState",
+                    message: Some(format!("The area '{}' is not loaded.", area_id)),
+                }
+            })?;
+            let time = GameState::turn_manager().borrow().current_time();
+
+            let margin =
+                stealth::detection_margin(&area.borrow(), time, &entity, &observer.borrow());
+            Ok(margin)
+        });
+
+        methods.add_method("check_detection", |_, entity, ()| {
+            let entity = entity.try_unwrap()?;
+            let mgr = GameState::turn_manager();
+            let area = GameState::area_state();
+
+            let detected =
+                stealth::find_observer(&mgr.borrow(), &area.borrow(), &entity.borrow()).is_some();
+            if detected {
+                mgr.borrow_mut()
+                    .queue_stealth_check(entity.borrow().index());
+            }
+            Ok(detected)
+        });
+
+        methods.add_method("search", |_, entity, ()| {
+            let entity = entity.try_unwrap()?;
+            stealth::search(&entity);
+            Ok(())
+        });
+
+        methods.add_method("has_schedule", |_, entity, ()| {
+            let entity = entity.try_unwrap()?;
+            let has_schedule = entity.borrow().schedule().is_some();
+            Ok(has_schedule)
+        });
+
+        methods.add_method(
+            "set_schedule",
+            |_, entity, (points, route): (Vec<HashMap<String, i32>>, Option<String>)| {
+                let entity = entity.try_unwrap()?;
+                if entity.borrow().is_party_member() {
+                    warn!("Unable to set the schedule of a party member");
+                    return Ok(());
+                }
+
+                let mut waypoints = Vec::new();
+                for point in points {
+                    let wait = point.get("wait").copied().unwrap_or(0).max(0) as u32;
+                    let (x, y) = unwrap_point(point)?;
+                    waypoints.push(Waypoint {
+                        location: Point::new(x, y),
+                        wait,
+                    });
+                }
+
+                let route = route
+                    .map(|route| ScheduleRoute::unwrap_from_str(&route))
+                    .unwrap_or_default();
+                let entry = ScheduleEntry {
+                    hours: None,
+                    waypoints,
+                    route,
+                };
+
+                let mut entity = entity.borrow_mut();
+                let home = entity.location.to_point();
+                entity.set_schedule(Some(ScheduleState::new(home, vec![entry])));
+                Ok(())
+            },
+        );
+
+        methods.add_method("clear_schedule", |_, entity, ()| {
+            let entity = entity.try_unwrap()?;
+            entity.borrow_mut().set_schedule(None);
+            Ok(())
+        });
+
+        methods.add_method("schedule_dest", |lua, entity, ()| {
+            let entity = entity.try_unwrap()?;
+            let entity = entity.borrow();
+            let dest = match entity.schedule() {
+                None => return Ok(None),
+                Some(schedule) => schedule.destination(),
+            };
+
+            let table = lua.create_table()?;
+            table.set("x", dest.x)?;
+            table.set("y", dest.y)?;
+            Ok(Some(table))
+        });
+
+        methods.add_method("hold_schedule", |_, entity, rounds: u32| {
+            let entity = entity.try_unwrap()?;
+            schedule::hold(&entity, rounds);
+            Ok(())
+        });
+
+        methods.add_method("release_schedule", |_, entity, ()| {
+            let entity = entity.try_unwrap()?;
+            if let Some(schedule) = entity.borrow_mut().schedule_mut() {
+                schedule.release();
+            }
+            Ok(())
+        });
     }
 }
 
 #[allow(clippy::unnecessary_wraps)] // this must return a result to be added as a method in the LUA context
 fn move_towards_dest(parent: Rc<RefCell<EntityState>>, dest: Destination) -> Result<bool> {
+    Ok(move_with_ai_group(&parent, dest))
+}
+
+/// Moves the `parent` towards `dest`, passing through the friendly members of
+/// its AI group.  Returns true if a path was found.
+pub(crate) fn move_with_ai_group(parent: &Rc<RefCell<EntityState>>, dest: Destination) -> bool {
+    // party members under AI control move through each other, the same as
+    // when controlled by the player
+    if parent.borrow().is_party_member() {
+        let to_ignore: Vec<_> = GameState::party()
+            .iter()
+            .map(|e| e.borrow().index())
+            .collect();
+        return GameState::move_towards_dest(parent, &to_ignore, dest, None);
+    }
+
     let mgr = GameState::turn_manager();
     let area = GameState::get_area_state(&parent.borrow().location.area_id).unwrap();
     let mut to_ignore = vec![parent.borrow().index()];
@@ -1645,12 +1973,7 @@ fn move_towards_dest(parent: Rc<RefCell<EntityState>>, dest: Destination) -> Res
         }
     }
 
-    Ok(GameState::move_towards_dest(
-            &parent,
-            &to_ignore,
-            dest,
-            None,
-    ))
+    GameState::move_towards_dest(parent, &to_ignore, dest, None)
 }
 
 pub fn unwrap_point(point: HashMap<String, i32>) -> Result<(i32, i32)> {
@@ -1694,6 +2017,7 @@ fn create_stats_table<'a>(
     stats.set("current_hp", parent.actor.hp())?;
     stats.set("current_ap", parent.actor.ap())?;
     stats.set("current_xp", parent.actor.xp())?;
+    stats.set("elevation", parent.elevation())?;
 
     stats.set("strength", src.attributes.strength)?;
     stats.set("dexterity", src.attributes.dexterity)?;
@@ -1745,6 +2069,7 @@ fn create_stats_table<'a>(
 
     stats.set("level", parent.actor.actor.total_level)?;
     stats.set("caster_level", src.caster_level)?;
+    stats.set("reactions", src.reactions)?;
     stats.set("bonus_reach", src.bonus_reach)?;
     stats.set("bonus_range", src.bonus_range)?;
     stats.set("max_hp", src.max_hp)?;
@@ -1773,6 +2098,12 @@ fn create_stats_table<'a>(
     stats.set("movement_rate", src.movement_rate)?;
     stats.set("move_anim_rate", src.move_anim_rate)?;
     stats.set("attack_cost", src.attack_cost)?;
+    stats.set("carry_capacity", src.carry_capacity)?;
+    stats.set("stealth", src.stealth)?;
+    stats.set("load", src.load)?;
+    if let Some(encumbrance) = &src.encumbrance {
+        stats.set("encumbrance", encumbrance.to_string())?;
+    }
 
     stats.set("is_hidden", src.hidden)?;
     stats.set("is_abilities_disabled", src.abilities_disabled)?;
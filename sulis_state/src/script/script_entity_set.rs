@@ -25,7 +25,7 @@ use crate::script::{Result, ScriptActiveSurface, ScriptEntity};
 use crate::{
     is_threat, is_within, is_within_attack_dist, is_within_touch_dist, EntityState, GameState,
 };
-use sulis_core::util::{gen_rand, invalid_data_error};
+use sulis_core::util::invalid_data_error;
 use sulis_module::Faction;
 
 /// Represents a set of ScriptEntities, which can be created from a variety of
@@ -249,7 +249,7 @@ impl UserData for ScriptEntitySet {
                 .affected_points
                 .iter()
                 .filter_map(|p| {
-                    let roll = gen_rand(0.0, 1.0);
+                    let roll = GameState::with_rand(|rand| rand.gen(0.0, 1.0));
                     if roll > frac {
                         None
                     } else {
@@ -397,7 +397,7 @@ fn hostile_to(_lua: Context, set: &ScriptEntitySet, faction: String) -> Result<S
         Some(faction) => faction,
     };
     filter_entities(set, (), &|_, entity, _| {
-        faction.is_hostile(entity.borrow().actor.faction())
+        GameState::is_hostile_faction(&faction, entity.borrow().actor.faction())
     })
 }
 
@@ -415,7 +415,7 @@ fn friendly_to(_lua: Context, set: &ScriptEntitySet, faction: String) -> Result<
     };
 
     filter_entities(set, (), &|_, entity, _| {
-        faction.is_friendly(entity.borrow().actor.faction())
+        GameState::is_friendly_faction(&faction, entity.borrow().actor.faction())
     })
 }
 
@@ -17,10 +17,12 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use rlua::{self, UserData, UserDataMethods};
+use rlua::{self, Context, UserData, UserDataMethods};
 
+use crate::ai_trace::{self, AITraceStep, AITurnTrace, TracedCandidate, TracedEntity};
+use crate::autosave::{self, AutosaveTrigger};
 use crate::script::*;
-use crate::{animation::Anim, AreaState, EntityState, GameState, Location};
+use crate::{animation::Anim, AIState, AreaState, EntityState, GameState, Location};
 use sulis_core::{config::Config};
 use sulis_module::on_trigger::{self, QuestEntryState};
 use sulis_module::{Faction, ItemState, Module, OnTrigger, Time};
@@ -132,6 +134,36 @@ use sulis_module::{Faction, ItemState, Module, OnTrigger, Time};
 /// is not needed when scripts cause movement, as it is called automatically
 /// in those cases.  The entity should be the one whose state has changed.
 ///
+/// # `ai_group_blackboard(entity: ScriptEntity) -> ScriptAIBlackboard`
+/// Returns the blackboard shared by the AI group of the specified `entity`,
+/// or nil if the entity is not part of an AI group.  See `ScriptAIBlackboard`.
+///
+/// # `ai_trace() -> Table`
+/// Returns the trace of the most recent turn taken by the AI, or nil if there
+/// is none.  The table has the `entity` field, the unique ID of the entity,
+/// `template`, the ID of its AI template, `end`, the reason the turn ended or
+/// nil if it is still in progress, and `steps`, a list of the decisions made.
+/// Each step has a `kind`, one of `Action`, `Wait`, `Script`, `State`, `Plan`,
+/// `Failed`, `Squares`, or `Note`.  `State` steps have the `state` returned at
+/// the end of the action, one of `Run`, `Wait`, or `End`, and the wait `time`.
+/// `Plan` steps have a list of `candidates`, each with `action`, `target`,
+/// `ap`, `squares`, and `score`, and `chosen`, the position of the chosen
+/// candidate in the list.  This is the data shown in the AI trace window.
+///
+/// # `ai_trace_candidate(action: String, target: ScriptEntity, score: Float, ap: Int (Optional))`
+/// Records an action considered by an AI script in the trace of the current
+/// turn.  `action` is a name such as `Attack` or the name of an ability,
+/// `target` the entity the action would be performed on, and `score` how
+/// desirable the action is, higher being better.  `ap` is the AP cost of the
+/// action, or 0 if not specified.  The candidates of each action are shown
+/// together in the AI trace window.  Does nothing when no AI turn is in
+/// progress.
+///
+/// # `ai_trace_choose(action: String, target: ScriptEntity) -> Bool`
+/// Marks the candidate recorded with `ai_trace_candidate` for the current
+/// action with the specified `action` and `target` as the one chosen.  Returns
+/// false if there is no such candidate.
+///
 /// # `fade_out_in()`
 /// Causes the main view to fade out, then back in again.  This duration of the
 /// fades is defined in the theme for the `WindowFade` widget.
@@ -139,7 +171,7 @@ use sulis_module::{Faction, ItemState, Module, OnTrigger, Time};
 /// # `init_party_day()`
 /// Starts a new day for the player character and party.  This resets all skill
 /// uses and sets maximum hit points.  This is normally used in a script when the
-/// party rests.
+/// party rests, and requests an autosave.
 ///
 /// # `create_menu_selection(value: String)`
 /// Creates a ScriptMenuSelection object with the specified value.  Useful to
@@ -181,6 +213,14 @@ use sulis_module::{Faction, ItemState, Module, OnTrigger, Time};
 /// # `atan2(x: Float, y: Float) -> Float`
 /// Computes the four quadrant arctan function.  See `f32::atan2`
 ///
+/// # `gen_rand(min: Float, max: Float) -> Float`
+/// Returns a random number between `min` (inclusive) and `max` (exclusive).  The
+/// number is drawn from the campaign random stream, which is stored in save files so
+/// that results are reproducible.  The standard Lua `math.random` also uses this stream.
+/// Rolls that only affect visuals or flavor, such as particle positions, should use
+/// `math.cosmetic_random`, which takes the same arguments as `math.random` but draws
+/// from a separate stream, so that they do not change later gameplay rolls.
+///
 /// # `block_ui(time: Float)`
 /// Locks the UI so the player cannot take any additional in game actions (such as movement
 /// or combat) for the specified `time` number of seconds.
@@ -325,6 +365,41 @@ use sulis_module::{Faction, ItemState, Module, OnTrigger, Time};
 /// Adds the specified number of coins to the party.  Note that this value is divided by
 /// the item_value_display_factor to get the displayed coinage.
 ///
+/// # `reputation(faction: String) -> Int`
+/// Returns the party's current reputation with the specified faction.  Reputation is
+/// added to the relationship between the party and the faction, so a high reputation
+/// makes the faction friendly, and a low reputation makes it hostile.
+///
+/// # `set_reputation(faction: String, value: Int)`
+/// Sets the party's reputation with the specified faction.  Members of the faction
+/// who become hostile to the party will attack if they can see it.
+///
+/// # `add_reputation(faction: String, amount: Int)`
+/// Adds the specified amount, which may be negative, to the party's reputation
+/// with the specified faction.
+///
+/// # `faction_relationship(faction: String, other: String) -> Int`
+/// Returns the relationship between the two specified factions, from -100 to 100,
+/// including the party's reputation if one of the factions is `Friendly`.  Factions
+/// are hostile at -50 or below, and friendly at 50 or above.
+///
+/// # `difficulty() -> Table`
+/// Returns a table describing the difficulty chosen by the player, with the `id`,
+/// `name`, `enemy_hp_multiplier`, `enemy_damage_multiplier`,
+/// `enemy_accuracy_multiplier`, `xp_multiplier`, and `party_injuries` fields.
+/// `party_injuries` is a boolean indicating whether the campaign should injure
+/// party members who fall in combat.
+///
+/// # `set_difficulty(id: String)`
+/// Sets the difficulty to the one with the specified ID, defined in the module rules.
+///
+/// # `is_auto_combat() -> Bool`
+/// Returns true if auto-combat is on, placing all party members without an AI
+/// mode of their own under AI control in combat.
+///
+/// # `set_auto_combat(auto_combat: Bool)`
+/// Turns auto-combat on or off.
+///
 /// # `find_party_item(id: String, adjective: String (Optional, up to 3)) -> ScriptStashItem`
 /// Returns a ScriptStashItem representing the first item in the party stash found
 /// matching the specified ID and all specified `adjective`s.  If no such item is found,
@@ -486,6 +561,41 @@ impl UserData for ScriptInterface {
             Ok(())
         });
 
+        methods.add_method("ai_group_blackboard", |_, _, entity: ScriptEntity| {
+            let entity = entity.try_unwrap()?;
+            let group = entity.borrow().ai_group();
+            Ok(group.map(ScriptAIBlackboard::new))
+        });
+
+        methods.add_method("ai_trace", |lua, _, ()| match ai_trace::latest() {
+            None => Ok(None),
+            Some(turn) => Ok(Some(create_ai_trace_table(lua, &turn)?)),
+        });
+
+        methods.add_method(
+            "ai_trace_candidate",
+            |_, _, (action, target, score, ap): (String, ScriptEntity, f32, Option<u32>)| {
+                let target = target.try_unwrap()?;
+                ai_trace::record_candidate(TracedCandidate {
+                    action,
+                    target: TracedEntity::new(&target.borrow()),
+                    ap: ap.unwrap_or(0),
+                    squares: 0,
+                    score,
+                });
+                Ok(())
+            },
+        );
+
+        methods.add_method(
+            "ai_trace_choose",
+            |_, _, (action, target): (String, ScriptEntity)| {
+                let target = target.try_unwrap()?;
+                let index = target.borrow().index();
+                Ok(ai_trace::choose_candidate(&action, index))
+            },
+        );
+
         methods.add_method("fade_out_in", |_, _, ()| {
             let pc = GameState::player();
             let cb = OnTrigger::FadeOutIn;
@@ -497,6 +607,7 @@ impl UserData for ScriptInterface {
             for member in GameState::party() {
                 member.borrow_mut().actor.init_day();
             }
+            autosave::request(AutosaveTrigger::Rest);
             Ok(())
         });
 
@@ -561,6 +672,13 @@ impl UserData for ScriptInterface {
 
         methods.add_method("atan2", |_, _, (x, y): (f32, f32)| Ok(y.atan2(x)));
 
+        methods.add_method("gen_rand", |_, _, (min, max): (f32, f32)| {
+            if max <= min {
+                return Ok(min);
+            }
+            Ok(GameState::with_rand(|rand| rand.gen(min, max)))
+        });
+
         methods.add_method("block_ui", |_, _, time: f32| {
             let pc = GameState::player();
             let cb = OnTrigger::BlockUI((time * 1000.0) as u32);
@@ -978,6 +1096,65 @@ impl UserData for ScriptInterface {
             Ok(())
         });
 
+        methods.add_method("reputation", |_, _, faction: String| {
+            let faction = parse_faction(&faction)?;
+            Ok(GameState::reputation(&faction))
+        });
+
+        methods.add_method("set_reputation", |_, _, (faction, value): (String, i32)| {
+            let faction = parse_faction(&faction)?;
+            GameState::set_reputation(&faction, value);
+            Ok(())
+        });
+
+        methods.add_method("add_reputation", |_, _, (faction, amount): (String, i32)| {
+            let faction = parse_faction(&faction)?;
+            GameState::add_reputation(&faction, amount);
+            Ok(())
+        });
+
+        methods.add_method(
+            "faction_relationship",
+            |_, _, (faction, other): (String, String)| {
+                let faction = parse_faction(&faction)?;
+                let other = parse_faction(&other)?;
+                Ok(GameState::faction_relationship(&faction, &other))
+            },
+        );
+
+        methods.add_method("difficulty", |lua, _, ()| {
+            let difficulty = GameState::difficulty();
+            let table = lua.create_table()?;
+            table.set("id", difficulty.id)?;
+            table.set("name", difficulty.name)?;
+            table.set("enemy_hp_multiplier", difficulty.enemy_hp_multiplier)?;
+            table.set("enemy_damage_multiplier", difficulty.enemy_damage_multiplier)?;
+            table.set("enemy_accuracy_multiplier", difficulty.enemy_accuracy_multiplier)?;
+            table.set("xp_multiplier", difficulty.xp_multiplier)?;
+            table.set("party_injuries", difficulty.party_injuries)?;
+            Ok(table)
+        });
+
+        methods.add_method("set_difficulty", |_, _, id: String| {
+            let rules = Module::rules();
+            let difficulty = rules
+                .difficulty(&id)
+                .ok_or(rlua::Error::FromLuaConversionError {
+                    from: "String",
+                    to: "Difficulty",
+                    message: Some(format!("The difficulty '{}' does not exist.", id)),
+                })?;
+            GameState::set_difficulty(difficulty.clone());
+            Ok(())
+        });
+
+        methods.add_method("is_auto_combat", |_, _, ()| Ok(GameState::is_auto_combat()));
+
+        methods.add_method("set_auto_combat", |_, _, auto_combat: bool| {
+            GameState::set_auto_combat(auto_combat);
+            Ok(())
+        });
+
         methods.add_method("find_party_item", |_, _, (id, adj1, adj2, adj3):
                            (String, Option<String>, Option<String>, Option<String>)| {
 
@@ -1081,6 +1258,100 @@ fn get_area(id: Option<String>) -> Result<Rc<RefCell<AreaState>>> {
     }
 }
 
+fn create_ai_trace_table<'a>(lua: Context<'a>, turn: &AITurnTrace) -> Result<rlua::Table<'a>> {
+    use AITraceStep::*;
+
+    let table = lua.create_table()?;
+    table.set("entity", turn.entity.id.to_string())?;
+    table.set("template", turn.template.clone())?;
+    table.set("end", turn.end.map(|end| format!("{:?}", end)))?;
+
+    let steps = lua.create_table()?;
+    for (index, step) in turn.steps.iter().enumerate() {
+        let entry = lua.create_table()?;
+        match step {
+            Action { count } => {
+                entry.set("kind", "Action")?;
+                entry.set("count", *count)?;
+            }
+            Wait { time, total } => {
+                entry.set("kind", "Wait")?;
+                entry.set("time", *time)?;
+                entry.set("total", *total)?;
+            }
+            Script { func } => {
+                entry.set("kind", "Script")?;
+                entry.set("func", func.to_string())?;
+            }
+            State { state } => {
+                entry.set("kind", "State")?;
+                match state {
+                    AIState::Run => entry.set("state", "Run")?,
+                    AIState::Wait(time) => {
+                        entry.set("state", "Wait")?;
+                        entry.set("time", *time)?;
+                    }
+                    AIState::End => entry.set("state", "End")?,
+                }
+            }
+            Plan { candidates, chosen } => {
+                entry.set("kind", "Plan")?;
+                let list = lua.create_table()?;
+                for (index, candidate) in candidates.iter().enumerate() {
+                    let item = lua.create_table()?;
+                    item.set("action", candidate.action.to_string())?;
+                    item.set("target", candidate.target.id.to_string())?;
+                    item.set("ap", candidate.ap)?;
+                    item.set("squares", candidate.squares)?;
+                    item.set("score", candidate.score)?;
+                    list.set(index + 1, item)?;
+                }
+                entry.set("candidates", list)?;
+                entry.set("chosen", chosen.map(|index| index + 1))?;
+            }
+            Failed { action, target } => {
+                entry.set("kind", "Failed")?;
+                entry.set("action", action.to_string())?;
+                entry.set("target", target.id.to_string())?;
+            }
+            Squares {
+                target,
+                squares,
+                chosen,
+            } => {
+                entry.set("kind", "Squares")?;
+                entry.set("target", target.id.to_string())?;
+                let list = lua.create_table()?;
+                for (index, square) in squares.iter().enumerate() {
+                    let item = lua.create_table()?;
+                    item.set("x", square.x)?;
+                    item.set("y", square.y)?;
+                    item.set("score", square.score)?;
+                    list.set(index + 1, item)?;
+                }
+                entry.set("squares", list)?;
+                entry.set("chosen", chosen.map(|index| index + 1))?;
+            }
+            Note { text } => {
+                entry.set("kind", "Note")?;
+                entry.set("text", text.to_string())?;
+            }
+        }
+        steps.set(index + 1, entry)?;
+    }
+    table.set("steps", steps)?;
+
+    Ok(table)
+}
+
+fn parse_faction(id: &str) -> Result<Faction> {
+    Faction::option_from_str(id).ok_or(rlua::Error::FromLuaConversionError {
+        from: "String",
+        to: "Faction",
+        message: Some(format!("The faction '{}' does not exist.", id)),
+    })
+}
+
 fn entities_with_ids(ids: Vec<String>) -> Vec<ScriptEntity> {
     let mut result = Vec::new();
 
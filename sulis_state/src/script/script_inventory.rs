@@ -43,7 +43,17 @@ use sulis_module::{ability::AIData, ItemKind, QuickSlot, Slot};
 /// errors if there is no item or the slot is invalid.  See `has_equipped`
 /// for valid slots.  The table `stats` includes `stats.name`, `stats.value`,
 /// `stats.weight`, `stats.kind`, and `stats.armor_kind` for armor or
-/// `stats.weapon_kind` for weapons.
+/// `stats.weapon_kind` for weapons.  Items that can wear out also include
+/// `stats.durability` and `stats.max_durability`.
+///
+/// # `wear_equipped(slot: String, amount: Int)`
+/// Reduces the durability of the item in the given slot by `amount`.  Items
+/// reduced to zero durability are broken and no longer grant any bonuses.
+/// Does nothing if durability is disabled by the module rules.
+///
+/// # `repair_all()`
+/// Restores all equipped and quick slot items of the parent to full
+/// durability.
 ///
 /// # `equip_item(item: ScriptStashItem)`
 /// Equips the given `item` from the stash into the appropriate inventory
@@ -155,9 +165,14 @@ impl UserData for ScriptInventory {
                 Some(item) => item,
             };
 
+            let stats = lua.create_table()?;
+            if let (Some(cur), Some(max)) = (item.durability, item.max_durability()) {
+                stats.set("durability", cur)?;
+                stats.set("max_durability", max)?;
+            }
+
             let item = &item.item;
 
-            let stats = lua.create_table()?;
             stats.set("name", item.name.to_string())?;
             stats.set("value", item.value)?;
             stats.set("weight", item.weight)?;
@@ -221,6 +236,29 @@ impl UserData for ScriptInventory {
             Ok(ScriptStashItem { index })
         });
 
+        methods.add_method("wear_equipped", |_, data, (slot, amount): (String, u32)| {
+            let slot = match Slot::from_str(&slot) {
+                Err(_) => {
+                    return Err(rlua::Error::FromLuaConversionError {
+                        from: "String",
+                        to: "Slot",
+                        message: Some(format!("Invalid slot '{}'", slot)),
+                    });
+                }
+                Ok(slot) => slot,
+            };
+
+            let parent = data.parent.try_unwrap()?;
+            parent.borrow_mut().actor.wear_item(slot, amount);
+            Ok(())
+        });
+
+        methods.add_method("repair_all", |_, data, ()| {
+            let parent = data.parent.try_unwrap()?;
+            parent.borrow_mut().actor.repair_all();
+            Ok(())
+        });
+
         methods.add_method("has_equipped_weapon", |_, data, ()| {
             try_unwrap!(data => inv);
 
@@ -0,0 +1,1316 @@
+//  This file is part of Sulis, a turn based RPG written in Rust.
+//  Copyright 2018 Jared Stephen
+//
+//  Sulis is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Sulis is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Sulis.  If not, see <http://www.gnu.org/licenses/>
+
+//! Runs Lua test files against a game built without a window.  Each test file
+//! defines a `fixture` table, describing the area and the named actors to
+//! place in it, and any number of global functions with names starting with
+//! `test_`.  For each test, a new game is created from the fixture, and the
+//! test function is called with a `ScriptTestContext`.
+//!
+//! ```lua
+//! fixture = {
+//!   area = "farmhouse01",
+//!   actors = {
+//!     { name = "caster", id = "npc_jhilsara", x = 10, y = 13, party = true },
+//!     { name = "goblin", id = "goblin", x = 14, y = 13 },
+//!   },
+//! }
+//!
+//! function test_firebolt(t)
+//!   local caster = t:entity("caster")
+//!   local goblin = t:entity("goblin")
+//!   local hp = goblin:stats().current_hp
+//!
+//!   t:assert_true(caster:use_ability(caster:get_ability("firebolt")))
+//!   t:select_target(goblin)
+//!   t:wait_for_animations()
+//!   t:assert_true(goblin:stats().current_hp < hp, "goblin took damage")
+//! end
+//! ```
+//!
+//! The first actor with `party` set becomes the player character.  If `area`
+//! is not specified, the campaign starting area is used.  All other actors
+//! already in the area are removed.  An actor may set `ai` to the ID of an
+//! AI template to use in place of its own, and `encounter` to the index of an
+//! encounter in the area, to share an AI group with the other actors in that
+//! encounter.  The game is kept modal locked while tests run, so AI controlled
+//! entities never take their turns on their own, other than with `run_turn`.
+//! The fixture may also list optional `rules` which are disabled by default,
+//! `durability`, `encumbrance`, `reactions`, `cover` and `elevation`, to
+//! enable for its tests.  Once built, the AI of hostiles in view of the party
+//! is activated, starting combat, unless the fixture sets `activate_ai = false`.
+//!
+//! Most tests only need the player character in an open area, so the harness
+//! defines `farmhouse_fixture`, which builds a fixture in `farmhouse01` with
+//! the `caster` party member at 10,13, followed by any other `actors`:
+//!
+//! ```lua
+//! fixture = farmhouse_fixture {
+//!   rules = { "durability" },
+//!   actors = { { name = "goblin", id = "goblin", x = 14, y = 13 } },
+//! }
+//! ```
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::error::Error as StdError;
+use std::fmt::{self, Write};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Instant;
+
+use rlua::{self, Context, FromLua, UserData, UserDataMethods, Value};
+
+use crate::script::{script_cache, script_callback, Result, ScriptEntity, ScriptState};
+use crate::{combat_log, CombatLogEntry, GameState, Location, Replay, ReplayRecorder, SaveState};
+use sulis_core::config::Config;
+use sulis_core::serde_json;
+use sulis_core::util::{ActiveResources, Point};
+use sulis_module::{Actor, Module, Rules, Time};
+
+const TEST_PREFIX: &str = "test_";
+const FRAME_MILLIS: u32 = 16;
+const MAX_ANIMATION_MILLIS: u32 = 10_000;
+const MAX_TURNS_PER_ROUND: u32 = 1_000;
+const MAX_TURN_MILLIS: u32 = 60_000;
+
+/// The animation speed used for all tests, in place of the configured one,
+/// so that movement takes the same number of frames on every machine
+const ANIMATION_BASE_TIME_MILLIS: u32 = 50;
+
+/// Lua helpers defined for every test file, before the file is loaded
+const FIXTURE_HELPERS: &str = r#"
+function farmhouse_fixture(options)
+  options = options or {}
+  local actors = {
+    { name = "caster", id = "npc_jhilsara", x = 10, y = 13, party = true },
+  }
+  for _, actor in ipairs(options.actors or {}) do
+    table.insert(actors, actor)
+  end
+  return {
+    area = "farmhouse01",
+    rules = options.rules,
+    activate_ai = options.activate_ai,
+    actors = actors,
+  }
+end
+"#;
+
+thread_local! {
+    /// Whether the running test has been marked as expected to fail
+    static EXPECT_FAILURE: Cell<bool> = const { Cell::new(false) };
+
+    /// The replay being recorded by the running test, if any
+    static RECORDING: RefCell<Option<Recording>> = const { RefCell::new(None) };
+}
+
+/// A replay recorded by a test.  There is no UI in tests to reproduce the
+/// modal lock when the replay is played, so it is kept for each frame.
+struct Recording {
+    recorder: ReplayRecorder,
+    frames: Vec<RecordedFrame>,
+    actions: Vec<HarnessAction>,
+}
+
+/// The test harness state for a recorded frame, which the replay file does
+/// not hold
+struct RecordedFrame {
+    modal_locked: bool,
+
+    /// The actions taken directly by the harness before the frame
+    actions: Vec<HarnessAction>,
+}
+
+/// Changes to the game made by the harness outside of a game update, which
+/// must be repeated when playing a replay
+#[derive(Copy, Clone)]
+enum HarnessAction {
+    ActivateAI,
+    PassTurn,
+}
+
+impl HarnessAction {
+    fn perform(self) {
+        let mgr = GameState::turn_manager();
+        match self {
+            HarnessAction::ActivateAI => {
+                let area_state = GameState::area_state();
+                mgr.borrow_mut()
+                    .check_ai_activation_for_party(&mut area_state.borrow_mut());
+            }
+            HarnessAction::PassTurn => {
+                let cbs = mgr.borrow_mut().next();
+                script_callback::fire_round_elapsed(cbs);
+            }
+        }
+
+        RECORDING.with(|recording| {
+            if let Some(recording) = recording.borrow_mut().as_mut() {
+                recording.actions.push(self);
+            }
+        });
+    }
+}
+
+impl Recording {
+    fn record_frame(&mut self, millis: u32) {
+        if let Err(e) = self.recorder.record_frame(millis) {
+            warn!("Unable to record replay frame: {}", e);
+            return;
+        }
+        self.frames.push(RecordedFrame {
+            modal_locked: GameState::is_modal_locked(),
+            actions: std::mem::take(&mut self.actions),
+        });
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum TestOutcome {
+    Passed,
+
+    /// One of the test's assertions did not hold
+    Failed {
+        message: String,
+        details: String,
+    },
+
+    /// The test, or a game script run during the test, raised an error
+    Error {
+        message: String,
+        details: String,
+    },
+}
+
+impl TestOutcome {
+    fn from_error(error: &rlua::Error) -> TestOutcome {
+        let details = match error {
+            rlua::Error::CallbackError { traceback, .. } => traceback.to_string(),
+            _ => String::new(),
+        };
+
+        let cause = root_cause(error);
+        if let rlua::Error::ExternalError(e) = cause {
+            if let Some(failure) = e.downcast_ref::<AssertionFailure>() {
+                return TestOutcome::Failed {
+                    message: failure.0.to_string(),
+                    details,
+                };
+            }
+        }
+
+        TestOutcome::Error {
+            message: cause.to_string(),
+            details,
+        }
+    }
+
+    fn with_script_errors(self, errors: &[String]) -> TestOutcome {
+        if errors.is_empty() {
+            return self;
+        }
+
+        let errors = format!("Game script errors:\n{}", errors.join("\n"));
+        let append = |details: String| {
+            if details.is_empty() {
+                errors
+            } else {
+                format!("{}\n{}", details, errors)
+            }
+        };
+
+        match self {
+            // errors in game scripts fail the test even if all of its
+            // assertions held
+            TestOutcome::Passed => TestOutcome::Error {
+                message: "game script errors".to_string(),
+                details: append(String::new()),
+            },
+            TestOutcome::Failed { message, details } => TestOutcome::Failed {
+                message,
+                details: append(details),
+            },
+            TestOutcome::Error { message, details } => TestOutcome::Error {
+                message,
+                details: append(details),
+            },
+        }
+    }
+
+    /// Returns the outcome of a test which is expected to fail
+    fn expecting_failure(self) -> TestOutcome {
+        match self {
+            TestOutcome::Passed => TestOutcome::Failed {
+                message: "expected the test to fail".to_string(),
+                details: String::new(),
+            },
+            _ => TestOutcome::Passed,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScriptTestCase {
+    pub name: String,
+    pub millis: f64,
+    pub outcome: TestOutcome,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScriptTestSuite {
+    /// The path of the test file
+    pub name: String,
+    pub cases: Vec<ScriptTestCase>,
+}
+
+impl ScriptTestSuite {
+    pub fn failures(&self) -> usize {
+        self.cases
+            .iter()
+            .filter(|case| matches!(case.outcome, TestOutcome::Failed { .. }))
+            .count()
+    }
+
+    pub fn errors(&self) -> usize {
+        self.cases
+            .iter()
+            .filter(|case| matches!(case.outcome, TestOutcome::Error { .. }))
+            .count()
+    }
+
+    pub fn millis(&self) -> f64 {
+        self.cases.iter().map(|case| case.millis).sum()
+    }
+}
+
+/// The results of all test files that have been run
+#[derive(Debug, Clone, Default)]
+pub struct ScriptTestReport {
+    pub suites: Vec<ScriptTestSuite>,
+}
+
+impl ScriptTestReport {
+    pub fn tests(&self) -> usize {
+        self.suites.iter().map(|suite| suite.cases.len()).sum()
+    }
+
+    pub fn failures(&self) -> usize {
+        self.suites.iter().map(|suite| suite.failures()).sum()
+    }
+
+    pub fn errors(&self) -> usize {
+        self.suites.iter().map(|suite| suite.errors()).sum()
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failures() == 0 && self.errors() == 0
+    }
+
+    /// Runs each test in the specified file, adding the results to this
+    /// report.  The `Module` must already be loaded.  If the file cannot be
+    /// loaded, a single errored test named `load` is added.
+    pub fn run_file(&mut self, path: &Path) {
+        let mut suite = ScriptTestSuite {
+            name: path.to_string_lossy().to_string(),
+            cases: Vec::new(),
+        };
+
+        let config = Config::get_clone();
+        let mut test_config = config.clone();
+        test_config.display.animation_base_time_millis = ANIMATION_BASE_TIME_MILLIS;
+        Config::set(test_config);
+
+        script_cache::set_error_capture(true);
+        match test_names(path) {
+            Err(e) => suite.cases.push(ScriptTestCase {
+                name: "load".to_string(),
+                millis: 0.0,
+                outcome: TestOutcome::from_error(&e),
+            }),
+            Ok(names) => {
+                for name in names {
+                    suite.cases.push(run_test(path, name));
+                }
+            }
+        }
+        script_cache::set_error_capture(false);
+
+        Config::set(config);
+        Config::take_old_config(); // throw away the test config
+
+        self.suites.push(suite);
+    }
+
+    /// Writes this report in the JUnit XML format read by most build servers
+    pub fn write_junit_xml(&self, out: &mut dyn Write) -> fmt::Result {
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(
+            out,
+            "<testsuites name=\"sulis_script_test\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">",
+            self.tests(),
+            self.failures(),
+            self.errors(),
+            self.suites.iter().map(|s| s.millis()).sum::<f64>() / 1000.0
+        )?;
+
+        for suite in self.suites.iter() {
+            let name = escape(&suite.name);
+            writeln!(
+                out,
+                "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">",
+                name,
+                suite.cases.len(),
+                suite.failures(),
+                suite.errors(),
+                suite.millis() / 1000.0
+            )?;
+
+            for case in suite.cases.iter() {
+                write!(
+                    out,
+                    "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                    escape(&case.name),
+                    name,
+                    case.millis / 1000.0
+                )?;
+
+                let (tag, message, details) = match &case.outcome {
+                    TestOutcome::Passed => {
+                        writeln!(out, "/>")?;
+                        continue;
+                    }
+                    TestOutcome::Failed { message, details } => ("failure", message, details),
+                    TestOutcome::Error { message, details } => ("error", message, details),
+                };
+
+                writeln!(out, ">")?;
+                writeln!(
+                    out,
+                    "      <{} message=\"{}\">{}</{}>",
+                    tag,
+                    escape(message),
+                    escape(details),
+                    tag
+                )?;
+                writeln!(out, "    </testcase>")?;
+            }
+
+            writeln!(out, "  </testsuite>")?;
+        }
+
+        writeln!(out, "</testsuites>")
+    }
+}
+
+/// The context passed to each test function.
+///
+/// # `entity(name: String) -> ScriptEntity`
+/// Returns the fixture entity with the specified `name`.
+///
+/// # `select_target(target: ScriptEntity or Table)`
+/// Selects the specified target with the currently active targeter, as if
+/// the player had clicked on it.  The target may be an entity, or a table
+/// with `x` and `y` coordinates for targeters that select a location.
+/// Raises an error if there is no active targeter or the target is not valid.
+///
+/// # `update(millis: Int)`
+/// Runs the game for the specified number of milliseconds.
+///
+/// # `run_schedules(millis: Int)`
+/// Runs the game for the specified number of milliseconds, allowing entities
+/// outside the party to follow their schedules.  The game is otherwise held
+/// still between updates, as if a modal window was open.  The game is run in
+/// fixed frames at a fixed animation speed, so running schedules for the same
+/// time always leaves entities at the same positions.
+///
+/// # `wait_for_animations()`
+/// Runs the game until all blocking animations, such as attacks, have
+/// completed.
+///
+/// # `advance_rounds(rounds: Int)`
+/// Advances the game by the specified number of rounds.  In combat, this
+/// passes the turn to each entity in turn order, without that entity taking
+/// any actions.  Outside of combat, the game is run for the length of the
+/// rounds.
+///
+/// # `run_turn(entity: ScriptEntity)`
+/// Passes the turn to each entity in turn order until it is the turn of the
+/// specified `entity`, and then runs the game until its AI ends its turn.
+/// Combat must be active.
+///
+/// # `assert_eq(actual: Any, expected: Any, message: String (Optional))`
+/// Fails the test if `actual` is not equal to `expected`.  Integers and
+/// floats are compared by value.
+///
+/// # `assert_true(value: Bool, message: String (Optional))`
+/// Fails the test if `value` is not true.
+///
+/// # `assert_false(value: Bool, message: String (Optional))`
+/// Fails the test if `value` is true.
+///
+/// # `combat_log() -> Table`
+/// Returns the entries currently held in the combat log, oldest first.  Each
+/// entry is a table in the same form as in an exported combat log, such as
+/// `{ time = { day = 1, ... }, event = { Attack = { attacker = ... } } }`.
+///
+/// # `export_combat_log() -> Table`
+/// Exports the combat log to a temporary file, and returns the entries read
+/// back from that file, in the same form as `combat_log`.
+///
+/// # `save_and_load()`
+/// Saves the game, and then loads it again from the saved data.  Loading
+/// replaces all entities, so fixture entities should be found again with
+/// `game:entity_with_id` afterwards.
+///
+/// # `record_replay()`
+/// Begins recording a replay of the game from its current state.  Updates of
+/// the game, such as with `update` and `wait_for_animations`, are recorded,
+/// along with the turns passed by `advance_rounds` and `run_turn`.  Replays
+/// start from a save, and the game can't be saved during combat, so this is
+/// an error while combat is active.
+///
+/// # `activate_ai()`
+/// Activates the AI of hostile entities in view of the party, starting
+/// combat, as when the fixture is built.  This is only needed by fixtures
+/// that set `activate_ai = false`.
+///
+/// # `play_replay()`
+/// Stops recording the replay started with `record_replay`, loads the game
+/// state from the start of the recording, and runs the game for each
+/// recorded frame.  Loading replaces all entities, so fixture entities
+/// should be found again with `game:entity_with_id` afterwards.
+///
+/// # `expect_failure()`
+/// Marks the test as expected to fail.  The test then passes only if one of
+/// its assertions fails, or it or a game script run during it raises an
+/// error.  This is used to check the test harness itself.
+#[derive(Clone)]
+pub struct ScriptTestContext {
+    entities: HashMap<String, usize>,
+}
+
+impl UserData for ScriptTestContext {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("entity", |_, context, name: String| {
+            match context.entities.get(&name) {
+                None => Err(runtime_error(format!("No fixture entity named '{}'", name))),
+                Some(index) => Ok(ScriptEntity::new(*index)),
+            }
+        });
+        methods.add_method("select_target", |_, _, target: Value| select_target(target));
+        methods.add_method("update", |_, _, millis: u32| {
+            run_millis(millis);
+            Ok(())
+        });
+        methods.add_method("run_schedules", |_, _, millis: u32| {
+            // schedules are only followed while the game is not modal locked
+            GameState::set_modal_locked(false);
+            run_millis(millis);
+            GameState::set_modal_locked(true);
+            Ok(())
+        });
+        methods.add_method("wait_for_animations", |_, _, ()| wait_for_animations());
+        methods.add_method("advance_rounds", |_, _, rounds: u32| advance_rounds(rounds));
+        methods.add_method("run_turn", |_, _, entity: ScriptEntity| run_turn(entity));
+        methods.add_method("combat_log", |lua, _, ()| {
+            to_lua(lua, &combat_log::entries())
+        });
+        methods.add_method("export_combat_log", |lua, _, ()| {
+            to_lua(lua, &export_combat_log()?)
+        });
+        methods.add_method("save_and_load", |_, _, ()| save_and_load());
+        methods.add_method("activate_ai", |_, _, ()| {
+            HarnessAction::ActivateAI.perform();
+            step(0);
+            Ok(())
+        });
+        methods.add_method("record_replay", |_, _, ()| record_replay());
+        methods.add_method("play_replay", |_, _, ()| play_replay());
+        methods.add_method("expect_failure", |_, _, ()| {
+            EXPECT_FAILURE.with(|expect| expect.set(true));
+            Ok(())
+        });
+        methods.add_method(
+            "assert_eq",
+            |_, _, (actual, expected, message): (Value, Value, Option<String>)| {
+                if lua_eq(&actual, &expected) {
+                    return Ok(());
+                }
+                let text = format!(
+                    "expected {}, got {}",
+                    describe(&expected),
+                    describe(&actual)
+                );
+                Err(assertion_failure(message, text))
+            },
+        );
+        methods.add_method(
+            "assert_true",
+            |_, _, (value, message): (bool, Option<String>)| {
+                if value {
+                    return Ok(());
+                }
+                Err(assertion_failure(message, "expected true".to_string()))
+            },
+        );
+        methods.add_method(
+            "assert_false",
+            |_, _, (value, message): (bool, Option<String>)| {
+                if !value {
+                    return Ok(());
+                }
+                Err(assertion_failure(message, "expected false".to_string()))
+            },
+        );
+    }
+}
+
+#[derive(Debug)]
+struct AssertionFailure(String);
+
+impl fmt::Display for AssertionFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl StdError for AssertionFailure {}
+
+fn assertion_failure(message: Option<String>, text: String) -> rlua::Error {
+    let text = match message {
+        None => text,
+        Some(message) => format!("{}: {}", message, text),
+    };
+    rlua::Error::external(AssertionFailure(text))
+}
+
+struct FixtureActor {
+    name: String,
+    id: String,
+    x: i32,
+    y: i32,
+    party: bool,
+    ai: Option<String>,
+    encounter: Option<usize>,
+}
+
+impl<'lua> FromLua<'lua> for FixtureActor {
+    fn from_lua(value: Value<'lua>, _lua: Context<'lua>) -> Result<FixtureActor> {
+        let table = match value {
+            Value::Table(table) => table,
+            _ => return Err(table_expected(&value, "FixtureActor")),
+        };
+
+        let party: Option<bool> = table.get("party")?;
+        Ok(FixtureActor {
+            name: table.get("name")?,
+            id: table.get("id")?,
+            x: table.get("x")?,
+            y: table.get("y")?,
+            party: party.unwrap_or(false),
+            ai: table.get("ai")?,
+            encounter: table.get("encounter")?,
+        })
+    }
+}
+
+/// The optional rules, disabled by default, which a fixture may enable
+#[derive(Clone, Copy)]
+enum OptionalRules {
+    Durability,
+    Encumbrance,
+    Reactions,
+    Cover,
+    Elevation,
+}
+
+impl OptionalRules {
+    fn parse(id: &str) -> Result<OptionalRules> {
+        match id {
+            "durability" => Ok(OptionalRules::Durability),
+            "encumbrance" => Ok(OptionalRules::Encumbrance),
+            "reactions" => Ok(OptionalRules::Reactions),
+            "cover" => Ok(OptionalRules::Cover),
+            "elevation" => Ok(OptionalRules::Elevation),
+            _ => Err(runtime_error(format!("Unknown optional rules '{}'", id))),
+        }
+    }
+
+    /// The flag in the module `rules` enabling these rules
+    fn enabled(self, rules: &mut Rules) -> &mut bool {
+        match self {
+            OptionalRules::Durability => &mut rules.durability.enabled,
+            OptionalRules::Encumbrance => &mut rules.encumbrance.enabled,
+            OptionalRules::Reactions => &mut rules.reactions.enabled,
+            OptionalRules::Cover => &mut rules.cover.enabled,
+            OptionalRules::Elevation => &mut rules.elevation.enabled,
+        }
+    }
+}
+
+struct Fixture {
+    area: Option<String>,
+    actors: Vec<FixtureActor>,
+    rules: Vec<OptionalRules>,
+    activate_ai: bool,
+}
+
+impl<'lua> FromLua<'lua> for Fixture {
+    fn from_lua(value: Value<'lua>, _lua: Context<'lua>) -> Result<Fixture> {
+        let table = match value {
+            Value::Table(table) => table,
+            _ => return Err(table_expected(&value, "Fixture")),
+        };
+
+        let rules: Option<Vec<String>> = table.get("rules")?;
+        let rules = rules
+            .unwrap_or_default()
+            .iter()
+            .map(|id| OptionalRules::parse(id))
+            .collect::<Result<_>>()?;
+        let activate_ai: Option<bool> = table.get("activate_ai")?;
+        Ok(Fixture {
+            area: table.get("area")?,
+            actors: table.get("actors")?,
+            rules,
+            activate_ai: activate_ai.unwrap_or(true),
+        })
+    }
+}
+
+impl Fixture {
+    /// Sets whether each of the optional rules of the fixture is enabled,
+    /// returning the previous settings
+    fn set_rules(&self, enabled: &[bool]) -> Result<Vec<bool>> {
+        let previous = Module::modify_rules(|rules| {
+            self.rules
+                .iter()
+                .zip(enabled)
+                .map(|(optional, enabled)| std::mem::replace(optional.enabled(rules), *enabled))
+                .collect()
+        });
+        previous.ok_or_else(|| runtime_error("Unable to change the module rules while in use"))
+    }
+
+    /// Creates a new game containing the fixture actors, returning the
+    /// entity index of each actor by name
+    fn build(&self) -> Result<HashMap<String, usize>> {
+        let mut names = HashSet::new();
+        for spec in self.actors.iter() {
+            if !names.insert(&spec.name) {
+                return Err(runtime_error(format!(
+                    "Duplicate fixture actor name '{}'",
+                    spec.name
+                )));
+            }
+        }
+
+        let player = match self.actors.iter().find(|spec| spec.party) {
+            None => return Err(runtime_error("The fixture must have a party actor")),
+            Some(spec) => spec,
+        };
+
+        GameState::init(find_actor(&player.id)?, Vec::new(), HashMap::new(), None)
+            .map_err(rlua::Error::external)?;
+        GameState::set_modal_locked(true);
+
+        if let Some(area) = &self.area {
+            if GameState::area_state().borrow().area.area.id != *area {
+                let location = Point::new(player.x, player.y);
+                GameState::transition_to(
+                    Some(area),
+                    Some(location),
+                    Point::new(0, 0),
+                    Time::default(),
+                );
+            }
+
+            if GameState::area_state().borrow().area.area.id != *area {
+                return Err(runtime_error(format!(
+                    "Unable to load fixture area '{}'",
+                    area
+                )));
+            }
+        }
+
+        remove_npcs();
+
+        let mut entities = HashMap::new();
+        let pc = GameState::player();
+        let area_state = GameState::area_state();
+        let moved = {
+            let location = &pc.borrow().location;
+            location.x != player.x || location.y != player.y
+        };
+        if moved {
+            let passable =
+                area_state
+                    .borrow()
+                    .is_passable_for_entity(&pc.borrow(), player.x, player.y);
+            if !passable
+                || !area_state
+                    .borrow_mut()
+                    .move_entity(&pc, player.x, player.y, 0)
+            {
+                return Err(placement_error(player));
+            }
+        }
+        entities.insert(player.name.to_string(), pc.borrow().index());
+
+        let mgr = GameState::turn_manager();
+        let mut groups = HashMap::new();
+        for spec in self.actors.iter() {
+            if std::ptr::eq(spec, player) {
+                continue;
+            }
+
+            let group = match spec.encounter {
+                None => None,
+                Some(encounter) => Some(ai_group(&mut groups, encounter)?),
+            };
+
+            let location = Location::new(spec.x, spec.y, &area_state.borrow().area.area);
+            let actor = match &spec.ai {
+                None => find_actor(&spec.id)?,
+                Some(ai) => with_ai(&find_actor(&spec.id)?, ai)?,
+            };
+            let index = area_state
+                .borrow_mut()
+                .add_actor(
+                    actor,
+                    location,
+                    Some(spec.name.to_string()),
+                    spec.party,
+                    group,
+                )
+                .map_err(|_| placement_error(spec))?;
+
+            if spec.party {
+                GameState::add_party_member(mgr.borrow().entity(index), true);
+            }
+            entities.insert(spec.name.to_string(), index);
+        }
+
+        area_state.borrow_mut().update_view_visibility();
+        if self.activate_ai {
+            HarnessAction::ActivateAI.perform();
+        }
+        step(0);
+
+        Ok(entities)
+    }
+}
+
+fn find_actor(id: &str) -> Result<Rc<Actor>> {
+    match Module::actor(id) {
+        None => Err(runtime_error(format!("Actor '{}' does not exist", id))),
+        Some(actor) => Ok(actor),
+    }
+}
+
+/// Returns a copy of the `actor` using the AI template with the specified `id`
+fn with_ai(actor: &Rc<Actor>, id: &str) -> Result<Rc<Actor>> {
+    let ai = match Module::ai_template(id) {
+        None => {
+            return Err(runtime_error(format!(
+                "AI template '{}' does not exist",
+                id
+            )))
+        }
+        Some(ai) => ai,
+    };
+
+    let mut actor = actor.reloaded();
+    actor.ai = Some(ai);
+    Ok(Rc::new(actor))
+}
+
+/// Returns the AI group shared by the actors in the specified `encounter` of
+/// the current area, creating it if needed
+fn ai_group(groups: &mut HashMap<usize, usize>, encounter: usize) -> Result<usize> {
+    if let Some(group) = groups.get(&encounter) {
+        return Ok(*group);
+    }
+
+    let area_state = GameState::area_state();
+    let area_state = area_state.borrow();
+    if encounter >= area_state.area.encounters.len() {
+        return Err(runtime_error(format!(
+            "Area '{}' has no encounter {}",
+            area_state.area.area.id, encounter
+        )));
+    }
+
+    let group = GameState::turn_manager()
+        .borrow_mut()
+        .get_next_ai_group(&area_state.area.area.id, encounter);
+    groups.insert(encounter, group);
+    Ok(group)
+}
+
+fn placement_error(spec: &FixtureActor) -> rlua::Error {
+    runtime_error(format!(
+        "Unable to place fixture actor '{}' at {},{}",
+        spec.name, spec.x, spec.y
+    ))
+}
+
+/// Removes all entities other than the party from the current area
+fn remove_npcs() {
+    let mgr = GameState::turn_manager();
+    let indices: Vec<usize> = GameState::area_state()
+        .borrow()
+        .entity_iter()
+        .copied()
+        .collect();
+
+    for index in indices {
+        let entity = mgr.borrow().entity(index);
+        let mut entity = entity.borrow_mut();
+        if !entity.is_party_member() {
+            entity.marked_for_removal = true;
+        }
+    }
+
+    // the turn manager removes marked entities on its next update
+    step(0);
+}
+
+fn load_file(path: &Path) -> Result<ScriptState> {
+    let script = fs::read_to_string(path).map_err(rlua::Error::external)?;
+    let id = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let mut state = ScriptState::default();
+    state.remove_limits();
+    state.load("fixture_helpers", FIXTURE_HELPERS)?;
+    state.load(&id, &script)?;
+    Ok(state)
+}
+
+fn test_names(path: &Path) -> Result<Vec<String>> {
+    let state = load_file(path)?;
+    let mut names: Vec<String> = state
+        .global_functions()?
+        .into_iter()
+        .filter(|name| name.starts_with(TEST_PREFIX))
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+fn run_test(path: &Path, name: String) -> ScriptTestCase {
+    info!("Running script test {}:{}", path.display(), name);
+    let start = Instant::now();
+    script_cache::take_errors();
+    EXPECT_FAILURE.with(|expect| expect.set(false));
+    let result = execute(path, &name);
+
+    // errors in game scripts are only logged by the game, so check for them
+    // separately
+    let script_errors = script_cache::take_errors();
+    let outcome = match result {
+        Ok(()) => TestOutcome::Passed,
+        Err(e) => TestOutcome::from_error(&e),
+    };
+    if stop_recording().is_some() {
+        let _ = fs::remove_file(replay_path());
+    }
+    let mut outcome = outcome.with_script_errors(&script_errors);
+    if EXPECT_FAILURE.with(|expect| expect.get()) {
+        outcome = outcome.expecting_failure();
+    }
+
+    ScriptTestCase {
+        name,
+        millis: start.elapsed().as_secs_f64() * 1000.0,
+        outcome,
+    }
+}
+
+fn execute(path: &Path, name: &str) -> Result<()> {
+    // each test gets its own Lua state, so tests can't see each other's globals
+    let state = load_file(path)?;
+    let fixture: Option<Fixture> = state.global("fixture")?;
+    let fixture = match fixture {
+        None => return Err(runtime_error("No fixture table is defined")),
+        Some(fixture) => fixture,
+    };
+
+    if fixture.rules.is_empty() {
+        let entities = fixture.build()?;
+        return state.exec_func(name, ScriptTestContext { entities }, false);
+    }
+
+    // the module rules are restored after the test, whatever its outcome
+    let previous = fixture.set_rules(&vec![true; fixture.rules.len()])?;
+    let result = fixture
+        .build()
+        .and_then(|entities| state.exec_func(name, ScriptTestContext { entities }, false));
+    fixture.set_rules(&previous)?;
+    result
+}
+
+/// Runs a single game update.  UI callbacks, such as conversations and
+/// cutscenes, are not shown during tests and are discarded.
+fn step(millis: u32) {
+    RECORDING.with(|recording| {
+        if let Some(recording) = recording.borrow_mut().as_mut() {
+            recording.record_frame(millis);
+        }
+    });
+    let _ = GameState::update(millis);
+}
+
+fn export_combat_log() -> Result<Vec<CombatLogEntry>> {
+    let mut path = std::env::temp_dir();
+    path.push(format!("sulis_combat_log_{}.json", std::process::id()));
+    combat_log::export_to(&path).map_err(rlua::Error::external)?;
+
+    let entries = fs::read_to_string(&path)
+        .map_err(rlua::Error::external)
+        .and_then(|data| serde_json::from_str(&data).map_err(rlua::Error::external));
+    let _ = fs::remove_file(&path);
+    entries
+}
+
+fn save_and_load() -> Result<()> {
+    // the save passes through JSON, as it would when written to a file
+    let save = serde_json::to_string(&SaveState::create()).map_err(rlua::Error::external)?;
+    let save: SaveState = serde_json::from_str(&save).map_err(rlua::Error::external)?;
+
+    GameState::load(save).map_err(rlua::Error::external)?;
+    GameState::set_modal_locked(true);
+    step(0);
+    Ok(())
+}
+
+fn replay_path() -> PathBuf {
+    let mut path = std::env::temp_dir();
+    path.push(format!("sulis_script_test_{}.jsonl", std::process::id()));
+    path
+}
+
+fn is_recording() -> bool {
+    RECORDING.with(|recording| recording.borrow().is_some())
+}
+
+fn record_replay() -> Result<()> {
+    if is_recording() {
+        return Err(runtime_error("A replay is already being recorded"));
+    }
+    if GameState::is_combat_active() {
+        return Err(runtime_error(
+            "Unable to record a replay during combat, which saves do not hold",
+        ));
+    }
+
+    let recorder = ReplayRecorder::start_at(replay_path(), ActiveResources::default())
+        .map_err(rlua::Error::external)?;
+    RECORDING.with(|recording| {
+        *recording.borrow_mut() = Some(Recording {
+            recorder,
+            frames: Vec::new(),
+            actions: Vec::new(),
+        })
+    });
+    Ok(())
+}
+
+/// Stops recording, returning the frames that were recorded, if any
+fn stop_recording() -> Option<Vec<RecordedFrame>> {
+    let recording = RECORDING.with(|recording| recording.borrow_mut().take())?;
+    // the recorder is dropped here, closing the file
+    Some(recording.frames)
+}
+
+fn play_replay() -> Result<()> {
+    let frames = match stop_recording() {
+        None => return Err(runtime_error("No replay is being recorded")),
+        Some(frames) => frames,
+    };
+
+    let path = replay_path();
+    let replay = Replay::load(&path);
+    let _ = fs::remove_file(&path);
+    let mut replay = replay.map_err(rlua::Error::external)?;
+    replay.start().map_err(rlua::Error::external)?;
+
+    for recorded in frames {
+        for action in recorded.actions {
+            action.perform();
+        }
+
+        let frame = match replay.next_frame() {
+            None => {
+                return Err(runtime_error(
+                    "The replay ended before all frames were played",
+                ))
+            }
+            Some(frame) => frame,
+        };
+        GameState::set_modal_locked(recorded.modal_locked);
+        let _ = GameState::update(frame.millis);
+    }
+    GameState::set_modal_locked(true);
+
+    if !replay.is_complete() {
+        return Err(runtime_error(
+            "The replay has more frames than were recorded",
+        ));
+    }
+    Ok(())
+}
+
+fn run_millis(millis: u32) {
+    let mut remaining = millis;
+    while remaining > 0 {
+        let frame = remaining.min(FRAME_MILLIS);
+        step(frame);
+        remaining -= frame;
+    }
+}
+
+fn wait_for_animations() -> Result<()> {
+    let mut elapsed = 0;
+    while GameState::has_any_blocking_animations() {
+        if elapsed >= MAX_ANIMATION_MILLIS {
+            return Err(runtime_error(format!(
+                "Animations did not complete within {} millis",
+                MAX_ANIMATION_MILLIS
+            )));
+        }
+        step(FRAME_MILLIS);
+        elapsed += FRAME_MILLIS;
+    }
+    Ok(())
+}
+
+fn advance_rounds(rounds: u32) -> Result<()> {
+    let mgr = GameState::turn_manager();
+    let target = mgr.borrow().current_round() + rounds;
+
+    let mut turns = 0;
+    while mgr.borrow().current_round() < target {
+        wait_for_animations()?;
+
+        if !GameState::is_combat_active() {
+            step(FRAME_MILLIS);
+            continue;
+        }
+
+        turns += 1;
+        if turns > MAX_TURNS_PER_ROUND * rounds {
+            return Err(runtime_error(
+                "Unable to advance rounds, the turn order is stuck",
+            ));
+        }
+
+        HarnessAction::PassTurn.perform();
+        step(0);
+    }
+
+    wait_for_animations()
+}
+
+fn is_current(index: usize) -> bool {
+    match GameState::turn_manager().borrow().current() {
+        None => false,
+        Some(entity) => entity.borrow().index() == index,
+    }
+}
+
+fn run_turn(entity: ScriptEntity) -> Result<()> {
+    let index = entity.try_unwrap_index()?;
+    if !GameState::is_combat_active() {
+        return Err(runtime_error("Combat must be active to run a turn"));
+    }
+
+    let mut turns = 0;
+    while !is_current(index) {
+        wait_for_animations()?;
+
+        turns += 1;
+        if turns > MAX_TURNS_PER_ROUND {
+            return Err(runtime_error("Unable to run turn, the turn order is stuck"));
+        }
+
+        HarnessAction::PassTurn.perform();
+        step(0);
+    }
+
+    // the AI only runs while the game is not modal locked
+    GameState::set_modal_locked(false);
+    let mut elapsed = 0;
+    while is_current(index) && elapsed < MAX_TURN_MILLIS {
+        step(FRAME_MILLIS);
+        elapsed += FRAME_MILLIS;
+    }
+    GameState::set_modal_locked(true);
+
+    if is_current(index) {
+        return Err(runtime_error(format!(
+            "The turn did not end within {} millis",
+            MAX_TURN_MILLIS
+        )));
+    }
+
+    wait_for_animations()
+}
+
+fn select_target(target: Value) -> Result<()> {
+    let (x, y) = match target {
+        Value::UserData(data) => {
+            let entity = data.borrow::<ScriptEntity>()?.try_unwrap()?;
+            let entity = entity.borrow();
+            (entity.location.x, entity.location.y)
+        }
+        Value::Table(table) => (table.get("x")?, table.get("y")?),
+        _ => {
+            return Err(rlua::Error::FromLuaConversionError {
+                from: target.type_name(),
+                to: "Target",
+                message: Some("Expected an entity or a table with x and y".to_string()),
+            })
+        }
+    };
+
+    let targeter = match GameState::area_state().borrow().targeter() {
+        None => return Err(runtime_error("No targeter is active")),
+        Some(targeter) => targeter,
+    };
+
+    targeter.borrow_mut().on_mouse_move(x, y);
+    if !targeter.borrow().is_valid_to_activate() {
+        return Err(runtime_error(format!(
+            "{},{} is not a valid target for '{}'",
+            x,
+            y,
+            targeter.borrow().name()
+        )));
+    }
+
+    targeter.borrow_mut().on_activate();
+    // the game removes the used targeter on its next update
+    step(0);
+    Ok(())
+}
+
+fn root_cause(error: &rlua::Error) -> &rlua::Error {
+    match error {
+        rlua::Error::CallbackError { cause, .. } => root_cause(cause),
+        _ => error,
+    }
+}
+
+fn runtime_error<T: Into<String>>(message: T) -> rlua::Error {
+    rlua::Error::RuntimeError(message.into())
+}
+
+fn table_expected(value: &Value, to: &'static str) -> rlua::Error {
+    rlua::Error::FromLuaConversionError {
+        from: value.type_name(),
+        to,
+        message: Some("Expected a table".to_string()),
+    }
+}
+
+/// Converts the `value` to Lua, in the same form as it is written to JSON
+fn to_lua<'lua, T: serde::Serialize>(lua: Context<'lua>, value: &T) -> Result<Value<'lua>> {
+    let json = serde_json::to_value(value).map_err(rlua::Error::external)?;
+    json_to_lua(lua, &json)
+}
+
+fn json_to_lua<'lua>(lua: Context<'lua>, value: &serde_json::Value) -> Result<Value<'lua>> {
+    use serde_json::Value as Json;
+    Ok(match value {
+        Json::Null => Value::Nil,
+        Json::Bool(value) => Value::Boolean(*value),
+        Json::Number(value) => match value.as_i64() {
+            Some(value) => Value::Integer(value),
+            None => Value::Number(value.as_f64().unwrap_or_default()),
+        },
+        Json::String(value) => Value::String(lua.create_string(value)?),
+        Json::Array(values) => {
+            let table = lua.create_table()?;
+            for (index, value) in values.iter().enumerate() {
+                table.set(index + 1, json_to_lua(lua, value)?)?;
+            }
+            Value::Table(table)
+        }
+        Json::Object(values) => {
+            let table = lua.create_table()?;
+            for (key, value) in values.iter() {
+                table.set(key.as_str(), json_to_lua(lua, value)?)?;
+            }
+            Value::Table(table)
+        }
+    })
+}
+
+fn lua_eq(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Nil, Value::Nil) => true,
+        (Value::Boolean(a), Value::Boolean(b)) => a == b,
+        (Value::String(a), Value::String(b)) => a.as_bytes() == b.as_bytes(),
+        _ => match (number(a), number(b)) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        },
+    }
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(value) => Some(*value as f64),
+        Value::Number(value) => Some(*value),
+        _ => None,
+    }
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Nil => "nil".to_string(),
+        Value::Boolean(value) => value.to_string(),
+        Value::Integer(value) => value.to_string(),
+        Value::Number(value) => value.to_string(),
+        Value::String(value) => format!("'{}'", value.to_str().unwrap_or("<invalid utf8>")),
+        _ => value.type_name().to_string(),
+    }
+}
+
+/// Escapes text for use in XML attributes and content.  Control characters
+/// other than whitespace are not allowed in XML, and are dropped.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\n' | '\r' | '\t' => out.push(c),
+            c if c.is_control() => (),
+            c => out.push(c),
+        }
+    }
+    out
+}
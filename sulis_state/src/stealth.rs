@@ -0,0 +1,204 @@
+//  This file is part of Sulis, a turn based RPG written in Rust.
+//  Copyright 2019 Jared Stephen
+//
+//  Sulis is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Sulis is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Sulis.  If not, see <http://www.gnu.org/licenses/>
+
+//! Stealth determines when hidden entities are noticed by the hostile
+//! entities around them.  Each round, and each time a hidden entity moves,
+//! every hostile entity that can see it checks its perception against the
+//! hidden entity's stealth, as described in the `stealth` rules.
+//!
+//! A detected entity fires its `OnDetected` callbacks, loses the effects
+//! granting it the `Hidden` bonus, and activates the AI of nearby hostiles.
+//! Checks are queued and resolved on the next update, so that scripts are
+//! never fired while the turn manager or the moving entity is in use.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::area_feedback_text::ColorKind;
+use crate::script::{ScriptCallback, ScriptEntitySet};
+use crate::{
+    combat_log, dist, AreaFeedbackText, AreaState, Effect, EntityState, GameState, StackPolicy,
+    TurnManager,
+};
+use sulis_core::util::ExtInt;
+use sulis_module::{
+    bonus::Contingent, Attribute, Bonus, BonusKind, BonusList, Module, Time, ROUND_TIME_MILLIS,
+};
+
+/// Returns the margin by which `entity` remains hidden from `observer`.  The
+/// entity is detected if the margin is negative.
+pub fn detection_margin(
+    area: &AreaState,
+    time: Time,
+    entity: &EntityState,
+    observer: &EntityState,
+) -> i32 {
+    let rules = Module::rules();
+    let stealth = &rules.stealth;
+
+    let color = rules.get_area_color(area.area.area.location_kind, time);
+    let cover = area.cover(observer, entity);
+    let dist = dist(observer, entity) - stealth.break_even_distance;
+    let perception = observer.actor.stats.attributes.perception as i32;
+
+    entity.actor.stats.stealth
+        + entity.actor.stats.concealment
+        + stealth.darkness_bonus(color)
+        + stealth.cover_bonus(cover)
+        + (dist * stealth.distance_factor as f32) as i32
+        - perception * stealth.perception_factor
+}
+
+/// Returns the index of the first hostile entity that can see and detects
+/// `entity`, along with the margin of its check
+pub(crate) fn find_observer(
+    mgr: &TurnManager,
+    area: &AreaState,
+    entity: &EntityState,
+) -> Option<(usize, i32)> {
+    if !Module::rules().stealth.enabled || !entity.actor.stats.hidden {
+        return None;
+    }
+
+    if entity.actor.is_dead() || !entity.location.is_in(area) {
+        return None;
+    }
+
+    let time = mgr.current_time();
+    for index in area.entity_iter() {
+        let observer = match mgr.entity_checked(*index) {
+            None => continue,
+            Some(observer) => observer,
+        };
+        let observer = observer.borrow();
+
+        if observer.actor.is_dead() || observer.actor.is_disabled() {
+            continue;
+        }
+
+        if !observer.is_hostile(entity) || !area.has_visibility(&observer, entity) {
+            continue;
+        }
+
+        let margin = detection_margin(area, time, entity, &observer);
+        if margin < 0 {
+            return Some((observer.index(), margin));
+        }
+    }
+
+    None
+}
+
+/// Runs the queued checks for each of the `entities`, detecting those that
+/// are noticed
+pub(crate) fn check_detection(entities: Vec<usize>) {
+    let mgr = GameState::turn_manager();
+    let area_state = GameState::area_state();
+
+    for index in entities {
+        let detection = {
+            let mgr = mgr.borrow();
+            let entity = match mgr.entity_checked(index) {
+                None => continue,
+                Some(entity) => entity,
+            };
+            let entity = entity.borrow();
+            find_observer(&mgr, &area_state.borrow(), &entity)
+        };
+
+        if let Some((observer, margin)) = detection {
+            detect(index, observer, margin);
+        }
+    }
+}
+
+fn detect(index: usize, observer: usize, margin: i32) {
+    let mgr = GameState::turn_manager();
+    let (entity, observer) = {
+        let mgr = mgr.borrow();
+        match (mgr.entity_checked(index), mgr.entity_checked(observer)) {
+            (Some(entity), Some(observer)) => (entity, observer),
+            _ => return,
+        }
+    };
+
+    info!(
+        "'{}' detected by '{}' ({})",
+        entity.borrow().actor.actor.name,
+        observer.borrow().actor.actor.name,
+        margin
+    );
+    combat_log::record_detected(&entity.borrow(), &observer.borrow(), margin);
+
+    let area_state = GameState::area_state();
+    let mut text = AreaFeedbackText::with_target(&entity.borrow(), &area_state.borrow());
+    text.add_entry("Spotted!".to_string(), ColorKind::Info);
+    area_state.borrow_mut().add_feedback_text(text);
+
+    let targets = ScriptEntitySet::from_pair(&entity, &observer);
+    let cbs = entity.borrow().callbacks(&mgr.borrow());
+    cbs.iter().for_each(|cb| cb.on_detected(&targets));
+
+    entity.borrow_mut().actor.remove_hidden_effects();
+
+    mgr.borrow_mut()
+        .check_ai_activation(&entity, &mut area_state.borrow_mut());
+}
+
+/// The `searcher` spends action points to gain a bonus to perception for one
+/// round, and checks each hidden entity it is hostile to for detection.
+pub(crate) fn search(searcher: &Rc<RefCell<EntityState>>) {
+    let rules = Module::rules();
+    let stealth = &rules.stealth;
+    let mgr = GameState::turn_manager();
+
+    if mgr.borrow().is_combat_active() {
+        searcher.borrow_mut().actor.remove_ap(stealth.search_ap);
+    }
+
+    let mut bonuses = BonusList::default();
+    bonuses.add(Bonus {
+        when: Contingent::Always,
+        kind: BonusKind::Attribute {
+            attribute: Attribute::Perception,
+            amount: stealth.search_bonus,
+        },
+    });
+    let mut effect = Effect::new(
+        "Searching",
+        "searching",
+        ExtInt::Int(ROUND_TIME_MILLIS),
+        bonuses,
+        None,
+    );
+    effect.set_stack_policy(StackPolicy::Refresh);
+    mgr.borrow_mut()
+        .add_effect(effect, searcher, Vec::new(), Vec::new());
+
+    let mut mgr = mgr.borrow_mut();
+    let hidden: Vec<usize> = mgr
+        .entity_iter()
+        .filter(|entity| {
+            let entity = entity.borrow();
+            entity.actor.stats.hidden && entity.is_hostile(&searcher.borrow())
+        })
+        .map(|entity| entity.borrow().index())
+        .collect();
+
+    for index in hidden {
+        mgr.queue_stealth_check(index);
+    }
+}
@@ -17,6 +17,7 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use crate::autosave::{self, AutosaveTrigger};
 use crate::{AreaState, EntityState, GameState, Location, TurnManager};
 use sulis_core::{util::Point};
 use sulis_module::{
@@ -73,6 +74,7 @@ pub(crate) fn transition_to(area_id: Option<&str>, p: Option<Point>, offset: Poi
     mgr.borrow_mut().add_time(time);
 
     transition_party(&mgr, &area, p, &party);
+    mgr.borrow_mut().queue_contingent_update();
 
     let pc = GameState::player();
     area.borrow_mut().push_scroll_to_callback(Rc::clone(&pc));
@@ -81,6 +83,8 @@ pub(crate) fn transition_to(area_id: Option<&str>, p: Option<Point>, offset: Poi
 
     area.update_view_visibility();
 
+    autosave::request(AutosaveTrigger::AreaTransition);
+
     if !area.on_load_fired {
         area.on_load_fired = true;
         GameState::add_ui_callbacks_of_kind(
@@ -18,10 +18,16 @@ use std::cell::{Cell, RefCell};
 use std::collections::{vec_deque::Iter, HashMap, HashSet, VecDeque};
 use std::rc::Rc;
 
+use crate::ai::AIBlackboard;
+use crate::autosave::{self, AutosaveTrigger};
+use crate::combat_log;
+use crate::reaction_handler::Reaction;
 use crate::script::{CallbackData, FuncKind, TriggeredCallback};
-use crate::{AreaState, ChangeListener, ChangeListenerList, Effect, EntityState, GameState};
-use sulis_core::{config::Config, util::{gen_rand, Point}};
-use sulis_module::{Faction, Module, Time, ROUND_TIME_MILLIS, OnTrigger};
+use crate::{
+    AreaState, ChangeListener, ChangeListenerList, Effect, EntityState, GameState, StackPolicy,
+};
+use sulis_core::{config::Config, util::Point};
+use sulis_module::{Module, Time, ROUND_TIME_MILLIS, OnTrigger};
 
 fn add_campaign_elapsed_callback(cbs: &mut Vec<Rc<CallbackData>>) {
     let script_data = match Module::campaign().on_round_elapsed_script {
@@ -44,6 +50,17 @@ enum Entry {
     TurnChange,
 }
 
+// The result of applying a new effect's `StackPolicy` to the entity's
+// existing effects
+enum StackOutcome {
+    // the new effect should be added alongside any existing ones
+    Added,
+    // the existing effect at this index absorbed the new one
+    Refreshed(usize),
+    // the new effect was weaker than the existing effect at this index
+    Discarded(usize),
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct EncounterRef {
@@ -60,6 +77,9 @@ pub struct TurnManager {
     effects_remove_next_update: Vec<usize>,
     entities_move_callback_next_update: HashSet<usize>,
     triggered_cbs_next_update: Vec<TriggeredCallback>,
+    reactions_next_update: Vec<Reaction>,
+    stealth_checks_next_update: HashSet<usize>,
+    contingents_next_update: bool,
     combat_active: bool,
 
     pub time_listeners: ChangeListenerList<Time>,
@@ -68,6 +88,8 @@ pub struct TurnManager {
 
     pub(crate) ai_groups: HashMap<usize, EncounterRef>,
     pub(crate) cur_ai_group_index: usize,
+    ai_blackboards: HashMap<usize, AIBlackboard>,
+    turn_taken_over: bool,
 
     total_elapsed_millis: usize,
 }
@@ -116,6 +138,7 @@ impl TurnManager {
 
         let new_round = self.current_round();
         if prev_round != new_round {
+            self.contingents_next_update = true;
             let time = self.current_time();
             self.time_listeners.notify(&time);
             true
@@ -130,13 +153,18 @@ impl TurnManager {
         self.surfaces.clear();
         self.auras.clear();
         self.effects_remove_next_update.clear();
+        self.entities_move_callback_next_update.clear();
         self.triggered_cbs_next_update.clear();
+        self.reactions_next_update.clear();
+        self.stealth_checks_next_update.clear();
+        self.contingents_next_update = true;
         self.combat_active = false;
         self.listeners = ChangeListenerList::default();
         self.time_listeners = ChangeListenerList::default();
         self.order.clear();
         self.cur_ai_group_index = 0;
         self.ai_groups.clear();
+        self.ai_blackboards.clear();
         self.total_elapsed_millis = total_elapsed_millis;
     }
 
@@ -222,6 +250,26 @@ impl TurnManager {
         value
     }
 
+    /// Returns the blackboard shared by the members of the AI `group`,
+    /// creating it if needed
+    pub(crate) fn ai_blackboard(&mut self, group: usize) -> &mut AIBlackboard {
+        self.ai_blackboards.entry(group).or_default()
+    }
+
+    pub(crate) fn ai_blackboard_checked(&self, group: usize) -> Option<&AIBlackboard> {
+        self.ai_blackboards.get(&group)
+    }
+
+    /// Gives the player control of the current turn, if it belongs to a
+    /// party member under AI control.  The AI resumes control on the next turn.
+    pub fn take_over_turn(&mut self) {
+        self.turn_taken_over = true;
+    }
+
+    pub fn is_turn_taken_over(&self) -> bool {
+        self.turn_taken_over
+    }
+
     pub fn entity_checked(&self, index: usize) -> Option<Rc<RefCell<EntityState>>> {
         if index >= self.entities.len() {
             return None;
@@ -254,6 +302,45 @@ impl TurnManager {
         result
     }
 
+    pub(crate) fn queue_reaction(&mut self, reaction: Reaction) {
+        self.reactions_next_update.push(reaction);
+    }
+
+    #[must_use]
+    pub(crate) fn drain_reactions(&mut self) -> Vec<Reaction> {
+        self.reactions_next_update.drain(..).collect()
+    }
+
+    pub(crate) fn queue_stealth_check(&mut self, entity_index: usize) {
+        self.stealth_checks_next_update.insert(entity_index);
+    }
+
+    /// Queues a stealth check for every hidden entity, as each round elapses
+    fn queue_hidden_stealth_checks(&mut self) {
+        for entity in self.entities.iter().flatten() {
+            let entity = entity.borrow();
+            if entity.actor.stats.hidden {
+                self.stealth_checks_next_update.insert(entity.index());
+            }
+        }
+    }
+
+    /// Queues an update of the conditions used to evaluate contingent bonuses,
+    /// such as after an entity moves or an effect is added or removed
+    pub(crate) fn queue_contingent_update(&mut self) {
+        self.contingents_next_update = true;
+    }
+
+    /// Returns true if an update of the contingent conditions was queued
+    /// since the last call
+    pub(crate) fn take_contingent_update(&mut self) -> bool {
+        std::mem::take(&mut self.contingents_next_update)
+    }
+
+    pub(crate) fn drain_stealth_checks(&mut self) -> Vec<usize> {
+        self.stealth_checks_next_update.drain().collect()
+    }
+
     #[must_use]
     pub fn update_entity_move_callbacks(&mut self) -> Vec<Rc<CallbackData>> {
         let mut cbs = Vec::new();
@@ -287,6 +374,8 @@ impl TurnManager {
 
         let new_round = self.add_millis(elapsed_millis);
         if new_round {
+            self.queue_hidden_stealth_checks();
+
             let mut cec_cbs = Vec::new();
             add_campaign_elapsed_callback(&mut cec_cbs);
             for cb in cec_cbs {
@@ -386,6 +475,7 @@ impl TurnManager {
             _ => unreachable!(),
         };
 
+        self.turn_taken_over = false;
         if current.borrow().is_party_member() {
             GameState::set_selected_party_member(Rc::clone(current));
 
@@ -398,7 +488,7 @@ impl TurnManager {
             area_state.range_indicators().remove_attack();
         }
 
-        if Config::scroll_to_active() {
+        if Config::scroll_to_active() && !current.borrow().is_hidden_from_party() {
             let (x, y) = {
                 let loc = &current.borrow().location;
                 (loc.x, loc.y)
@@ -466,6 +556,7 @@ impl TurnManager {
                 }
                 Entry::TurnChange => {
                     self.add_millis(ROUND_TIME_MILLIS);
+                    self.queue_hidden_stealth_checks();
                     self.order.push_back(Entry::TurnChange);
                     add_campaign_elapsed_callback(&mut cbs);
                 }
@@ -528,9 +619,11 @@ impl TurnManager {
                 continue;
             }
 
+            // hidden entities are activated by seeing the mover, but not by
+            // being seen until they are detected
             let mover = mover.borrow();
-            if !area_state.has_visibility(&mover, &entity)
-                && !area_state.has_visibility(&entity, &mover)
+            if !area_state.has_visibility(&entity, &mover)
+                && (entity.actor.stats.hidden || !area_state.has_visibility(&mover, &entity))
             {
                 continue;
             }
@@ -631,6 +724,7 @@ impl TurnManager {
 
         info!("Setting combat mode active = {}", active);
         self.combat_active = active;
+        self.contingents_next_update = true;
 
         if !active {
             self.end_combat();
@@ -669,6 +763,8 @@ impl TurnManager {
     }
 
     fn end_combat(&mut self) {
+        self.ai_blackboards.clear();
+
         for entity in self.entities.iter() {
             let entity = match entity {
                 None => continue,
@@ -710,6 +806,8 @@ impl TurnManager {
         let area = GameState::area_state();
         area.borrow_mut().range_indicators().clear();
         area.borrow().update_music(false, None);
+
+        autosave::request(AutosaveTrigger::CombatEnd);
     }
 
     fn initiate_combat(&mut self) {
@@ -729,7 +827,8 @@ impl TurnManager {
                         .actor
                         .stats
                         .initiative;
-                    last_initiative = base + gen_rand(0, initiative_roll_max);
+                    last_initiative =
+                        base + GameState::with_rand(|rand| rand.gen(0, initiative_roll_max));
                     initiative[index] = 2 * last_initiative;
                 }
                 Entry::Effect(_) => {
@@ -765,6 +864,7 @@ impl TurnManager {
 
     pub(crate) fn fire_on_moved_next_update(&mut self, entity_index: usize) {
         self.entities_move_callback_next_update.insert(entity_index);
+        self.contingents_next_update = true;
     }
 
     pub(crate) fn increment_surface_squares_moved(
@@ -856,6 +956,7 @@ impl TurnManager {
 
         entity.borrow_mut().set_index(index);
         entity.borrow_mut().actor.init_turn();
+        self.contingents_next_update = true;
         self.listeners.notify(self);
 
         index
@@ -882,6 +983,7 @@ impl TurnManager {
 
         self.effects.push(Some(effect));
         self.order.push_back(Entry::Effect(index));
+        self.contingents_next_update = true;
         debug!("Added effect at {} to turn manager", index);
 
         index
@@ -920,21 +1022,126 @@ impl TurnManager {
         index
     }
 
+    /// Adds the effect to the entity, subject to the effect's `StackPolicy`.
+    /// Returns the index of the effect, which is an existing effect on the
+    /// entity if that effect absorbed the new one.
     pub fn add_effect(
         &mut self,
         effect: Effect,
         entity: &Rc<RefCell<EntityState>>,
         cbs: Vec<CallbackData>,
         removal_markers: Vec<Rc<Cell<bool>>>,
+    ) -> usize {
+        let time = self.current_time();
+        let absorbed_by = match self.apply_stack_policy(&effect, entity) {
+            StackOutcome::Added => None,
+            StackOutcome::Refreshed(index) => Some(index),
+            StackOutcome::Discarded(index) => {
+                // the new effect is discarded, so remove its animations
+                removal_markers.iter().for_each(|marker| marker.set(true));
+                return index;
+            }
+        };
+
+        combat_log::record_effect(
+            time,
+            &entity.borrow(),
+            &effect.name,
+            effect.total_duration_rounds(),
+        );
+
+        if let Some(index) = absorbed_by {
+            removal_markers.iter().for_each(|marker| marker.set(true));
+            return index;
+        }
+
+        self.load_effect(effect, entity, cbs, removal_markers)
+    }
+
+    /// Adds the effect to the entity without checking its `StackPolicy`
+    /// against the entity's existing effects
+    pub(crate) fn load_effect(
+        &mut self,
+        effect: Effect,
+        entity: &Rc<RefCell<EntityState>>,
+        cbs: Vec<CallbackData>,
+        removal_markers: Vec<Rc<Cell<bool>>>,
     ) -> usize {
         let index = self.add_effect_internal(effect, cbs, removal_markers);
 
-        let bonuses = self.effect(index).bonuses().clone();
+        let bonuses = self.effect(index).stacked_bonuses();
         entity.borrow_mut().actor.add_effect(index, bonuses);
 
         index
     }
 
+    // Applies the stacking policy of the new effect against an effect with the
+    // same tag already on the entity.
+    fn apply_stack_policy(
+        &mut self,
+        effect: &Effect,
+        entity: &Rc<RefCell<EntityState>>,
+    ) -> StackOutcome {
+        // the default tag is shared by all untagged effects, so never stack on it
+        if effect.stack_policy == StackPolicy::Independent || effect.tag == "default" {
+            return StackOutcome::Added;
+        }
+
+        let existing = entity
+            .borrow()
+            .actor
+            .effects_iter()
+            .copied()
+            .find(|index| {
+                let other = self.effect(*index);
+                other.tag == effect.tag && !other.is_expired()
+            });
+        let existing = match existing {
+            None => return StackOutcome::Added,
+            Some(existing) => existing,
+        };
+
+        match effect.stack_policy {
+            StackPolicy::Independent => StackOutcome::Added,
+            StackPolicy::Refresh => {
+                self.effect_mut(existing)
+                    .refresh_duration(effect.total_duration);
+                StackOutcome::Refreshed(existing)
+            }
+            StackPolicy::Stack { max } => {
+                let other = self.effect_mut(existing);
+                if other.stacks < max {
+                    other.stacks += 1;
+                }
+                other.refresh_duration(effect.total_duration);
+                info!("Stacked effect '{}' to {}", other.name, other.stacks);
+
+                let bonuses = other.stacked_bonuses();
+                entity
+                    .borrow_mut()
+                    .actor
+                    .update_effect_bonuses(existing, bonuses);
+                StackOutcome::Refreshed(existing)
+            }
+            StackPolicy::ReplaceIfStronger { strength } => {
+                let other = self.effect_mut(existing);
+                let other_strength = match other.stack_policy {
+                    StackPolicy::ReplaceIfStronger { strength } => strength,
+                    _ => 0.0,
+                };
+
+                if strength < other_strength {
+                    return StackOutcome::Discarded(existing);
+                }
+
+                info!("Replacing effect '{}' with '{}'", other.name, effect.name);
+                other.mark_for_removal();
+                entity.borrow_mut().actor.remove_effect(existing);
+                StackOutcome::Added
+            }
+        }
+    }
+
     /// Adds the specified cells to be set to true when the given effect is removed.  this
     /// is used when loading, in order to associate animations with effects
     pub fn add_removal_listener_for_effect(&mut self, index: usize, marked: Vec<Rc<Cell<bool>>>) {
@@ -976,6 +1183,7 @@ impl TurnManager {
             self.remove_from_surface(entity, index);
         }
         self.effects[index] = None;
+        self.contingents_next_update = true;
         self.order.retain(|e| match e {
             Entry::Effect(i) => *i != index,
             Entry::Entity(_) => true,
@@ -1038,6 +1246,10 @@ impl TurnManager {
         // self.entities[index] = None;
         entity.borrow_mut().marked_for_removal = false;
 
+        for blackboard in self.ai_blackboards.values_mut() {
+            blackboard.remove_entity(index);
+        }
+
         // can't do this with a collect because of lifetime issues
         let mut effects_to_remove = Vec::new();
         {
@@ -1057,7 +1269,7 @@ impl TurnManager {
             Entry::Effect(_) => true,
             Entry::Entity(index) => {
                 let entity = self.entities[*index].as_ref().unwrap().borrow();
-                !entity.is_ai_active() || entity.actor.faction() != Faction::Hostile
+                !entity.is_ai_active() || !entity.is_hostile_to_party()
             }
             Entry::TurnChange => true,
         }) {
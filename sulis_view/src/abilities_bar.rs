@@ -678,6 +678,7 @@ impl WidgetKind for AbilityButton {
 fn activate_ability(entity: &Rc<RefCell<EntityState>>, ability: &Rc<Ability>) -> bool {
     let can_activate = entity.borrow().actor.can_activate(&ability.id);
     if can_activate {
+        GameState::take_over_turn();
         let index = entity.borrow().index();
         Script::ability_on_activate(index, "on_activate".to_string(), ability);
         return true;
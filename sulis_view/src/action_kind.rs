@@ -23,10 +23,10 @@ use sulis_core::ui::{animation_state, Widget};
 use sulis_core::util::Point;
 use sulis_module::{
     area::{Destination, ToKind},
-    Faction, Module, ObjectSize, OnTrigger, Time, MOVE_TO_THRESHOLD,
+    Module, ObjectSize, OnTrigger, Time, MOVE_TO_THRESHOLD,
 };
 use sulis_state::{can_attack, is_within};
-use sulis_state::{AreaState, EntityState, GameState, PropState, ScriptCallback};
+use sulis_state::{schedule, AreaState, EntityState, GameState, PropState, ScriptCallback};
 
 pub fn get_action(x_f32: f32, y_f32: f32) -> Box<dyn ActionKind> {
     let (x, y) = (x_f32 as i32, y_f32 as i32);
@@ -230,9 +230,15 @@ impl ActionKind for SelectAction {
     }
 }
 
+/// The number of times the party approaches an entity to talk to it, which
+/// may walk out of range on its schedule meanwhile, before starting the
+/// dialog anyway
+const MAX_DIALOG_APPROACHES: u32 = 2;
+
 struct DialogAction {
     target: Rc<RefCell<EntityState>>,
     pc: Rc<RefCell<EntityState>>,
+    approaches: u32,
 }
 
 impl DialogAction {
@@ -262,11 +268,16 @@ impl DialogAction {
         };
 
         if is_within(&*pc.borrow(), &*target.borrow(), max_dist) {
-            Some(Box::new(DialogAction { target, pc }))
+            Some(Box::new(DialogAction {
+                target,
+                pc,
+                approaches: 0,
+            }))
         } else {
             let cb_action = Box::new(DialogAction {
                 target: Rc::clone(&target),
                 pc: Rc::clone(&pc),
+                approaches: 1,
             });
             MoveThenAction::create_if_valid(
                 &pc,
@@ -290,7 +301,7 @@ impl ActionKind for DialogAction {
         Some(ActionHoverInfo::new(&self.target.borrow().size, point))
     }
 
-    fn fire_action(&mut self, _widget: &Rc<RefCell<Widget>>) -> bool {
+    fn fire_action(&mut self, widget: &Rc<RefCell<Widget>>) -> bool {
         trace!("Firing dialog action.");
 
         let convo = match self.target.borrow().actor.actor.conversation {
@@ -301,6 +312,32 @@ impl ActionKind for DialogAction {
             Some(ref convo) => Rc::clone(convo),
         };
 
+        // the target may have walked away on its schedule during the approach
+        let max_dist = Module::rules().max_dialog_distance;
+        if self.approaches < MAX_DIALOG_APPROACHES
+            && !is_within(&*self.pc.borrow(), &*self.target.borrow(), max_dist)
+        {
+            schedule::hold(&self.target, schedule::CONVERSATION_HOLD_ROUNDS);
+            let cb_action = Box::new(DialogAction {
+                target: Rc::clone(&self.target),
+                pc: Rc::clone(&self.pc),
+                approaches: self.approaches + 1,
+            });
+            let pos = self.target.borrow().location.to_point();
+            let size = Rc::clone(&self.target.borrow().size);
+            if let Some(mut action) = MoveThenAction::create_if_valid(
+                &self.pc,
+                pos,
+                &size,
+                max_dist,
+                cb_action,
+                animation_state::Kind::MouseDialog,
+            ) {
+                action.fire_action(widget);
+                return false;
+            }
+        }
+
         // trigger the dialog indirectly to avoid double borrow of area view
         let cb = OnTrigger::StartConversation(convo.id.to_string());
         GameState::add_ui_callback(vec![cb], &self.pc, &self.target);
@@ -535,7 +572,7 @@ fn get_attack_target(area_state: &AreaState, x: i32, y: i32) -> Option<Rc<RefCel
             if entity.borrow().is_party_member() {
                 return None;
             }
-            if entity.borrow().actor.faction() != Faction::Hostile {
+            if !entity.borrow().is_hostile_to_party() {
                 return None;
             }
             Some(Rc::clone(entity))
@@ -583,7 +620,7 @@ impl AttackAction {
                 &pc,
                 target.borrow().location.to_point(),
                 &target.borrow().size,
-                pc.borrow().actor.stats.attack_distance(),
+                pc.borrow().attack_distance_to(&target.borrow()),
                 cb_action,
                 animation_state::Kind::MouseAttack,
             )
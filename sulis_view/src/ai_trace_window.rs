@@ -0,0 +1,175 @@
+//  This file is part of Sulis, a turn based RPG written in Rust.
+//  Copyright 2018 Jared Stephen
+//
+//  Sulis is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Sulis is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Sulis.  If not, see <http://www.gnu.org/licenses/>
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use sulis_core::ui::{Callback, Widget, WidgetKind};
+use sulis_core::widgets::{Button, ScrollDirection, ScrollPane, TextArea};
+use sulis_module::Module;
+use sulis_state::{ai_trace, AIState, AITraceStep, AITurnTrace, ChangeListener};
+
+use crate::RootView;
+
+pub const NAME: &str = "ai_trace_window";
+
+/// The maximum number of turns shown at once, newest first
+const MAX_SHOWN: usize = 20;
+
+/// Shows the decisions made by the AI on its most recent turns.  While the
+/// window is open, the area view draws the scores of the latest turn over
+/// the candidate targets and positions.
+pub struct AITraceWindow {}
+
+impl AITraceWindow {
+    pub fn new() -> Rc<RefCell<AITraceWindow>> {
+        Rc::new(RefCell::new(AITraceWindow {}))
+    }
+}
+
+impl WidgetKind for AITraceWindow {
+    widget_kind!(NAME);
+
+    fn on_remove(&mut self, _widget: &Rc<RefCell<Widget>>) {
+        ai_trace::set_overlay_shown(false);
+    }
+
+    fn on_add(&mut self, widget: &Rc<RefCell<Widget>>) -> Vec<Rc<RefCell<Widget>>> {
+        ai_trace::add_change_listener(ChangeListener::invalidate(NAME, widget));
+        ai_trace::set_overlay_shown(true);
+
+        let close = Widget::with_theme(Button::empty(), "close");
+        close
+            .borrow_mut()
+            .state
+            .add_callback(Callback::new(Rc::new(|widget, _| {
+                let (parent, _) = Widget::parent::<AITraceWindow>(widget);
+                parent.borrow_mut().mark_for_removal();
+            })));
+
+        let clear = Widget::with_theme(Button::empty(), "clear");
+        clear
+            .borrow_mut()
+            .state
+            .add_callback(Callback::new(Rc::new(|widget, _| {
+                ai_trace::clear();
+                let (parent, _) = Widget::parent::<AITraceWindow>(widget);
+                parent.borrow_mut().invalidate_children();
+            })));
+
+        let export = Widget::with_theme(Button::empty(), "export");
+        export
+            .borrow_mut()
+            .state
+            .add_callback(Callback::new(Rc::new(|widget, _| {
+                let (_, root_view) = Widget::parent_mut::<RootView>(widget);
+                match ai_trace::export() {
+                    Ok(_) => root_view.add_status_text("AI Trace Exported."),
+                    Err(e) => {
+                        error!("Error exporting AI trace");
+                        error!("{}", e);
+                        root_view.add_status_text("Error exporting AI Trace!");
+                    }
+                }
+            })));
+
+        let rules = Module::rules();
+        let turns_pane = ScrollPane::new(ScrollDirection::Vertical);
+        let turns_widget = Widget::with_theme(turns_pane.clone(), "turns");
+        for turn in ai_trace::turns().iter().rev().take(MAX_SHOWN) {
+            let widget = Widget::with_theme(TextArea::empty(), "turn");
+            {
+                let state = &mut widget.borrow_mut().state;
+                state.add_text_arg("round", &turn.time.round.to_string());
+                state.add_text_arg("hour", rules.get_hour_name(turn.time.hour));
+                state.add_text_arg("name", &turn.entity.name);
+                if let Some(template) = &turn.template {
+                    state.add_text_arg("template", template);
+                }
+                match turn.end {
+                    None => state.add_text_arg("end", "in progress"),
+                    Some(end) => state.add_text_arg("end", end.to_str()),
+                }
+                state.add_text_arg("text", &describe(turn));
+            }
+            turns_pane.borrow().add_to_content(widget);
+        }
+
+        vec![close, clear, export, turns_widget]
+    }
+}
+
+fn describe(turn: &AITurnTrace) -> String {
+    let lines: Vec<String> = turn.steps.iter().map(describe_step).collect();
+    lines.join("\n")
+}
+
+fn chosen_mark(index: usize, chosen: Option<usize>) -> &'static str {
+    if chosen == Some(index) {
+        "* "
+    } else {
+        "  "
+    }
+}
+
+fn describe_step(step: &AITraceStep) -> String {
+    use AITraceStep::*;
+    match step {
+        Action { count } => format!("Action {}", count),
+        Wait { time, total } => format!("Wait {} ({} this turn)", time, total),
+        Script { func } => format!("Script function {}", func),
+        State { state } => match state {
+            AIState::Run => "Continue".to_string(),
+            AIState::Wait(time) => format!("Wait {} before the next action", time),
+            AIState::End => "End turn".to_string(),
+        },
+        Plan { candidates, chosen } => {
+            let mut text = format!("Plan with {} candidates", candidates.len());
+            for (index, candidate) in candidates.iter().enumerate() {
+                text.push_str(&format!(
+                    "\n{}{} on {}: {:.2} ({} AP, {} squares)",
+                    chosen_mark(index, *chosen),
+                    candidate.action,
+                    candidate.target.name,
+                    candidate.score,
+                    candidate.ap,
+                    candidate.squares
+                ));
+            }
+            text
+        }
+        Failed { action, target } => format!("Unable to use {} on {}", action, target.name),
+        Squares {
+            target,
+            squares,
+            chosen,
+        } => {
+            let mut text = format!("Flanking {}", target.name);
+            for (index, square) in squares.iter().enumerate() {
+                text.push_str(&format!(
+                    "\n{}{}, {}: {:.2}",
+                    chosen_mark(index, *chosen),
+                    square.x,
+                    square.y,
+                    square.score
+                ));
+            }
+            text
+        }
+        Note { text } => text.to_string(),
+    }
+}
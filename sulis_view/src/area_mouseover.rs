@@ -22,6 +22,7 @@ use sulis_core::io::{event::ClickKind, GraphicsRenderer};
 use sulis_core::ui::{Widget, WidgetKind, WidgetState};
 use sulis_core::util::Point;
 use sulis_core::widgets::TextArea;
+use sulis_module::{Cover, Faction};
 use sulis_state::{ChangeListener, EntityState, GameState};
 
 const NAME: &str = "area_mouseover";
@@ -53,6 +54,7 @@ impl PartialEq for AreaMouseover {
 
 pub struct AreaMouseover {
     kind: Kind,
+    attacker: Option<Rc<RefCell<EntityState>>>,
     text_area: Rc<RefCell<TextArea>>,
 }
 
@@ -61,6 +63,17 @@ impl AreaMouseover {
         AreaMouseover::new(Kind::Entity(Rc::clone(entity)))
     }
 
+    /// Creates a mouseover for the current target of a targeter, showing the
+    /// cover the target has against the targeter's `parent`
+    pub fn new_target(
+        entity: &Rc<RefCell<EntityState>>,
+        parent: &Rc<RefCell<EntityState>>,
+    ) -> Rc<RefCell<AreaMouseover>> {
+        let mouseover = AreaMouseover::new(Kind::Entity(Rc::clone(entity)));
+        mouseover.borrow_mut().attacker = Some(Rc::clone(parent));
+        mouseover
+    }
+
     pub fn new_prop(index: usize) -> Rc<RefCell<AreaMouseover>> {
         AreaMouseover::new(Kind::Prop(index))
     }
@@ -72,6 +85,7 @@ impl AreaMouseover {
     fn new(kind: Kind) -> Rc<RefCell<AreaMouseover>> {
         Rc::new(RefCell::new(AreaMouseover {
             kind,
+            attacker: None,
             text_area: TextArea::empty(),
         }))
     }
@@ -81,10 +95,27 @@ impl AreaMouseover {
 
         match self.kind {
             Kind::Entity(ref entity) => {
-                let actor = &entity.borrow().actor;
+                self.add_cover_text_arg(state, entity);
+
+                let entity = entity.borrow();
+                let actor = &entity.actor;
                 state.add_text_arg("name", &actor.actor.name);
                 state.add_text_arg("cur_hp", &actor.hp().to_string());
                 state.add_text_arg("max_hp", &actor.stats.max_hp.to_string());
+
+                if !entity.is_party_member() {
+                    let faction = actor.faction();
+                    let party = Faction::friendly();
+                    let attitude = if GameState::is_hostile_faction(faction, &party) {
+                        "hostile"
+                    } else if GameState::is_friendly_faction(faction, &party) {
+                        "friendly"
+                    } else {
+                        "neutral"
+                    };
+                    state.add_text_arg(attitude, "true");
+                    state.add_text_arg("faction", &faction.name());
+                }
             }
             Kind::Prop(index) => {
                 let area_state = GameState::area_state();
@@ -112,6 +143,25 @@ impl AreaMouseover {
 
         true
     }
+
+    fn add_cover_text_arg(&self, state: &mut WidgetState, target: &Rc<RefCell<EntityState>>) {
+        let attacker = match self.attacker {
+            Some(ref attacker) => Rc::clone(attacker),
+            None => GameState::player(),
+        };
+
+        if Rc::ptr_eq(&attacker, target) || !attacker.borrow().is_hostile(&target.borrow()) {
+            return;
+        }
+
+        let area_state = GameState::area_state();
+        let cover = area_state
+            .borrow()
+            .cover(&attacker.borrow(), &target.borrow());
+        if cover != Cover::None {
+            state.add_text_arg(&format!("{}_cover", cover.to_str()), "true");
+        }
+    }
 }
 
 impl WidgetKind for AreaMouseover {
@@ -123,10 +123,11 @@ impl AreaOverlayHandler {
 
         if let Some(ref targeter) = targeter {
             let mut targeter = targeter.borrow_mut();
+            let parent = Rc::clone(targeter.parent());
             let mouse_over = targeter.on_mouse_move(x, y);
 
             if let Some(entity) = mouse_over {
-                return Some(AreaMouseover::new_entity(entity));
+                return Some(AreaMouseover::new_target(entity, &parent));
             } else {
                 return None;
             }
@@ -134,8 +135,7 @@ impl AreaOverlayHandler {
 
         let area_state = area_state.borrow();
         if let Some(entity) = area_state.get_entity_at(x, y) {
-            let pc = GameState::player();
-            if pc.borrow().is_hostile(&entity.borrow()) && entity.borrow().actor.stats.hidden {
+            if entity.borrow().is_hidden_from_party() {
                 None
             } else {
                 Some(AreaMouseover::new_entity(&entity))
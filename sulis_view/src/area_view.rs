@@ -17,6 +17,7 @@
 use std::any::Any;
 use std::cell::{RefCell, RefMut};
 use std::cmp::{self, Ordering};
+use std::collections::HashMap;
 use std::mem;
 use std::rc::Rc;
 use std::time;
@@ -27,7 +28,7 @@ use sulis_core::image::Image;
 use sulis_core::io::event::ClickKind;
 use sulis_core::io::*;
 use sulis_core::resource::{ResourceSet, Sprite};
-use sulis_core::ui::{animation_state, compute_area_scaling};
+use sulis_core::ui::{animation_state, compute_area_scaling, LineRenderer};
 use sulis_core::ui::{color, Color, Cursor, Scrollable, Widget, WidgetKind};
 use sulis_core::util::{self, Offset, Point, Rect, Scale};
 use sulis_core::widgets::Label;
@@ -35,7 +36,7 @@ use sulis_module::{
     area::{Layer, Tile},
     DamageKind, Module,
 };
-use sulis_state::{area_feedback_text, area_state::PCVisRedraw, RangeIndicatorImageSet};
+use sulis_state::{ai_trace, area_feedback_text, area_state::PCVisRedraw, RangeIndicatorImageSet};
 use sulis_state::{AreaDrawable, AreaState, EntityState, EntityTextureCache, GameState};
 
 use crate::{action_kind, window_fade, AreaOverlayHandler, ScreenShake, WindowFade};
@@ -64,6 +65,10 @@ pub struct AreaView {
     feedback_text_params: area_feedback_text::Params,
     entity_see_through_alpha: f32,
 
+    ai_trace_text_scale: f32,
+    ai_trace_color: Color,
+    ai_trace_chosen_color: Color,
+
     scroll_target: Option<(f32, f32)>,
     screen_shake: Option<ScreenShake>,
 
@@ -97,6 +102,9 @@ impl AreaView {
             active_entity: None,
             entity_see_through_alpha: 0.2,
             feedback_text_params: area_feedback_text::Params::default(),
+            ai_trace_text_scale: 0.75,
+            ai_trace_color: color::LIGHT_GRAY,
+            ai_trace_chosen_color: color::GREEN,
             scroll_target: None,
             screen_shake: None,
             overlay_handler: AreaOverlayHandler::default(),
@@ -435,6 +443,10 @@ impl AreaView {
         let y_base = widget.state.inner_top() as f32 - self.scroll.y();
 
         let selected = selected.borrow();
+        if selected.is_hidden_from_party() {
+            return;
+        }
+
         let w = selected.size.width as f32;
         let h = selected.size.height as f32;
         let x = x_base + selected.location.x as f32 + selected.sub_pos.0;
@@ -453,6 +465,64 @@ impl AreaView {
         renderer.draw(draw_list);
     }
 
+    /// Draws the scores of the candidates and positions considered on the
+    /// most recent AI turn over the area.  Labels for the same target are
+    /// stacked above it.
+    fn draw_ai_trace(&self, renderer: &mut dyn GraphicsRenderer, offset: Offset, scale: Scale) {
+        let turn = match ai_trace::latest() {
+            None => return,
+            Some(turn) => turn,
+        };
+
+        let font = &self.feedback_text_params.font;
+        let font_renderer = LineRenderer::new(font);
+        let text_scale = self.ai_trace_text_scale;
+        let mut draw_label = |text: &str, x: f32, y: f32, color: Color| {
+            let width = text_scale * font.get_width(text) as f32 / font.line_height as f32;
+            let pos = Offset {
+                x: offset.x + x - width / 2.0,
+                y: offset.y + y,
+            };
+            let (mut draw_list, _) = font_renderer.get_draw_list(text, pos, text_scale);
+            draw_list.set_scale(scale);
+            draw_list.set_color(color);
+            renderer.draw(draw_list);
+        };
+
+        if let Some((candidates, chosen)) = turn.last_plan() {
+            let mut lines: HashMap<usize, f32> = HashMap::new();
+            for (index, candidate) in candidates.iter().enumerate() {
+                let target = &candidate.target;
+                let line = lines.entry(target.index).or_insert(0.0);
+                let x = target.x as f32 + target.width as f32 / 2.0;
+                let y = target.y as f32 - text_scale * (*line + 1.0);
+                *line += 1.0;
+
+                let color = if chosen == Some(index) {
+                    self.ai_trace_chosen_color
+                } else {
+                    self.ai_trace_color
+                };
+                let text = format!("{} {:.2}", candidate.action, candidate.score);
+                draw_label(&text, x, y, color);
+            }
+        }
+
+        if let Some((squares, chosen)) = turn.last_squares() {
+            let (w, h) = (turn.entity.width as f32, turn.entity.height as f32);
+            for (index, square) in squares.iter().enumerate() {
+                let color = if chosen == Some(index) {
+                    self.ai_trace_chosen_color
+                } else {
+                    self.ai_trace_color
+                };
+                let x = square.x as f32 + w / 2.0;
+                let y = square.y as f32 + (h - text_scale) / 2.0;
+                draw_label(&format!("{:.2}", square.score), x, y, color);
+            }
+        }
+    }
+
     pub fn scroll(&mut self, delta_x: f32, delta_y: f32, millis: u32) {
         let speed = Config::scroll_speed() * millis as f32 / 33.0;
         let delta_x = speed * delta_x / self.scale.0;
@@ -588,6 +658,10 @@ impl WidgetKind for AreaView {
         }
 
         self.entity_see_through_alpha = theme.get_custom_or_default("entity_see_through_alpha", 0.2);
+        self.ai_trace_text_scale = theme.get_custom_or_default("ai_trace_text_scale", 0.75);
+        self.ai_trace_color = theme.get_custom_or_default("ai_trace_color", color::LIGHT_GRAY);
+        self.ai_trace_chosen_color =
+            theme.get_custom_or_default("ai_trace_chosen_color", color::GREEN);
         self.feedback_text_params.scale = theme.get_custom_or_default("feedback_text_scale", 1.0);
         self.feedback_text_params.ap_scale =
             theme.get_custom_or_default("ap_hover_text_scale", 1.0);
@@ -906,6 +980,10 @@ impl WidgetKind for AreaView {
         for feedback_text in state.feedback_text_iter_mut() {
             feedback_text.draw(renderer, &self.feedback_text_params, offset, scale, millis);
         }
+
+        if ai_trace::is_overlay_shown() {
+            self.draw_ai_trace(renderer, offset, scale);
+        }
     }
 
     fn on_mouse_release(&mut self, widget: &Rc<RefCell<Widget>>, kind: ClickKind) -> bool {
@@ -938,6 +1016,7 @@ impl WidgetKind for AreaView {
             };
 
             if fire_action {
+                GameState::take_over_turn();
                 let mut action = action_kind::get_action(x, y);
                 let clear_mouse_state = action.fire_action(widget);
 
@@ -18,6 +18,7 @@ use std::fmt::Display;
 
 use sulis_core::ui::WidgetState;
 use sulis_module::bonus::{AttackBuilder, AttackKindBuilder, Contingent};
+use sulis_module::item::format_item_weight;
 use sulis_module::{Armor, Bonus, BonusList, DamageKind, Module, PrereqList};
 
 pub fn format_bonus_or_penalty(amount: i32) -> String {
@@ -182,6 +183,9 @@ fn add_bonus(
         MovementRate(amount) => state.add_text_arg("movement_rate", &format!("{:.2}", amount)),
         MoveAnimRate(amount) => state.add_text_arg("move_anim_rate", &format!("{:.2}", amount)),
         CasterLevel(amount) => add(state, "caster_level", amount),
+        Reactions(amount) => add(state, "reactions", amount),
+        CarryCapacity(amount) => state.add_text_arg("carry_capacity", &format_item_weight(*amount)),
+        Stealth(amount) => add(state, "stealth", amount),
         AttackCost(amount) => {
             let cost = Module::rules().to_display_ap(*amount);
             add(state, "attack_cost", cost);
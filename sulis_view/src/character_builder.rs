@@ -315,7 +315,7 @@ impl BuilderSet for CharacterCreator {
             inline_race: None,
             sex: builder.sex,
             attributes: builder.attributes.unwrap(),
-            faction: Some(Faction::Friendly),
+            faction: Some(Faction::friendly()),
             conversation: None,
             images: builder.images.clone(),
             hue: builder.hue,
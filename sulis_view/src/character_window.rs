@@ -28,8 +28,8 @@ use sulis_core::ui::{Callback, Widget, WidgetKind, WidgetState};
 use sulis_core::util::ExtInt;
 use sulis_core::widgets::{Button, ScrollDirection, ScrollPane, TextArea};
 use sulis_module::{
-    ActorBuilder, Attribute, DamageKind, InventoryBuilder, ItemListEntrySaveState, ItemSaveState,
-    Module, QuickSlot, Slot,
+    ai::PartyAIMode, ActorBuilder, Attribute, DamageKind, InventoryBuilder, ItemListEntrySaveState,
+    ItemSaveState, Module, QuickSlot, Slot,
 };
 use sulis_state::{ActorState, ChangeListener, Effect, EntityState, GameState};
 
@@ -120,6 +120,8 @@ impl WidgetKind for CharacterWindow {
             .state
             .set_enabled(!GameState::is_combat_active());
 
+        let ai_mode = create_ai_mode_button(&self.character, "ai_mode");
+
         let char_pane = Widget::with_theme(Button::empty(), "char_pane_button");
         char_pane
             .borrow_mut()
@@ -176,6 +178,7 @@ impl WidgetKind for CharacterWindow {
             close,
             cur_pane,
             level_up,
+            ai_mode,
             char_pane,
             abilities_pane,
             effects_pane,
@@ -183,6 +186,43 @@ impl WidgetKind for CharacterWindow {
     }
 }
 
+fn add_ai_mode_text_arg(state: &mut WidgetState, mode: Option<PartyAIMode>) {
+    match mode {
+        None => state.add_text_arg("mode", "Manual"),
+        Some(mode) => state.add_text_arg("mode", &format!("{:?}", mode)),
+    }
+}
+
+/// Creates a button showing the party AI mode of the `entity`, which cycles
+/// through the modes when clicked
+pub fn create_ai_mode_button(
+    entity: &Rc<RefCell<EntityState>>,
+    theme: &str,
+) -> Rc<RefCell<Widget>> {
+    let button = Widget::with_theme(Button::empty(), theme);
+    {
+        let entity = entity.borrow();
+        let state = &mut button.borrow_mut().state;
+        state.add_text_arg("name", &entity.actor.actor.name);
+        add_ai_mode_text_arg(state, entity.party_ai_mode());
+    }
+
+    let entity = Rc::clone(entity);
+    button
+        .borrow_mut()
+        .state
+        .add_callback(Callback::new(Rc::new(move |widget, _| {
+            let mode = PartyAIMode::cycle(entity.borrow().party_ai_mode());
+            entity.borrow_mut().set_party_ai_mode(mode);
+
+            let mut widget = widget.borrow_mut();
+            add_ai_mode_text_arg(&mut widget.state, mode);
+            widget.invalidate_layout();
+        })));
+
+    button
+}
+
 pub fn get_inventory(pc: &ActorState, include_stash: bool) -> InventoryBuilder {
     let coins = GameState::party_coins();
 
@@ -248,7 +288,7 @@ fn export_character(pc: &ActorState) {
         inline_race: None,
         sex: Some(pc.actor.sex),
         attributes: pc.actor.attributes,
-        faction: Some(pc.actor.faction()),
+        faction: Some(pc.actor.faction().clone()),
         conversation: None,
         images: pc.actor.builder_images.clone(),
         hue: pc.actor.hue,
@@ -0,0 +1,221 @@
+//  This file is part of Sulis, a turn based RPG written in Rust.
+//  Copyright 2018 Jared Stephen
+//
+//  Sulis is free software: you can redistribute it and/or modify
+//  it under the terms of the GNU General Public License as published by
+//  the Free Software Foundation, either version 3 of the License, or
+//  (at your option) any later version.
+//
+//  Sulis is distributed in the hope that it will be useful,
+//  but WITHOUT ANY WARRANTY; without even the implied warranty of
+//  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//  GNU General Public License for more details.
+//
+//  You should have received a copy of the GNU General Public License
+//  along with Sulis.  If not, see <http://www.gnu.org/licenses/>
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use sulis_core::ui::{Callback, Widget, WidgetKind};
+use sulis_core::widgets::{Button, ScrollDirection, ScrollPane, TextArea};
+use sulis_module::{Cover, DamageRoll, Module};
+use sulis_state::{combat_log, ChangeListener, CombatEvent, CombatEventKind};
+
+use crate::RootView;
+
+pub const NAME: &str = "combat_log_window";
+
+/// The maximum number of entries shown at once, newest first
+const MAX_SHOWN: usize = 200;
+
+pub struct CombatLogWindow {
+    hidden: HashSet<CombatEventKind>,
+}
+
+impl CombatLogWindow {
+    pub fn new() -> Rc<RefCell<CombatLogWindow>> {
+        Rc::new(RefCell::new(CombatLogWindow {
+            hidden: HashSet::new(),
+        }))
+    }
+}
+
+impl WidgetKind for CombatLogWindow {
+    widget_kind!(NAME);
+
+    fn on_add(&mut self, widget: &Rc<RefCell<Widget>>) -> Vec<Rc<RefCell<Widget>>> {
+        combat_log::add_change_listener(ChangeListener::invalidate(NAME, widget));
+
+        let close = Widget::with_theme(Button::empty(), "close");
+        close
+            .borrow_mut()
+            .state
+            .add_callback(Callback::new(Rc::new(|widget, _| {
+                let (parent, _) = Widget::parent::<CombatLogWindow>(widget);
+                parent.borrow_mut().mark_for_removal();
+            })));
+
+        let filters = Widget::empty("filters");
+        for kind in CombatEventKind::iter() {
+            let kind = *kind;
+            let button = Widget::with_theme(Button::empty(), kind.to_str());
+            button
+                .borrow_mut()
+                .state
+                .set_active(!self.hidden.contains(&kind));
+            button
+                .borrow_mut()
+                .state
+                .add_callback(Callback::new(Rc::new(move |widget, _| {
+                    let (parent, window) = Widget::parent_mut::<CombatLogWindow>(widget);
+                    if !window.hidden.remove(&kind) {
+                        window.hidden.insert(kind);
+                    }
+                    parent.borrow_mut().invalidate_children();
+                })));
+            Widget::add_child_to(&filters, button);
+        }
+
+        let export = Widget::with_theme(Button::empty(), "export");
+        export
+            .borrow_mut()
+            .state
+            .add_callback(Callback::new(Rc::new(|widget, _| {
+                let (_, root_view) = Widget::parent_mut::<RootView>(widget);
+                match combat_log::export() {
+                    Ok(_) => root_view.add_status_text("Combat Log Exported."),
+                    Err(e) => {
+                        error!("Error exporting combat log");
+                        error!("{}", e);
+                        root_view.add_status_text("Error exporting Combat Log!");
+                    }
+                }
+            })));
+
+        let rules = Module::rules();
+        let entries_pane = ScrollPane::new(ScrollDirection::Vertical);
+        let entries_widget = Widget::with_theme(entries_pane.clone(), "entries");
+        for entry in combat_log::entries()
+            .iter()
+            .rev()
+            .filter(|entry| !self.hidden.contains(&entry.event.kind()))
+            .take(MAX_SHOWN)
+        {
+            let widget = Widget::with_theme(TextArea::empty(), "entry");
+            {
+                let state = &mut widget.borrow_mut().state;
+                state.add_text_arg("day", &entry.time.day.to_string());
+                state.add_text_arg("hour", rules.get_hour_name(entry.time.hour));
+                state.add_text_arg("round", &entry.time.round.to_string());
+                state.add_text_arg(entry.event.kind().to_str(), "true");
+                state.add_text_arg("text", &describe(&entry.event));
+            }
+            entries_pane.borrow().add_to_content(widget);
+        }
+
+        vec![close, filters, export, entries_widget]
+    }
+}
+
+fn describe_damage(damage: &[DamageRoll]) -> String {
+    damage
+        .iter()
+        .map(|roll| {
+            format!(
+                "{} {} (rolled {}, {:.1} after resistance, -{:.1} armor)",
+                roll.amount,
+                roll.kind.to_str(),
+                roll.rolled,
+                roll.resisted,
+                roll.armor
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn describe(event: &CombatEvent) -> String {
+    use CombatEvent::*;
+    match event {
+        Attack {
+            attacker,
+            target,
+            accuracy_kind,
+            roll,
+            accuracy,
+            defense,
+            hit_kind,
+            flanking,
+            sneak_attack,
+            cover,
+            damage,
+        } => {
+            let mut text = format!(
+                "{} attacks {}: {:?} ({} + {:?} accuracy {} vs defense {})",
+                attacker.name, target.name, hit_kind, roll, accuracy_kind, accuracy, defense
+            );
+            if *flanking {
+                text.push_str(", flanking");
+            }
+            if *sneak_attack {
+                text.push_str(", sneak attack");
+            }
+            if *cover != Cover::None {
+                text.push_str(&format!(", {} cover", cover.to_str()));
+            }
+            if !damage.is_empty() {
+                text.push_str(&format!(".  {}", describe_damage(damage)));
+            }
+            text
+        }
+        Concealed {
+            attacker,
+            target,
+            concealment,
+        } => format!(
+            "{} attacks {}: Miss ({}% concealment)",
+            attacker.name, target.name, concealment
+        ),
+        Detected {
+            entity,
+            observer,
+            margin,
+        } => format!("{} spots {} (by {})", observer.name, entity.name, margin),
+        Save {
+            entity,
+            kind,
+            roll,
+            bonus,
+            dc,
+            result,
+        } => format!(
+            "{} rolls a {} save: {:?} ({} + {} vs DC {})",
+            entity.name,
+            kind.to_str(),
+            result,
+            roll,
+            bonus,
+            dc
+        ),
+        Damage {
+            attacker,
+            target,
+            damage,
+        } => format!(
+            "{} damages {}.  {}",
+            attacker.name,
+            target.name,
+            describe_damage(damage)
+        ),
+        Effect {
+            target,
+            name,
+            duration,
+        } => format!("{} gains {} ({} rounds)", target.name, name, duration),
+        Ability { entity, name, .. } => format!("{} uses {}", entity.name, name),
+        Death { entity, killer } => format!("{} is killed by {}", entity.name, killer.name),
+    }
+}
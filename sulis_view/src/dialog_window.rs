@@ -23,8 +23,8 @@ use sulis_core::ui::{theme, Widget, WidgetKind};
 use sulis_core::widgets::TextArea;
 use sulis_module::{conversation::Response, Conversation, OnTrigger};
 use sulis_state::{
-    area_feedback_text::ColorKind, script::entity_with_id, AreaFeedbackText, ChangeListener,
-    EntityState, GameState,
+    area_feedback_text::ColorKind, schedule, script::entity_with_id, AreaFeedbackText,
+    ChangeListener, EntityState, GameState,
 };
 
 use crate::trigger_activator::{activate, is_match, scroll_view};
@@ -77,6 +77,9 @@ impl WidgetKind for DialogWindow {
 
     fn on_remove(&mut self, _widget: &Rc<RefCell<Widget>>) {
         self.entity.borrow_mut().actor.listeners.remove(NAME);
+
+        // linger for a moment before going back to the schedule
+        schedule::hold(&self.entity, schedule::CONVERSATION_HOLD_ROUNDS);
     }
 
     fn on_add(&mut self, widget: &Rc<RefCell<Widget>>) -> Vec<Rc<RefCell<Widget>>> {
@@ -242,6 +245,8 @@ pub fn show_convo(
     target: &Rc<RefCell<EntityState>>,
     widget: &Rc<RefCell<Widget>>,
 ) {
+    schedule::hold(target, schedule::CONVERSATION_HOLD_ROUNDS);
+
     let initial_node = get_initial_node(&convo, pc, target);
     if convo.responses(&initial_node).is_empty() {
         let area = GameState::area_state();
@@ -23,6 +23,8 @@ use sulis_core::util::Size;
 use sulis_core::widgets::{Button, Label};
 use sulis_state::{ChangeListener, GameState};
 
+use crate::character_window::create_ai_mode_button;
+
 pub const NAME: &str = "formation_window";
 
 struct Entry {
@@ -196,10 +198,25 @@ impl WidgetKind for FormationWindow {
                 parent.borrow_mut().mark_for_removal();
             })));
 
-        let mut children = vec![close];
+        let party = GameState::party();
+        let ai_modes = Widget::empty("ai_modes");
+        for entity in party.iter() {
+            let button = create_ai_mode_button(entity, "ai_mode");
+
+            let label = Widget::with_theme(Label::empty(), "portrait");
+            if let Some(ref image) = entity.borrow().actor.actor.portrait {
+                label
+                    .borrow_mut()
+                    .state
+                    .add_text_arg("portrait", &image.id());
+            }
+            Widget::add_child_to(&button, label);
+            Widget::add_child_to(&ai_modes, button);
+        }
+
+        let mut children = vec![close, ai_modes];
 
         self.entries.clear();
-        let party = GameState::party();
         let formation = GameState::party_formation();
         let formation = formation.borrow();
         for (index, (x, y)) in formation.positions_iter().enumerate() {
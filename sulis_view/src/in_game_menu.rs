@@ -20,6 +20,7 @@ use std::rc::Rc;
 
 use sulis_core::ui::{Callback, Widget, WidgetKind};
 use sulis_core::widgets::{Button, ConfirmationWindow};
+use sulis_module::Module;
 use sulis_state::GameState;
 
 use crate::{LoadWindow, RootView};
@@ -81,6 +82,28 @@ impl WidgetKind for InGameMenu {
                 Widget::add_child_to(&root, window);
             })));
 
+        let difficulty = GameState::difficulty();
+        let difficulty_button = Widget::with_theme(Button::empty(), "difficulty");
+        difficulty_button
+            .borrow_mut()
+            .state
+            .add_text_arg("name", &difficulty.name);
+        difficulty_button
+            .borrow_mut()
+            .state
+            .add_text_arg("description", &difficulty.description);
+        difficulty_button
+            .borrow_mut()
+            .state
+            .add_callback(Callback::new(Rc::new(|widget, _| {
+                let (parent, _) = Widget::parent::<InGameMenu>(widget);
+                let rules = Module::rules();
+                if let Some(next) = rules.next_difficulty(&GameState::difficulty().id) {
+                    GameState::set_difficulty(next.clone());
+                }
+                parent.borrow_mut().invalidate_children();
+            })));
+
         let menu = Widget::with_theme(Button::empty(), "menu");
         let menu_cb = self.menu_callback.clone();
         menu.borrow_mut()
@@ -111,6 +134,6 @@ impl WidgetKind for InGameMenu {
                 Widget::add_child_to(&root, window);
             })));
 
-        vec![back, save, load, menu, exit]
+        vec![back, save, load, difficulty_button, menu, exit]
     }
 }
@@ -21,8 +21,8 @@ use std::time;
 
 use sulis_core::ui::{Callback, Widget, WidgetKind};
 use sulis_core::util;
-use sulis_core::widgets::{Button, Label};
-use sulis_module::{QuickSlot, Slot};
+use sulis_core::widgets::{Button, Label, TextArea};
+use sulis_module::{item::format_item_weight, QuickSlot, Slot};
 use sulis_state::{script::ScriptItemKind, ChangeListener, EntityState, GameState};
 
 use crate::{item_callback_handler::*, item_list_pane::Filter, ItemButton, ItemListPane};
@@ -170,13 +170,37 @@ impl WidgetKind for InventoryWindow {
             }
         }
 
+        let load = Widget::with_theme(TextArea::empty(), "load");
+        {
+            let state = &mut load.borrow_mut().state;
+            state.add_text_arg("load", &format_item_weight(actor.stats.load));
+            state.add_text_arg("capacity", &format_item_weight(actor.stats.carry_capacity));
+            if let Some(encumbrance) = &actor.stats.encumbrance {
+                state.add_text_arg("encumbrance", encumbrance);
+            }
+        }
+
+        // the stash may hold whatever the party members have room for
+        // beyond their own equipped items
+        let stash_capacity: i32 = GameState::party()
+            .iter()
+            .map(|member| {
+                let actor = &member.borrow().actor;
+                (actor.stats.carry_capacity - actor.inventory().weight()).max(0)
+            })
+            .sum();
         let stash_title = Widget::with_theme(Label::empty(), "stash_title");
+        {
+            let state = &mut stash_title.borrow_mut().state;
+            state.add_text_arg("weight", &format_item_weight(stash.borrow().weight()));
+            state.add_text_arg("capacity", &format_item_weight(stash_capacity));
+        }
 
         trace!(
             "Inventory window creation time: {}",
             util::format_elapsed_secs(start_time.elapsed())
         );
 
-        vec![close, equipped_area, item_list_pane, stash_title]
+        vec![close, equipped_area, load, item_list_pane, stash_title]
     }
 }
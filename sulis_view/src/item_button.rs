@@ -413,6 +413,17 @@ impl WidgetKind for ItemButton {
             item_window
                 .state
                 .add_text_arg("weight", &format_item_weight(item_state.item.weight));
+            if let (Some(cur), Some(max)) = (item_state.durability, item_state.max_durability()) {
+                item_window
+                    .state
+                    .add_text_arg("durability", &cur.to_string());
+                item_window
+                    .state
+                    .add_text_arg("max_durability", &max.to_string());
+                if item_state.is_broken() {
+                    item_window.state.add_text_arg("broken", "true");
+                }
+            }
             self.add_price_text_arg(&root, &mut item_window, &item_state);
 
             if let Some(ref prereqs) = &item_state.item.prereqs {
@@ -72,6 +72,7 @@ pub fn use_item_cb(entity: &Rc<RefCell<EntityState>>, kind: ScriptItemKind) -> C
                 return;
             }
         }
+        GameState::take_over_turn();
         Script::item_on_activate(&entity, "on_activate".to_string(), kind.clone());
     }))
 }
@@ -31,6 +31,9 @@ pub use self::ability_pane::AbilityPane;
 mod action_kind;
 pub use self::action_kind::ActionKind;
 
+mod ai_trace_window;
+pub use self::ai_trace_window::AITraceWindow;
+
 mod ap_bar;
 pub use self::ap_bar::ApBar;
 
@@ -54,6 +57,9 @@ pub use self::character_window::CharacterWindow;
 mod class_pane;
 pub use self::class_pane::ClassPane;
 
+mod combat_log_window;
+pub use self::combat_log_window::CombatLogWindow;
+
 mod console_window;
 pub use self::console_window::ConsoleWindow;
 
@@ -43,7 +43,7 @@ impl LoadWindow {
         let accept = Widget::with_theme(Button::empty(), "accept");
         let cancel = Widget::with_theme(Button::empty(), "cancel");
         let delete = Widget::with_theme(Button::empty(), "delete");
-        let entries = match get_available_save_files() {
+        let mut entries = match get_available_save_files() {
             Ok(files) => files,
             Err(e) => {
                 warn!("Unable to read saved files");
@@ -52,6 +52,9 @@ impl LoadWindow {
             }
         };
 
+        // list autosaves first, keeping the newest first order within each group
+        entries.sort_by_key(|meta| !meta.autosave);
+
         Rc::new(RefCell::new(LoadWindow {
             accept,
             delete,
@@ -62,9 +65,11 @@ impl LoadWindow {
         }))
     }
 
-    pub fn load(&self, root: &Rc<RefCell<Widget>>) {
+    /// Attempts to load the selected save, returning true if successful.  If
+    /// the save cannot be read, the error is recorded on the selected entry.
+    pub fn load(&mut self, root: &Rc<RefCell<Widget>>) -> bool {
         let index = match self.selected_entry {
-            None => return,
+            None => return false,
             Some(index) => index,
         };
 
@@ -72,9 +77,12 @@ impl LoadWindow {
             Err(e) => {
                 error!("Error reading game state");
                 error!("{}", e);
+                self.entries[index].error = Some(e.to_string());
+                false
             }
             Ok(state) => {
                 self.set_load_step(state, root);
+                true
             }
         }
     }
@@ -173,15 +181,30 @@ impl WidgetKind for LoadWindow {
             .add_callback(Callback::new(Rc::new(|widget, _| {
                 let (parent, load_window) = Widget::parent_mut::<LoadWindow>(widget);
                 let root = Widget::get_root(&parent);
-                load_window.load(&root);
-
-                parent.borrow_mut().mark_for_removal();
+                if load_window.load(&root) {
+                    parent.borrow_mut().mark_for_removal();
+                } else {
+                    parent.borrow_mut().invalidate_children();
+                }
             })));
 
         let scrollpane = ScrollPane::new(ScrollDirection::Vertical);
         let entries = Widget::with_theme(scrollpane.clone(), "entries");
 
+        let mut prev_autosave = None;
         for (index, meta) in self.entries.iter().enumerate() {
+            if prev_autosave != Some(meta.autosave) {
+                prev_autosave = Some(meta.autosave);
+                let theme = if meta.autosave {
+                    "autosaves_header"
+                } else {
+                    "saves_header"
+                };
+                scrollpane
+                    .borrow()
+                    .add_to_content(Widget::with_theme(Label::empty(), theme));
+            }
+
             let text_area = Widget::with_defaults(TextArea::empty());
             {
                 let area = &mut text_area.borrow_mut().state;
@@ -200,6 +223,10 @@ impl WidgetKind for LoadWindow {
                 if let Some(error) = &meta.error {
                     area.add_text_arg("error", error);
                 }
+
+                if meta.is_incompatible() {
+                    area.add_text_arg("incompatible_version", &meta.schema_version.to_string());
+                }
             }
 
             let widget = Widget::with_theme(Button::empty(), "entry");
@@ -22,7 +22,7 @@ use sulis_core::ui::*;
 use sulis_core::widgets::{
     Button, ConfirmationWindow, Label, ScrollDirection, ScrollPane, TextArea,
 };
-use sulis_module::{Actor, Module};
+use sulis_module::{Actor, Difficulty, Module};
 use sulis_state::{ActorState, NextGameStep};
 
 use crate::character_window::create_details_text_box;
@@ -33,6 +33,7 @@ pub struct CharacterSelector {
     first_add: bool,
     main_menu: Rc<RefCell<Widget>>,
     to_select: Option<String>,
+    difficulty: Difficulty,
 }
 
 impl CharacterSelector {
@@ -42,6 +43,7 @@ impl CharacterSelector {
             first_add: true,
             main_menu,
             to_select: None,
+            difficulty: Module::rules().default_difficulty(),
         }))
     }
 
@@ -191,15 +193,42 @@ impl WidgetKind for CharacterSelector {
                     None => return,
                     Some(ref selected) => Rc::clone(selected),
                 };
+                let difficulty = selector.difficulty.clone();
 
                 let (root, window) = Widget::parent_mut::<MainMenu>(&parent);
-                window.next_step = Some(NextGameStep::NewCampaign { pc_actor: selected });
+                window.next_step = Some(NextGameStep::NewCampaign {
+                    pc_actor: selected,
+                    difficulty,
+                });
 
                 let loading_screen = Widget::with_defaults(LoadingScreen::new());
                 loading_screen.borrow_mut().state.set_modal(true);
                 Widget::add_child_to(&root, loading_screen);
             })));
 
+        let difficulty = &self.difficulty;
+        let difficulty_button = Widget::with_theme(Button::empty(), "difficulty_button");
+        difficulty_button
+            .borrow_mut()
+            .state
+            .add_text_arg("name", &difficulty.name);
+        difficulty_button
+            .borrow_mut()
+            .state
+            .add_text_arg("description", &difficulty.description);
+        difficulty_button
+            .borrow_mut()
+            .state
+            .add_callback(Callback::new(Rc::new(|widget, _| {
+                let (parent, selector) = Widget::parent_mut::<CharacterSelector>(widget);
+
+                let rules = Module::rules();
+                if let Some(next) = rules.next_difficulty(&selector.difficulty.id) {
+                    selector.difficulty = next.clone();
+                }
+                parent.borrow_mut().invalidate_children();
+            })));
+
         let details = if let Some(ref actor) = self.selected {
             let mut actor_state = ActorState::new(Rc::clone(actor));
             actor_state.compute_stats();
@@ -229,6 +258,7 @@ impl WidgetKind for CharacterSelector {
             new_character_button,
             delete_char_button,
             play_button,
+            difficulty_button,
             details,
             invalid_level,
         ]
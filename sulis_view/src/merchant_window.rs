@@ -67,6 +67,7 @@ impl WidgetKind for MerchantWindow {
     }
 
     fn on_add(&mut self, widget: &Rc<RefCell<Widget>>) -> Vec<Rc<RefCell<Widget>>> {
+        let repair_all = Widget::with_theme(Button::empty(), "repair_all");
         {
             let area_state = GameState::area_state();
             let mut area_state = area_state.borrow_mut();
@@ -80,6 +81,26 @@ impl WidgetKind for MerchantWindow {
             merchant
                 .listeners
                 .add(ChangeListener::invalidate(NAME, widget));
+
+            match merchant.repair_frac {
+                None => repair_all.borrow_mut().state.set_visible(false),
+                Some(repair_frac) => {
+                    let price = merchant.get_repair_price().unwrap_or(0);
+                    let mut repair_all = repair_all.borrow_mut();
+                    repair_all.state.add_text_arg("cost", &price.to_string());
+                    repair_all
+                        .state
+                        .set_enabled(price > 0 && GameState::party_coins() >= price);
+                    repair_all
+                        .state
+                        .add_callback(Callback::new(Rc::new(move |widget, _| {
+                            if GameState::repair_party_items(repair_frac) {
+                                let (parent, _) = Widget::parent::<MerchantWindow>(widget);
+                                parent.borrow_mut().invalidate_children();
+                            }
+                        })));
+                }
+            }
         }
 
         let close = Widget::with_theme(Button::empty(), "close");
@@ -97,6 +118,6 @@ impl WidgetKind for MerchantWindow {
             &self.filter,
         ));
 
-        vec![close, item_list_pane]
+        vec![close, repair_all, item_list_pane]
     }
 }
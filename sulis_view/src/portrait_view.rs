@@ -137,6 +137,12 @@ impl WidgetKind for PortraitView {
                 .borrow_mut()
                 .state
                 .add_text_arg("text", &icon.text);
+            if effect.stacks() > 1 {
+                icon_widget
+                    .borrow_mut()
+                    .state
+                    .add_text_arg("stacks", &effect.stacks().to_string());
+            }
             Widget::add_child_to(&icons, icon_widget);
         }
 
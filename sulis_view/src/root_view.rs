@@ -18,10 +18,11 @@ use std::collections::HashMap;
 use std::{any::Any, cell::RefCell, rc::Rc, time::Instant};
 
 use crate::{
-    character_window, formation_window, inventory_window, merchant_window, prop_window,
-    quest_window, world_map_window, AbilitiesBar, ApBar, AreaView, CharacterWindow, ConsoleWindow,
-    FormationWindow, GameOverWindow, InGameMenu, InitiativeTicker, InventoryWindow, MerchantWindow,
-    PortraitPane, PropWindow, QuestWindow, QuickItemBar, WorldMapWindow,
+    ai_trace_window, character_window, combat_log_window, formation_window, inventory_window,
+    merchant_window, prop_window, quest_window, world_map_window, AITraceWindow, AbilitiesBar,
+    ApBar, AreaView, CharacterWindow, CombatLogWindow, ConsoleWindow, FormationWindow,
+    GameOverWindow, InGameMenu, InitiativeTicker, InventoryWindow, MerchantWindow, PortraitPane,
+    PropWindow, QuestWindow, QuickItemBar, WorldMapWindow,
 };
 use sulis_core::config::Config;
 use sulis_core::io::{keyboard_event::Key, InputActionKind};
@@ -31,15 +32,17 @@ use sulis_core::widgets::{Button, ConfirmationWindow, Label};
 use sulis_module::{area::OnRest, Module};
 use sulis_state::{
     area_feedback_text::ColorKind, save_file::create_save, script::script_callback,
-    script::ScriptEntity, AreaFeedbackText, ChangeListener, EntityState, GameState, NextGameStep,
-    Script,
+    hot_reload::ReloadReport, script::ScriptEntity, AreaFeedbackText, ChangeListener, EntityState,
+    GameState, HotReloader, NextGameStep, Script,
 };
 
-const WINDOW_NAMES: [&str; 7] = [
+const WINDOW_NAMES: [&str; 9] = [
     self::formation_window::NAME,
     self::inventory_window::NAME,
     self::character_window::NAME,
     self::quest_window::NAME,
+    self::combat_log_window::NAME,
+    self::ai_trace_window::NAME,
     self::world_map_window::NAME,
     self::merchant_window::NAME,
     self::prop_window::NAME,
@@ -55,6 +58,7 @@ pub struct RootView {
     area_view_widget: Rc<RefCell<Widget>>,
     console: Rc<RefCell<ConsoleWindow>>,
     console_widget: Rc<RefCell<Widget>>,
+    hot_reloader: Option<HotReloader>,
 
     quick_item_bar: Option<Rc<RefCell<Widget>>>,
     abilities_bar: Option<Rc<RefCell<Widget>>>,
@@ -100,6 +104,11 @@ impl RootView {
             area: "".to_string(),
             console,
             console_widget,
+            hot_reloader: if Config::debug().hot_reload {
+                Some(HotReloader::new())
+            } else {
+                None
+            },
             quick_item_bar: None,
             abilities_bar: None,
             scroll_keys_down: Vec::new(),
@@ -176,12 +185,42 @@ impl RootView {
         }
     }
 
+    fn handle_reload(&mut self, widget: &Rc<RefCell<Widget>>, report: ReloadReport) {
+        if report.errors.is_empty() {
+            self.add_status_text("Resources reloaded.");
+            return;
+        }
+
+        self.add_status_text("Error reloading resources.");
+        self.console.borrow().set_output(format!(
+            "{} reload error(s): {}",
+            report.errors.len(),
+            report.errors.join(" | ")
+        ));
+        self.set_console_window(widget, true);
+    }
+
     pub fn set_quest_window(&mut self, widget: &Rc<RefCell<Widget>>, desired_state: bool) {
         self.set_window(widget, self::quest_window::NAME, desired_state, &|| {
             Some(QuestWindow::new())
         });
     }
 
+    pub fn set_combat_log_window(&mut self, widget: &Rc<RefCell<Widget>>, desired_state: bool) {
+        self.set_window(
+            widget,
+            self::combat_log_window::NAME,
+            desired_state,
+            &|| Some(CombatLogWindow::new()),
+        );
+    }
+
+    pub fn set_ai_trace_window(&mut self, widget: &Rc<RefCell<Widget>>, desired_state: bool) {
+        self.set_window(widget, self::ai_trace_window::NAME, desired_state, &|| {
+            Some(AITraceWindow::new())
+        });
+    }
+
     pub fn set_formation_window(&mut self, widget: &Rc<RefCell<Widget>>, desired_state: bool) {
         self.set_window(widget, self::formation_window::NAME, desired_state, &|| {
             Some(FormationWindow::new())
@@ -266,6 +305,16 @@ impl RootView {
         self.set_quest_window(widget, desired_state);
     }
 
+    pub fn toggle_combat_log_window(&mut self, widget: &Rc<RefCell<Widget>>) {
+        let desired_state = !Widget::has_child_with_name(widget, self::combat_log_window::NAME);
+        self.set_combat_log_window(widget, desired_state);
+    }
+
+    pub fn toggle_ai_trace_window(&mut self, widget: &Rc<RefCell<Widget>>) {
+        let desired_state = !Widget::has_child_with_name(widget, self::ai_trace_window::NAME);
+        self.set_ai_trace_window(widget, desired_state);
+    }
+
     pub fn toggle_map_window(&mut self, widget: &Rc<RefCell<Widget>>) {
         let desired_state = !Widget::has_child_with_name(widget, self::world_map_window::NAME);
         self.set_map_window(widget, desired_state, false);
@@ -350,6 +399,17 @@ impl RootView {
         }
     }
 
+    pub fn toggle_auto_combat(&mut self) {
+        let auto_combat = !GameState::is_auto_combat();
+        GameState::set_auto_combat(auto_combat);
+
+        if auto_combat {
+            self.add_status_text("Auto-combat enabled.");
+        } else {
+            self.add_status_text("Auto-combat disabled.");
+        }
+    }
+
     pub fn select_party_member(&self, index: usize) {
         let party = GameState::party();
 
@@ -371,6 +431,10 @@ impl WidgetKind for RootView {
             root.borrow_mut().invalidate_children();
         }
 
+        if let Some(report) = self.hot_reloader.as_mut().and_then(|r| r.update()) {
+            self.handle_reload(widget, report);
+        }
+
         if let Some(instant) = self.status_added {
             let elapsed = util::get_elapsed_millis(instant.elapsed());
             if elapsed > 5000 {
@@ -469,6 +533,9 @@ impl WidgetKind for RootView {
             ToggleMap => self.toggle_map_window(widget),
             ToggleJournal => self.toggle_quest_window(widget),
             ToggleFormation => self.toggle_formation_window(widget),
+            ToggleCombatLog => self.toggle_combat_log_window(widget),
+            ToggleAutoCombat => self.toggle_auto_combat(),
+            ToggleAITrace => self.toggle_ai_trace_window(widget),
             EndTurn => self.end_turn(),
             Rest => self.rest(),
             Exit => self.show_exit(widget),
@@ -19,8 +19,8 @@ use std::rc::Rc;
 
 use sulis_core::ui::{Callback, Widget};
 use sulis_module::{
-    on_trigger::{self, Kind, ModuleLoadData, QuestStateData},
-    Actor, ItemState, MerchantData, Module, OnTrigger,
+    on_trigger::{self, Kind, ModuleLoadData, QuestStateData, ReputationData},
+    Actor, Faction, ItemState, MerchantData, Module, OnTrigger,
 };
 use sulis_state::{
     area_feedback_text::ColorKind,
@@ -133,6 +133,14 @@ pub fn is_match(
                     return false;
                 }
             }
+            Reputation(ref data) => match reputation(data) {
+                Some(cur) if cur >= data.amount => (),
+                _ => return false,
+            },
+            NotReputation(ref data) => match reputation(data) {
+                Some(cur) if cur < data.amount => (),
+                _ => return false,
+            },
             _ => {
                 warn!("Unsupported OnTrigger kind '{:?}' in validator", trigger);
             }
@@ -262,7 +270,24 @@ pub fn activate(
             NotQuestState(_) => {
                 warn!("NotQuestState invalid for trigger/dialog on_activate");
             }
+            Reputation(ref data) => match Faction::option_from_str(&data.faction) {
+                None => warn!("Reputation for invalid faction '{}'", data.faction),
+                Some(faction) => GameState::add_reputation(&faction, data.amount),
+            },
+            NotReputation(_) => {
+                warn!("NotReputation invalid for trigger/dialog on_activate");
+            }
+        }
+    }
+}
+
+fn reputation(data: &ReputationData) -> Option<i32> {
+    match Faction::option_from_str(&data.faction) {
+        None => {
+            warn!("Reputation for invalid faction '{}'", data.faction);
+            None
         }
+        Some(faction) => Some(GameState::reputation(&faction)),
     }
 }
 
@@ -493,6 +518,7 @@ fn show_merchant(widget: &Rc<RefCell<Widget>>, merch: &MerchantData) {
             &loot,
             merch.buy_frac,
             merch.sell_frac,
+            merch.repair_frac,
             merch.refresh_time,
         );
     }